@@ -2,30 +2,76 @@
 //! 
 //! See `safe-hook` crate for more details.
 
-
-
 use proc_macro::TokenStream;
 use quote::{ToTokens, format_ident, quote};
 use syn::parse::Parse;
-use syn::{ItemFn, LitStr, parse_macro_input};
+use syn::{Ident, ItemFn, ItemTrait, LitStr, Token, parse_macro_input};
 struct HookableProcArgs {
     name: LitStr,
-    // args: Punctuated<MetaNameValue, Token![,]>,
+    debug_only: bool,
+    by_ref: bool,
+    json: bool,
+    track_caller: bool,
+    version: Option<LitStr>,
 }
 
 impl Parse for HookableProcArgs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let name = input.parse::<LitStr>()?;
-        // let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let mut debug_only = false;
+        let mut by_ref = false;
+        let mut json = false;
+        let mut track_caller = false;
+        let mut version = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option = input.parse::<Ident>()?;
+            match option.to_string().as_str() {
+                "debug_only" => debug_only = true,
+                "by_ref" => by_ref = true,
+                "json" => json = true,
+                "track_caller" => track_caller = true,
+                "version" => {
+                    input.parse::<Token![=]>()?;
+                    let value = input.parse::<LitStr>()?;
+                    let major = value.value();
+                    let major = major
+                        .trim_start_matches('v')
+                        .split('.')
+                        .next()
+                        .unwrap_or("");
+                    if major.parse::<u64>().is_err() {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "Expected a semantic version like \"1.2.0\"",
+                        ));
+                    }
+                    version = Some(value);
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        format!("Unknown hookable option '{}'", option),
+                    ));
+                }
+            }
+        }
         Ok(HookableProcArgs {
             name,
-            // args
+            debug_only,
+            by_ref,
+            json,
+            track_caller,
+            version,
         })
     }
 }
 
 fn gen_args_name_list(f: &ItemFn) -> proc_macro2::TokenStream {
-    // fn xxx(a:ta,b:tb,c:tc) -> td;  ==> a,b,c
+    // fn xxx(a:ta,b:tb,c:tc) -> td;  ==> a,b,c,
     let mut args = Vec::new();
     for arg in f.sig.inputs.iter() {
         if let syn::FnArg::Typed(pat_type) = arg {
@@ -37,7 +83,7 @@ fn gen_args_name_list(f: &ItemFn) -> proc_macro2::TokenStream {
         }
     }
     quote! {
-        #(#args),*
+        #(#args,)*
     }
 }
 
@@ -64,9 +110,106 @@ fn get_hookable_lifetime(f: &ItemFn) -> Option<proc_macro2::TokenStream> {
         ),
     }
 }
+/// The type with a `'static` lifetime if it is a reference, like the arguments of hook points.
+fn with_static_lifetime(ty: &syn::Type) -> proc_macro2::TokenStream {
+    if let syn::Type::Reference(ref_ty) = ty {
+        let mut ref_ty = ref_ty.clone();
+        ref_ty.lifetime = Some(syn::Lifetime::new(
+            "'static",
+            proc_macro2::Span::call_site(),
+        ));
+        quote! { #ref_ty }
+    } else {
+        quote! { #ty }
+    }
+}
+
+/// The name stored in the metadata, a hash of `name` with the `hashed-names` feature,
+/// matching `safe_hook::hashed_name`.
+fn stored_name(name: String) -> String {
+    if !cfg!(feature = "hashed-names") {
+        return name;
+    }
+    // 64-bit FNV-1a, stable across builds and platforms.
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("#{:016x}", hash)
+}
+
+/// A string expression, which is empty in release builds with the `strip-names` feature.
+fn strip_in_release(value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    if !cfg!(feature = "strip-names") {
+        return value;
+    }
+    quote! {
+        {
+            #[cfg(debug_assertions)]
+            let value = #value;
+            #[cfg(not(debug_assertions))]
+            let value = "";
+            value
+        }
+    }
+}
+
+/// Submit the metadata `meta` to the collection backend,
+/// `inventory` or `linkme` with the `linkme` feature of `safe-hook`.
+fn gen_submit(meta: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        ::safe_hook::__submit!(#meta);
+    }
+}
+
+/// Register the metadata `meta` on its first call on wasm targets, where `inventory` may not collect it.
+fn gen_wasm_register(meta: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        #[cfg(target_family = "wasm")]
+        {
+            static REGISTER: ::std::sync::Once = ::std::sync::Once::new();
+            REGISTER.call_once(|| ::safe_hook::register_static(&#meta));
+        }
+    }
+}
+
+/// Whether the tokens of a type contain a reference or a lifetime.
+fn has_borrow(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Punct(punct) => punct.as_char() == '&' || punct.as_char() == '\'',
+        proc_macro2::TokenTree::Group(group) => has_borrow(group.stream()),
+        _ => false,
+    })
+}
+
 /// This macro is used to mark a function as hookable, without changing the signature.
 /// It generates some extra codes to support hooks, and registers the function to the inventory.
 /// 
+/// With the `safe_hook_strip` cfg, the function is emitted unchanged and is not registered.
+/// With the `linkme` feature of `safe-hook`, it is registered to a `linkme` distributed slice instead.
+/// With the `hashed-names` feature enabled, only a hash of the name is kept in the binary.
+/// With the `strip-names` feature enabled, the name and the type names are empty in release builds,
+/// and the function can only be found with `hookable_meta!`.
+///
+/// On wasm targets, where the inventory may not be collected, the function is also registered
+/// on its first call.
+///
+/// Options (after the name, separated by commas):
+/// - `debug_only`: The function is only hookable in debug builds (with `debug_assertions`),
+///   and is emitted unchanged in release builds.
+/// - `by_ref`: Hooks receive the arguments as `&mut ByRef<(A, B, ...)>` instead of `(A, B, ...)`,
+///   so large arguments are not moved through every hook.
+/// - `json`: The function can be called with JSON arguments, see `HookableFuncMetadata::invoke_json`.
+///   Requires the `serde` feature of `safe-hook`,
+///   and arguments implementing `Deserialize` and a result implementing `Serialize`.
+/// - `track_caller`: The function is marked with `#[track_caller]`,
+///   and hooks can get the location of each call, see `safe_hook::call_info`.
+/// - `version = "1.2.0"`: The semantic version of the contract of the function,
+///   hooks declaring an incompatible major version cannot be attached,
+///   see `safe_hook::HookableFuncMetadata::version`.
+///
+/// With the `metrics` feature of `safe_hook`, calls returning an `Err` of [`std::result::Result`],
+/// or of an alias of it like [`std::io::Result`], are counted as errors in metrics.
+///
 /// Not Supported:
 /// - functions with generic types
 /// - functions with `self` receiver
@@ -74,10 +217,16 @@ fn get_hookable_lifetime(f: &ItemFn) -> Option<proc_macro2::TokenStream> {
 /// 
 /// # Examples:
 /// ```
+/// # use safe_hook_macros::hookable;
 /// #[hookable("add")]
 /// fn add(left: i64, right: i64) -> i64 {
 ///    left + right
 /// }
+///
+/// #[hookable("sub", debug_only)]
+/// fn sub(left: i64, right: i64) -> i64 {
+///    left - right
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn hookable(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -86,6 +235,11 @@ pub fn hookable(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let input_fn_ident = input_fn.sig.ident.clone();
 
+    // The metadata is kept next to the function, so `register_hookables!` can name it.
+    let inner_ident = format_ident!("__hookable_{}_inner", input_fn_ident);
+    let flag_ident = format_ident!("__hookable_{}_flag", input_fn_ident);
+    let meta_ident = hookable_meta_ident(&input_fn_ident);
+
     let _ = get_hookable_lifetime(&input_fn);
     let generics = input_fn.sig.generics.clone();
 
@@ -105,15 +259,7 @@ pub fn hookable(args: TokenStream, input: TokenStream) -> TokenStream {
         .collect::<Vec<_>>();
     let input_type_with_static_lifetime = input_type
         .iter()
-        .map(|ty| {
-            if let syn::Type::Reference(ref_ty) = &**ty {
-                let mut ref_ty = ref_ty.clone();
-                ref_ty.lifetime = Some(syn::Lifetime::new("'static", proc_macro2::Span::call_site()));
-                quote! { #ref_ty }
-            } else {
-                quote! { #ty }
-            }
-        })
+        .map(|ty| with_static_lifetime(ty))
         .collect::<Vec<_>>();
 
     let ret_type = match &input_fn.sig.output {
@@ -121,17 +267,62 @@ pub fn hookable(args: TokenStream, input: TokenStream) -> TokenStream {
         syn::ReturnType::Type(_, ty) => quote! { #ty },
     };
 
+    // Untyped hooks see the arguments and the result as `Any`, which is only sound without borrows.
+    let erasable = !args.by_ref
+        && !input_type
+            .iter()
+            .map(|ty| ty.to_token_stream())
+            .chain(std::iter::once(ret_type.clone()))
+            .any(has_borrow);
+    let submit = gen_submit(&meta_ident);
+    let wasm_register = gen_wasm_register(&meta_ident);
+    let with_json = if args.json {
+        if !erasable {
+            panic!("The json option cannot be used with references or by_ref");
+        }
+        quote! {
+            let metadata = metadata.with_invoke_json(
+                ::safe_hook::invoke_json_erased::<#ret_type, (#(#input_type,)*)>,
+            );
+        }
+    } else {
+        quote! {}
+    };
+    let with_version = match &args.version {
+        Some(version) => quote! { let metadata = metadata.with_version(#version); },
+        None => quote! {},
+    };
+    let invoke = if erasable {
+        quote! { Some(::safe_hook::invoke_erased::<#ret_type, (#(#input_type,)*)>) }
+    } else {
+        quote! { None }
+    };
+
     let func_type = quote! {
         fn(#(#input_type),*) -> #ret_type
     };
 
-    let hookable_name = args.name;
+    let hookable_name = strip_in_release(stored_name(args.name.value()).into_token_stream());
 
     let args_name_list = gen_args_name_list(&input_fn);
 
     let mut inner_fn = input_fn.clone();
-    inner_fn.sig.ident = format_ident!("__hookable_inner");
-    let fn_sig = &input_fn.sig;
+    inner_fn.sig.ident = inner_ident.clone();
+    inner_fn.vis = syn::Visibility::Inherited;
+    inner_fn.attrs.clear();
+    let fn_attrs = &input_fn.attrs;
+    let fn_vis = &input_fn.vis;
+    // Arguments are only passed through, `mut` is kept for the inner function only.
+    let mut fn_sig = input_fn.sig.clone();
+    for arg in fn_sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat_type) = arg
+            && let syn::Pat::Ident(pat_ident) = &mut *pat_type.pat
+        {
+            pat_ident.mutability = None;
+        }
+    }
+    let mut slow_sig = fn_sig.clone();
+    slow_sig.ident = format_ident!("__hookable_slow");
 
     let unpack_list: proc_macro2::TokenStream = (0..input_fn.sig.inputs.len())
         .map(|i| {
@@ -140,41 +331,469 @@ pub fn hookable(args: TokenStream, input: TokenStream) -> TokenStream {
         })
         .collect();
 
+    let debug_only = args.debug_only;
+
+    // The caller location of each call, passed to hooks.
+    let (caller_attr, call_location) = if args.track_caller {
+        (
+            quote! { #[track_caller] },
+            quote! { ::core::option::Option::Some(::core::panic::Location::caller()) },
+        )
+    } else {
+        (quote! {}, quote! { ::core::option::Option::None })
+    };
+
+    // The arguments passed through the hook chain.
+    let (args_type, args_type_static, args_value, compose_func) = if args.by_ref {
+        (
+            quote! { &mut ::safe_hook::ByRef<(#(#input_type,)*)> },
+            quote! { &'static mut ::safe_hook::ByRef<(#(#input_type_with_static_lifetime,)*)> },
+            quote! { &mut ::safe_hook::ByRef::new((#args_name_list)) },
+            quote! {
+                |args| {
+                    let args = args.take();
+                    #inner_ident(#unpack_list)
+                }
+            },
+        )
+    } else {
+        (
+            quote! { (#(#input_type,)*) },
+            quote! { (#(#input_type_with_static_lifetime,)*) },
+            quote! { (#args_name_list) },
+            quote! { |args| #inner_ident(#unpack_list) },
+        )
+    };
+    let ret_type_name = strip_in_release(quote! { std::any::type_name::<#ret_type>() });
+    let args_type_name = strip_in_release(quote! { std::any::type_name::<#args_type_static>() });
+
+    let cfg_attr = if debug_only {
+        quote! { #[cfg(debug_assertions)] }
+    } else {
+        quote! {}
+    };
+
     // 原样返回函数代码
     let generated = quote! {
-        #fn_sig {
-            #inner_fn
+        #cfg_attr
+        #[doc(hidden)]
+        #inner_fn
 
-            use ::safe_hook::HookableFuncMetadata;
-            use ::core::sync::atomic::AtomicBool;
-            use ::std::sync::LazyLock;
-            use ::std::sync::atomic::Ordering;
+        #cfg_attr
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        static #flag_ident: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
 
-            type SelfFunc #generics = #func_type;
+        #cfg_attr
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        #fn_vis static #meta_ident: ::std::sync::LazyLock<::safe_hook::HookableFuncMetadata> =
+            ::std::sync::LazyLock::new(|| {
+                use ::safe_hook::HookableFuncMetadata;
 
-            static FLAG: AtomicBool = AtomicBool::new(false);
-            static META: LazyLock<HookableFuncMetadata> = LazyLock::new(|| {
+                fn __hookable_compose(
+                    hooks: &[::std::sync::Arc<dyn ::safe_hook::HookDyn>],
+                    options: &::safe_hook::ComposeOptions,
+                ) -> ::std::boxed::Box<dyn ::std::any::Any + Send + Sync> {
+                    ::safe_hook::compose_chain::<#ret_type, #args_type_static>(#compose_func, hooks, options)
+                }
                 let metadata = unsafe {
                     HookableFuncMetadata::new(
                         #hookable_name.to_string(),
                         #input_fn_ident as *const (),
                         (
                             std::any::TypeId::of::<#ret_type>(),
-                            std::any::TypeId::of::<(#(#input_type_with_static_lifetime),*)>(),
+                            std::any::TypeId::of::<#args_type_static>(),
                         ),
-                        &FLAG,
+                        (#ret_type_name, #args_type_name),
+                        (
+                            ::safe_hook::type_fingerprint::<#ret_type>(),
+                            ::safe_hook::type_fingerprint::<#args_type_static>(),
+                        ),
+                        &#flag_ident,
+                        #invoke,
+                        __hookable_compose,
+                        {
+                            use ::safe_hook::{ProbeClone as _, ProbeNotClone as _};
+                            (&&::safe_hook::CloneProbe::<#args_type_static>::new())
+                                .clone_fn()
+                                .map(|clone_args| {
+                                    ::std::boxed::Box::new(clone_args)
+                                        as ::std::boxed::Box<dyn ::std::any::Any + Send + Sync>
+                                })
+                        },
+                        {
+                            use ::safe_hook::{ProbeNotResult as _, ProbeResult as _};
+                            ::std::boxed::Box::new(
+                                (&&::safe_hook::ErrProbe::<#ret_type>::new()).is_err_fn(),
+                            )
+                        },
                     )
                 };
+                #with_json
+                #with_version
                 metadata
             });
-            ::safe_hook::inventory::submit! {
-                ::safe_hook::HookableFuncRegistry::new(&META)
+
+        #cfg_attr
+        #(#fn_attrs)*
+        #caller_attr
+        #fn_vis #fn_sig {
+            use ::std::sync::atomic::Ordering;
+
+            type SelfFunc #generics = #func_type;
+
+            #submit
+            #wasm_register
+            // Keep the hooked path out of line, so the fast path stays small enough to inline.
+            #[cold]
+            #[inline(never)]
+            #caller_attr
+            #slow_sig {
+                ::safe_hook::call_with_hook::<#ret_type, #args_type>(&#meta_ident, #args_value, #call_location)
+            }
+            if !#flag_ident.load(Ordering::Acquire) {
+                return ::safe_hook::call_fast_path(&#meta_ident, || #inner_ident(#args_name_list));
+            }
+            __hookable_slow(#args_name_list)
+        }
+    };
+    let generated = if debug_only {
+        quote! {
+            #generated
+            #[cfg(not(debug_assertions))]
+            #input_fn
+        }
+    } else {
+        generated
+    };
+    quote! {
+        ::safe_hook::__if_stripped! {
+            { #input_fn }
+            { #generated }
+        }
+    }
+    .into()
+}
+
+/// The name of the metadata generated next to a hookable function.
+fn hookable_meta_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("__hookable_{}_meta", ident)
+}
+
+/// This macro is used on a trait to generate a proxy type implementing it,
+/// whose every method is a hookable function delegating to the inner object.
+/// The trait itself is emitted unchanged.
+///
+/// The proxy of `Trait` is named `TraitProxy<T>`, with the visibility of the trait,
+/// and implements `Trait` for any `T: Trait`.
+/// The hookable function of a method is named `{prefix}::{method}`,
+/// where the prefix is the argument of the macro, or the name of the trait by default.
+/// Hooks receive the arguments without the receiver, like functions marked with [macro@hookable].
+/// Hooks are shared by all proxies of the trait, whatever the inner type,
+/// use `safe_hook::HookExt::for_instance` to hook a single proxy,
+/// keyed by its address, or by the key given to `TraitProxy::with_key`.
+///
+/// With the `safe_hook_strip` cfg, the proxy only delegates to the inner object.
+///
+/// Not Supported:
+/// - traits with generics, associated types or constants
+/// - methods without a `&self` or `&mut self` receiver
+/// - methods with generic types, `impl Trait` arguments or `async`
+/// - methods returning references
+///
+/// # Examples:
+/// ```ignore
+/// #[hookable_trait("users")]
+/// trait UserRepository {
+///     fn find(&self, id: u64) -> Option<String>;
+///     fn insert(&mut self, id: u64, name: String);
+/// }
+///
+/// let mut repository = UserRepositoryProxy::new(InMemoryRepository::default());
+/// lookup_hookable("users::find").unwrap().add_hook(Arc::new(cache)).unwrap();
+/// repository.find(1);
+/// ```
+#[proc_macro_attribute]
+pub fn hookable_trait(args: TokenStream, input: TokenStream) -> TokenStream {
+    let prefix = if args.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(args as LitStr))
+    };
+    let input_trait = parse_macro_input!(input as ItemTrait);
+
+    if !input_trait.generics.params.is_empty() || input_trait.generics.where_clause.is_some() {
+        panic!("hookable_trait cannot be used with generic traits");
+    }
+    let trait_ident = &input_trait.ident;
+    let trait_vis = &input_trait.vis;
+    let proxy_ident = format_ident!("{}Proxy", trait_ident);
+    let prefix = prefix.map_or_else(|| trait_ident.to_string(), |prefix| prefix.value());
+
+    let mut methods = Vec::new();
+    for item in input_trait.items.iter() {
+        let syn::TraitItem::Fn(method) = item else {
+            panic!(
+                "hookable_trait only supports methods, found '{}'",
+                item.to_token_stream()
+            );
+        };
+        methods.push(gen_proxy_method(&prefix, &method.sig));
+    }
+
+    let proxy_doc = format!(
+        "A proxy of [`{}`] whose methods are hookable functions named `{}::{{method}}`.",
+        trait_ident, prefix
+    );
+    quote! {
+        #input_trait
+
+        #[doc = #proxy_doc]
+        #trait_vis struct #proxy_ident<T> {
+            inner: T,
+            key: ::core::option::Option<::safe_hook::InstanceKey>,
+        }
+
+        impl<T> #proxy_ident<T> {
+            /// Wrap `inner`, calling its methods through hooks.
+            /// The proxy is keyed by its address.
+            pub fn new(inner: T) -> Self {
+                Self { inner, key: ::core::option::Option::None }
+            }
+
+            /// Wrap `inner`, calling its methods through hooks.
+            /// The proxy is keyed by `key`, which is kept when the proxy is moved.
+            pub fn with_key(inner: T, key: impl ::core::convert::Into<::safe_hook::InstanceKey>) -> Self {
+                Self { inner, key: ::core::option::Option::Some(key.into()) }
+            }
+
+            /// Get the key of the proxy, passed to hooks of its methods.
+            pub fn instance_key(&self) -> ::safe_hook::InstanceKey {
+                self.key.unwrap_or_else(|| ::safe_hook::InstanceKey::of(self))
             }
-            if !FLAG.load(Ordering::Acquire) {
-                return __hookable_inner(#args_name_list);
+
+            /// Get the inner object, calling it directly bypasses hooks.
+            pub fn get_ref(&self) -> &T {
+                &self.inner
+            }
+
+            /// Get the inner object mutably, calling it directly bypasses hooks.
+            pub fn get_mut(&mut self) -> &mut T {
+                &mut self.inner
+            }
+
+            /// Unwrap the inner object.
+            pub fn into_inner(self) -> T {
+                self.inner
             }
-            ::safe_hook::call_with_hook::<#ret_type, (#(#input_type),*)>(|args| __hookable_inner(#unpack_list), &META, (#args_name_list))
         }
+
+        impl<T: #trait_ident> #trait_ident for #proxy_ident<T> {
+            #(#methods)*
+        }
+    }
+    .into()
+}
+
+/// Generate the method of the proxy generated by [macro@hookable_trait].
+fn gen_proxy_method(prefix: &str, sig: &syn::Signature) -> proc_macro2::TokenStream {
+    let method_ident = &sig.ident;
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        panic!(
+            "hookable_trait cannot be used with generic method '{}'",
+            method_ident
+        );
+    }
+    if sig.asyncness.is_some() || sig.variadic.is_some() {
+        panic!(
+            "hookable_trait cannot be used with method '{}'",
+            method_ident
+        );
+    }
+    match sig.receiver() {
+        Some(receiver) if receiver.reference.is_some() => {}
+        _ => panic!(
+            "hookable_trait requires method '{}' to take &self or &mut self",
+            method_ident
+        ),
+    }
+
+    // Arguments are renamed, as patterns are only allowed in the implementation.
+    let mut arg_names = Vec::new();
+    let mut arg_types = Vec::new();
+    for (i, arg) in sig.inputs.iter().enumerate() {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            if matches!(&*pat_type.ty, syn::Type::ImplTrait(_)) {
+                panic!(
+                    "hookable_trait cannot be used with impl Trait arguments of '{}'",
+                    method_ident
+                );
+            }
+            arg_names.push(format_ident!("__arg{}", i));
+            arg_types.push((*pat_type.ty).clone());
+        }
+    }
+    let arg_types_static = arg_types.iter().map(with_static_lifetime);
+    let ret_type = match &sig.output {
+        syn::ReturnType::Default => quote! { () },
+        syn::ReturnType::Type(_, ty) => quote! { #ty },
     };
-    generated.into()
+    if has_borrow(ret_type.clone()) {
+        panic!(
+            "hookable_trait cannot be used with method '{}' returning references",
+            method_ident
+        );
+    }
+
+    let mut proxy_sig = sig.clone();
+    for (arg, name) in proxy_sig
+        .inputs
+        .iter_mut()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => Some(pat_type),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .zip(arg_names.iter())
+    {
+        *arg.pat = syn::parse_quote! { #name };
+    }
+    let delegate = if sig.unsafety.is_some() {
+        quote! { unsafe { self.inner.#method_ident(#(#arg_names),*) } }
+    } else {
+        quote! { self.inner.#method_ident(#(#arg_names),*) }
+    };
+    let hookable_name =
+        strip_in_release(stored_name(format!("{}::{}", prefix, method_ident)).into_token_stream());
+    let submit = gen_submit(&format_ident!("META"));
+    let wasm_register = gen_wasm_register(&format_ident!("META"));
+    quote! {
+        #proxy_sig {
+            ::safe_hook::__if_stripped! {
+                { #delegate }
+                {{
+                    use ::safe_hook::HookableFuncMetadata;
+                    use ::core::sync::atomic::AtomicBool;
+                    use ::std::sync::LazyLock;
+
+                    static FLAG: AtomicBool = AtomicBool::new(false);
+                    static META: LazyLock<HookableFuncMetadata> = LazyLock::new(|| {
+                        ::safe_hook::callback_metadata::<#ret_type, (#(#arg_types_static,)*)>(
+                            #hookable_name.to_string(),
+                            &FLAG,
+                        )
+                    });
+                    #submit
+                    #wasm_register
+                    let instance = self.instance_key();
+                    ::safe_hook::call_callback::<#ret_type, (#(#arg_types,)*)>(
+                        &META,
+                        (#(#arg_names,)*),
+                        &mut |(#(#arg_names,)*)| #delegate,
+                        ::core::option::Option::None,
+                        ::core::option::Option::Some(instance),
+                    )
+                }}
+            }
+        }
+    }
+}
+
+/// This macro marks a test changing hooks, like `#[test]`,
+/// restoring the hooks of every hookable function after the test, even if it panics.
+///
+/// The body runs while holding a `safe_hook::testing::HookGuard`,
+/// so tests marked with this attribute also run one at a time.
+/// Other attributes like `#[should_panic]` are kept.
+///
+/// # Examples:
+/// ```ignore
+/// #[hook_test]
+/// fn test_with_stub() {
+///     lookup_hookable("now").unwrap().add_hook(Arc::new(Stub::returning(0))).unwrap();
+///     assert_eq!(now(), 0);
+///     // No need to remove the hook.
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn hook_test(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = proc_macro2::TokenStream::from(args);
+        return syn::Error::new_spanned(args, "hook_test does not take arguments")
+            .to_compile_error()
+            .into();
+    }
+    let input_fn = parse_macro_input!(input as ItemFn);
+    let attrs = &input_fn.attrs;
+    let vis = &input_fn.vis;
+    let sig = &input_fn.sig;
+    let block = &input_fn.block;
+    quote! {
+        #[test]
+        #(#attrs)*
+        #vis #sig {
+            let __hook_guard = ::safe_hook::testing::HookGuard::new();
+            #block
+        }
+    }
+    .into()
+}
+
+/// This macro registers hookable functions explicitly, by their paths,
+/// for targets where the inventory of hookable functions is not collected.
+///
+/// Registering a function twice, or a function already collected, has no effect.
+/// Functions marked with `debug_only` can only be registered in debug builds,
+/// and methods of `hookable_trait` proxies are registered on their first call.
+/// With the `safe_hook_strip` cfg, nothing is registered.
+///
+/// # Examples:
+/// ```ignore
+/// fn main() {
+///     register_hookables!(add, math::concat);
+///     lookup_hookable("concat").unwrap().add_hook(hook).unwrap();
+/// }
+/// ```
+#[proc_macro]
+pub fn register_hookables(input: TokenStream) -> TokenStream {
+    let paths = parse_macro_input!(
+        input with syn::punctuated::Punctuated::<syn::Path, Token![,]>::parse_terminated
+    );
+    let metas = paths.into_iter().map(|mut path| {
+        let last = path.segments.last_mut().expect("Path cannot be empty");
+        last.ident = hookable_meta_ident(&last.ident);
+        path
+    });
+    quote! {
+        {
+            ::safe_hook::__if_stripped! {
+                {}
+                { #(::safe_hook::register_static(&#metas);)* }
+            }
+        }
+    }
+    .into()
+}
+
+/// This macro gets the metadata of a hookable function by its path, without looking up its name,
+/// or `None` with the `safe_hook_strip` cfg.
+///
+/// # Examples:
+/// ```ignore
+/// let meta = hookable_meta!(math::add).unwrap();
+/// meta.add_hook(hook).unwrap();
+/// ```
+#[proc_macro]
+pub fn hookable_meta(input: TokenStream) -> TokenStream {
+    let mut path = parse_macro_input!(input as syn::Path);
+    let last = path.segments.last_mut().expect("Path cannot be empty");
+    last.ident = hookable_meta_ident(&last.ident);
+    quote! {
+        ::safe_hook::__if_stripped! {
+            { ::core::option::Option::<&'static ::safe_hook::HookableFuncMetadata>::None }
+            { ::core::option::Option::Some::<&'static ::safe_hook::HookableFuncMetadata>(&#path) }
+        }
+    }
+    .into()
 }
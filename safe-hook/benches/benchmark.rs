@@ -72,9 +72,9 @@ fn benchmark(c: &mut Criterion) {
         b.iter(|| add_hookable_call(black_box(1), black_box(2)))
     });
 
-    add_metadata.remove_hook(hook1.as_ref());
-    add_metadata.remove_hook(hook2.as_ref());
-    add_metadata.remove_hook(hook3.as_ref());
+    add_metadata.remove_hook(hook1.as_ref()).unwrap();
+    add_metadata.remove_hook(hook2.as_ref()).unwrap();
+    add_metadata.remove_hook(hook3.as_ref()).unwrap();
     c.bench_function("add_hookable(hooks removed)", |b| {
         b.iter(|| add_hookable_call(black_box(1), black_box(2)))
     });
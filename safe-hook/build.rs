@@ -0,0 +1,44 @@
+use std::process::Command;
+
+fn main() {
+    // Plugins must be built with the same compiler, target and features as the host,
+    // see the `plugin` module.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_default();
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let stripped = std::env::var_os("CARGO_CFG_SAFE_HOOK_STRIP").is_some();
+    let mut features = std::env::vars()
+        .filter_map(|(name, _)| Some(name.strip_prefix("CARGO_FEATURE_")?.to_lowercase()))
+        .chain(stripped.then(|| "safe_hook_strip".to_string()))
+        .collect::<Vec<_>>();
+    features.sort();
+    println!(
+        "cargo:rustc-env=SAFE_HOOK_PLUGIN_ABI={} {} [{}]",
+        rustc_version,
+        target,
+        features.join(",")
+    );
+
+    // `lookup_by_name`: hookable functions are registered and can be looked up by name,
+    // `original_names`: and their names are kept verbatim.
+    // `safe_hook_strip`, `strip-names` and `hashed-names` remove names,
+    // so tests depending on names check these instead.
+    println!("cargo:rustc-check-cfg=cfg(safe_hook_strip, lookup_by_name, original_names)");
+    let enabled = |feature: &str| features.iter().any(|f| f == feature);
+    let names_stripped =
+        enabled("strip_names") && std::env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_none();
+    if !stripped && !names_stripped {
+        println!("cargo:rustc-cfg=lookup_by_name");
+        if !enabled("hashed_names") {
+            println!("cargo:rustc-cfg=original_names");
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -0,0 +1,56 @@
+/*
+ * C API to manage the hooks of a Rust library using safe-hook,
+ * built with the `ffi` feature. See the `ffi` module for details.
+ */
+#ifndef SAFE_HOOK_H
+#define SAFE_HOOK_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The hookable function or the hook is not found. */
+#define SAFE_HOOK_NOT_FOUND (-1)
+/* A name is null or not valid UTF-8. */
+#define SAFE_HOOK_INVALID_ARGUMENT (-2)
+/* Hooks cannot be changed, see `freeze` and `require_capability`. */
+#define SAFE_HOOK_REFUSED (-3)
+
+typedef void (*safe_hook_list_callback)(const char *name, size_t hooks, void *user_data);
+typedef void (*safe_hook_list_hooks_callback)(uint64_t id, int32_t priority, bool enabled,
+                                              const char *description, void *user_data);
+
+/* Get the number of hooks attached to the hookable function `name`. */
+int safe_hook_lookup(const char *name);
+
+/* Call `callback` for each hookable function, returning their number. */
+int safe_hook_list(safe_hook_list_callback callback, void *user_data);
+
+/* Call `callback` for each hook of the hookable function `name`, returning their number. */
+int safe_hook_list_hooks(const char *name, safe_hook_list_hooks_callback callback,
+                         void *user_data);
+
+/* Remove all hooks of the hookable function `name`, returning the number of removed hooks. */
+int safe_hook_remove_by_name(const char *name);
+
+/* Remove the hook `id` from the hookable function `name`. */
+int safe_hook_remove(const char *name, uint64_t id);
+
+/* Enable or disable the hook `id` of the hookable function `name`. */
+int safe_hook_set_enabled(const char *name, uint64_t id, bool enabled);
+
+/* Disable all hooks of all hookable functions. */
+int safe_hook_disable_all(void);
+
+/* Enable hooks again after safe_hook_disable_all. */
+int safe_hook_enable_all(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SAFE_HOOK_H */
@@ -0,0 +1,100 @@
+use crate::AddHookError;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUIRED: AtomicBool = AtomicBool::new(false);
+static FROZEN: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static AUTHORIZED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The capability to change hooks once [`require_capability`] is called.
+///
+/// There is only one, it cannot be cloned, and it only allows changes inside [`Capability::scope`],
+/// so code that is not given it cannot rewire hookable functions.
+#[derive(Debug)]
+pub struct Capability {
+    _private: (),
+}
+
+impl Capability {
+    /// Allow changing hooks on the current thread while `f` runs.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Exit;
+        impl Drop for Exit {
+            fn drop(&mut self) {
+                AUTHORIZED.with(|depth| depth.set(depth.get() - 1));
+            }
+        }
+        AUTHORIZED.with(|depth| depth.set(depth.get() + 1));
+        let _exit = Exit;
+        f()
+    }
+}
+
+/// Require the returned [`Capability`] to change hooks from now on, for hardened deployments.
+/// Call it once at startup and keep the capability where only trusted code can use it.
+///
+/// Afterwards, changing hooks outside [`Capability::scope`] fails:
+/// adding, removing, clearing, restoring, toggling or reordering hooks,
+/// holding hooks with [`add_hook_pending`](crate::add_hook_pending), adding global hooks
+/// or committing a [`HookTransaction`](crate::HookTransaction) returns [`AddHookError::Unauthorized`].
+///
+/// Returns `None` if it was already called, so the capability cannot be minted again.
+/// It cannot be undone.
+/// # Example
+/// ```no_run
+/// use safe_hook::{lookup_hookable, require_capability};
+///
+/// let capability = require_capability().unwrap();
+/// let add = lookup_hookable("add").unwrap();
+/// capability.scope(|| add.add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 }))
+///     .unwrap();
+/// // Without the capability, hooks cannot be added anymore.
+/// assert!(add.add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) }).is_err());
+/// ```
+pub fn require_capability() -> Option<Capability> {
+    if REQUIRED.swap(true, Ordering::AcqRel) {
+        return None;
+    }
+    Some(Capability { _private: () })
+}
+
+/// Forbid changing hooks from now on, e.g. once they are wired at startup.
+///
+/// Afterwards, adding, removing, clearing, restoring, toggling or reordering hooks
+/// or committing a [`HookTransaction`](crate::HookTransaction) returns [`AddHookError::RegistryFrozen`],
+/// even with the [`Capability`]. Hooks already attached keep being called.
+///
+/// It cannot be undone.
+/// # Example
+/// ```no_run
+/// use safe_hook::{freeze, lookup_hookable, AddHookError};
+///
+/// let add = lookup_hookable("add").unwrap();
+/// add.add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 }).unwrap();
+/// freeze();
+/// let result = add.add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) });
+/// assert!(matches!(result, Err(AddHookError::RegistryFrozen)));
+/// ```
+pub fn freeze() {
+    FROZEN.store(true, Ordering::Release);
+}
+
+/// Check whether hooks can no longer be changed, see [`freeze`].
+pub fn is_frozen() -> bool {
+    FROZEN.load(Ordering::Acquire)
+}
+
+/// Check whether hooks can be changed on the current thread,
+/// see [`freeze`] and [`require_capability`].
+pub(crate) fn check() -> Result<(), AddHookError> {
+    if is_frozen() {
+        return Err(AddHookError::RegistryFrozen);
+    }
+    if REQUIRED.load(Ordering::Acquire) && AUTHORIZED.with(|depth| depth.get()) == 0 {
+        return Err(AddHookError::Unauthorized);
+    }
+    Ok(())
+}
@@ -0,0 +1,866 @@
+use crate::context::current_instance;
+use crate::{Hook, HookId, HookableFuncMetadata, InstanceKey, Observer, iter_hookables, lock};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Forward the metadata and lifecycle methods of [`Hook`] to the wrapped hook in `self.$field`,
+/// for wrappers that only change when the wrapped hook is called.
+/// With `except attached`, [`Hook::attached`] and [`Hook::detached`] are left to the wrapper.
+macro_rules! forward_to_inner {
+    ($field:ident) => {
+        forward_to_inner!($field, except attached);
+
+        fn attached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+            self.$field.attached(hookable, id)
+        }
+
+        fn detached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+            self.$field.detached(hookable, id)
+        }
+    };
+    ($field:ident, except attached) => {
+        fn description(&self) -> String {
+            self.$field.description()
+        }
+
+        fn runs_before(&self) -> Vec<String> {
+            self.$field.runs_before()
+        }
+
+        fn runs_after(&self) -> Vec<String> {
+            self.$field.runs_after()
+        }
+
+        fn version(&self) -> Option<String> {
+            self.$field.version()
+        }
+
+        fn on_attach(&self, hookable: &HookableFuncMetadata) {
+            self.$field.on_attach(hookable)
+        }
+
+        fn on_detach(&self, hookable: &HookableFuncMetadata) {
+            self.$field.on_detach(hookable)
+        }
+    };
+}
+
+/// A simpler trait for hooks that only inspect the arguments before the call,
+/// and optionally return early, e.g. on a cache hit or an authorization failure.
+///
+/// Wrap it in [`Pre`] to attach it to a hookable function.
+pub trait PreHook: Send + Sync + 'static {
+    /// The arguments type of the hook, see [`Hook::Args`].
+    type Args<'b>;
+
+    /// The result type of the hook, see [`Hook::Result`].
+    type Result;
+
+    /// Called before the next hook or the original function.
+    /// Return [`ControlFlow::Continue`] with the (possibly modified) arguments to continue the call,
+    /// or [`ControlFlow::Break`] with a result to return it without calling the rest of the chain.
+    fn before<'a>(&'a self, args: Self::Args<'a>) -> ControlFlow<Self::Result, Self::Args<'a>>;
+
+    /// A human-readable description of the hook, see [`Hook::description`].
+    fn description(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+/// Adapts a [`PreHook`] into a [`Hook`].
+/// # Example
+/// ```rust
+/// use std::ops::ControlFlow;
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, Pre, PreHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("pre-div")]
+/// fn div(left: i64, right: i64) -> i64 {
+///     left / right
+/// }
+///
+/// struct NonZero;
+///
+/// impl PreHook for NonZero {
+///     type Args<'a> = (i64, i64);
+///     type Result = i64;
+///     fn before(&self, args: (i64, i64)) -> ControlFlow<i64, (i64, i64)> {
+///         if args.1 == 0 {
+///             ControlFlow::Break(0)
+///         } else {
+///             ControlFlow::Continue(args)
+///         }
+///     }
+/// }
+///
+/// lookup_hookable("pre-div").unwrap().add_hook(Arc::new(Pre(NonZero))).unwrap();
+/// assert_eq!(div(6, 0), 0);
+/// assert_eq!(div(6, 3), 2);
+/// ```
+pub struct Pre<H>(pub H);
+
+impl<H: PreHook> Hook for Pre<H> {
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        match self.0.before(args) {
+            ControlFlow::Continue(args) => next(args),
+            ControlFlow::Break(result) => result,
+        }
+    }
+
+    fn description(&self) -> String {
+        self.0.description()
+    }
+}
+
+/// A simpler trait for hooks that only observe calls, e.g. for metrics or logging.
+/// They are called after the original function with the arguments and the result,
+/// and never change the behavior of the call.
+///
+/// Wrap it in [`Post`] to attach it to a hookable function.
+pub trait PostHook: Send + Sync + 'static {
+    /// The arguments type of the hook, see [`Hook::Args`].
+    type Args<'b>;
+
+    /// The result type of the hook, see [`Hook::Result`].
+    type Result;
+
+    /// Called after the next hook or the original function has returned.
+    fn after<'a>(&'a self, args: &Self::Args<'a>, result: &Self::Result);
+
+    /// A human-readable description of the hook, see [`Hook::description`].
+    fn description(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+/// Adapts a [`PostHook`] into a [`Hook`].
+///
+/// The arguments are moved into the next hook, so they are cloned before the call
+/// to be passed to [`PostHook::after`].
+///
+/// If all hooks attached to a hookable function are [`Post`] hooks,
+/// the arguments are cloned only once, and the hooks are called one by one after the original function
+/// instead of wrapping each other.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicI64, Ordering};
+/// use safe_hook::{lookup_hookable, Post, PostHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("post-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// #[derive(Default)]
+/// struct Total(AtomicI64);
+///
+/// impl PostHook for Total {
+///     type Args<'a> = (i64, i64);
+///     type Result = i64;
+///     fn after(&self, _args: &(i64, i64), result: &i64) {
+///         self.0.fetch_add(*result, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let total = Arc::new(Post(Total::default()));
+/// lookup_hookable("post-add").unwrap().add_hook(total.clone()).unwrap();
+/// assert_eq!(add(1, 2), 3);
+/// assert_eq!(add(3, 4), 7);
+/// assert_eq!(total.0.0.load(Ordering::Relaxed), 10);
+/// ```
+pub struct Post<H>(pub H);
+
+impl<H: PostHook> Hook for Post<H>
+where
+    for<'a> H::Args<'a>: Clone,
+{
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        let result = next(args.clone());
+        self.0.after(&args, &result);
+        result
+    }
+
+    fn description(&self) -> String {
+        self.0.description()
+    }
+
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>> {
+        type Args<H> = <H as PostHook>::Args<'static>;
+        let observer = Observer::<Args<H>, H::Result> {
+            clone_args: Args::<H>::clone,
+            observe: Box::new(move |args, result| {
+                // SAFETY: the hook is kept alive by the observer, and the reference does not
+                // outlive the call. Lifetimes in the arguments are erased, see `HookDyn::compose`.
+                let this = unsafe { &*Arc::as_ptr(&self) };
+                this.0.after(args, result)
+            }),
+        };
+        Some(Box::new(observer))
+    }
+}
+
+/// Conversion of a closure into a [`Hook`], see [`HookableFuncMetadata::add_hook_fn`].
+///
+/// It is implemented for closures `Fn(A, &dyn Fn(A) -> R) -> R`,
+/// where `A` is the arguments tuple and `R` is the result of the hookable function.
+///
+/// [`HookableFuncMetadata::add_hook_fn`]: crate::HookableFuncMetadata::add_hook_fn
+pub trait IntoHook<A, R> {
+    /// The hook created from the closure.
+    type Hook: Hook<Result = R>;
+
+    /// Convert the closure into a hook.
+    fn into_hook(self) -> Self::Hook;
+}
+
+impl<A, R, F> IntoHook<A, R> for F
+where
+    F: Fn(A, &dyn Fn(A) -> R) -> R + Send + Sync + 'static,
+    A: 'static,
+    R: 'static,
+{
+    type Hook = FnHook<A, R, F>;
+
+    fn into_hook(self) -> Self::Hook {
+        FnHook {
+            f: self,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A [`Hook`] calling a closure, created by [`IntoHook::into_hook`].
+///
+/// The arguments can only contain `'static` references,
+/// implement [`Hook`] to hook functions with borrowed arguments.
+pub struct FnHook<A, R, F> {
+    f: F,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R, F> Hook for FnHook<A, R, F>
+where
+    F: Fn(A, &dyn Fn(A) -> R) -> R + Send + Sync + 'static,
+    A: 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        (self.f)(args, next)
+    }
+}
+
+/// A [`Hook`] calling a closure with mutable access to some state
+/// after the next hook or the original function has returned,
+/// e.g. for hooks that count or accumulate.
+///
+/// The closure is called with the state, the arguments and the result.
+/// The state is only locked while the closure runs, not during `next`,
+/// so recursive and concurrent calls of the hooked function are not blocked by it.
+/// Like [`Post`], the arguments are cloned before the call to be passed to the closure,
+/// and like [`FnHook`], they can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, StatefulHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("stateful-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let sum = Arc::new(StatefulHook::new(0, |sum: &mut i64, _args: &(i64, i64), result: &i64| {
+///     *sum += result;
+/// }));
+/// lookup_hookable("stateful-add").unwrap().add_hook(sum.clone()).unwrap();
+/// add(1, 2);
+/// add(3, 4);
+/// assert_eq!(*sum.state(), 10);
+/// ```
+pub struct StatefulHook<S, A, R, F> {
+    state: Mutex<S>,
+    f: F,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<S, A, R, F> StatefulHook<S, A, R, F>
+where
+    F: Fn(&mut S, &A, &R),
+{
+    /// Create a hook with the initial `state`, calling `f` with the state, the arguments and the result.
+    pub fn new(state: S, f: F) -> Self {
+        Self {
+            state: Mutex::new(state),
+            f,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Lock the state, e.g. to read what the hook accumulated.
+    pub fn state(&self) -> MutexGuard<'_, S> {
+        lock::lock(&self.state)
+    }
+}
+
+impl<S, A, R, F> Hook for StatefulHook<S, A, R, F>
+where
+    S: Send + 'static,
+    F: Fn(&mut S, &A, &R) + Send + Sync + 'static,
+    A: Clone + 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let result = next(args.clone());
+        (self.f)(&mut lock::lock(&self.state), &args, &result);
+        result
+    }
+}
+
+/// A [`Hook`] wrapper that only calls the inner hook if `predicate` returns `true` for the arguments,
+/// otherwise the call goes directly to `next`.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{ConditionalHook, fn_hook, lookup_hookable};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("conditional-balance")]
+/// fn balance(user_id: u64) -> i64 {
+///     100
+/// }
+///
+/// let hook = ConditionalHook::new(
+///     fn_hook!(|args: (u64,), next| -> i64 { next(args) * 2 }),
+///     |args: &(u64,)| args.0 == 42,
+/// );
+/// lookup_hookable("conditional-balance").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(balance(1), 100);
+/// assert_eq!(balance(42), 200);
+/// ```
+pub struct ConditionalHook<H, P> {
+    hook: H,
+    predicate: P,
+}
+
+impl<H: Hook, P> ConditionalHook<H, P>
+where
+    P: for<'a> Fn(&H::Args<'a>) -> bool,
+{
+    /// Wrap `hook`, calling it only if `predicate` returns `true`.
+    pub fn new(hook: H, predicate: P) -> Self {
+        Self { hook, predicate }
+    }
+}
+
+impl<H: Hook, P> Hook for ConditionalHook<H, P>
+where
+    P: for<'a> Fn(&H::Args<'a>) -> bool + Send + Sync + 'static,
+{
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        if (self.predicate)(&args) {
+            self.hook.call(args, next)
+        } else {
+            next(args)
+        }
+    }
+
+    forward_to_inner!(hook);
+}
+
+/// Combinators for hooks, implemented for every [`Hook`].
+pub trait HookExt: Hook + Sized {
+    /// Call the hook only for the first call, see [`Take`].
+    fn once(self) -> Take<Self> {
+        self.take(1)
+    }
+
+    /// Call the hook only for the first `n` calls, see [`Take`].
+    fn take(self, n: usize) -> Take<Self> {
+        Take {
+            hook: self,
+            remaining: AtomicUsize::new(n),
+            attachments: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Call the hook only for one in every `n` calls, see [`Sampled`].
+    /// # Panics
+    /// Panics if `n` is zero.
+    fn sampled(self, n: usize) -> Sampled<Self> {
+        assert!(n > 0, "Sampling rate must not be zero");
+        Sampled {
+            hook: self,
+            rate: n,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Call the hook at most `per_second` times per second, see [`RateLimited`].
+    fn rate_limited(self, per_second: u32) -> RateLimited<Self> {
+        RateLimited {
+            hook: self,
+            per_second: per_second as f64,
+            bucket: Mutex::new((per_second as f64, Instant::now())),
+        }
+    }
+
+    /// Call the hook only for calls of methods on `instance`, see [`InstanceHook`].
+    fn for_instance(self, instance: impl Into<InstanceKey>) -> InstanceHook<Self> {
+        InstanceHook {
+            hook: self,
+            instance: instance.into(),
+        }
+    }
+}
+
+impl<H: Hook> HookExt for H {}
+
+/// A [`Hook`] wrapper that calls the inner hook only for calls of hookable methods
+/// on a single object, created by [`HookExt::for_instance`].
+/// Other calls go directly to `next`, so one connection or session can be instrumented
+/// without affecting the others.
+///
+/// Hookable methods are the methods of proxies generated by [`hookable_trait`](crate::hookable_trait).
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{HookExt, InstanceKey, fn_hook, hookable_trait, lookup_hookable};
+///
+/// #[hookable_trait("instance-session")]
+/// trait Session {
+///     fn send(&self, message: String) -> usize;
+/// }
+///
+/// struct Connection;
+///
+/// impl Session for Connection {
+///     fn send(&self, message: String) -> usize {
+///         message.len()
+///     }
+/// }
+///
+/// let first = SessionProxy::with_key(Connection, 1);
+/// let second = SessionProxy::with_key(Connection, 2);
+/// let hook = fn_hook!(|args: (String,), next| -> usize { next(args) * 10 }).for_instance(2);
+/// lookup_hookable("instance-session::send").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(first.send("hello".to_string()), 5);
+/// assert_eq!(second.send("hello".to_string()), 50);
+/// assert_eq!(second.instance_key(), InstanceKey::Key(2));
+/// ```
+pub struct InstanceHook<H> {
+    hook: H,
+    instance: InstanceKey,
+}
+
+impl<H> InstanceHook<H> {
+    /// The object the hook is called for.
+    pub fn instance(&self) -> InstanceKey {
+        self.instance
+    }
+}
+
+impl<H: Hook> Hook for InstanceHook<H> {
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        if current_instance() == Some(self.instance) {
+            self.hook.call(args, next)
+        } else {
+            next(args)
+        }
+    }
+
+    forward_to_inner!(hook);
+}
+
+/// A [`Hook`] wrapper that calls the inner hook only for a limited number of calls,
+/// created by [`HookExt::once`] and [`HookExt::take`].
+///
+/// Once spent, calls go directly to `next`, and the hook detaches itself
+/// from every hookable function it is attached to.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{HookExt, fn_hook, lookup_hookable};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("take-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let add_hookable = lookup_hookable("take-add").unwrap();
+/// let hook = fn_hook!(|args: (i64, i64), next| -> i64 { next(args) + 1 }).take(2);
+/// add_hookable.add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(add(1, 2), 4);
+/// assert_eq!(add(1, 2), 4);
+/// assert_eq!(add(1, 2), 3);
+/// assert!(add_hookable.list_hooks().is_empty());
+/// ```
+pub struct Take<H> {
+    hook: H,
+    remaining: AtomicUsize,
+    /// The hookable functions the hook is attached to, with the ids of the attachments.
+    attachments: Mutex<Vec<(&'static HookableFuncMetadata, HookId)>>,
+}
+
+impl<H> Take<H> {
+    /// The number of calls left before the hook is spent.
+    pub fn remaining(&self) -> usize {
+        self.remaining.load(Ordering::Acquire)
+    }
+}
+
+impl<H> Take<H> {
+    /// Detach the hook from every hookable function it is attached to, by the ids of the attachments,
+    /// so it is also detached when it is wrapped in another hook.
+    ///
+    /// The attachments are taken out first, so a refused removal is not retried by every later call.
+    /// The spent hook is left attached then, and only calls `next`.
+    fn detach(&self) {
+        let attachments = std::mem::take(&mut *lock::lock(&self.attachments));
+        for (target, id) in attachments {
+            if target.remove_hook_by_id(id).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl<H: Hook> Hook for Take<H> {
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        let taken = self
+            .remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+        match taken {
+            Ok(remaining) => {
+                let result = self.hook.call(args, next);
+                if remaining == 1 {
+                    self.detach();
+                }
+                result
+            }
+            Err(_) => {
+                self.detach();
+                next(args)
+            }
+        }
+    }
+
+    forward_to_inner!(hook, except attached);
+
+    fn attached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        if let Some(target) = iter_hookables().find(|target| std::ptr::eq(*target, hookable)) {
+            lock::lock(&self.attachments).push((target, id));
+        }
+        self.hook.attached(hookable, id)
+    }
+
+    fn detached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        lock::lock(&self.attachments)
+            .retain(|(target, attached)| !(std::ptr::eq(*target, hookable) && *attached == id));
+        self.hook.detached(hookable, id)
+    }
+}
+
+/// A [`Hook`] wrapper that calls the inner hook for one in every `n` calls,
+/// starting with the first call, created by [`HookExt::sampled`].
+/// Other calls go directly to `next`.
+///
+/// Useful to keep heavyweight diagnostic hooks attached in production.
+pub struct Sampled<H> {
+    hook: H,
+    rate: usize,
+    calls: AtomicUsize,
+}
+
+impl<H: Hook> Hook for Sampled<H> {
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        if self
+            .calls
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.rate)
+        {
+            self.hook.call(args, next)
+        } else {
+            next(args)
+        }
+    }
+
+    forward_to_inner!(hook);
+}
+
+/// A [`Hook`] wrapper that calls the inner hook at most `per_second` times per second,
+/// created by [`HookExt::rate_limited`]. Other calls go directly to `next`.
+///
+/// Calls are limited by a token bucket, which holds up to `per_second` tokens and starts full,
+/// so bursts of up to `per_second` calls are allowed. Useful to protect logs from flooding.
+pub struct RateLimited<H> {
+    hook: H,
+    per_second: f64,
+    /// The available tokens, and when they were last refilled.
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl<H> RateLimited<H> {
+    fn try_acquire(&self) -> bool {
+        let mut bucket = lock::lock(&self.bucket);
+        let (tokens, last) = &mut *bucket;
+        let now = Instant::now();
+        let elapsed = now.duration_since(*last).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.per_second).min(self.per_second);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<H: Hook> Hook for RateLimited<H> {
+    type Args<'b> = H::Args<'b>;
+    type Result = H::Result;
+
+    fn call<'a>(
+        &'a self,
+        args: Self::Args<'a>,
+        next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
+    ) -> Self::Result {
+        if self.try_acquire() {
+            self.hook.call(args, next)
+        } else {
+            next(args)
+        }
+    }
+
+    forward_to_inner!(hook);
+}
+
+/// A [`Hook`] caching the results of the hooked function by its arguments,
+/// so calls with cached arguments return without calling `next`.
+///
+/// By default the cache is unbounded and never expires,
+/// see [`MemoHook::capacity`] and [`MemoHook::ttl`].
+/// The arguments can only contain `'static` references, like [`FnHook`].
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use safe_hook::{lookup_hookable, MemoHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("memo-fib")]
+/// fn fib(n: u64) -> u64 {
+///     if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+/// }
+///
+/// let memo = MemoHook::<(u64,), u64>::new()
+///     .capacity(1000)
+///     .ttl(Duration::from_secs(60));
+/// lookup_hookable("memo-fib").unwrap().add_hook(Arc::new(memo)).unwrap();
+/// assert_eq!(fib(80), 23416728348467685);
+/// ```
+pub struct MemoHook<A, R> {
+    cache: Mutex<MemoCache<A, R>>,
+    capacity: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+struct MemoCache<A, R> {
+    entries: HashMap<A, (R, Instant)>,
+    /// Keys in insertion order, the oldest is evicted first.
+    order: VecDeque<A>,
+}
+
+impl<A: Hash + Eq + Clone, R: Clone> MemoHook<A, R> {
+    /// Create an empty, unbounded cache.
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(MemoCache {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            capacity: None,
+            ttl: None,
+        }
+    }
+
+    /// Keep at most `capacity` results, evicting the oldest ones.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Discard results older than `ttl`.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The number of cached results, including expired ones not discarded yet.
+    pub fn len(&self) -> usize {
+        lock::lock(&self.cache).entries.len()
+    }
+
+    /// Check whether no result is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard all cached results.
+    pub fn clear(&self) {
+        let mut cache = lock::lock(&self.cache);
+        cache.entries.clear();
+        cache.order.clear();
+    }
+
+    fn get(&self, args: &A) -> Option<R> {
+        let mut cache = lock::lock(&self.cache);
+        let (result, inserted) = cache.entries.get(args)?;
+        if self.ttl.is_some_and(|ttl| inserted.elapsed() > ttl) {
+            cache.entries.remove(args);
+            cache.order.retain(|key| key != args);
+            return None;
+        }
+        Some(result.clone())
+    }
+
+    fn insert(&self, args: A, result: R) {
+        if self.capacity == Some(0) {
+            return;
+        }
+        let mut cache = lock::lock(&self.cache);
+        let cache = &mut *cache;
+        if cache
+            .entries
+            .insert(args.clone(), (result, Instant::now()))
+            .is_some()
+        {
+            cache.order.retain(|key| *key != args);
+        }
+        cache.order.push_back(args);
+        while self
+            .capacity
+            .is_some_and(|capacity| cache.order.len() > capacity)
+        {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl<A: Hash + Eq + Clone, R: Clone> Default for MemoHook<A, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, R> Hook for MemoHook<A, R>
+where
+    A: Hash + Eq + Clone + Send + 'static,
+    R: Clone + Send + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        if let Some(result) = self.get(&args) {
+            return result;
+        }
+        // The cache is not locked while calling `next`, so recursive functions can be cached.
+        let result = next(args.clone());
+        self.insert(args, result.clone());
+        result
+    }
+}
+
+/// Build a [`Hook`] from a closure expression with explicit argument and result types,
+/// see [`IntoHook`].
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{fn_hook, lookup_hookable};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("fn-hook-sub")]
+/// fn sub(left: i64, right: i64) -> i64 {
+///     left - right
+/// }
+///
+/// let offset = 10;
+/// let hook = fn_hook!(move |(left, right): (i64, i64), next| -> i64 {
+///     next((left + offset, right))
+/// });
+/// lookup_hookable("fn-hook-sub").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(sub(3, 1), 12);
+/// ```
+#[macro_export]
+macro_rules! fn_hook {
+    (move |$args:tt : $args_ty:ty, $next:ident| -> $ret:ty $body:block) => {
+        $crate::IntoHook::<$args_ty, $ret>::into_hook(
+            move |$args: $args_ty, $next: &dyn Fn($args_ty) -> $ret| -> $ret { $body },
+        )
+    };
+    (|$args:tt : $args_ty:ty, $next:ident| -> $ret:ty $body:block) => {
+        $crate::IntoHook::<$args_ty, $ret>::into_hook(
+            |$args: $args_ty, $next: &dyn Fn($args_ty) -> $ret| -> $ret { $body },
+        )
+    };
+}
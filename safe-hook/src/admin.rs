@@ -0,0 +1,602 @@
+//! A control socket to manage hooks of a running process, available with the `admin` feature.
+//!
+//! [`AdminServer`] accepts connections on a TCP or Unix socket, and answers line-based commands,
+//! so instrumentation can be inspected and toggled without redeploying,
+//! e.g. with `nc localhost 7070` or `socat - UNIX-CONNECT:/run/app.sock`:
+//! - `list`: list the hookable functions, with their signature and number of hooks.
+//! - `hooks <hookable>`: list the hooks of a hookable function, in calling order.
+//! - `enable <hookable> <id>`, `disable <hookable> <id>`, `remove <hookable> <id>`: change a hook.
+//! - `disable-all`, `enable-all`: see [`disable_all`](crate::disable_all).
+//! - `stats [<hookable>]`: show call statistics, with the `stats` feature.
+//! - `help`, `quit`.
+//!
+//! The connection is closed after the first unknown command,
+//! so requests of other protocols cannot run commands,
+//! e.g. an HTTP request sent by a web page with a body of `disable-all`.
+//!
+//! The same operations are available as REST endpoints returning JSON, see [`handle_http`],
+//! served by [`AdminServer::bind_http`] or mounted into an existing HTTP server,
+//! e.g. with [`router`] and the `axum` feature.
+//! Requests changing hooks must send the [`ADMIN_HEADER`] header,
+//! which browsers only send cross-origin after a CORS preflight, so web pages cannot forge them.
+//!
+//! There is no authentication, bind to a local address or a Unix socket with restricted permissions.
+//! # Example
+//! ```rust
+//! use safe_hook::admin::{self, AdminServer};
+//! use std::io::{BufRead, BufReader, Write};
+//! use std::net::TcpStream;
+//!
+//! let server = AdminServer::bind_tcp("127.0.0.1:0").unwrap();
+//! let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+//! stream.write_all(b"help\n").unwrap();
+//! let mut line = String::new();
+//! BufReader::new(&stream).read_line(&mut line).unwrap();
+//! assert!(line.starts_with("commands:"));
+//! // Commands can also be executed in process.
+//! assert!(admin::execute("list").is_ok());
+//! ```
+
+use crate::{iter_hookables, lookup_hookable};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The header required by HTTP requests changing hooks, with any value, see [`handle_http`].
+pub const ADMIN_HEADER: &str = "X-Safe-Hook-Admin";
+
+/// How long [`AdminServer::bind_http`] waits for a request, so idle connections do not pile up.
+const HTTP_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HELP: &str = "commands: list, hooks <hookable>, enable <hookable> <id>, \
+disable <hookable> <id>, remove <hookable> <id>, disable-all, enable-all, stats [<hookable>], help, quit";
+
+/// The first words of the commands answered on a connection.
+const COMMANDS: &[&str] = &[
+    "help",
+    "list",
+    "hooks",
+    "enable",
+    "disable",
+    "remove",
+    "disable-all",
+    "enable-all",
+    "stats",
+    "quit",
+];
+
+/// Execute an admin command, returning the response or an error message.
+/// Responses may span several lines, without a trailing newline.
+pub fn execute(command: &str) -> Result<String, String> {
+    let words = command.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        ["help"] => Ok(HELP.to_string()),
+        ["list"] => {
+            let mut hookables = iter_hookables().collect::<Vec<_>>();
+            hookables.sort_by(|a, b| a.name().cmp(b.name()));
+            Ok(hookables
+                .iter()
+                .map(|meta| format!("{} hooks={}", meta.signature(), meta.list_hooks().len()))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        ["hooks", name] => {
+            let meta = find(name)?;
+            Ok(meta
+                .list_hooks()
+                .iter()
+                .map(|hook| {
+                    format!(
+                        "{} priority={} enabled={} {}",
+                        hook.id.as_u64(),
+                        hook.priority,
+                        hook.enabled,
+                        hook.description
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        [action @ ("enable" | "disable" | "remove"), name, id] => {
+            let meta = find(name)?;
+            let id = id
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid hook id '{}'", id))?;
+            let hook = meta
+                .list_hooks()
+                .into_iter()
+                .find(|hook| hook.id.as_u64() == id)
+                .ok_or_else(|| format!("Hook {} not found on '{}'", id, name))?;
+            let changed = match *action {
+                "remove" => meta.remove_hook_by_id(hook.id).map(|_| ()),
+                action => meta
+                    .set_hook_enabled(hook.id, action == "enable")
+                    .map(|_| ()),
+            };
+            changed.map_err(|err| format!("Refused: {}", err))?;
+            Ok("ok".to_string())
+        }
+        [action @ ("disable-all" | "enable-all")] => {
+            let changed = match *action {
+                "disable-all" => crate::disable_all(),
+                _ => crate::enable_all(),
+            };
+            changed.map_err(|err| format!("Refused: {}", err))?;
+            Ok("ok".to_string())
+        }
+        ["stats", rest @ ..] if rest.len() <= 1 => stats(rest.first().copied()),
+        [] => Err("Empty command".to_string()),
+        _ => Err(format!("Unknown command '{}', try 'help'", command.trim())),
+    }
+}
+
+fn find(name: &str) -> Result<&'static crate::HookableFuncMetadata, String> {
+    lookup_hookable(name).ok_or_else(|| format!("Hookable '{}' not found", name))
+}
+
+#[cfg(feature = "stats")]
+fn stats(name: Option<&str>) -> Result<String, String> {
+    let hookables = match name {
+        Some(name) => vec![find(name)?],
+        None => {
+            let mut hookables = iter_hookables().collect::<Vec<_>>();
+            hookables.sort_by(|a, b| a.name().cmp(b.name()));
+            hookables
+        }
+    };
+    Ok(hookables
+        .iter()
+        .map(|meta| {
+            let stats = meta.stats();
+            format!(
+                "{} fast_path_calls={} hooked_calls={} total_time={:?}",
+                meta.name(),
+                stats.fast_path_calls,
+                stats.hooked_calls,
+                stats.total_time
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(not(feature = "stats"))]
+fn stats(_name: Option<&str>) -> Result<String, String> {
+    Err("Statistics need the stats feature".to_string())
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+/// A control socket answering admin commands, see the [module documentation](self).
+///
+/// Each connection is served on its own thread, with one response per command line.
+/// Responses end with an empty line, error responses start with `error: `.
+/// The server stops when dropped.
+pub struct AdminServer {
+    listener: Arc<Listener>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AdminServer {
+    /// Listen on a TCP address, e.g. `127.0.0.1:7070`.
+    pub fn bind_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::start(Listener::Tcp(TcpListener::bind(addr)?), serve)
+    }
+
+    /// Listen on a TCP address for HTTP requests, answered by [`handle_http`].
+    /// Only meant for admin tools: one request per connection, request bodies are ignored,
+    /// and connections are closed if the request is not received within 10 seconds.
+    pub fn bind_http(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::start(Listener::Tcp(TcpListener::bind(addr)?), serve_http)
+    }
+
+    /// Listen on a Unix socket at `path`, removed when the server stops.
+    #[cfg(unix)]
+    pub fn bind_unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Self::start(Listener::Unix(UnixListener::bind(&path)?, path), serve)
+    }
+
+    /// The TCP address the server listens on, `None` for a Unix socket.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &*self.listener {
+            Listener::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Listener::Unix(..) => None,
+        }
+    }
+
+    fn start(listener: Listener, serve: ServeFn) -> io::Result<Self> {
+        let listener = Arc::new(listener);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::Builder::new()
+            .name("safe-hook-admin".to_string())
+            .spawn({
+                let listener = listener.clone();
+                let stopped = stopped.clone();
+                move || accept_loop(&listener, &stopped, serve)
+            })?;
+        Ok(Self {
+            listener,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for AdminServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // Wake up the accepting thread with a connection of our own.
+        match &*self.listener {
+            Listener::Tcp(listener) => {
+                if let Ok(addr) = listener.local_addr() {
+                    let _ = TcpStream::connect(addr);
+                }
+            }
+            #[cfg(unix)]
+            Listener::Unix(_, path) => {
+                let _ = UnixStream::connect(path);
+            }
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = &*self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+type ServeFn = fn(Box<dyn Connection>) -> io::Result<()>;
+
+fn accept_loop(listener: &Listener, stopped: &AtomicBool, serve: ServeFn) {
+    loop {
+        let stream: io::Result<Box<dyn Connection>> = match listener {
+            Listener::Tcp(listener) => listener
+                .accept()
+                .map(|(stream, _)| Box::new(stream) as Box<dyn Connection>),
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => listener
+                .accept()
+                .map(|(stream, _)| Box::new(stream) as Box<dyn Connection>),
+        };
+        if stopped.load(Ordering::Acquire) {
+            return;
+        }
+        if let Ok(stream) = stream {
+            let _ = std::thread::Builder::new()
+                .name("safe-hook-admin-conn".to_string())
+                .spawn(move || serve(stream));
+        }
+    }
+}
+
+trait Connection: Send {
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>>;
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+    fn reader(&mut self) -> &mut dyn io::Read;
+    fn writer(&mut self) -> &mut dyn Write;
+}
+
+impl Connection for TcpStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(TcpStream::try_clone(self)?))
+    }
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+    fn reader(&mut self) -> &mut dyn io::Read {
+        self
+    }
+    fn writer(&mut self) -> &mut dyn Write {
+        self
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(UnixStream::try_clone(self)?))
+    }
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+    fn reader(&mut self) -> &mut dyn io::Read {
+        self
+    }
+    fn writer(&mut self) -> &mut dyn Write {
+        self
+    }
+}
+
+fn serve(mut stream: Box<dyn Connection>) -> io::Result<()> {
+    let mut read_half = stream.try_clone()?;
+    let reader = BufReader::new(read_half.reader());
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim() == "quit" {
+            break;
+        }
+        let known = line
+            .split_whitespace()
+            .next()
+            .is_none_or(|word| COMMANDS.contains(&word));
+        let response = execute(&line).unwrap_or_else(|err| format!("error: {}", err));
+        let mut out = String::new();
+        for line in response.lines() {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+        stream.writer().write_all(out.as_bytes())?;
+        if !known {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// A response to an HTTP admin request, see [`handle_http`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The status code, e.g. 200.
+    pub status: u16,
+    /// The body, JSON for successful requests and `{"error": ...}` otherwise.
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn ok(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: format!("{{\"error\":{}}}", json_string(message)),
+        }
+    }
+}
+
+/// Handle an HTTP admin request, to mount the admin endpoints into any HTTP server,
+/// by passing the method and the path of requests under some prefix:
+/// - `GET /hookables`: the hookable functions, with their signature and number of hooks.
+/// - `GET /hookables/<hookable>/hooks`: the hooks of a hookable function, in calling order.
+/// - `POST /hookables/<hookable>/hooks/<id>/enable` (or `/disable`): enable or disable a hook.
+/// - `DELETE /hookables/<hookable>/hooks/<id>`: remove a hook.
+/// - `GET /stats`: call statistics, with the `stats` feature.
+///
+/// `headers` are the names and values of the request headers.
+/// Requests with other methods than `GET` must send the [`ADMIN_HEADER`] header,
+/// otherwise they return 403, so they cannot be forged by web pages.
+///
+/// Responses are JSON, see [`HttpResponse`].
+/// Changes refused because hooks cannot be changed, see [`freeze`](crate::freeze), return 403.
+/// # Example
+/// ```rust
+/// use safe_hook::admin::{ADMIN_HEADER, handle_http};
+///
+/// let response = handle_http("GET", "/hookables", &[]);
+/// assert_eq!(response.status, 200);
+/// assert!(response.body.starts_with('['));
+/// assert_eq!(handle_http("GET", "/nothing", &[]).status, 404);
+/// assert_eq!(handle_http("POST", "/hookables/none/hooks/1/enable", &[]).status, 403);
+/// let headers = [(ADMIN_HEADER, "1")];
+/// assert_eq!(handle_http("POST", "/hookables/none/hooks/1/enable", &headers).status, 404);
+/// ```
+pub fn handle_http(method: &str, path: &str, headers: &[(&str, &str)]) -> HttpResponse {
+    let allowed = method == "GET"
+        || headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(ADMIN_HEADER));
+    if !allowed {
+        return HttpResponse::error(403, &format!("Missing the {} header", ADMIN_HEADER));
+    }
+    let path = path.split('?').next().unwrap_or_default();
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        ("GET", ["hookables"]) => {
+            let mut hookables = iter_hookables().collect::<Vec<_>>();
+            hookables.sort_by(|a, b| a.name().cmp(b.name()));
+            let items = hookables
+                .iter()
+                .map(|meta| {
+                    format!(
+                        "{{\"name\":{},\"signature\":{},\"hooks\":{}}}",
+                        json_string(meta.name()),
+                        json_string(&meta.signature()),
+                        meta.list_hooks().len()
+                    )
+                })
+                .collect::<Vec<_>>();
+            HttpResponse::ok(format!("[{}]", items.join(",")))
+        }
+        ("GET", ["hookables", name, "hooks"]) => {
+            let Some(meta) = lookup_hookable(name) else {
+                return HttpResponse::error(404, &format!("Hookable '{}' not found", name));
+            };
+            let items = meta
+                .list_hooks()
+                .iter()
+                .map(|hook| {
+                    format!(
+                        "{{\"id\":{},\"priority\":{},\"enabled\":{},\"description\":{}}}",
+                        hook.id.as_u64(),
+                        hook.priority,
+                        hook.enabled,
+                        json_string(&hook.description)
+                    )
+                })
+                .collect::<Vec<_>>();
+            HttpResponse::ok(format!("[{}]", items.join(",")))
+        }
+        (
+            "POST",
+            [
+                "hookables",
+                name,
+                "hooks",
+                id,
+                action @ ("enable" | "disable"),
+            ],
+        ) => command_response(&format!("{} {} {}", action, name, id)),
+        ("DELETE", ["hookables", name, "hooks", id]) => {
+            command_response(&format!("remove {} {}", name, id))
+        }
+        ("GET", ["stats"]) => stats_json(),
+        (_, ["hookables", ..] | ["stats"]) => HttpResponse::error(405, "Method not allowed"),
+        _ => HttpResponse::error(404, "Not found"),
+    }
+}
+
+/// An `axum` router serving the endpoints of [`handle_http`], available with the `axum` feature,
+/// to be nested under a prefix of an existing application.
+///
+/// Requests are handled synchronously on the runtime thread, the operations are short.
+/// # Example
+/// ```rust
+/// use safe_hook::admin;
+///
+/// let app: axum::Router = axum::Router::new().nest("/admin", admin::router());
+/// ```
+#[cfg(feature = "axum")]
+pub fn router() -> axum::Router {
+    use axum::http::{HeaderMap, Method, StatusCode, Uri, header};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::any;
+
+    async fn handle(method: Method, uri: Uri, headers: HeaderMap) -> Response {
+        let headers = headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+            .collect::<Vec<_>>();
+        let response = handle_http(method.as_str(), uri.path(), &headers);
+        let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::NOT_IMPLEMENTED);
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/json")],
+            response.body,
+        )
+            .into_response()
+    }
+
+    axum::Router::new()
+        .route("/hookables", any(handle))
+        .route("/hookables/{name}/hooks", any(handle))
+        .route("/hookables/{name}/hooks/{id}", any(handle))
+        .route("/hookables/{name}/hooks/{id}/{action}", any(handle))
+        .route("/stats", any(handle))
+}
+
+/// Run a command of [`execute`] with an id checked to be a number.
+fn command_response(command: &str) -> HttpResponse {
+    match execute(command) {
+        Ok(_) => HttpResponse::ok("{}".to_string()),
+        Err(err) if err.starts_with("Invalid") => HttpResponse::error(400, &err),
+        Err(err) if err.starts_with("Refused") => HttpResponse::error(403, &err),
+        Err(err) => HttpResponse::error(404, &err),
+    }
+}
+
+#[cfg(feature = "stats")]
+fn stats_json() -> HttpResponse {
+    let mut hookables = iter_hookables().collect::<Vec<_>>();
+    hookables.sort_by(|a, b| a.name().cmp(b.name()));
+    let items = hookables
+        .iter()
+        .map(|meta| {
+            let stats = meta.stats();
+            format!(
+                "{{\"name\":{},\"fast_path_calls\":{},\"hooked_calls\":{},\"total_time_seconds\":{}}}",
+                json_string(meta.name()),
+                stats.fast_path_calls,
+                stats.hooked_calls,
+                stats.total_time.as_secs_f64()
+            )
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::ok(format!("[{}]", items.join(",")))
+}
+
+#[cfg(not(feature = "stats"))]
+fn stats_json() -> HttpResponse {
+    HttpResponse::error(501, "Statistics need the stats feature")
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn serve_http(mut stream: Box<dyn Connection>) -> io::Result<()> {
+    stream.set_read_timeout(Some(HTTP_READ_TIMEOUT))?;
+    let mut read_half = stream.try_clone()?;
+    let mut reader = BufReader::new(read_half.reader());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let headers = headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => handle_http(method, path, &headers),
+        _ => HttpResponse::error(400, "Bad request"),
+    };
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Not Implemented",
+    };
+    write!(
+        stream.writer(),
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        response.body.len(),
+        response.body
+    )
+}
@@ -0,0 +1,171 @@
+//! An audit trail of hook changes: who attached, detached, enabled or disabled which hook, and when.
+//!
+//! Disabled by default. [`enable`] keeps the last records in memory, see [`records`],
+//! and [`set_sink`] sends every record elsewhere, e.g. to a log file.
+//! Each record carries the location of the code making the change,
+//! propagated with `#[track_caller]` through the methods of
+//! [`HookableFuncMetadata`](crate::HookableFuncMetadata) and friends.
+//! # Example
+//! ```rust
+//! use safe_hook::{audit, lookup_hookable};
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("audited-add")]
+//! fn add(left: i64, right: i64) -> i64 {
+//!     left + right
+//! }
+//!
+//! audit::enable(1000);
+//! audit::set_sink(|record| eprintln!("{}", record));
+//! lookup_hookable("audited-add")
+//!     .unwrap()
+//!     .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) })
+//!     .unwrap();
+//! let record = audit::records().pop().unwrap();
+//! # #[cfg(not(feature = "hashed-names"))]
+//! assert_eq!(record.event.hookable, "audited-add");
+//! assert_eq!(record.event.location.file(), file!());
+//! audit::disable();
+//! ```
+
+use crate::{HookEvent, HookEventKind, ObserverId, lock};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::SystemTime;
+
+/// A hook change recorded by the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// When the change was made.
+    pub timestamp: SystemTime,
+    /// The thread making the change.
+    pub thread: ThreadId,
+    /// The name of the thread making the change, if any.
+    pub thread_name: Option<String>,
+    /// The change, including the location of the code making it.
+    pub event: HookEvent,
+}
+
+impl std::fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let since_epoch = self
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        write!(
+            f,
+            "[{}.{:06}] {:?}",
+            since_epoch.as_secs(),
+            since_epoch.subsec_micros(),
+            self.thread
+        )?;
+        if let Some(name) = &self.thread_name {
+            write!(f, " ({})", name)?;
+        }
+        let action = match self.event.kind {
+            HookEventKind::Added => "added",
+            HookEventKind::Removed => "removed",
+            HookEventKind::Enabled => "enabled",
+            HookEventKind::Disabled => "disabled",
+        };
+        write!(
+            f,
+            " {} {} ({:?}, priority {}) on '{}' at {}",
+            action,
+            self.event.hook.description,
+            self.event.hook.id,
+            self.event.hook.priority,
+            self.event.hookable,
+            self.event.location
+        )
+    }
+}
+
+type AuditSink = Arc<dyn Fn(&AuditRecord) + Send + Sync>;
+
+#[derive(Default)]
+struct AuditLog {
+    observer: Option<ObserverId>,
+    capacity: usize,
+    records: VecDeque<AuditRecord>,
+    sink: Option<AuditSink>,
+}
+
+static LOG: Mutex<AuditLog> = Mutex::new(AuditLog {
+    observer: None,
+    capacity: 0,
+    records: VecDeque::new(),
+    sink: None,
+});
+
+/// Start recording hook changes, keeping the last `capacity` records in memory.
+/// If already enabled, only the capacity is changed.
+pub fn enable(capacity: usize) {
+    let mut log = lock::lock(&LOG);
+    log.capacity = capacity;
+    while log.records.len() > capacity {
+        log.records.pop_front();
+    }
+    if log.observer.is_none() {
+        log.observer = Some(crate::add_registry_observer(record));
+    }
+}
+
+/// Stop recording hook changes. The records kept in memory are not discarded.
+pub fn disable() {
+    let observer = lock::lock(&LOG).observer.take();
+    if let Some(observer) = observer {
+        crate::remove_registry_observer(observer);
+    }
+}
+
+/// Check whether hook changes are recorded.
+pub fn is_enabled() -> bool {
+    lock::lock(&LOG).observer.is_some()
+}
+
+/// Also pass every record to `sink`, while the audit log is enabled.
+/// Replaces the previous sink.
+pub fn set_sink(sink: impl Fn(&AuditRecord) + Send + Sync + 'static) {
+    lock::lock(&LOG).sink = Some(Arc::new(sink));
+}
+
+/// Remove the sink set by [`set_sink`].
+pub fn clear_sink() {
+    lock::lock(&LOG).sink = None;
+}
+
+/// Get the records kept in memory, oldest first.
+pub fn records() -> Vec<AuditRecord> {
+    lock::lock(&LOG).records.iter().cloned().collect()
+}
+
+/// Discard the records kept in memory.
+pub fn clear() {
+    lock::lock(&LOG).records.clear();
+}
+
+fn record(event: &HookEvent) {
+    let thread = std::thread::current();
+    let record = AuditRecord {
+        timestamp: SystemTime::now(),
+        thread: thread.id(),
+        thread_name: thread.name().map(str::to_string),
+        event: event.clone(),
+    };
+    let sink = {
+        let mut log = lock::lock(&LOG);
+        if log.capacity > 0 {
+            if log.records.len() == log.capacity {
+                log.records.pop_front();
+            }
+            log.records.push_back(record.clone());
+        }
+        log.sink.clone()
+    };
+    // The sink is called without holding the lock, so it may read the records.
+    if let Some(sink) = sink {
+        sink(&record);
+    }
+}
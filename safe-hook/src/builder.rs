@@ -0,0 +1,178 @@
+use crate::{
+    AddHookError, HookableFuncMetadata, REGISTERED, attach_pending, call_fast_path_of,
+    call_with_hook, collected, compose_chain, invoke, lock, stored_name, stored_type_names,
+    type_fingerprint,
+};
+use std::any::{Any, TypeId};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A builder of hookable functions without the macro [`hookable`](crate::hookable),
+/// e.g. for code generators. Created by [`HookableFuncMetadata::builder`].
+///
+/// `A` is the arguments tuple and `R` is the result, like for [`Hook`](crate::Hook).
+/// They cannot contain references other than `'static` ones.
+pub struct HookableFuncBuilder<A, R> {
+    name: String,
+    func: fn(A) -> R,
+    clone_args: Option<fn(&A) -> A>,
+    is_err: fn(&R) -> bool,
+    version: Option<&'static str>,
+}
+
+impl HookableFuncMetadata {
+    /// Create a hookable function named `name` calling `func`, see [`HookableFuncBuilder`].
+    /// # Example
+    /// ```rust
+    /// use std::sync::LazyLock;
+    /// use safe_hook::{lookup_hookable, HookableFuncMetadata, HookPoint};
+    ///
+    /// static ADD: LazyLock<HookPoint<(i64, i64), i64>> = LazyLock::new(|| {
+    ///     HookableFuncMetadata::builder("builder-add", |(left, right): (i64, i64)| left + right)
+    ///         .register()
+    ///         .unwrap()
+    /// });
+    ///
+    /// fn add(left: i64, right: i64) -> i64 {
+    ///     ADD.call((left, right))
+    /// }
+    ///
+    /// assert_eq!(add(1, 2), 3);
+    /// lookup_hookable("builder-add")
+    ///     .unwrap()
+    ///     .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+    ///     .unwrap();
+    /// assert_eq!(add(1, 2), 4);
+    /// ```
+    pub fn builder<A: 'static, R: 'static>(
+        name: impl Into<String>,
+        func: fn(A) -> R,
+    ) -> HookableFuncBuilder<A, R> {
+        HookableFuncBuilder {
+            name: name.into(),
+            func,
+            clone_args: None,
+            is_err: |_| false,
+            version: None,
+        }
+    }
+}
+
+impl<A: 'static, R: 'static> HookableFuncBuilder<A, R> {
+    /// Let panicking hooks be skipped, see [`HookableFuncMetadata::set_panic_policy`].
+    pub fn clone_args(mut self) -> Self
+    where
+        A: Clone,
+    {
+        self.clone_args = Some(A::clone);
+        self
+    }
+
+    /// Count results for which `is_err` returns `true` as errors in metrics,
+    /// see the `metrics` module.
+    pub fn is_err(mut self, is_err: fn(&R) -> bool) -> Self {
+        self.is_err = is_err;
+        self
+    }
+
+    /// Set the semantic version of the contract of the function, see [`HookableFuncMetadata::version`].
+    pub fn version(mut self, version: &'static str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Register the hookable function, so it can be found by [`lookup_hookable`](crate::lookup_hookable),
+    /// and return the handle to call it.
+    /// Returns [`AddHookError::AlreadyRegistered`] if a hookable function with that name is registered.
+    ///
+    /// Registered hookable functions live until the end of the process.
+    pub fn register(self) -> Result<HookPoint<A, R>, AddHookError> {
+        let func = self.func;
+        let meta = register_with(self.name, |name| {
+            let metadata = HookableFuncMetadata::from_parts(
+                name,
+                func as *const (),
+                (TypeId::of::<R>(), TypeId::of::<A>()),
+                stored_type_names::<R, A>(),
+                (type_fingerprint::<R>(), type_fingerprint::<A>()),
+                Box::leak(Box::new(AtomicBool::new(false))),
+                Some(invoke::invoke_erased::<R, A>),
+                Box::new(move |hooks, options| compose_chain::<R, A>(func, hooks, options)),
+                self.clone_args
+                    .map(|clone_args| Box::new(clone_args) as Box<dyn Any + Send + Sync>),
+                Box::new(self.is_err),
+            );
+            match self.version {
+                Some(version) => metadata.with_version(version),
+                None => metadata,
+            }
+        })?;
+        Ok(HookPoint { meta, func })
+    }
+}
+
+/// Register the metadata created by `build` at runtime,
+/// unless a hookable function named `name` is already registered.
+/// The metadata lives until the end of the process.
+pub(crate) fn register_with(
+    name: String,
+    build: impl FnOnce(String) -> HookableFuncMetadata,
+) -> Result<&'static HookableFuncMetadata, AddHookError> {
+    let name = stored_name(&name).into_owned();
+    let mut registered = lock::write(&REGISTERED);
+    let taken = collected().any(|item| item.metadata.name == name)
+        || registered.iter().any(|other| other.name == name);
+    if taken {
+        return Err(AddHookError::AlreadyRegistered(name));
+    }
+    let meta: &'static HookableFuncMetadata = Box::leak(Box::new(build(name)));
+    registered.push(meta);
+    drop(registered);
+    attach_pending();
+    Ok(meta)
+}
+
+/// Register a hookable function generated by the macros, unless it is already registered.
+///
+/// The macros call it on wasm targets, where `inventory` may not collect their registrations.
+#[doc(hidden)]
+pub fn register_static(meta: &'static HookableFuncMetadata) {
+    let mut registered = lock::write(&REGISTERED);
+    let known = collected().any(|item| std::ptr::eq(&**item.metadata, meta))
+        || registered.iter().any(|other| std::ptr::eq(*other, meta));
+    if known {
+        return;
+    }
+    registered.push(meta);
+    drop(registered);
+    attach_pending();
+}
+
+/// A handle to call a hookable function created by [`HookableFuncBuilder`], through its hooks.
+pub struct HookPoint<A, R> {
+    meta: &'static HookableFuncMetadata,
+    func: fn(A) -> R,
+}
+
+impl<A, R> Clone for HookPoint<A, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A, R> Copy for HookPoint<A, R> {}
+
+impl<A: 'static, R: 'static> HookPoint<A, R> {
+    /// Call the hookable function. Hooks can get the caller location, see [`call_info`](crate::call_info).
+    #[track_caller]
+    pub fn call(&self, args: A) -> R {
+        if !self.meta.fast_path_flag.load(Ordering::Acquire) {
+            return call_fast_path_of(&self.meta, || (self.func)(args));
+        }
+        call_with_hook::<R, A>(self.meta, args, Some(std::panic::Location::caller()))
+    }
+
+    /// Get the metadata of the hookable function, to add hooks.
+    pub fn metadata(&self) -> &'static HookableFuncMetadata {
+        self.meta
+    }
+}
@@ -0,0 +1,192 @@
+use crate::builder::register_with;
+use crate::{
+    AddHookError, HookableFuncMetadata, InstanceKey, call_fast_path_of, call_with_hook_on,
+    compose_chain, lock, stored_type_names, type_fingerprint,
+};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// A callback stored in a field, e.g. of a strategy object or a plugin table,
+/// whose calls go through the hooks of a hookable function.
+///
+/// The callback takes the arguments tuple `A` and returns `R`, like [`Hook`](crate::Hook).
+/// All callbacks created with the same name share the hookable function,
+/// so its hooks apply to each of them, and only the callback at the end of the chain differs.
+/// # Example
+/// ```rust
+/// use safe_hook::{lookup_hookable, Hookable};
+///
+/// struct Pricing {
+///     discount: Hookable<fn((u32,)) -> u32>,
+/// }
+///
+/// fn half((price,): (u32,)) -> u32 {
+///     price / 2
+/// }
+///
+/// fn full((price,): (u32,)) -> u32 {
+///     price
+/// }
+///
+/// let sale = Pricing {
+///     discount: Hookable::new("pricing-discount", half as fn(_) -> _).unwrap(),
+/// };
+/// let regular = Pricing {
+///     discount: Hookable::new("pricing-discount", full as fn(_) -> _).unwrap(),
+/// };
+/// lookup_hookable("pricing-discount")
+///     .unwrap()
+///     .add_hook_fn(|args: (u32,), next| -> u32 { next(args).max(10) })
+///     .unwrap();
+/// assert_eq!(sale.discount.call((100,)), 50);
+/// assert_eq!(sale.discount.call((10,)), 10);
+/// assert_eq!(regular.discount.call((4,)), 10);
+/// ```
+pub struct Hookable<F> {
+    point: &'static HookableFuncMetadata,
+    func: F,
+}
+
+/// The hookable functions created for callbacks, by name.
+static POINTS: LazyLock<Mutex<HashMap<String, &'static HookableFuncMetadata>>> =
+    LazyLock::new(Default::default);
+
+thread_local! {
+    /// The callbacks called by this thread, innermost last, as `*mut &mut dyn FnMut(A) -> R`.
+    static CALLBACKS: RefCell<Vec<*mut ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The original function of the hookable functions of callbacks,
+/// calling the innermost callback of the thread.
+fn call_current<A: 'static, R: 'static>(args: A) -> R {
+    let callback = CALLBACKS
+        .with(|callbacks| callbacks.borrow().last().copied())
+        .expect("Callbacks can only be called through call_callback");
+    // SAFETY: the callback is pushed by `call_callback`, with the types of the hookable function,
+    // where lifetimes of the arguments are erased, see `call_with_hook`.
+    // It is alive until the call returns, and the chain calls it at most once at a time.
+    let callback = unsafe { &mut *(callback as *mut &mut dyn FnMut(A) -> R) };
+    callback(args)
+}
+
+/// Create the metadata of a hookable function calling a callback, see [`call_callback`].
+/// It is used inside the macro [`hookable_trait`](crate::hookable_trait).
+/// Lifetimes in `A` are `'static`, see [`call_with_hook`].
+#[doc(hidden)]
+pub fn callback_metadata<R: 'static, A: 'static>(
+    name: String,
+    fast_path_flag: &'static AtomicBool,
+) -> HookableFuncMetadata {
+    let mut meta = HookableFuncMetadata::from_parts(
+        name,
+        call_current::<A, R> as *const (),
+        (TypeId::of::<R>(), TypeId::of::<A>()),
+        stored_type_names::<R, A>(),
+        (type_fingerprint::<R>(), type_fingerprint::<A>()),
+        fast_path_flag,
+        // There is no callback to call without a call site.
+        None,
+        Box::new(|hooks, options| compose_chain::<R, A>(call_current::<A, R>, hooks, options)),
+        None,
+        Box::new((|_| false) as fn(&R) -> bool),
+    );
+    meta.callback = true;
+    meta
+}
+
+/// Call `callback` through the hooks of `meta`, created by [`callback_metadata`],
+/// as a method of `instance` if any.
+/// It is used inside the macro [`hookable_trait`](crate::hookable_trait).
+#[doc(hidden)]
+pub fn call_callback<R: 'static, A>(
+    meta: &'static HookableFuncMetadata,
+    args: A,
+    mut callback: &mut dyn FnMut(A) -> R,
+    location: Option<&'static Location<'static>>,
+    instance: Option<InstanceKey>,
+) -> R {
+    if !meta.fast_path_flag.load(Ordering::Acquire) {
+        return call_fast_path_of(&meta, || callback(args));
+    }
+    let pointer = &mut callback as *mut &mut dyn FnMut(A) -> R as *mut ();
+    CALLBACKS.with(|callbacks| callbacks.borrow_mut().push(pointer));
+    let _guard = PopGuard;
+    call_with_hook_on::<R, A>(meta, args, location, instance)
+}
+
+impl<F> Hookable<F> {
+    /// Wrap the callback `func`, so calls go through the hooks of the hookable function `name`,
+    /// registering it if needed.
+    ///
+    /// Returns [`AddHookError::TypeMismatch`] if the hookable function `name` was created
+    /// for callbacks of other types, or [`AddHookError::AlreadyRegistered`]
+    /// if it is a hookable function other than a callback.
+    pub fn new<A: 'static, R: 'static>(name: &str, func: F) -> Result<Self, AddHookError>
+    where
+        F: Fn(A) -> R,
+    {
+        let mut points = lock::lock(&POINTS);
+        let point = match points.get(name) {
+            Some(&point) => {
+                let found = (TypeId::of::<R>(), TypeId::of::<A>());
+                if point.type_info != found {
+                    return Err(point.type_mismatch(found, stored_type_names::<R, A>()));
+                }
+                point
+            }
+            None => {
+                let point = register_with(name.to_string(), |name| {
+                    callback_metadata::<R, A>(name, Box::leak(Box::new(AtomicBool::new(false))))
+                })?;
+                points.insert(name.to_string(), point);
+                point
+            }
+        };
+        Ok(Self { point, func })
+    }
+
+    /// Call the callback through the hooks.
+    /// Hooks can get the caller location, see [`call_info`](crate::call_info).
+    #[track_caller]
+    pub fn call<A: 'static, R: 'static>(&self, args: A) -> R
+    where
+        F: Fn(A) -> R,
+    {
+        // Closures implement `Fn` for a single argument type, the one checked by `new`.
+        assert_eq!(
+            self.point.type_info,
+            (TypeId::of::<R>(), TypeId::of::<A>()),
+            "Hookable callback called with other types"
+        );
+        call_callback(
+            self.point,
+            args,
+            &mut |args| (self.func)(args),
+            Some(Location::caller()),
+            None,
+        )
+    }
+
+    /// Get the metadata of the hookable function, to add hooks.
+    pub fn metadata(&self) -> &'static HookableFuncMetadata {
+        self.point
+    }
+
+    /// Get the wrapped callback.
+    pub fn get(&self) -> &F {
+        &self.func
+    }
+}
+
+/// Pops the innermost callback when its call returns or unwinds.
+struct PopGuard;
+
+impl Drop for PopGuard {
+    fn drop(&mut self) {
+        let _ = CALLBACKS.try_with(|callbacks| callbacks.borrow_mut().pop());
+    }
+}
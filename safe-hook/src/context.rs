@@ -0,0 +1,276 @@
+use crate::HookableFuncMetadata;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::ThreadId;
+use std::time::SystemTime;
+
+/// Values shared by the hooks of a single call, one per type,
+/// see [`with_context`].
+///
+/// Each call running hooks starts with an empty context,
+/// so hooks can pass values to the hooks called after them, e.g. a start time.
+/// Contexts hold a few values, so they are kept in a list, which allocates nothing until used.
+#[derive(Default)]
+pub struct HookContext {
+    values: Vec<(TypeId, Box<dyn Any>)>,
+}
+
+impl HookContext {
+    fn position<T: 'static>(&self) -> Option<usize> {
+        self.values
+            .iter()
+            .position(|(type_id, _)| *type_id == TypeId::of::<T>())
+    }
+
+    /// Insert a value, returning the previous value of the same type if any.
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        let value = Box::new(value);
+        match self.position::<T>() {
+            Some(pos) => {
+                let old = std::mem::replace(&mut self.values[pos].1, value);
+                Some(*old.downcast().unwrap())
+            }
+            None => {
+                self.values.push((TypeId::of::<T>(), value));
+                None
+            }
+        }
+    }
+
+    /// Get the value of type `T`.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        let pos = self.position::<T>()?;
+        Some(self.values[pos].1.downcast_ref().unwrap())
+    }
+
+    /// Get the value of type `T` mutably.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        let pos = self.position::<T>()?;
+        Some(self.values[pos].1.downcast_mut().unwrap())
+    }
+
+    /// Get the value of type `T`, inserting the result of `f` if there is none.
+    pub fn get_or_insert_with<T: 'static>(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        let pos = match self.position::<T>() {
+            Some(pos) => pos,
+            None => {
+                self.values.push((TypeId::of::<T>(), Box::new(f())));
+                self.values.len() - 1
+            }
+        };
+        self.values[pos].1.downcast_mut().unwrap()
+    }
+
+    /// Remove the value of type `T` and return it.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let pos = self.position::<T>()?;
+        let (_, value) = self.values.swap_remove(pos);
+        Some(*value.downcast().unwrap())
+    }
+
+    /// Check whether there is a value of type `T`.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.position::<T>().is_some()
+    }
+}
+
+/// The object a hookable method is called on, see [`HookExt::for_instance`](crate::HookExt::for_instance).
+///
+/// Proxies generated by [`hookable_trait`](crate::hookable_trait) are keyed by their address,
+/// unless they are created with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstanceKey {
+    /// The address of the object, only stable while it is not moved.
+    Address(usize),
+    /// A key given by the user, e.g. the id of a session.
+    Key(u64),
+}
+
+impl InstanceKey {
+    /// The key of `value` by its address.
+    pub fn of<T: ?Sized>(value: &T) -> Self {
+        InstanceKey::Address(value as *const T as *const () as usize)
+    }
+}
+
+impl From<u64> for InstanceKey {
+    fn from(key: u64) -> Self {
+        InstanceKey::Key(key)
+    }
+}
+
+/// Information about a call running hooks, see [`call_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInfo {
+    /// The name of the hookable function.
+    pub name: &'static str,
+    /// The id of the call, unique in the process.
+    /// Assigned when information about the call is first requested.
+    pub call_id: u64,
+    /// The thread making the call.
+    pub thread: ThreadId,
+    /// When information about the call was first requested,
+    /// so it is the same for all the hooks of the call.
+    /// Calls do not read the clock otherwise,
+    /// so a hook called first can request it to get the start of the call.
+    pub timestamp: SystemTime,
+    /// Where the function was called from,
+    /// only if it is hookable with the `track_caller` option, see [`hookable`](crate::hookable).
+    pub location: Option<&'static Location<'static>>,
+    /// The object the method is called on, only for hookable methods.
+    pub instance: Option<InstanceKey>,
+}
+
+/// A call running hooks on this thread.
+struct Frame {
+    meta: &'static HookableFuncMetadata,
+    /// Set by [`call_info`], so calls not asking for them do not pay for them.
+    call_id: Option<u64>,
+    timestamp: Option<SystemTime>,
+    location: Option<&'static Location<'static>>,
+    instance: Option<InstanceKey>,
+    context: HookContext,
+}
+
+thread_local! {
+    /// The calls running hooks on this thread, innermost last.
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Get information about the innermost call running hooks on the current thread,
+/// so hooks can tell calls apart in logs and metrics.
+/// Returns `None` outside of such calls.
+/// # Example
+/// ```rust
+/// use safe_hook::{call_info, lookup_hookable};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("call-info-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// lookup_hookable("call-info-add")
+///     .unwrap()
+///     .add_hook_fn(|args: (i64, i64), next| -> i64 {
+///         let info = call_info().unwrap();
+///         println!("call #{} of {} on {:?}", info.call_id, info.name, info.thread);
+///         next(args)
+///     })
+///     .unwrap();
+/// assert_eq!(add(1, 2), 3);
+/// ```
+pub fn call_info() -> Option<CallInfo> {
+    static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(0);
+    let (meta, call_id, timestamp, location, instance) = FRAMES
+        .try_with(|frames| {
+            let mut frames = frames.borrow_mut();
+            let frame = frames.last_mut()?;
+            let call_id = *frame
+                .call_id
+                .get_or_insert_with(|| NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed));
+            let timestamp = *frame.timestamp.get_or_insert_with(SystemTime::now);
+            Some((
+                frame.meta,
+                call_id,
+                timestamp,
+                frame.location,
+                frame.instance,
+            ))
+        })
+        .ok()
+        .flatten()?;
+    Some(CallInfo {
+        name: meta.name(),
+        call_id,
+        thread: std::thread::current().id(),
+        timestamp,
+        location,
+        instance,
+    })
+}
+
+/// The object the innermost call running hooks on the current thread is made on.
+pub(crate) fn current_instance() -> Option<InstanceKey> {
+    FRAMES
+        .try_with(|frames| frames.borrow().last()?.instance)
+        .ok()
+        .flatten()
+}
+
+/// Access the [`HookContext`] of the innermost call running hooks on the current thread.
+/// Returns `None` outside of such calls.
+///
+/// The context is taken out while `f` runs, so calls made by `f` do not see it.
+/// # Example
+/// ```rust
+/// use std::time::Instant;
+/// use safe_hook::{lookup_hookable, with_context};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("context-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let add_hookable = lookup_hookable("context-add").unwrap();
+/// // Called last, it reads the start time stored by the first hook.
+/// add_hookable
+///     .add_hook_fn(|args: (i64, i64), next| -> i64 {
+///         let start = with_context(|context| *context.get::<Instant>().unwrap()).unwrap();
+///         println!("add started {:?} ago", start.elapsed());
+///         next(args)
+///     })
+///     .unwrap();
+/// add_hookable
+///     .add_hook_fn(|args: (i64, i64), next| -> i64 {
+///         with_context(|context| context.insert(Instant::now()));
+///         next(args)
+///     })
+///     .unwrap();
+/// assert_eq!(add(1, 2), 3);
+/// assert!(with_context(|_| ()).is_none());
+/// ```
+pub fn with_context<T>(f: impl FnOnce(&mut HookContext) -> T) -> Option<T> {
+    let mut context = FRAMES
+        .try_with(|frames| Some(std::mem::take(&mut frames.borrow_mut().last_mut()?.context)))
+        .ok()
+        .flatten()?;
+    let result = f(&mut context);
+    let _ = FRAMES.try_with(|frames| {
+        if let Some(last) = frames.borrow_mut().last_mut() {
+            last.context = context;
+        }
+    });
+    Some(result)
+}
+
+/// Removes a call from the calls running hooks when it returns or unwinds.
+pub(crate) struct FrameGuard(());
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        let _ = FRAMES.try_with(|frames| frames.borrow_mut().pop());
+    }
+}
+
+/// Start a call running hooks with an empty context, until the returned guard is dropped.
+#[inline(always)]
+pub(crate) fn enter(
+    meta: &'static HookableFuncMetadata,
+    location: Option<&'static Location<'static>>,
+    instance: Option<InstanceKey>,
+) -> FrameGuard {
+    let frame = Frame {
+        meta,
+        call_id: None,
+        timestamp: None,
+        location,
+        instance,
+        context: HookContext::default(),
+    };
+    let _ = FRAMES.try_with(|frames| frames.borrow_mut().push(frame));
+    FrameGuard(())
+}
@@ -0,0 +1,80 @@
+//! Track which hookable functions were called, available with the `coverage` feature.
+//!
+//! Every call marks its hookable function as executed, hooked or not,
+//! so integration tests can check that they exercise every hook point.
+//! # Example
+//! ```rust
+//! use safe_hook::{coverage, hookable_meta};
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("coverage-ping")]
+//! fn ping() -> bool {
+//!     true
+//! }
+//!
+//! ping();
+//! let report = coverage::report();
+//! let name = hookable_meta!(ping).unwrap().name();
+//! assert!(report.executed.iter().any(|executed| executed == name));
+//! for name in &report.missed {
+//!     println!("never called: {}", name);
+//! }
+//! ```
+
+use crate::{HookableFuncMetadata, iter_hookables};
+use std::sync::atomic::Ordering;
+
+/// The hookable functions called or not since the start or the last [`reset`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CoverageReport {
+    /// The names of the hookable functions called at least once, sorted.
+    pub executed: Vec<String>,
+    /// The names of the hookable functions never called, sorted.
+    pub missed: Vec<String>,
+}
+
+impl CoverageReport {
+    /// Check whether every hookable function was called.
+    pub fn is_complete(&self) -> bool {
+        self.missed.is_empty()
+    }
+}
+
+/// Get the hookable functions called or not so far.
+pub fn report() -> CoverageReport {
+    let mut report = CoverageReport::default();
+    for meta in iter_hookables() {
+        if meta.was_executed() {
+            report.executed.push(meta.name().to_string());
+        } else {
+            report.missed.push(meta.name().to_string());
+        }
+    }
+    report.executed.sort();
+    report.missed.sort();
+    report
+}
+
+/// Mark every hookable function as not called.
+pub fn reset() {
+    for meta in iter_hookables() {
+        meta.executed.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Mark a hookable function as called.
+/// Only stores when needed, so frequent calls do not contend on the cache line.
+#[inline(always)]
+pub(crate) fn mark(meta: &HookableFuncMetadata) {
+    if !meta.executed.load(Ordering::Relaxed) {
+        meta.executed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl HookableFuncMetadata {
+    /// Check whether the hookable function was called since the start or the last [`reset`],
+    /// available with the `coverage` feature.
+    pub fn was_executed(&self) -> bool {
+        self.executed.load(Ordering::Relaxed)
+    }
+}
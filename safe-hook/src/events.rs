@@ -0,0 +1,141 @@
+use crate::{HookEntry, HookInfo, lock};
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// What happened to a hook, see [`HookEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HookEventKind {
+    /// The hook was attached.
+    Added,
+    /// The hook was detached.
+    Removed,
+    /// The hook was enabled, see [`HookableFuncMetadata::set_hook_enabled`](crate::HookableFuncMetadata::set_hook_enabled).
+    Enabled,
+    /// The hook was disabled.
+    Disabled,
+}
+
+/// A change of the hooks of a hookable function, passed to registry observers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookEvent {
+    /// The name of the hookable function.
+    pub hookable: String,
+    /// What happened to the hook.
+    pub kind: HookEventKind,
+    /// The hook, as it is after the change (or before, if it was removed).
+    pub hook: HookInfo,
+    /// The code making the change.
+    pub location: &'static Location<'static>,
+}
+
+/// Identifies a registry observer, returned by [`add_registry_observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+type ObserverFn = Arc<dyn Fn(&HookEvent) + Send + Sync>;
+
+static OBSERVERS: RwLock<Vec<(ObserverId, ObserverFn)>> = RwLock::new(Vec::new());
+static HAS_OBSERVERS: AtomicBool = AtomicBool::new(false);
+
+/// Register an observer notified whenever a hook is added to, removed from,
+/// enabled or disabled on any hookable function.
+///
+/// Observers are called on the thread making the change, after the hookable function is unlocked,
+/// so they can inspect or even change hooks.
+/// Events of concurrent changes may reach observers in any order.
+/// # Example
+/// ```rust
+/// use safe_hook::{add_registry_observer, lookup_hookable, remove_registry_observer};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("observed-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let observer = add_registry_observer(|event| {
+///     println!("{:?} {} on {}", event.kind, event.hook.description, event.hookable);
+/// });
+/// let add_hookable = lookup_hookable("observed-add").unwrap();
+/// let id = add_hookable
+///     .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) })
+///     .unwrap();
+/// add_hookable.remove_hook_by_id(id).unwrap();
+/// remove_registry_observer(observer);
+/// ```
+pub fn add_registry_observer(observer: impl Fn(&HookEvent) + Send + Sync + 'static) -> ObserverId {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = ObserverId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let mut observers = lock::write(&OBSERVERS);
+    observers.push((id, Arc::new(observer)));
+    HAS_OBSERVERS.store(true, Ordering::Release);
+    id
+}
+
+/// Unregister an observer. Returns `false` if it is not registered.
+pub fn remove_registry_observer(id: ObserverId) -> bool {
+    let mut observers = lock::write(&OBSERVERS);
+    let Some(pos) = observers.iter().position(|(i, _)| *i == id) else {
+        return false;
+    };
+    observers.remove(pos);
+    HAS_OBSERVERS.store(!observers.is_empty(), Ordering::Release);
+    true
+}
+
+/// Compare the hooks before and after a change, if anyone is observing.
+pub(crate) fn diff(
+    hookable: &str,
+    location: &'static Location<'static>,
+    before: &[HookEntry],
+    after: &[HookEntry],
+) -> Vec<HookEvent> {
+    if !HAS_OBSERVERS.load(Ordering::Acquire) {
+        return Vec::new();
+    }
+    let event = |kind, entry: &HookEntry| HookEvent {
+        hookable: hookable.to_string(),
+        kind,
+        hook: entry.info(),
+        location,
+    };
+    let mut events = Vec::new();
+    for old in before {
+        if !after.iter().any(|new| new.id == old.id) {
+            events.push(event(HookEventKind::Removed, old));
+        }
+    }
+    for new in after {
+        match before.iter().find(|old| old.id == new.id) {
+            None => events.push(event(HookEventKind::Added, new)),
+            Some(old) if old.enabled != new.enabled => events.push(event(
+                if new.enabled {
+                    HookEventKind::Enabled
+                } else {
+                    HookEventKind::Disabled
+                },
+                new,
+            )),
+            Some(_) => {}
+        }
+    }
+    events
+}
+
+/// Pass the events to the observers. Must not be called while holding the lock of a hookable function.
+pub(crate) fn notify(events: Vec<HookEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let observers = lock::read(&OBSERVERS)
+        .iter()
+        .map(|(_, observer)| observer.clone())
+        .collect::<Vec<_>>();
+    for event in &events {
+        for observer in &observers {
+            observer(event);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Ready-made hooks injecting faults into hookable functions, for chaos testing.
+//!
+//! Each [`FaultHook`] injects its fault with a configurable probability, and calls `next` otherwise.
+//! The probability can be changed at runtime with [`FaultHook::set_probability`].
+//! [`LatencyHook`] slows down calls instead.
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::faults::FaultHook;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("faults-read")]
+//! fn read(path: &'static str) -> Result<String, String> {
+//!     Ok(format!("contents of {}", path))
+//! }
+//!
+//! let fault = Arc::new(FaultHook::<(&'static str,), Result<String, String>>::error(
+//!     1.0,
+//!     || "disk failure".to_string(),
+//! ));
+//! lookup_hookable("faults-read").unwrap().add_hook(fault.clone()).unwrap();
+//! assert_eq!(read("a.txt"), Err("disk failure".to_string()));
+//! fault.set_probability(0.0);
+//! assert_eq!(read("a.txt"), Ok("contents of a.txt".to_string()));
+//! ```
+
+use crate::{Hook, lock};
+use std::hash::{BuildHasher, RandomState};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A lock-free pseudo random number generator (SplitMix64), not suitable for cryptography.
+pub(crate) struct Rng(AtomicU64);
+
+impl Rng {
+    /// Create a generator with a random seed.
+    pub(crate) fn new() -> Self {
+        Self::with_seed(RandomState::new().hash_one(0u64))
+    }
+
+    /// Create a generator with a fixed seed, so the sequence is reproducible.
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        Rng(AtomicU64::new(seed))
+    }
+
+    pub(crate) fn next_u64(&self) -> u64 {
+        const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut z = self
+            .0
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed number in `[0, 1)`.
+    pub(crate) fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+type FaultFn<A, R> = Box<dyn Fn(A, &dyn Fn(A) -> R) -> R + Send + Sync>;
+
+/// A [`Hook`] injecting a fault with some probability, see the [module docs](self).
+///
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct FaultHook<A, R> {
+    /// The probability, stored as the bits of a `f64`.
+    probability: AtomicU64,
+    rng: Rng,
+    fault: FaultFn<A, R>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> FaultHook<A, R> {
+    fn new(probability: f64, fault: FaultFn<A, R>) -> Self {
+        Self {
+            probability: AtomicU64::new(probability.to_bits()),
+            rng: Rng::new(),
+            fault,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Panic with `message` instead of calling `next`.
+    pub fn panic(probability: f64, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::new(probability, Box::new(move |_, _| panic!("{}", message)))
+    }
+
+    /// Call `next`, then replace its result with the one returned by `corrupt`.
+    pub fn corrupt(probability: f64, corrupt: impl Fn(R) -> R + Send + Sync + 'static) -> Self {
+        Self::new(probability, Box::new(move |args, next| corrupt(next(args))))
+    }
+
+    /// Use a fixed seed for the random decisions, so they are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::with_seed(seed);
+        self
+    }
+
+    /// The probability to inject the fault, between 0 and 1.
+    pub fn probability(&self) -> f64 {
+        f64::from_bits(self.probability.load(Ordering::Relaxed))
+    }
+
+    /// Change the probability to inject the fault, between 0 and 1.
+    pub fn set_probability(&self, probability: f64) {
+        self.probability
+            .store(probability.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl<A, T, E> FaultHook<A, Result<T, E>> {
+    /// Return the error made by `make_error` instead of calling `next`.
+    pub fn error(probability: f64, make_error: impl Fn() -> E + Send + Sync + 'static) -> Self {
+        Self::new(probability, Box::new(move |_, _| Err(make_error())))
+    }
+}
+
+impl<A, R> Hook for FaultHook<A, R>
+where
+    A: 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        if self.rng.next_f64() < self.probability() {
+            (self.fault)(args, next)
+        } else {
+            next(args)
+        }
+    }
+}
+
+/// The distribution of the delays injected by [`LatencyHook`].
+#[derive(Clone)]
+pub enum Latency {
+    /// Always the same delay.
+    Fixed(Duration),
+    /// Uniformly distributed between the two delays.
+    Uniform(Duration, Duration),
+    /// Exponentially distributed with the given mean.
+    Exponential(Duration),
+    /// Sampled by calling the function.
+    Custom(Arc<dyn Fn() -> Duration + Send + Sync>),
+}
+
+impl Latency {
+    fn sample(&self, rng: &Rng) -> Duration {
+        match self {
+            Latency::Fixed(delay) => *delay,
+            Latency::Uniform(min, max) => *min + max.saturating_sub(*min).mul_f64(rng.next_f64()),
+            Latency::Exponential(mean) => mean.mul_f64(-(1.0 - rng.next_f64()).ln()),
+            Latency::Custom(sample) => sample(),
+        }
+    }
+}
+
+/// A [`Hook`] sleeping for a delay sampled from a [`Latency`] distribution,
+/// before or after calling `next`, to simulate slow dependencies.
+/// The distribution can be changed at runtime with [`LatencyHook::set_latency`].
+///
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct LatencyHook<A, R> {
+    latency: Mutex<Latency>,
+    after: bool,
+    rng: Rng,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> LatencyHook<A, R> {
+    /// Sleep before calling `next`.
+    pub fn before(latency: Latency) -> Self {
+        Self {
+            latency: Mutex::new(latency),
+            after: false,
+            rng: Rng::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sleep after `next` returns.
+    pub fn after(latency: Latency) -> Self {
+        Self {
+            after: true,
+            ..Self::before(latency)
+        }
+    }
+
+    /// Use a fixed seed for the sampled delays, so they are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::with_seed(seed);
+        self
+    }
+
+    /// Change the distribution of the delays.
+    pub fn set_latency(&self, latency: Latency) {
+        *lock::lock(&self.latency) = latency;
+    }
+
+    fn sleep(&self) {
+        let latency = lock::lock(&self.latency).clone();
+        std::thread::sleep(latency.sample(&self.rng));
+    }
+}
+
+impl<A, R> Hook for LatencyHook<A, R>
+where
+    A: 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        if self.after {
+            let result = next(args);
+            self.sleep();
+            result
+        } else {
+            self.sleep();
+            next(args)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! A C API to manage hooks, available with the `ffi` feature.
+//!
+//! These functions are exported with unmangled names, so a C or C++ host application
+//! embedding a Rust library built as a `staticlib` or `cdylib` can inspect and toggle its hook points.
+//! The declarations are in `include/safe_hook.h`.
+//!
+//! Names are null-terminated UTF-8 strings, and hooks are identified by their id, see [`HookId::as_u64`].
+//! Functions return a non-negative value on success,
+//! or [`SAFE_HOOK_NOT_FOUND`], [`SAFE_HOOK_INVALID_ARGUMENT`] or [`SAFE_HOOK_REFUSED`].
+//! # Example
+//! ```c
+//! #include "safe_hook.h"
+//! #include <stdio.h>
+//!
+//! static void print_hookable(const char *name, size_t hooks, void *user_data) {
+//!     printf("%s: %zu hooks\n", name, hooks);
+//! }
+//!
+//! int main(void) {
+//!     safe_hook_list(print_hookable, NULL);
+//!     safe_hook_remove_by_name("add");
+//!     return 0;
+//! }
+//! ```
+
+use crate::{HookId, HookableFuncMetadata, iter_hookables, lookup_hookable};
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+
+/// The hookable function or the hook is not found.
+pub const SAFE_HOOK_NOT_FOUND: c_int = -1;
+/// A name is null or not valid UTF-8.
+pub const SAFE_HOOK_INVALID_ARGUMENT: c_int = -2;
+/// Hooks cannot be changed, see [`freeze`](crate::freeze) and [`require_capability`](crate::require_capability).
+pub const SAFE_HOOK_REFUSED: c_int = -3;
+
+/// Called by [`safe_hook_list`] with the name of a hookable function and its number of hooks.
+pub type SafeHookListCallback =
+    extern "C" fn(name: *const c_char, hooks: usize, user_data: *mut c_void);
+
+/// Called by [`safe_hook_list_hooks`] with the id, priority, state and description of a hook.
+pub type SafeHookListHooksCallback = extern "C" fn(
+    id: u64,
+    priority: i32,
+    enabled: bool,
+    description: *const c_char,
+    user_data: *mut c_void,
+);
+
+/// Find a hookable function by its C name.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string.
+unsafe fn find(name: *const c_char) -> Result<&'static HookableFuncMetadata, c_int> {
+    if name.is_null() {
+        return Err(SAFE_HOOK_INVALID_ARGUMENT);
+    }
+    let name = unsafe { CStr::from_ptr(name) }
+        .to_str()
+        .map_err(|_| SAFE_HOOK_INVALID_ARGUMENT)?;
+    lookup_hookable(name).ok_or(SAFE_HOOK_NOT_FOUND)
+}
+
+/// Find a hook of a hookable function by its id.
+fn find_hook(meta: &HookableFuncMetadata, id: u64) -> Result<HookId, c_int> {
+    meta.list_hooks()
+        .into_iter()
+        .map(|hook| hook.id)
+        .find(|hook| hook.as_u64() == id)
+        .ok_or(SAFE_HOOK_NOT_FOUND)
+}
+
+/// Convert a string to C, dropping interior null bytes.
+fn to_c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+/// Get the number of hooks attached to the hookable function `name`.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn safe_hook_lookup(name: *const c_char) -> c_int {
+    match unsafe { find(name) } {
+        Ok(meta) => meta.list_hooks().len() as c_int,
+        Err(code) => code,
+    }
+}
+
+/// Call `callback` for each hookable function, returning their number.
+/// The name passed to `callback` is only valid during the call.
+#[unsafe(no_mangle)]
+pub extern "C" fn safe_hook_list(callback: SafeHookListCallback, user_data: *mut c_void) -> c_int {
+    let mut count = 0;
+    for meta in iter_hookables() {
+        let name = to_c_string(meta.name());
+        callback(name.as_ptr(), meta.list_hooks().len(), user_data);
+        count += 1;
+    }
+    count
+}
+
+/// Call `callback` for each hook of the hookable function `name`, in calling order,
+/// returning their number.
+/// The description passed to `callback` is only valid during the call.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string,
+/// and `callback` must be safe to call with `user_data`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn safe_hook_list_hooks(
+    name: *const c_char,
+    callback: SafeHookListHooksCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let meta = match unsafe { find(name) } {
+        Ok(meta) => meta,
+        Err(code) => return code,
+    };
+    let hooks = meta.list_hooks();
+    for hook in &hooks {
+        let description = to_c_string(&hook.description);
+        callback(
+            hook.id.as_u64(),
+            hook.priority,
+            hook.enabled,
+            description.as_ptr(),
+            user_data,
+        );
+    }
+    hooks.len() as c_int
+}
+
+/// Remove all hooks of the hookable function `name`, returning the number of removed hooks.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn safe_hook_remove_by_name(name: *const c_char) -> c_int {
+    match unsafe { find(name) } {
+        Ok(meta) => {
+            let count = meta.list_hooks().len();
+            match meta.clear_hooks() {
+                Ok(()) => count as c_int,
+                Err(_) => SAFE_HOOK_REFUSED,
+            }
+        }
+        Err(code) => code,
+    }
+}
+
+/// Remove the hook `id` from the hookable function `name`, returning 0.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn safe_hook_remove(name: *const c_char, id: u64) -> c_int {
+    let result = unsafe { find(name) }.and_then(|meta| {
+        let id = find_hook(meta, id)?;
+        meta.remove_hook_by_id(id).map_err(|_| SAFE_HOOK_REFUSED)?;
+        Ok(0)
+    });
+    result.unwrap_or_else(|code| code)
+}
+
+/// Enable or disable the hook `id` of the hookable function `name`, returning 0.
+///
+/// # Safety
+/// `name` must be null or a valid null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn safe_hook_set_enabled(
+    name: *const c_char,
+    id: u64,
+    enabled: bool,
+) -> c_int {
+    let result = unsafe { find(name) }.and_then(|meta| {
+        let id = find_hook(meta, id)?;
+        meta.set_hook_enabled(id, enabled)
+            .map_err(|_| SAFE_HOOK_REFUSED)?;
+        Ok(0)
+    });
+    result.unwrap_or_else(|code| code)
+}
+
+/// Disable all hooks of all hookable functions, returning 0, see [`disable_all`](crate::disable_all).
+#[unsafe(no_mangle)]
+pub extern "C" fn safe_hook_disable_all() -> c_int {
+    match crate::disable_all() {
+        Ok(()) => 0,
+        Err(_) => SAFE_HOOK_REFUSED,
+    }
+}
+
+/// Enable hooks again after [`safe_hook_disable_all`], returning 0, see [`enable_all`](crate::enable_all).
+#[unsafe(no_mangle)]
+pub extern "C" fn safe_hook_enable_all() -> c_int {
+    match crate::enable_all() {
+        Ok(()) => 0,
+        Err(_) => SAFE_HOOK_REFUSED,
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    AddHookError, HookDyn, HookId, HookTransaction, HookableFuncMetadata, lock, lookup_hookable,
+};
+use std::sync::{Arc, Mutex};
+
+/// A bundle of hooks targeting hookable functions by name,
+/// installed and uninstalled as a whole.
+///
+/// Both [`HookSet::install`] and [`HookSet::uninstall`] are atomic,
+/// see [`HookTransaction`].
+#[derive(Default)]
+pub struct HookSet {
+    hooks: Vec<(String, Arc<dyn HookDyn>, i32)>,
+    installed: Mutex<Vec<(&'static HookableFuncMetadata, HookId)>>,
+}
+
+impl HookSet {
+    /// Create an empty hook set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hook targeting the hookable function named `target` to the set.
+    /// The hook is attached when the set is installed.
+    pub fn add_hook(&mut self, target: &str, hook: Arc<dyn HookDyn>, priority: i32) -> &mut Self {
+        self.hooks.push((target.to_string(), hook, priority));
+        self
+    }
+
+    /// Attach all hooks of the set to their targets.
+    /// Does nothing if the set is already installed.
+    /// If any target is not found or any hook cannot be added, no hook is attached.
+    #[track_caller]
+    pub fn install(&self) -> Result<(), AddHookError> {
+        let mut installed = lock::lock(&self.installed);
+        if !installed.is_empty() {
+            return Ok(());
+        }
+        let mut targets = Vec::with_capacity(self.hooks.len());
+        let mut transaction = HookTransaction::new();
+        for (name, hook, priority) in &self.hooks {
+            let target =
+                lookup_hookable(name).ok_or_else(|| AddHookError::NotFound(name.clone()))?;
+            targets.push(target);
+            transaction.add_hook(target, hook.clone(), *priority);
+        }
+        let ids = transaction.commit()?;
+        *installed = targets.into_iter().zip(ids).collect();
+        Ok(())
+    }
+
+    /// Detach all hooks of the set from their targets.
+    /// Does nothing if the set is not installed.
+    /// If the hooks cannot be removed, e.g. after [`freeze`](crate::freeze),
+    /// the set stays installed and the error is returned.
+    #[track_caller]
+    pub fn uninstall(&self) -> Result<(), AddHookError> {
+        let mut installed = lock::lock(&self.installed);
+        let mut transaction = HookTransaction::new();
+        for (target, id) in installed.iter() {
+            transaction.remove_hook_by_id(target, *id);
+        }
+        transaction.commit()?;
+        installed.clear();
+        Ok(())
+    }
+
+    /// Check whether the set is installed.
+    pub fn is_installed(&self) -> bool {
+        !lock::lock(&self.installed).is_empty()
+    }
+}
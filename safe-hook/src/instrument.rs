@@ -0,0 +1,376 @@
+use crate::{Hook, lock};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant, SystemTime};
+
+type LogSink = Box<dyn Fn(&str) + Send + Sync>;
+
+/// A [`Hook`] logging the arguments, the result and the duration of each call.
+///
+/// Lines are written to stderr by default, see [`LoggingHook::with_sink`]
+/// to send them to a logging library instead.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, LoggingHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("logging-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let hook = LoggingHook::<(i64, i64), i64>::new("add");
+/// lookup_hookable("logging-add").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// // Prints something like `add(1, 2) -> 3 (1.2µs)`.
+/// assert_eq!(add(1, 2), 3);
+/// ```
+pub struct LoggingHook<A, R> {
+    label: String,
+    sink: LogSink,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> LoggingHook<A, R> {
+    /// Create a hook logging calls to stderr, prefixed with `label`.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            sink: Box::new(|line| eprintln!("{}", line)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Write the lines with `sink` instead of printing them to stderr.
+    pub fn with_sink(mut self, sink: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+}
+
+impl<A, R> Hook for LoggingHook<A, R>
+where
+    A: Debug + 'static,
+    R: Debug + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let args_text = format!("{:?}", args);
+        let start = Instant::now();
+        let result = next(args);
+        let elapsed = start.elapsed();
+        // Single-argument tuples are printed as `(a,)`.
+        let args_text = match args_text.strip_suffix(",)") {
+            Some(prefix) => format!("{})", prefix),
+            None => args_text,
+        };
+        (self.sink)(&format!(
+            "{}{} -> {:?} ({:?})",
+            self.label, args_text, result, elapsed
+        ));
+        result
+    }
+
+    fn description(&self) -> String {
+        format!("LoggingHook({})", self.label)
+    }
+}
+
+/// A [`Hook`] opening a span around each call, for instrumentation libraries like `tracing`.
+///
+/// `enter` is called with the label and the arguments before calling `next`,
+/// and the returned guard is dropped once `next` returns, which closes the span.
+/// With `tracing`, `enter` would be something like
+/// `|name, args| tracing::info_span!("hook", name, args = ?args).entered()`,
+/// letting the subscriber record the timing.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use std::time::Instant;
+/// use safe_hook::{lookup_hookable, SpanHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("span-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// struct Span(&'static str, Instant);
+///
+/// impl Drop for Span {
+///     fn drop(&mut self) {
+///         println!("{} took {:?}", self.0, self.1.elapsed());
+///     }
+/// }
+///
+/// let hook = SpanHook::<_, i64, _>::new("add", |name, _args: &(i64, i64)| Span(name, Instant::now()));
+/// lookup_hookable("span-add").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(add(1, 2), 3);
+/// ```
+pub struct SpanHook<A, R, F> {
+    label: &'static str,
+    enter: F,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R, F, G> SpanHook<A, R, F>
+where
+    F: Fn(&'static str, &A) -> G,
+{
+    /// Create a hook opening spans with `enter`, named `label`.
+    pub fn new(label: &'static str, enter: F) -> Self {
+        Self {
+            label,
+            enter,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, R, F, G> Hook for SpanHook<A, R, F>
+where
+    A: 'static,
+    R: 'static,
+    F: Fn(&'static str, &A) -> G + Send + Sync + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let _span = (self.enter)(self.label, &args);
+        next(args)
+    }
+
+    fn description(&self) -> String {
+        format!("SpanHook({})", self.label)
+    }
+}
+
+/// A [`Hook`] opening a `tracing` span around each call, available with the `tracing` feature.
+///
+/// The span is named `hookable` at the `INFO` level, with the fields `name`,
+/// the name of the hookable function, `args`, the arguments formatted with [`Debug`],
+/// and `elapsed`, the duration of the call including the rest of the chain, recorded once it returns.
+/// Unlike [`SpanHook`], it needs no label, so one hook can be attached to many hookable functions.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, TracingSpanHook};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("tracing-span-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let hook = TracingSpanHook::<(i64, i64), i64>::new();
+/// lookup_hookable("tracing-span-add").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// // Opens a span like `hookable{name="tracing-span-add" args=(1, 2) elapsed=1.2µs}`.
+/// assert_eq!(add(1, 2), 3);
+/// ```
+#[cfg(feature = "tracing")]
+pub struct TracingSpanHook<A, R> {
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+#[cfg(feature = "tracing")]
+impl<A, R> TracingSpanHook<A, R> {
+    /// Create a hook opening a span for each call.
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<A, R> Default for TracingSpanHook<A, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<A, R> Hook for TracingSpanHook<A, R>
+where
+    A: Debug + 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let name = crate::call_info().map_or("", |info| info.name);
+        let span = tracing::info_span!(
+            "hookable",
+            name,
+            args = ?args,
+            elapsed = tracing::field::Empty
+        );
+        let _entered = span.enter();
+        let start = Instant::now();
+        let result = next(args);
+        span.record("elapsed", tracing::field::debug(start.elapsed()));
+        result
+    }
+
+    fn description(&self) -> String {
+        "TracingSpanHook".to_string()
+    }
+}
+
+/// A call captured by [`FlightRecorder`].
+#[derive(Debug, Clone)]
+pub struct CallRecord {
+    /// When the call started.
+    pub timestamp: SystemTime,
+    /// The thread making the call.
+    pub thread: ThreadId,
+    /// The name of the thread making the call, if any.
+    pub thread_name: Option<String>,
+    /// The arguments, formatted with [`Debug`].
+    pub args: String,
+    /// The result, formatted with [`Debug`], or `None` if the call panicked.
+    pub result: Option<String>,
+    /// How long the call took, including the rest of the chain.
+    pub duration: Duration,
+}
+
+impl std::fmt::Display for CallRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let since_epoch = self
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        write!(
+            f,
+            "[{}.{:06}] {:?}",
+            since_epoch.as_secs(),
+            since_epoch.subsec_micros(),
+            self.thread
+        )?;
+        if let Some(name) = &self.thread_name {
+            write!(f, " ({})", name)?;
+        }
+        match &self.result {
+            Some(result) => write!(f, " {} -> {} ({:?})", self.args, result, self.duration),
+            None => write!(f, " {} -> panicked ({:?})", self.args, self.duration),
+        }
+    }
+}
+
+/// A [`Hook`] keeping the last calls of a hookable function in a ring buffer,
+/// to be dumped on demand, e.g. after an incident.
+///
+/// The arguments are formatted before the call, and the result after it,
+/// calls that panic are recorded too.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, FlightRecorder};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("flight-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// let recorder = Arc::new(FlightRecorder::<(i64, i64), i64>::new(100));
+/// lookup_hookable("flight-add").unwrap().add_hook(recorder.clone()).unwrap();
+/// add(1, 2);
+/// for record in recorder.dump() {
+///     println!("{}", record);
+/// }
+/// ```
+pub struct FlightRecorder<A, R> {
+    capacity: usize,
+    records: Mutex<VecDeque<CallRecord>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> FlightRecorder<A, R> {
+    /// Create a recorder keeping the last `capacity` calls.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the recorded calls, oldest first.
+    pub fn dump(&self) -> Vec<CallRecord> {
+        lock::lock(&self.records).iter().cloned().collect()
+    }
+
+    /// Discard the recorded calls.
+    pub fn clear(&self) {
+        lock::lock(&self.records).clear();
+    }
+
+    fn push(&self, record: CallRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = lock::lock(&self.records);
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// Records a call when dropped, so calls that panic are recorded too.
+struct PendingRecord<'a, A, R> {
+    recorder: &'a FlightRecorder<A, R>,
+    record: Option<CallRecord>,
+    start: Instant,
+}
+
+impl<A, R> Drop for PendingRecord<'_, A, R> {
+    fn drop(&mut self) {
+        if let Some(mut record) = self.record.take() {
+            record.duration = self.start.elapsed();
+            self.recorder.push(record);
+        }
+    }
+}
+
+impl<A, R> Hook for FlightRecorder<A, R>
+where
+    A: Debug + 'static,
+    R: Debug + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let thread = std::thread::current();
+        let mut pending = PendingRecord {
+            recorder: self,
+            record: Some(CallRecord {
+                timestamp: SystemTime::now(),
+                thread: thread.id(),
+                thread_name: thread.name().map(str::to_string),
+                args: format!("{:?}", args),
+                result: None,
+                duration: Duration::ZERO,
+            }),
+            start: Instant::now(),
+        };
+        let result = next(args);
+        if let Some(record) = &mut pending.record {
+            record.result = Some(format!("{:?}", result));
+        }
+        result
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{AddHookError, ChainFn, HookableFuncMetadata, call_with_hook, stored_type_names};
+use std::any::{Any, TypeId};
+
+/// The type-erased call of a hookable function, see [`HookableFuncMetadata::invoke`].
+pub(crate) type InvokeFn =
+    fn(&'static HookableFuncMetadata, Box<dyn Any>) -> Result<Box<dyn Any>, Box<dyn Any>>;
+
+/// The JSON call of a hookable function, see [`HookableFuncMetadata::invoke_json`].
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub type JsonInvokeFn =
+    fn(&'static HookableFuncMetadata, serde_json::Value) -> Result<serde_json::Value, InvokeError>;
+
+/// The error returned by [`HookableFuncMetadata::invoke`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvokeError {
+    /// The arguments are not of the arguments tuple type of the hookable function.
+    ArgsMismatch {
+        /// The type name of the expected arguments tuple.
+        expected: &'static str,
+    },
+    /// The arguments or the result of the hookable function contain references,
+    /// so it cannot be called dynamically.
+    /// For [`HookableFuncMetadata::invoke_json`], the function is not declared with the `json` option.
+    NotSupported,
+    /// The arguments could not be deserialized, or the result could not be serialized.
+    Json(String),
+}
+
+impl std::fmt::Display for InvokeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvokeError::ArgsMismatch { expected } => {
+                write!(f, "Arguments type mismatch: expected {}", expected)
+            }
+            InvokeError::NotSupported => {
+                write!(f, "Hookable with references cannot be invoked dynamically")
+            }
+            InvokeError::Json(err) => write!(f, "JSON error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for InvokeError {}
+
+/// Call a hookable function with type-erased arguments.
+/// It is used inside the macro [`hookable`](crate::hookable) for functions without references.
+#[doc(hidden)]
+pub fn invoke_erased<R: 'static, A: 'static>(
+    meta: &'static HookableFuncMetadata,
+    args: Box<dyn Any>,
+) -> Result<Box<dyn Any>, Box<dyn Any>> {
+    let args = args.downcast::<A>()?;
+    Ok(Box::new(call_with_hook::<R, A>(meta, *args, None)))
+}
+
+impl HookableFuncMetadata {
+    /// Call the hookable function through its hooks, with the arguments tuple boxed,
+    /// and return the boxed result.
+    /// Useful for debugging consoles and scripting layers,
+    /// which do not know the signature at compile time.
+    ///
+    /// Only functions whose arguments and result contain no references can be invoked,
+    /// see [`InvokeError::NotSupported`].
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("invoke-add")]
+    /// fn add(left: i64, right: i64) -> i64 {
+    ///     left + right
+    /// }
+    ///
+    /// let add_hookable = lookup_hookable("invoke-add").unwrap();
+    /// let result = add_hookable.invoke(Box::new((1i64, 2i64))).unwrap();
+    /// assert_eq!(*result.downcast::<i64>().unwrap(), 3);
+    /// assert!(add_hookable.invoke(Box::new((1i32, 2i32))).is_err());
+    /// ```
+    pub fn invoke(&'static self, args: Box<dyn Any>) -> Result<Box<dyn Any>, InvokeError> {
+        let invoke = self.invoke.ok_or(InvokeError::NotSupported)?;
+        invoke(self, args).map_err(|_| InvokeError::ArgsMismatch {
+            expected: self.args_type_name(),
+        })
+    }
+
+    /// Call the original function directly, without its hooks, e.g. for comparison or fallback logic.
+    /// `A` is the arguments tuple and `R` is the result, like for [`Hook`](crate::Hook),
+    /// [`AddHookError::TypeMismatch`] is returned if they do not match the hookable function.
+    /// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+    ///
+    /// The call is not counted in statistics or coverage.
+    ///
+    /// Hookable functions of callbacks, see [`Hookable`](crate::Hookable) and
+    /// [`hookable_trait`](crate::hookable_trait), have no original function to call outside of a call,
+    /// [`AddHookError::NoOriginal`] is returned for them.
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("call-original-add")]
+    /// fn add(left: i64, right: i64) -> i64 {
+    ///     left + right
+    /// }
+    ///
+    /// let add_hookable = lookup_hookable("call-original-add").unwrap();
+    /// add_hookable
+    ///     .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+    ///     .unwrap();
+    /// assert_eq!(add(1, 2), 4);
+    /// assert_eq!(add_hookable.call_original::<(i64, i64), i64>((1, 2)), Ok(3));
+    /// assert!(add_hookable.call_original::<(i32, i32), i32>((1, 2)).is_err());
+    /// ```
+    pub fn call_original<A: 'static, R: 'static>(&self, args: A) -> Result<R, AddHookError> {
+        let found = (TypeId::of::<R>(), TypeId::of::<A>());
+        if found != self.type_info {
+            return Err(self.type_mismatch(found, stored_type_names::<R, A>()));
+        }
+        // The innermost callback of the thread may be of another hookable function, or running.
+        if self.callback {
+            return Err(AddHookError::NoOriginal);
+        }
+        // SAFETY: the types are checked above, and the original function is not a callback.
+        let composed =
+            unsafe { &*(self.bare.composed.as_ref() as *const dyn Any as *const ChainFn<A, R>) };
+        Ok(composed(args))
+    }
+}
+
+/// Call a hookable function with the arguments as a JSON array.
+/// It is used inside the macro [`hookable`](crate::hookable) for functions with the `json` option.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub fn invoke_json_erased<R, A>(
+    meta: &'static HookableFuncMetadata,
+    args: serde_json::Value,
+) -> Result<serde_json::Value, InvokeError>
+where
+    R: serde::Serialize + 'static,
+    A: serde::de::DeserializeOwned + 'static,
+{
+    let args =
+        serde_json::from_value::<A>(args).map_err(|err| InvokeError::Json(err.to_string()))?;
+    let result = call_with_hook::<R, A>(meta, args, None);
+    serde_json::to_value(result).map_err(|err| InvokeError::Json(err.to_string()))
+}
+
+#[cfg(feature = "serde")]
+impl HookableFuncMetadata {
+    /// Call the hookable function through its hooks, with the arguments as a JSON array,
+    /// and return the result as JSON, available with the `serde` feature.
+    /// Useful for remote tooling calling functions by name.
+    ///
+    /// Only functions declared with the `json` option of [`hookable`](crate::hookable) can be invoked,
+    /// their arguments must implement `Deserialize` and their result `Serialize`.
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    /// use serde_json::json;
+    ///
+    /// #[hookable("json-greet", json)]
+    /// fn greet(name: String, times: usize) -> Vec<String> {
+    ///     vec![format!("Hello, {}!", name); times]
+    /// }
+    ///
+    /// let greet_hookable = lookup_hookable("json-greet").unwrap();
+    /// let result = greet_hookable.invoke_json(json!(["amy", 2])).unwrap();
+    /// assert_eq!(result, json!(["Hello, amy!", "Hello, amy!"]));
+    /// ```
+    pub fn invoke_json(
+        &'static self,
+        args: serde_json::Value,
+    ) -> Result<serde_json::Value, InvokeError> {
+        let invoke = self.invoke_json.ok_or(InvokeError::NotSupported)?;
+        invoke(self, args)
+    }
+
+    /// Set the JSON call of the hookable function.
+    /// It is used inside the macro [`hookable`](crate::hookable) for functions with the `json` option.
+    #[doc(hidden)]
+    pub fn with_invoke_json(mut self, invoke: JsonInvokeFn) -> Self {
+        self.invoke_json = Some(invoke);
+        self
+    }
+}
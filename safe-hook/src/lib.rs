@@ -1,9 +1,9 @@
 //! Safe-Hook is an inline hook library for Rust.
 //! It provides a simple and safe way to create hooks in your Rust applications,
 //! allowing you to modify the behavior of functions at runtime.
-//! 
+//!
 //! The design principle of Safe-Hook is safety and simplicity.
-//! 
+//!
 //! ## Features
 //! - **Inline Hooking**: Safe-Hook allows you to hook into functions at runtime,
 //!   enabling you to modify their behavior.
@@ -13,32 +13,39 @@
 //!   allowing you to add and remove hooks at runtime without any restrictions.
 //! - **Cross-Platform**: Safe-Hook is designed to work on multiple platforms,
 //!   it theoretically supports all platforms that Rust supports.
-//! 
+//!   On wasm targets, where `inventory` may not collect registrations,
+//!   hookable functions are registered on their first call instead,
+//!   or explicitly with [`register_hookables!`].
+//!
 //! ## Limitations
 //! - **Intrusive**: Needs to annotate target functions manually.
 //!   Which means it's not suitable for hook third-party libraries.
-//! 
-//! 
+//! - **Requires `std`**: `no_std` targets are not supported.
+//!   Calls keep per-thread state (the call context, reentrancy depth and panic reports)
+//!   in thread-locals, hooks are published with the default collector of `crossbeam-epoch`,
+//!   which needs `std`, and hookable functions use `std::sync::LazyLock`.
+//!
+//!
 //! ## Usage
 //! More Examples:
 //! - [Hook a function with reference parameters](#hook-a-function-with-reference-parameters)
-//! 
+//!
 //! Simple Usage:
 //! ```rust
 //! use std::sync::Arc;
 //! use safe_hook::{lookup_hookable, Hook};
 //! use safe_hook_macros::hookable;
-//! 
+//!
 //! #[hookable("add")]
 //! fn add(left: i64, right: i64) -> i64 {
 //!     left + right
 //! }
-//! 
+//!
 //! #[derive(Debug)]
 //! struct HookAdd {
 //!     x: i64,
 //! }
-//! 
+//!
 //! impl Hook for HookAdd {
 //!     type Args<'a> = (i64, i64);
 //!     type Result = i64;
@@ -46,7 +53,7 @@
 //!         next(args) + self.x
 //!     }
 //! }
-//! 
+//!
 //! fn main() {
 //!     let hook = Arc::new(HookAdd {
 //!         x: 1,
@@ -56,30 +63,199 @@
 //!     assert_eq!(add(1, 2), 4);
 //! }
 //! ```
-//! 
+//!
+//! ## Stripping
+//! Building with `RUSTFLAGS="--cfg safe_hook_strip"` makes [`hookable`] emit the original function unchanged,
+//! without any extra code or registration.
+//! Useful to remove all overhead in release builds while keeping hooks in development.
+//! It is a cfg rather than a Cargo feature, so enabling all features does not remove hookable functions.
+//!
+//! ## Cargo Features
+//! - `hashed-names`: Keeps only stable 64-bit hashes of the names of hookable functions in the binary,
+//!   see [`hashed_name`]. Lookups by name still work, and `HookableFuncMetadata::name` returns the hash.
+//! - `strip-names`: Omits the names and type names of hookable functions in release builds,
+//!   to reduce the binary size. They can only be found by path with [`hookable_meta!`].
+//! - `metrics`: Report calls, errors and latency of hookable functions to a recorder,
+//!   see the `metrics` module.
+//! - `stats`: Count calls and their cumulative time for each hookable function,
+//!   see `HookableFuncMetadata::stats`. Adds two clock reads to every call.
+//! - `serde`: Record and replay calls with `serde`, see the `replay` module,
+//!   call hookable functions with JSON, see `HookableFuncMetadata::invoke_json`,
+//!   serialize the state of the registry, see `registry_summary`,
+//!   and bridge hooks to scripting engines, see the `script` module.
+//! - `process`: Run hooks in other processes, e.g. Python functions, see the `process` module,
+//!   enables `serde`.
+//! - `coverage`: Track which hookable functions were called, see the `coverage` module.
+//! - `admin`: A control socket to manage hooks of a running process, see the `admin` module.
+//! - `plugin`: Load hooks from shared libraries at runtime, see the `plugin` module.
+//! - `abi_stable`: Attach hooks of plugins built independently of the host,
+//!   with the layouts of their types checked by `abi_stable`, see the `stable` module, enables `plugin`.
+//! - `ffi`: A C API to manage hooks from a host application, see the `ffi` module.
+//! - `metrics-rs`: Forward metrics to the `metrics` crate facade with `metrics::FacadeRecorder`,
+//!   enables `metrics`.
+//! - `tracing`: Open `tracing` spans around calls of hookable functions, see `TracingSpanHook`.
+//! - `linkme`: Collect hookable functions with a `linkme` distributed slice instead of `inventory`,
+//!   see [Registration](#registration).
+//! - `axum`: Serve the admin endpoints from an `axum` application, see `admin::router`,
+//!   enables `admin`.
+//! - `wasmtime`: Run hooks implemented as WebAssembly modules with fuel and time limits,
+//!   see the `wasm` module, enables `serde`.
+//! - `rhai`: Run hooks implemented as Rhai scripts, see the `rhai` module, enables `serde`.
+//! - `mlua`: Run hooks implemented as Lua functions, see the `lua` module, enables `serde`.
+//! - `pyo3`: Run hooks implemented as Python callables with `pyo3`, see the `python` module.
+//!
+//! ## Registration
+//! Hookable functions are collected before `main` by `inventory`, which keeps them in link sections,
+//! or by a `linkme` distributed slice with the `linkme` feature, which needs no constructor at startup.
+//! Where the sections are not collected, e.g. with some linkers or on some wasm targets,
+//! register hookable functions explicitly with [`register_hookables!`],
+//! once at startup before looking them up.
+//!
 //! ## Performance
 //! Extra overhead:
 //! - No Hook Added: One atomic load and one branch jump,
 //!   which should be very lightweight in most cases.
-//! - Hooks Added: There is a read/write lock (just some atomic operations in most cases),
-//!   some additional function calls via pointers,
+//! - Hooks Added: The hooks are loaded from a lock-free snapshot
+//!   (a short epoch pin and a reference count increment), some additional function calls via pointers,
 //!   and some copy operations to pack parameters into a tuple.
-//! 
-//! A sloppy benchmark (uses 12700H) shows that the extra overhead is
-//! about 0.5ns when no hooks are added
-//! (as a comparison, an `add(a,b)` function takes about 0.5ns),
-//! about 14ns when hooks are added,
-//! and that each additional hook results in about 2ns of overhead.
-
-use std::any::TypeId;
-use std::cell::Cell;
-use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, LazyLock, RwLock};
+//!
+//! A sloppy benchmark shows that the extra overhead is
+//! about the cost of the call itself when no hooks are added
+//! (about 1ns, as is an `add(a,b)` function),
+//! about 40ns when hooks are added, most of it spent once per call,
+//! and that each additional hook results in little additional overhead.
+//! The id and the timestamp of [`call_info`] are only taken when requested,
+//! so calls that do not use it do not read the clock.
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, LazyLock, RwLock, RwLockWriteGuard};
 
 #[doc(hidden)]
 pub use inventory;
+#[cfg(feature = "linkme")]
+#[doc(hidden)]
+pub use linkme;
+
+/// Expands to the first group with the `safe_hook_strip` cfg, and to the second otherwise,
+/// so the code generated by the macros follows the cfg of this crate.
+#[cfg(safe_hook_strip)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_stripped {
+    ({ $($stripped:tt)* } { $($hooked:tt)* }) => { $($stripped)* };
+}
+#[cfg(not(safe_hook_strip))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_stripped {
+    ({ $($stripped:tt)* } { $($hooked:tt)* }) => { $($hooked)* };
+}
+
+mod access;
+mod adapter;
+#[cfg(feature = "admin")]
+pub mod admin;
+pub mod audit;
+mod builder;
+mod callback;
+mod context;
+#[cfg(feature = "coverage")]
+pub mod coverage;
+mod events;
+pub mod faults;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod hook_set;
+mod instrument;
+mod invoke;
+mod lock;
+#[cfg(feature = "mlua")]
+pub mod lua;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod panic_guard;
+pub mod panic_report;
+mod pending;
+#[cfg(feature = "plugin")]
+pub mod plugin;
+mod probe;
+#[cfg(feature = "process")]
+pub mod process;
+mod profile;
+#[cfg(feature = "pyo3")]
+pub mod python;
+mod reentry;
+#[cfg(feature = "serde")]
+pub mod replay;
+mod resilience;
+#[cfg(feature = "rhai")]
+pub mod rhai;
+#[cfg(feature = "serde")]
+pub mod script;
+mod search;
+#[cfg(feature = "abi_stable")]
+pub mod stable;
+#[cfg(feature = "stats")]
+mod stats;
+mod summary;
+pub mod testing;
+mod transaction;
+mod untyped;
+#[cfg(feature = "wasmtime")]
+pub mod wasm;
+pub use access::{Capability, freeze, is_frozen, require_capability};
+pub use adapter::{
+    ConditionalHook, FnHook, HookExt, InstanceHook, IntoHook, MemoHook, Post, PostHook, Pre,
+    PreHook, RateLimited, Sampled, StatefulHook, Take,
+};
+#[doc(hidden)]
+pub use builder::register_static;
+pub use builder::{HookPoint, HookableFuncBuilder};
+pub use callback::Hookable;
+#[doc(hidden)]
+pub use callback::{call_callback, callback_metadata};
+pub use context::{CallInfo, HookContext, InstanceKey, call_info, with_context};
+pub use events::{
+    HookEvent, HookEventKind, ObserverId, add_registry_observer, remove_registry_observer,
+};
+pub use hook_set::HookSet;
+#[cfg(feature = "tracing")]
+pub use instrument::TracingSpanHook;
+pub use instrument::{CallRecord, FlightRecorder, LoggingHook, SpanHook};
+pub use invoke::InvokeError;
+#[doc(hidden)]
+pub use invoke::invoke_erased;
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use invoke::{JsonInvokeFn, invoke_json_erased};
+#[doc(hidden)]
+pub use panic_guard::{CloneProbe, ProbeClone, ProbeNotClone};
+pub use pending::{
+    GlobalHookId, add_global_hook, add_hook_pending, attach_pending, pending_errors, pending_hooks,
+    remove_global_hook, remove_pending,
+};
+#[doc(hidden)]
+pub use probe::{ErrProbe, ProbeNotResult, ProbeResult};
+pub use profile::{
+    ProfileError, active_profiles, disable_profile, enable_profile, is_profile_active,
+    register_profile, registered_profiles, unregister_profile,
+};
+pub use resilience::{CircuitBreaker, CircuitState, RetryHook};
+pub use search::{
+    attach_to_all_matching, lookup_by_signature, lookup_hookables_matching, similar_hookables,
+};
+#[cfg(feature = "stats")]
+pub use stats::CallStats;
+pub use summary::{HookableSummary, RegistrySummary, registry_summary};
+pub use transaction::HookTransaction;
+pub use untyped::{UntypedCall, UntypedHook};
 
-pub use safe_hook_macros::hookable;
+pub use safe_hook_macros::{
+    hook_test, hookable, hookable_meta, hookable_trait, register_hookables,
+};
 /// A Trait for hooks.
 /// Implements this trait to create a hook.
 pub trait Hook: Send + Sync + 'static {
@@ -96,11 +272,110 @@ pub trait Hook: Send + Sync + 'static {
     /// # Parameters:
     /// - `args`: The arguments of the target function.
     /// - `next`: The next function to call. This is the next hook or original target function.
+    ///
+    /// Hooks can be added to or removed from any hookable function inside this method,
+    /// including the one being called, e.g. a hook that removes itself after the first call.
+    /// Such changes take effect from the next call,
+    /// the current call always completes with the hooks it started with.
     fn call<'a>(
         &'a self,
         args: Self::Args<'a>,
         next: &dyn for<'c> Fn(Self::Args<'c>) -> Self::Result,
     ) -> Self::Result;
+
+    /// A human-readable description of the hook, shown by [`HookableFuncMetadata::list_hooks`].
+    /// Defaults to the type name of the hook.
+    fn description(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// Descriptions of hooks this hook must be called before.
+    /// The hooks of a hookable function are ordered to satisfy these constraints,
+    /// adding a hook that makes them unsatisfiable fails with [`AddHookError::OrderCycle`].
+    fn runs_before(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Descriptions of hooks this hook must be called after, see [`Hook::runs_before`].
+    fn runs_after(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The semantic version of the contract of the hookable function this hook was written for,
+    /// e.g. `"1.2.0"`. It cannot be attached to a hookable function of an incompatible version,
+    /// see [`HookableFuncMetadata::version`].
+    /// Defaults to `None`, compatible with every version.
+    fn version(&self) -> Option<String> {
+        None
+    }
+
+    /// Called right after the hook is attached to `hookable`, once per attachment,
+    /// e.g. to start a worker thread or open a file used by the hook.
+    /// It is called after the hookable function is unlocked,
+    /// so other threads may already be calling the hook.
+    fn on_attach(&self, hookable: &HookableFuncMetadata) {
+        let _ = hookable;
+    }
+
+    /// Called right after the hook is detached from `hookable`, once per attachment,
+    /// e.g. to release the resources acquired in [`Hook::on_attach`].
+    /// Calls that started before the hook was detached may still be running.
+    fn on_detach(&self, hookable: &HookableFuncMetadata) {
+        let _ = hookable;
+    }
+
+    /// Called right before [`Hook::on_attach`] with the id of the attachment,
+    /// so wrapped hooks can detach themselves by id, see [`Take`].
+    #[doc(hidden)]
+    fn attached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        let _ = (hookable, id);
+    }
+
+    /// Called right before [`Hook::on_detach`] with the id of the attachment, see [`Hook::attached`].
+    #[doc(hidden)]
+    fn detached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        let _ = (hookable, id);
+    }
+
+    /// An [`Observer`] of the same types if the hook never changes the call, see [`Post`].
+    #[doc(hidden)]
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>> {
+        None
+    }
+}
+
+/// The arguments of a hookable function declared with the `by_ref` option, see [`hookable`].
+/// Hooks receive `&mut ByRef<(A, B, ...)>`, and access the arguments tuple through [`Deref`](std::ops::Deref).
+///
+/// The arguments are moved into the original function when it is called,
+/// accessing them afterwards, or calling `next` twice, panics.
+pub struct ByRef<T>(Option<T>);
+
+impl<T> ByRef<T> {
+    #[doc(hidden)]
+    pub fn new(args: T) -> Self {
+        Self(Some(args))
+    }
+
+    #[doc(hidden)]
+    pub fn take(&mut self) -> T {
+        self.0.take().expect(BY_REF_MOVED)
+    }
+}
+
+const BY_REF_MOVED: &str = "The arguments were moved into the original function";
+
+impl<T> std::ops::Deref for ByRef<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.0.as_ref().expect(BY_REF_MOVED)
+    }
+}
+
+impl<T> std::ops::DerefMut for ByRef<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.0.as_mut().expect(BY_REF_MOVED)
+    }
 }
 
 /// A trait for dynamic dispatch of hooks.
@@ -108,31 +383,120 @@ pub trait Hook: Send + Sync + 'static {
 /// **THIS TRAIT SHOULD NEVER BE IMPLEMENTED BY USER CODE.**
 #[doc(hidden)]
 pub unsafe trait HookDyn: Send + Sync {
-    fn get_call_fn(&self) -> *const ();
+    /// Wrap `next`, a `Box<dyn Fn(Args<'static>) -> Result + Send + Sync>`,
+    /// into a callable of the same type that calls this hook.
+    fn compose(self: Arc<Self>, next: Box<dyn Any + Send + Sync>) -> Box<dyn Any + Send + Sync>;
     fn type_info(&self) -> (TypeId, TypeId);
+    /// The names of the types of [`HookDyn::type_info`], empty if unknown.
+    fn type_names(&self) -> (&'static str, &'static str) {
+        ("", "")
+    }
+    fn description(&self) -> String;
+    fn runs_before(&self) -> Vec<String>;
+    fn runs_after(&self) -> Vec<String>;
+    fn on_attach(&self, hookable: &HookableFuncMetadata);
+    fn on_detach(&self, hookable: &HookableFuncMetadata);
+    fn attached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        let _ = (hookable, id);
+    }
+    fn detached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        let _ = (hookable, id);
+    }
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>>;
+    fn version(&self) -> Option<String> {
+        None
+    }
+    /// The fingerprints of the result and the arguments types, see [`type_fingerprint`],
+    /// checked in addition to [`HookDyn::type_info`] since hooks may come from other builds.
+    fn fingerprint(&self) -> Option<(u64, u64)> {
+        None
+    }
+    /// The [`UntypedHook`] if the hook is one, composed by [`compose_chain`] instead of [`HookDyn::compose`].
+    #[doc(hidden)]
+    fn untyped(&self) -> Option<&untyped::UntypedEntry> {
+        None
+    }
 }
 
-/// A wrapper layer to avoid the calling convention difference between &T and *const ().
-unsafe fn hook_call_wrapper<'a, T: Hook + 'static>(
-    self_ptr: *const (),
-    args: <T as Hook>::Args<'a>,
-    next: &dyn for<'b> Fn(<T as Hook>::Args<'b>) -> <T as Hook>::Result,
-) -> <T as Hook>::Result {
-    let self_ref = unsafe { &*(self_ptr as *const T) };
-    self_ref.call(args, next)
-}
+/// A composed hook chain of a hookable function with the arguments `A` and the result `R`.
+/// Lifetimes in `A` are `'static`, see [`call_with_hook`].
+type ChainFn<A, R> = Box<dyn Fn(A) -> R + Send + Sync>;
 
 unsafe impl<T: Hook + 'static> HookDyn for T {
-    fn get_call_fn(&self) -> *const () {
-        hook_call_wrapper::<T> as *const ()
+    fn compose(self: Arc<Self>, next: Box<dyn Any + Send + Sync>) -> Box<dyn Any + Send + Sync> {
+        type Args<T> = <T as Hook>::Args<'static>;
+        let next = *next
+            .downcast::<ChainFn<Args<T>, T::Result>>()
+            .expect("Hook type mismatch");
+        let composed: ChainFn<Args<T>, T::Result> = Box::new(move |args| {
+            // SAFETY: lifetimes are erased in the chain, the real lifetime of the arguments
+            // lasts for the whole call, and the hook is kept alive by the chain.
+            // Only lifetimes are changed, so no function is called through a pointer of another type.
+            let (this, next) = unsafe {
+                (
+                    &*Arc::as_ptr(&self),
+                    std::mem::transmute::<
+                        &dyn Fn(Args<T>) -> T::Result,
+                        &dyn for<'c> Fn(T::Args<'c>) -> T::Result,
+                    >(next.as_ref()),
+                )
+            };
+            this.call(args, next)
+        });
+        Box::new(composed)
     }
     fn type_info(&self) -> (TypeId, TypeId) {
         let res = TypeId::of::<<T as Hook>::Result>();
         let args = TypeId::of::<<T as Hook>::Args<'static>>();
         (res, args)
     }
+    fn type_names(&self) -> (&'static str, &'static str) {
+        stored_type_names::<<T as Hook>::Result, <T as Hook>::Args<'static>>()
+    }
+    fn description(&self) -> String {
+        Hook::description(self)
+    }
+    fn runs_before(&self) -> Vec<String> {
+        Hook::runs_before(self)
+    }
+    fn runs_after(&self) -> Vec<String> {
+        Hook::runs_after(self)
+    }
+    fn on_attach(&self, hookable: &HookableFuncMetadata) {
+        Hook::on_attach(self, hookable)
+    }
+    fn on_detach(&self, hookable: &HookableFuncMetadata) {
+        Hook::on_detach(self, hookable)
+    }
+    fn attached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        Hook::attached(self, hookable, id)
+    }
+    fn detached(&self, hookable: &HookableFuncMetadata, id: HookId) {
+        Hook::detached(self, hookable, id)
+    }
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>> {
+        Hook::observer(self)
+    }
+    fn version(&self) -> Option<String> {
+        Hook::version(self)
+    }
+    fn fingerprint(&self) -> Option<(u64, u64)> {
+        Some((
+            type_fingerprint::<<T as Hook>::Result>(),
+            type_fingerprint::<<T as Hook>::Args<'static>>(),
+        ))
+    }
+}
+
+/// A read-only hook of a hookable function with the arguments `A` and the result `R`,
+/// called after the original function. Lifetimes in `A` are `'static`, see [`call_with_hook`].
+pub(crate) struct Observer<A, R> {
+    pub(crate) clone_args: fn(&A) -> A,
+    pub(crate) observe: ObserveFn<A, R>,
 }
 
+type ObserveFn<A, R> = Box<dyn Fn(&A, &R) + Send + Sync>;
+
 /// A registry entry for hookable functions.
 #[doc(hidden)]
 pub struct HookableFuncRegistry {
@@ -146,8 +510,55 @@ impl HookableFuncRegistry {
 
 inventory::collect!(HookableFuncRegistry);
 
+/// The hookable functions collected by `linkme` with the `linkme` feature.
+#[cfg(feature = "linkme")]
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static HOOKABLES: [HookableFuncRegistry];
+
+/// Submits the metadata of a hookable function to `inventory`, or to [`HOOKABLES`] with the `linkme` feature,
+/// so the code generated by the macros follows the features of this crate.
+#[cfg(feature = "linkme")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __submit {
+    ($meta:path) => {
+        #[::safe_hook::linkme::distributed_slice(::safe_hook::HOOKABLES)]
+        #[linkme(crate = ::safe_hook::linkme)]
+        static __SAFE_HOOK_REGISTRY: ::safe_hook::HookableFuncRegistry =
+            ::safe_hook::HookableFuncRegistry::new(&$meta);
+    };
+}
+#[cfg(not(feature = "linkme"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __submit {
+    ($meta:path) => {
+        ::safe_hook::inventory::submit! {
+            ::safe_hook::HookableFuncRegistry::new(&$meta)
+        }
+    };
+}
+
+/// The hookable functions collected before `main`, by `inventory` or by `linkme`.
+fn collected() -> impl Iterator<Item = &'static HookableFuncRegistry> {
+    #[cfg(feature = "linkme")]
+    return HOOKABLES.iter();
+    #[cfg(not(feature = "linkme"))]
+    return inventory::iter::<HookableFuncRegistry>.into_iter();
+}
+
 /// Lookup a hookable function by name.
+/// See [`similar_hookables`] to find the names closest to a name that is not found.
+/// Also attaches the hooks waiting for newly registered hookable functions, see [`add_hook_pending`].
+/// Held hooks failing to attach stay held, see [`pending_errors`].
 pub fn lookup_hookable(name: &str) -> Option<&'static HookableFuncMetadata> {
+    attach_pending();
+    lookup_registered(name)
+}
+
+/// Lookup a hookable function by name, without attaching pending hooks.
+fn lookup_registered(name: &str) -> Option<&'static HookableFuncMetadata> {
     // struct MyHashBuilder;
     // impl BuildHasher for MyHashBuilder {
     //     type Hasher = DefaultHasher;
@@ -157,26 +568,830 @@ pub fn lookup_hookable(name: &str) -> Option<&'static HookableFuncMetadata> {
     // }
     // static CACHE: Mutex<HashMap<String, &'static LazyLock<HookableFuncMetadata>, MyHashBuilder>> = Mutex::new(HashMap::with_hasher(MyHashBuilder{}));
 
-    for item in inventory::iter::<HookableFuncRegistry> {
+    let name = stored_name(name);
+    for item in collected() {
         if item.metadata.name == name {
             return Some(item.metadata);
         }
     }
-    None
+    lock::read(&REGISTERED)
+        .iter()
+        .find(|meta| meta.name == name)
+        .copied()
+}
+
+/// Get the stable 64-bit hash of the name of a hookable function (64-bit FNV-1a),
+/// the same across builds and platforms.
+pub fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Get the name stored for a hookable function named `name` with the `hashed-names` feature,
+/// which is returned by [`HookableFuncMetadata::name`] instead of the plaintext name.
+/// # Example
+/// ```rust
+/// use safe_hook::hashed_name;
+///
+/// assert_eq!(hashed_name("add"), "#e70bc3190530e654");
+/// ```
+pub fn hashed_name(name: &str) -> String {
+    format!("#{:016x}", name_hash(name))
+}
+
+/// Get a fingerprint of the type `T`, a hash of its name and layout,
+/// stable across builds of the same code unlike [`TypeId`].
+/// It is checked in addition to the [`TypeId`] when attaching hooks,
+/// so hooks from dynamically loaded code built differently are rejected.
+///
+/// With the `strip-names` feature, release builds only hash the layout.
+/// # Example
+/// ```rust
+/// use safe_hook::type_fingerprint;
+///
+/// assert_eq!(type_fingerprint::<(i64, i64)>(), type_fingerprint::<(i64, i64)>());
+/// assert_ne!(type_fingerprint::<i32>(), type_fingerprint::<i64>());
+/// ```
+pub fn type_fingerprint<T>() -> u64 {
+    let name = stored_type_names::<T, ()>().0;
+    let layout = [std::mem::size_of::<T>(), std::mem::align_of::<T>()];
+    layout.iter().fold(name_hash(name), |hash, value| {
+        value.to_le_bytes().iter().fold(hash, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    })
+}
+
+/// The name stored for a hookable function named `name`, see [`hashed_name`].
+pub(crate) fn stored_name(name: &str) -> Cow<'_, str> {
+    if cfg!(feature = "hashed-names") {
+        Cow::Owned(hashed_name(name))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Whether the names of hookable functions are kept in plain text,
+/// i.e. neither the `hashed-names` feature nor the `strip-names` feature in release builds is enabled.
+pub(crate) fn names_retained() -> bool {
+    !cfg!(any(
+        feature = "hashed-names",
+        all(feature = "strip-names", not(debug_assertions))
+    ))
+}
+
+/// The type names stored for a hookable function,
+/// empty in release builds with the `strip-names` feature.
+pub(crate) fn stored_type_names<R, A>() -> (&'static str, &'static str) {
+    if cfg!(all(feature = "strip-names", not(debug_assertions))) {
+        ("", "")
+    } else {
+        (std::any::type_name::<R>(), std::any::type_name::<A>())
+    }
+}
+
+/// Check whether two semantic versions are compatible, like Cargo does:
+/// same major version, or same minor version for `0.x` versions.
+fn versions_compatible(expected: &str, found: &str) -> bool {
+    fn key(version: &str) -> Option<(u64, Option<u64>)> {
+        let mut parts = version.trim().trim_start_matches('v').split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map(str::parse).transpose().ok()?;
+        Some((major, if major == 0 { minor } else { None }))
+    }
+    matches!((key(expected), key(found)), (Some(expected), Some(found)) if expected == found)
+}
+
+/// Hookable functions registered at runtime, see [`HookableFuncBuilder::register`].
+static REGISTERED: RwLock<Vec<&'static HookableFuncMetadata>> = RwLock::new(Vec::new());
+
+/// Iterate over all hookable functions registered in the process.
+pub fn iter_hookables() -> impl Iterator<Item = &'static HookableFuncMetadata> {
+    collected()
+        .map(|item| &**item.metadata)
+        .chain(lock::read(&REGISTERED).clone())
+}
+
+static ALL_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Names of hookable functions listed in the `SAFE_HOOK_DISABLE` environment variable,
+/// separated by commas. Read once, when the first hookable function is initialized.
+/// Kept as stored names, see [`stored_name`].
+static ENV_DISABLED: LazyLock<Vec<String>> = LazyLock::new(|| {
+    std::env::var("SAFE_HOOK_DISABLE")
+        .map(|names| {
+            names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| stored_name(name).into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+});
+
+/// Disable all hooks of all hookable functions, without removing them.
+/// Every hookable function takes the fast path until [`enable_all`] is called.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// if hooks cannot be changed, see [`freeze`].
+#[track_caller]
+pub fn disable_all() -> Result<(), AddHookError> {
+    access::check()?;
+    ALL_DISABLED.store(true, std::sync::atomic::Ordering::Release);
+    update_all_fast_path_flags();
+    Ok(())
+}
+
+/// Undo [`disable_all`], so attached hooks are called again.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// if hooks cannot be changed, see [`freeze`].
+#[track_caller]
+pub fn enable_all() -> Result<(), AddHookError> {
+    access::check()?;
+    ALL_DISABLED.store(false, std::sync::atomic::Ordering::Release);
+    update_all_fast_path_flags();
+    Ok(())
+}
+
+fn update_all_fast_path_flags() {
+    for meta in iter_hookables() {
+        let hooks = lock::read(&meta.hooks);
+        meta.update_fast_path_flag(&hooks);
+    }
+}
+
+/// Check whether all hooks are disabled by [`disable_all`].
+pub fn is_all_disabled() -> bool {
+    ALL_DISABLED.load(std::sync::atomic::Ordering::Acquire)
 }
 
 struct HookableFuncPtr(*const ());
 unsafe impl Send for HookableFuncPtr {}
 unsafe impl Sync for HookableFuncPtr {}
 
+/// A unique identifier of a hook attached to a hookable function.
+/// Returned by [`HookableFuncMetadata::add_hook`] and friends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct HookId(u64);
+
+impl HookId {
+    fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+    }
+
+    /// Get the id as a number, unique in the process.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Information about a hook attached to a hookable function.
+/// Returned by [`HookableFuncMetadata::list_hooks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HookInfo {
+    /// The id of the hook.
+    pub id: HookId,
+    /// The priority of the hook.
+    pub priority: i32,
+    /// Whether the hook is enabled. Disabled hooks are skipped when the function is called.
+    pub enabled: bool,
+    /// The description of the hook, see [`Hook::description`].
+    pub description: String,
+}
+
+/// The error returned when adding, removing or changing hooks fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AddHookError {
+    /// The types of the hook do not match the hookable function.
+    /// Type info is in the form of `(result, args)`, and signatures like `(i64, i64) -> i64`.
+    /// Signatures are empty if unknown, e.g. in release builds with the `strip-names` feature.
+    TypeMismatch {
+        expected: (TypeId, TypeId),
+        found: (TypeId, TypeId),
+        expected_signature: String,
+        found_signature: String,
+    },
+    /// The hook is already attached and the [`DuplicatePolicy`] is [`DuplicatePolicy::Reject`].
+    Duplicate(HookId),
+    /// No hookable function with the given name is registered.
+    NotFound(String),
+    /// The hook to insert next to is not attached,
+    /// see [`HookableFuncMetadata::add_hook_before`].
+    AnchorNotFound,
+    /// The ordering constraints of the hooks form a cycle, see [`Hook::runs_before`].
+    OrderCycle,
+    /// The ids passed to [`HookableFuncMetadata::reorder_hooks`]
+    /// are not exactly the ids of the attached hooks.
+    InvalidOrder,
+    /// The arguments or the result of the hookable function contain references,
+    /// so [`UntypedHook`]s cannot be attached.
+    UntypedNotSupported,
+    /// The arguments of the hookable function do not implement [`Clone`],
+    /// so a panicking hook cannot be skipped, see [`HookableFuncMetadata::set_panic_policy`].
+    ArgsNotClone,
+    /// A hookable function with the given name is already registered,
+    /// see [`HookableFuncBuilder::register`].
+    AlreadyRegistered(String),
+    /// Hooks can only be changed with the [`Capability`], see [`require_capability`].
+    Unauthorized,
+    /// Hooks can no longer be changed, see [`freeze`].
+    RegistryFrozen,
+    /// The major version of the hook is not the one of the hookable function,
+    /// see [`HookableFuncMetadata::version`].
+    IncompatibleVersion { expected: String, found: String },
+    /// The types of the hook have the same [`TypeId`]s as the hookable function,
+    /// but another structure, e.g. a hook built by another build of a plugin,
+    /// see [`type_fingerprint`]. Fingerprints are in the form of `(result, args)`.
+    FingerprintMismatch {
+        expected: (u64, u64),
+        found: (u64, u64),
+    },
+    /// The hookable function calls callbacks, so it has no original function
+    /// to call outside of a call, see [`HookableFuncMetadata::call_original`].
+    NoOriginal,
+    /// The snapshot was taken from another hookable function,
+    /// see [`HookableFuncMetadata::restore`].
+    SnapshotMismatch,
+}
+
+impl std::fmt::Display for AddHookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddHookError::TypeMismatch {
+                expected_signature,
+                found_signature,
+                ..
+            } if !expected_signature.is_empty() && !found_signature.is_empty() => write!(
+                f,
+                "Hook type mismatch: expected {}, got {}",
+                expected_signature, found_signature
+            ),
+            AddHookError::TypeMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "Hook type mismatch: expected {:?}, got {:?}",
+                expected, found
+            ),
+            AddHookError::Duplicate(id) => {
+                write!(f, "Hook is already attached with id {:?}", id)
+            }
+            AddHookError::NotFound(name) => write!(f, "Hookable '{}' not found", name),
+            AddHookError::AnchorNotFound => write!(f, "Anchor hook not found"),
+            AddHookError::OrderCycle => write!(f, "Hook ordering constraints form a cycle"),
+            AddHookError::InvalidOrder => {
+                write!(f, "Hook order does not match the attached hooks")
+            }
+            AddHookError::UntypedNotSupported => {
+                write!(
+                    f,
+                    "Untyped hooks cannot see arguments or results with references"
+                )
+            }
+            AddHookError::AlreadyRegistered(name) => {
+                write!(f, "Hookable '{}' is already registered", name)
+            }
+            AddHookError::Unauthorized => {
+                write!(f, "Changing hooks requires the capability")
+            }
+            AddHookError::RegistryFrozen => write!(f, "Hooks can no longer be changed"),
+            AddHookError::NoOriginal => {
+                write!(f, "Hookable of callbacks has no original function to call")
+            }
+            AddHookError::SnapshotMismatch => {
+                write!(f, "Snapshot was taken from another hookable function")
+            }
+            AddHookError::FingerprintMismatch { expected, found } => write!(
+                f,
+                "Hook type fingerprint mismatch: expected {:016x?}, got {:016x?}",
+                expected, found
+            ),
+            AddHookError::IncompatibleVersion { expected, found } => write!(
+                f,
+                "Hook version {} is incompatible with hookable version {}",
+                found, expected
+            ),
+            AddHookError::ArgsNotClone => {
+                write!(
+                    f,
+                    "Panicking hooks cannot be skipped without cloning the arguments"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddHookError {}
+
+/// Priority tiers for hooks, so independent hooks can express their intent without
+/// agreeing on numeric priorities. Each tier maps to a fixed priority, see [`Priority::value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Priority {
+    /// Called after all other tiers.
+    Last,
+    /// Called after [`Priority::Normal`].
+    Late,
+    /// The default priority (0).
+    #[default]
+    Normal,
+    /// Called before [`Priority::Normal`].
+    Early,
+    /// Called before all other tiers.
+    First,
+}
+
+impl Priority {
+    /// Get the numeric priority of the tier.
+    /// `First` and `Last` are the greatest and least priorities,
+    /// `Early` and `Late` leave a wide band around `Normal` for numeric priorities.
+    pub const fn value(self) -> i32 {
+        match self {
+            Priority::Last => i32::MIN,
+            Priority::Late => -1_000_000,
+            Priority::Normal => 0,
+            Priority::Early => 1_000_000,
+            Priority::First => i32::MAX,
+        }
+    }
+}
+
+impl From<Priority> for i32 {
+    fn from(priority: Priority) -> Self {
+        priority.value()
+    }
+}
+
+/// What to do when a hook that is already attached is added again.
+/// Hooks are compared by the address of the hook object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Attach the hook again, so it will be called more than once.
+    #[default]
+    Allow,
+    /// Fail with [`AddHookError::Duplicate`].
+    Reject,
+    /// Remove the attached one, then attach the hook with the new priority.
+    Replace,
+}
+
+/// What to do when a hook panics, see [`HookableFuncMetadata::set_panic_policy`].
+/// Panics of the rest of the chain, including the original function, are always propagated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Let the panic unwind through the caller.
+    #[default]
+    Propagate,
+    /// Skip the hook: the call continues with the rest of the chain,
+    /// using a copy of the arguments taken before calling the hook.
+    Skip,
+    /// Like [`PanicPolicy::Skip`], and detach the hook, so it is not called again.
+    Detach,
+}
+
+/// How hooks with equal priority are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// Last in, first out: a new hook is called before existing hooks with the same priority.
+    #[default]
+    Lifo,
+    /// First in, first out: a new hook is called after existing hooks with the same priority.
+    Fifo,
+}
+
+#[derive(Clone)]
+struct HookEntry {
+    id: HookId,
+    hook: Arc<dyn HookDyn>,
+    priority: i32,
+    enabled: bool,
+}
+
+impl HookEntry {
+    fn info(&self) -> HookInfo {
+        HookInfo {
+            id: self.id,
+            priority: self.priority,
+            enabled: self.enabled,
+            description: self.hook.description(),
+        }
+    }
+}
+
+/// Identifies an attached hook to insert another hook next to,
+/// see [`HookableFuncMetadata::add_hook_before`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookAnchor<'a> {
+    /// The hook with the given id.
+    Id(HookId),
+    /// The first hook (in calling order) with the given [`Hook::description`].
+    Description(&'a str),
+}
+
+impl From<HookId> for HookAnchor<'_> {
+    fn from(id: HookId) -> Self {
+        HookAnchor::Id(id)
+    }
+}
+
+impl<'a> From<&'a str> for HookAnchor<'a> {
+    fn from(description: &'a str) -> Self {
+        HookAnchor::Description(description)
+    }
+}
+
+/// Where to insert a new hook.
+enum Placement<'a> {
+    Priority(i32),
+    Before(HookAnchor<'a>),
+    After(HookAnchor<'a>),
+}
+
+#[derive(Clone, Default)]
+struct HookChain {
+    entries: Vec<HookEntry>,
+    /// The entries when last published, to tell registry observers what changed.
+    published: Vec<HookEntry>,
+    duplicate_policy: DuplicatePolicy,
+    tie_break: TieBreak,
+    panic_policy: PanicPolicy,
+    /// The hookable function itself, set with the panic policy,
+    /// so hooks guarded by [`PanicPolicy::Detach`] can remove themselves.
+    guarded: Option<&'static HookableFuncMetadata>,
+}
+
+impl HookChain {
+    fn find(&self, hook: &dyn HookDyn) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|h| std::ptr::addr_eq(h.hook.as_ref(), hook))
+    }
+
+    fn find_anchor(&self, anchor: &HookAnchor) -> Option<usize> {
+        self.entries.iter().position(|h| match anchor {
+            HookAnchor::Id(id) => h.id == *id,
+            HookAnchor::Description(description) => h.hook.description() == *description,
+        })
+    }
+
+    fn add(
+        &mut self,
+        owner: &HookableFuncMetadata,
+        hook: Arc<dyn HookDyn>,
+        priority: i32,
+    ) -> Result<HookId, AddHookError> {
+        self.insert(owner, hook, Placement::Priority(priority))
+    }
+
+    fn insert(
+        &mut self,
+        owner: &HookableFuncMetadata,
+        hook: Arc<dyn HookDyn>,
+        placement: Placement,
+    ) -> Result<HookId, AddHookError> {
+        if hook.type_info() != owner.type_info {
+            return Err(owner.type_mismatch(hook.type_info(), hook.type_names()));
+        }
+        let backup = self.entries.clone();
+        let mut anchor_pos = match &placement {
+            Placement::Priority(_) => None,
+            Placement::Before(anchor) | Placement::After(anchor) => Some(
+                self.find_anchor(anchor)
+                    .ok_or(AddHookError::AnchorNotFound)?,
+            ),
+        };
+        if let Some(pos) = self.find(hook.as_ref()) {
+            match self.duplicate_policy {
+                DuplicatePolicy::Allow => {}
+                DuplicatePolicy::Reject => {
+                    return Err(AddHookError::Duplicate(self.entries[pos].id));
+                }
+                DuplicatePolicy::Replace => {
+                    anchor_pos = match anchor_pos {
+                        Some(anchor) if anchor == pos => return Err(AddHookError::AnchorNotFound),
+                        Some(anchor) if anchor > pos => Some(anchor - 1),
+                        anchor => anchor,
+                    };
+                    self.entries.remove(pos);
+                }
+            }
+        }
+        let (pos, priority) = match (placement, anchor_pos) {
+            (Placement::Priority(priority), _) => {
+                let pos = self
+                    .entries
+                    .iter()
+                    .position(|h| match self.tie_break {
+                        TieBreak::Lifo => h.priority <= priority,
+                        TieBreak::Fifo => h.priority < priority,
+                    })
+                    .unwrap_or(self.entries.len());
+                (pos, priority)
+            }
+            (Placement::Before(_), Some(anchor)) => (anchor, self.entries[anchor].priority),
+            (Placement::After(_), Some(anchor)) => (anchor + 1, self.entries[anchor].priority),
+            _ => unreachable!(),
+        };
+        let id = HookId::next();
+        self.entries.insert(
+            pos,
+            HookEntry {
+                id,
+                hook,
+                priority,
+                enabled: true,
+            },
+        );
+        if let Err(err) = self.apply_order_constraints() {
+            self.entries = backup;
+            return Err(err);
+        }
+        Ok(id)
+    }
+
+    /// Reorder the hooks to satisfy [`Hook::runs_before`] and [`Hook::runs_after`],
+    /// keeping the current order wherever it is not constrained.
+    fn apply_order_constraints(&mut self) -> Result<(), AddHookError> {
+        let n = self.entries.len();
+        let descriptions = self
+            .entries
+            .iter()
+            .map(|h| h.hook.description())
+            .collect::<Vec<_>>();
+        // edges[i] contains the hooks that must be called after hook i.
+        let mut edges = vec![Vec::new(); n];
+        let mut in_degree = vec![0; n];
+        for (i, entry) in self.entries.iter().enumerate() {
+            let before = entry.hook.runs_before();
+            let after = entry.hook.runs_after();
+            for (j, description) in descriptions.iter().enumerate() {
+                if before.contains(description) {
+                    edges[i].push(j);
+                    in_degree[j] += 1;
+                }
+                if after.contains(description) {
+                    edges[j].push(i);
+                    in_degree[i] += 1;
+                }
+            }
+        }
+        if in_degree.iter().all(|&d| d == 0) {
+            return Ok(());
+        }
+        // Kahn's algorithm, always taking the first ready hook in the current order.
+        let mut order = Vec::with_capacity(n);
+        let mut done = vec![false; n];
+        while order.len() < n {
+            let next = (0..n)
+                .find(|&i| !done[i] && in_degree[i] == 0)
+                .ok_or(AddHookError::OrderCycle)?;
+            done[next] = true;
+            order.push(next);
+            for &j in &edges[next] {
+                in_degree[j] -= 1;
+            }
+        }
+        let mut entries = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.entries = order
+            .into_iter()
+            .map(|i| entries[i].take().unwrap())
+            .collect();
+        Ok(())
+    }
+
+    /// Put the hooks in the order of `order`, which must contain every attached id exactly once.
+    fn reorder(&mut self, order: &[HookId]) -> Result<(), AddHookError> {
+        if order.len() != self.entries.len() {
+            return Err(AddHookError::InvalidOrder);
+        }
+        let backup = self.entries.clone();
+        let mut entries = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        for id in order {
+            let entry = entries
+                .iter_mut()
+                .find(|h| h.as_ref().is_some_and(|h| h.id == *id))
+                .and_then(Option::take);
+            match entry {
+                Some(entry) => self.entries.push(entry),
+                None => {
+                    self.entries = backup;
+                    return Err(AddHookError::InvalidOrder);
+                }
+            }
+        }
+        if let Err(err) = self.apply_order_constraints() {
+            self.entries = backup;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, hook: &dyn HookDyn) -> Option<Arc<dyn HookDyn>> {
+        let pos = self.find(hook)?;
+        Some(self.entries.remove(pos).hook)
+    }
+
+    fn remove_by_id(&mut self, id: HookId) -> Option<Arc<dyn HookDyn>> {
+        let pos = self.entries.iter().position(|h| h.id == id)?;
+        Some(self.entries.remove(pos).hook)
+    }
+}
+
+/// A saved state of the hooks attached to a hookable function.
+/// Created by [`HookableFuncMetadata::snapshot`] and restored by [`HookableFuncMetadata::restore`].
+#[derive(Clone)]
+pub struct HookSnapshot {
+    owner: *const HookableFuncMetadata,
+    entries: Vec<HookEntry>,
+}
+unsafe impl Send for HookSnapshot {}
+unsafe impl Sync for HookSnapshot {}
+
+/// The changes made by publishing the hooks of a hookable function,
+/// handled by [`Published::finish`] once the hooks are unlocked.
+#[must_use]
+struct Published {
+    detached: Vec<(Arc<dyn HookDyn>, HookId)>,
+    attached: Vec<(Arc<dyn HookDyn>, HookId)>,
+    events: Vec<HookEvent>,
+}
+
+impl Published {
+    /// Call [`Hook::on_detach`] and [`Hook::on_attach`], then notify registry observers.
+    fn finish(self, meta: &HookableFuncMetadata) {
+        for (hook, id) in self.detached {
+            hook.detached(meta, id);
+            hook.on_detach(meta);
+        }
+        for (hook, id) in self.attached {
+            hook.attached(meta, id);
+            hook.on_attach(meta);
+        }
+        events::notify(self.events);
+    }
+}
+
+/// The enabled hooks of a hookable function composed into a single callable,
+/// see [`compose_chain`]. It is immutable once published, so calls can use it without locking.
+struct DispatchChain {
+    composed: Box<dyn Any + Send + Sync>,
+}
+
+/// Hooks removed by [`HookableFuncMetadata::drain_hooks`], with their priorities.
+type DrainedHooks = Vec<(Arc<dyn HookDyn>, i32)>;
+
+type ComposeFn = fn(&[Arc<dyn HookDyn>], &ComposeOptions) -> Box<dyn Any + Send + Sync>;
+type ComposeBox =
+    Box<dyn Fn(&[Arc<dyn HookDyn>], &ComposeOptions) -> Box<dyn Any + Send + Sync> + Send + Sync>;
+
+/// The settings of a hookable function used to compose its chain, see [`compose_chain`].
+#[doc(hidden)]
+pub struct ComposeOptions<'a> {
+    hookable: Option<&'static HookableFuncMetadata>,
+    /// The ids of the hooks, in the same order.
+    ids: &'a [HookId],
+    panic_policy: PanicPolicy,
+    /// A `fn(&A) -> A` cloning the arguments, if they implement `Clone`.
+    clone_args: Option<&'a (dyn Any + Send + Sync)>,
+}
+
+impl ComposeOptions<'_> {
+    /// The options of a chain without hooks.
+    const BARE: ComposeOptions<'static> = ComposeOptions {
+        hookable: None,
+        ids: &[],
+        panic_policy: PanicPolicy::Propagate,
+        clone_args: None,
+    };
+}
+
+/// Compose `hooks` and `func` into a `Box<dyn Fn(A) -> R + Send + Sync>`,
+/// so calling it calls the hooks in order, with `func` at the end.
+/// It is used inside the macro [`hookable`] to build the chain whenever the hooks change.
+///
+/// Unless the [`PanicPolicy`] is [`PanicPolicy::Propagate`], every hook is guarded
+/// with [`panic_guard::guard`].
+///
+/// If every hook is an observer (see [`Post`]), the chain is not nested:
+/// the arguments are cloned once, `func` is called, then the observers are called in reverse order,
+/// the same order as the nested chain would call them.
+#[doc(hidden)]
+pub fn compose_chain<R: 'static, A: 'static>(
+    func: fn(A) -> R,
+    hooks: &[Arc<dyn HookDyn>],
+    options: &ComposeOptions,
+) -> Box<dyn Any + Send + Sync> {
+    let clone_args = options
+        .clone_args
+        .and_then(|clone_args| clone_args.downcast_ref::<fn(&A) -> A>());
+    if options.panic_policy != PanicPolicy::Propagate
+        && let Some(&clone_args) = clone_args
+    {
+        let mut composed: Box<dyn Any + Send + Sync> = Box::new(Box::new(func) as ChainFn<A, R>);
+        for (hook, &id) in hooks.iter().zip(options.ids).rev() {
+            composed = panic_guard::guard::<A, R>(
+                hook,
+                id,
+                composed,
+                clone_args,
+                options.panic_policy,
+                options.hookable,
+            );
+        }
+        return composed;
+    }
+    let observers = hooks
+        .iter()
+        .rev()
+        .map(|hook| hook.clone().observer()?.downcast::<Observer<A, R>>().ok())
+        .collect::<Option<Vec<_>>>();
+    if let Some(observers) = observers
+        && let Some(first) = observers.first()
+    {
+        let clone_args = first.clone_args;
+        let composed: ChainFn<A, R> = Box::new(move |args| {
+            let args_copy = clone_args(&args);
+            let result = func(args);
+            for observer in &observers {
+                (observer.observe)(&args_copy, &result);
+            }
+            result
+        });
+        return Box::new(composed);
+    }
+    let mut composed: Box<dyn Any + Send + Sync> = Box::new(Box::new(func) as ChainFn<A, R>);
+    for hook in hooks.iter().rev() {
+        composed = compose_hook::<A, R>(hook, composed);
+    }
+    composed
+}
+
+/// Compose one hook on top of the chain `next`.
+fn compose_hook<A: 'static, R: 'static>(
+    hook: &Arc<dyn HookDyn>,
+    next: Box<dyn Any + Send + Sync>,
+) -> Box<dyn Any + Send + Sync> {
+    match hook.untyped() {
+        Some(untyped) => untyped.compose::<A, R>(next),
+        None => hook.clone().compose(next),
+    }
+}
+
 /// Metadata for a hookable function.
 #[doc(hidden)]
 pub struct HookableFuncMetadata {
     name: String,
     func: HookableFuncPtr,
     type_info: (TypeId, TypeId),
+    type_names: (&'static str, &'static str),
+    /// The fingerprints of the result and the arguments, see [`type_fingerprint`].
+    fingerprint: (u64, u64),
     fast_path_flag: &'static AtomicBool,
-    hooks: RwLock<Vec<(Arc<dyn HookDyn>, i32)>>,
+    disabled_by_env: bool,
+    /// The semantic version of the contract of the function, see [`Self::version`].
+    version: Option<&'static str>,
+    /// The type-erased call, only if the arguments and the result contain no references,
+    /// so they can be seen as `Any`.
+    invoke: Option<invoke::InvokeFn>,
+    #[cfg(feature = "serde")]
+    invoke_json: Option<invoke::JsonInvokeFn>,
+    compose: ComposeBox,
+    /// A `fn(&A) -> A` cloning the arguments, if they implement `Clone`.
+    clone_args: Option<Box<dyn Any + Send + Sync>>,
+    /// A `fn(&R) -> bool` checking whether a result is an error.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    is_err: Box<dyn Any + Send + Sync>,
+    hooks: RwLock<HookChain>,
+    /// The published chain, cloned out by calls so they are only pinned while loading it.
+    dispatch: Atomic<Arc<DispatchChain>>,
+    /// The chain without hooks, used when hooks are disabled but the call is recorded,
+    /// and for calls beyond the maximum depth, see [`Self::set_max_depth`].
+    bare: DispatchChain,
+    /// Whether the original function calls the innermost callback of the thread,
+    /// see [`callback_metadata`].
+    callback: bool,
+    /// The maximum depth of calls with hooks per thread, `usize::MAX` if unlimited.
+    max_depth: AtomicUsize,
+    /// A `ChainFn<A, R>` called instead of the function beyond the maximum depth.
+    depth_fallback: RwLock<Option<Arc<dyn Any + Send + Sync>>>,
+    #[cfg(feature = "metrics")]
+    metrics_enabled: AtomicBool,
+    #[cfg(feature = "stats")]
+    stats: stats::Stats,
+    #[cfg(feature = "coverage")]
+    executed: AtomicBool,
 }
 impl HookableFuncMetadata {
     /// Create a new [`HookableFuncMetadata`].
@@ -184,19 +1399,83 @@ impl HookableFuncMetadata {
     /// This function is unsafe because it takes a raw pointer to a function without type checking.
     /// It is used inside the macro [`hookable`] to create a new [`HookableFuncMetadata`] instance.
     /// **THIS FUNCTION SHOULD NOT BE CALLED DIRECTLY.**
+    /// See [`Self::builder`] to create hookable functions without the macro.
     #[doc(hidden)]
+    #[allow(clippy::too_many_arguments)]
     pub unsafe fn new(
         name: String,
         func: *const (),
         type_info: (TypeId, TypeId),
+        type_names: (&'static str, &'static str),
+        fingerprint: (u64, u64),
+        fast_path_flag: &'static AtomicBool,
+        invoke: Option<invoke::InvokeFn>,
+        compose: ComposeFn,
+        clone_args: Option<Box<dyn Any + Send + Sync>>,
+        is_err: Box<dyn Any + Send + Sync>,
+    ) -> Self {
+        Self::from_parts(
+            name,
+            func,
+            type_info,
+            type_names,
+            fingerprint,
+            fast_path_flag,
+            invoke,
+            Box::new(compose),
+            clone_args,
+            is_err,
+        )
+    }
+
+    /// Create a new [`HookableFuncMetadata`], see [`Self::new`] and [`Self::builder`].
+    #[allow(clippy::too_many_arguments)]
+    fn from_parts(
+        name: String,
+        func: *const (),
+        type_info: (TypeId, TypeId),
+        type_names: (&'static str, &'static str),
+        fingerprint: (u64, u64),
         fast_path_flag: &'static AtomicBool,
+        invoke: Option<invoke::InvokeFn>,
+        compose: ComposeBox,
+        clone_args: Option<Box<dyn Any + Send + Sync>>,
+        is_err: Box<dyn Any + Send + Sync>,
     ) -> Self {
+        let disabled_by_env = ENV_DISABLED.contains(&name);
+        let dispatch = DispatchChain {
+            composed: compose(&[], &ComposeOptions::BARE),
+        };
+        let bare = DispatchChain {
+            composed: compose(&[], &ComposeOptions::BARE),
+        };
         Self {
             name,
             func: HookableFuncPtr(func),
             type_info,
+            type_names,
+            fingerprint,
             fast_path_flag,
-            hooks: RwLock::new(Vec::new()),
+            disabled_by_env,
+            version: None,
+            invoke,
+            #[cfg(feature = "serde")]
+            invoke_json: None,
+            compose,
+            clone_args,
+            is_err,
+            hooks: RwLock::new(HookChain::default()),
+            dispatch: Atomic::new(Arc::new(dispatch)),
+            bare,
+            callback: false,
+            max_depth: AtomicUsize::new(usize::MAX),
+            depth_fallback: RwLock::new(None),
+            #[cfg(feature = "metrics")]
+            metrics_enabled: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            stats: stats::Stats::default(),
+            #[cfg(feature = "coverage")]
+            executed: AtomicBool::new(false),
         }
     }
 
@@ -205,94 +1484,757 @@ impl HookableFuncMetadata {
         &self.name
     }
 
+    /// Get the type name of the arguments tuple, e.g. `(i64, i64)`.
+    /// Lifetimes of reference arguments are shown as `'static`.
+    pub fn args_type_name(&self) -> &'static str {
+        self.type_names.1
+    }
+
+    /// Get the type name of the result, e.g. `i64`.
+    pub fn result_type_name(&self) -> &'static str {
+        self.type_names.0
+    }
+
+    /// Get a human-readable signature of the hookable function, e.g. `fn add(i64, i64) -> i64`.
+    pub fn signature(&self) -> String {
+        let args = self.args_type_name();
+        // `(i64,)` for functions with a single argument.
+        let args = args
+            .strip_suffix(",)")
+            .map_or(args.to_string(), |a| format!("{})", a));
+        format!("fn {}{} -> {}", self.name, args, self.result_type_name())
+    }
+
+    /// Check whether the hookable function is disabled by the `SAFE_HOOK_DISABLE` environment variable.
+    /// A disabled hookable function always takes the fast path, hooks can be attached but are never called.
+    pub fn is_disabled_by_env(&self) -> bool {
+        self.disabled_by_env
+    }
+
     /// Get the pointer to the hookable function.
     pub fn func_ptr(&self) -> *const () {
         self.func.0
     }
 
+    /// Get the semantic version of the contract of the hookable function,
+    /// set with the `version` option of [`hookable`].
+    /// Hooks declaring an incompatible version with [`Hook::version`] cannot be attached.
+    pub fn version(&self) -> Option<&'static str> {
+        self.version
+    }
+
+    /// Set the semantic version of the hookable function.
+    /// It is used inside the macro [`hookable`] for functions with the `version` option.
+    #[doc(hidden)]
+    pub fn with_version(mut self, version: &'static str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Get the fingerprints of the result and the arguments types, see [`type_fingerprint`].
+    pub fn fingerprint(&self) -> (u64, u64) {
+        self.fingerprint
+    }
+
+    /// The error for a hook or a call of other types, in the form of `(result, args)`.
+    pub(crate) fn type_mismatch(
+        &self,
+        found: (TypeId, TypeId),
+        found_names: (&'static str, &'static str),
+    ) -> AddHookError {
+        /// `(i64, i64) -> i64`, or empty if a name is unknown.
+        fn signature((result, args): (&str, &str)) -> String {
+            if result.is_empty() || args.is_empty() {
+                return String::new();
+            }
+            format!("{} -> {}", args, result)
+        }
+        AddHookError::TypeMismatch {
+            expected: self.type_info,
+            found,
+            expected_signature: signature(self.type_names),
+            found_signature: signature(found_names),
+        }
+    }
+
+    /// Check that `hook` was built for the types and the version of the hookable function,
+    /// beyond their [`TypeId`]s, see [`Self::fingerprint`] and [`Self::version`].
+    fn check_hook(&self, hook: &dyn HookDyn) -> Result<(), AddHookError> {
+        // Other types are reported as a type mismatch when inserting the hook.
+        if hook.type_info() == self.type_info
+            && let Some(found) = hook.fingerprint()
+            && found != self.fingerprint
+        {
+            return Err(AddHookError::FingerprintMismatch {
+                expected: self.fingerprint,
+                found,
+            });
+        }
+        match (self.version, hook.version()) {
+            (Some(expected), Some(found)) if !versions_compatible(expected, &found) => {
+                Err(AddHookError::IncompatibleVersion {
+                    expected: expected.to_string(),
+                    found,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Add a hook to the hookable function.
-    /// The greatest priority will be called first.
+    /// The greatest priority will be called first,
+    /// hooks with equal priority are ordered by the [`TieBreak`] policy.
+    #[track_caller]
     pub fn add_hook_with_priority(
         &self,
         hook: Arc<dyn HookDyn>,
         priority: i32,
-    ) -> Result<(), String> {
-        if hook.type_info() != self.type_info {
-            return Err(format!(
-                "Hook type mismatch: expected {:?}, got {:?}",
-                self.type_info,
-                hook.type_info()
-            ));
-        }
-        let mut hooks = self.hooks.write().unwrap();
-        let pos = hooks
-            .iter()
-            .position(|h| h.1 <= priority)
-            .unwrap_or(hooks.len());
-        hooks.insert(pos, (hook, priority));
-        self.fast_path_flag
-            .store(true, std::sync::atomic::Ordering::Release);
-        Ok(())
+    ) -> Result<HookId, AddHookError> {
+        self.check_hook(hook.as_ref())?;
+        let mut hooks = self.write_hooks()?;
+        let id = hooks.add(self, hook, priority)?;
+        self.publish(hooks);
+        Ok(id)
     }
 
     /// Add a hook to the hookable function with default (0) priority.
-    pub fn add_hook(&self, hook: Arc<dyn HookDyn>) -> Result<(), String> {
+    #[track_caller]
+    pub fn add_hook(&self, hook: Arc<dyn HookDyn>) -> Result<HookId, AddHookError> {
         self.add_hook_with_priority(hook, 0)
     }
 
+    /// Add a closure as a hook with the default priority, see [`IntoHook`].
+    /// The types of the arguments and the result usually need to be annotated.
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("fn-mul")]
+    /// fn mul(left: i64, right: i64) -> i64 {
+    ///     left * right
+    /// }
+    ///
+    /// lookup_hookable("fn-mul")
+    ///     .unwrap()
+    ///     .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+    ///     .unwrap();
+    /// assert_eq!(mul(2, 3), 7);
+    /// ```
+    #[track_caller]
+    pub fn add_hook_fn<A: 'static, R: 'static>(
+        &self,
+        f: impl Fn(A, &dyn Fn(A) -> R) -> R + Send + Sync + 'static,
+    ) -> Result<HookId, AddHookError> {
+        self.add_hook(Arc::new(f.into_hook()))
+    }
+
+    /// Add a hook to the hookable function right before the `anchor` hook,
+    /// so it is called before the anchor regardless of numeric priorities.
+    /// The new hook gets the same priority as the anchor.
+    #[track_caller]
+    pub fn add_hook_before<'a>(
+        &self,
+        anchor: impl Into<HookAnchor<'a>>,
+        hook: Arc<dyn HookDyn>,
+    ) -> Result<HookId, AddHookError> {
+        self.check_hook(hook.as_ref())?;
+        let mut hooks = self.write_hooks()?;
+        let id = hooks.insert(self, hook, Placement::Before(anchor.into()))?;
+        self.publish(hooks);
+        Ok(id)
+    }
+
+    /// Add a hook to the hookable function right after the `anchor` hook,
+    /// so it is called after the anchor regardless of numeric priorities.
+    /// The new hook gets the same priority as the anchor.
+    #[track_caller]
+    pub fn add_hook_after<'a>(
+        &self,
+        anchor: impl Into<HookAnchor<'a>>,
+        hook: Arc<dyn HookDyn>,
+    ) -> Result<HookId, AddHookError> {
+        self.check_hook(hook.as_ref())?;
+        let mut hooks = self.write_hooks()?;
+        let id = hooks.insert(self, hook, Placement::After(anchor.into()))?;
+        self.publish(hooks);
+        Ok(id)
+    }
+
+    /// Add a hook to the hookable function with the priority of a tier.
+    #[track_caller]
+    pub fn add_hook_with_tier(
+        &self,
+        hook: Arc<dyn HookDyn>,
+        tier: Priority,
+    ) -> Result<HookId, AddHookError> {
+        self.add_hook_with_priority(hook, tier.value())
+    }
+
+    /// Set the policy for adding a hook that is already attached.
+    /// Defaults to [`DuplicatePolicy::Allow`].
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    pub fn set_duplicate_policy(&self, policy: DuplicatePolicy) -> Result<(), AddHookError> {
+        self.write_hooks()?.duplicate_policy = policy;
+        Ok(())
+    }
+
+    /// Get the policy for adding a hook that is already attached.
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        lock::read(&self.hooks).duplicate_policy
+    }
+
+    /// Set how hooks with equal priority are ordered. Defaults to [`TieBreak::Lifo`].
+    /// Only affects hooks added afterwards.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    pub fn set_tie_break(&self, tie_break: TieBreak) -> Result<(), AddHookError> {
+        self.write_hooks()?.tie_break = tie_break;
+        Ok(())
+    }
+
+    /// Get how hooks with equal priority are ordered.
+    pub fn tie_break(&self) -> TieBreak {
+        lock::read(&self.hooks).tie_break
+    }
+
+    /// Set what to do when a hook of the hookable function panics.
+    /// Defaults to [`PanicPolicy::Propagate`].
+    ///
+    /// [`PanicPolicy::Skip`] and [`PanicPolicy::Detach`] catch panics with [`std::panic::catch_unwind`]
+    /// and clone the arguments before calling each hook, so they require arguments implementing
+    /// [`Clone`], otherwise [`AddHookError::ArgsNotClone`] is returned. Calls are slower with them.
+    /// The panic hook still reports the panic of a skipped hook.
+    /// # Example
+    /// ```rust
+    /// use safe_hook::{lookup_hookable, PanicPolicy};
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("panic-policy-div")]
+    /// fn div(left: i64, right: i64) -> i64 {
+    ///     left / right
+    /// }
+    ///
+    /// let div_hookable = lookup_hookable("panic-policy-div").unwrap();
+    /// div_hookable.set_panic_policy(PanicPolicy::Detach).unwrap();
+    /// div_hookable
+    ///     .add_hook_fn(|_: (i64, i64), _| -> i64 { panic!("faulty hook") })
+    ///     .unwrap();
+    /// assert_eq!(div(6, 2), 3);
+    /// assert!(div_hookable.list_hooks().is_empty());
+    /// ```
+    #[track_caller]
+    pub fn set_panic_policy(&'static self, policy: PanicPolicy) -> Result<(), AddHookError> {
+        if policy != PanicPolicy::Propagate && self.clone_args.is_none() {
+            return Err(AddHookError::ArgsNotClone);
+        }
+        let mut hooks = self.write_hooks()?;
+        hooks.panic_policy = policy;
+        hooks.guarded = Some(self);
+        self.publish(hooks);
+        Ok(())
+    }
+
+    /// Get what to do when a hook of the hookable function panics.
+    pub fn panic_policy(&self) -> PanicPolicy {
+        lock::read(&self.hooks).panic_policy
+    }
+
+    /// Enable or disable the reentrancy guard of the hookable function. Disabled by default.
+    ///
+    /// With the guard, if the function is called again while a thread is running its hooks,
+    /// e.g. by a hook calling it directly or indirectly, the nested call skips the hooks
+    /// and calls the original function, so the hooks cannot recurse infinitely.
+    /// It is the same as a maximum depth of 1, see [`Self::set_max_depth`].
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("reentrancy-double")]
+    /// fn double(x: i64) -> i64 {
+    ///     x * 2
+    /// }
+    ///
+    /// let double_hookable = lookup_hookable("reentrancy-double").unwrap();
+    /// double_hookable.set_reentrancy_guard(true).unwrap();
+    /// // Without the guard, this hook would call itself forever.
+    /// double_hookable
+    ///     .add_hook_fn(|args: (i64,), next| -> i64 { next(args) + double(args.0) })
+    ///     .unwrap();
+    /// assert_eq!(double(1), 4);
+    /// ```
+    pub fn set_reentrancy_guard(&self, enabled: bool) -> Result<(), AddHookError> {
+        self.set_max_depth(enabled.then_some(1))
+    }
+
+    /// Check whether the reentrancy guard of the hookable function is enabled,
+    /// i.e. the maximum depth is 1.
+    pub fn reentrancy_guard(&self) -> bool {
+        self.max_depth() == Some(1)
+    }
+
+    /// Set the maximum depth of nested calls of the hookable function running its hooks,
+    /// per thread, or `None` for no limit, the default.
+    ///
+    /// Calls beyond the limit skip the hooks and call the original function,
+    /// or the fallback set by [`Self::set_depth_fallback`],
+    /// so misbehaving hooks recursing into the function cannot exhaust the stack.
+    /// Calls with hooks are slightly slower with a limit.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("max-depth-count")]
+    /// fn count(n: u32) -> u32 {
+    ///     n
+    /// }
+    ///
+    /// let count_hookable = lookup_hookable("max-depth-count").unwrap();
+    /// count_hookable.set_max_depth(Some(3)).unwrap();
+    /// count_hookable
+    ///     .add_hook_fn(|args: (u32,), _| -> u32 { count(args.0 + 1) })
+    ///     .unwrap();
+    /// assert_eq!(count(0), 3);
+    /// ```
+    pub fn set_max_depth(&self, max_depth: Option<usize>) -> Result<(), AddHookError> {
+        access::check()?;
+        self.max_depth.store(
+            max_depth.unwrap_or(usize::MAX),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    /// Get the maximum depth of nested calls running the hooks, see [`Self::set_max_depth`].
+    pub fn max_depth(&self) -> Option<usize> {
+        match self.max_depth.load(std::sync::atomic::Ordering::Relaxed) {
+            usize::MAX => None,
+            max_depth => Some(max_depth),
+        }
+    }
+
+    /// Call `fallback` instead of the original function for calls beyond the maximum depth,
+    /// e.g. to return an error. See [`Self::set_max_depth`].
+    /// Returns [`AddHookError::TypeMismatch`] if the types do not match the hookable function,
+    /// and fails like [`Self::remove_hook`] if hooks cannot be changed.
+    /// Like [`FnHook`], the arguments can only contain `'static` references.
+    /// # Example
+    /// ```rust
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook_macros::hookable;
+    ///
+    /// #[hookable("depth-fallback-parse")]
+    /// fn parse(n: u32) -> Result<u32, String> {
+    ///     Ok(n)
+    /// }
+    ///
+    /// let parse_hookable = lookup_hookable("depth-fallback-parse").unwrap();
+    /// parse_hookable.set_max_depth(Some(1)).unwrap();
+    /// parse_hookable
+    ///     .set_depth_fallback(|_: (u32,)| -> Result<u32, String> { Err("too deep".to_string()) })
+    ///     .unwrap();
+    /// parse_hookable
+    ///     .add_hook_fn(|args: (u32,), _| -> Result<u32, String> { parse(args.0) })
+    ///     .unwrap();
+    /// assert_eq!(parse(1), Err("too deep".to_string()));
+    /// ```
+    pub fn set_depth_fallback<A: 'static, R: 'static>(
+        &self,
+        fallback: impl Fn(A) -> R + Send + Sync + 'static,
+    ) -> Result<(), AddHookError> {
+        let found = (TypeId::of::<R>(), TypeId::of::<A>());
+        if found != self.type_info {
+            return Err(self.type_mismatch(found, stored_type_names::<R, A>()));
+        }
+        access::check()?;
+        let fallback: ChainFn<A, R> = Box::new(fallback);
+        *lock::write(&self.depth_fallback) = Some(Arc::new(fallback));
+        Ok(())
+    }
+
+    /// Call the original function again for calls beyond the maximum depth.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    pub fn clear_depth_fallback(&self) -> Result<(), AddHookError> {
+        access::check()?;
+        *lock::write(&self.depth_fallback) = None;
+        Ok(())
+    }
+
+    /// Check whether a hook is attached to the hookable function.
+    pub fn contains_hook(&self, hook: &dyn HookDyn) -> bool {
+        lock::read(&self.hooks).find(hook).is_some()
+    }
+
     /// Remove a hook from the hookable function.
-    pub fn remove_hook(&self, hook: &dyn HookDyn) -> bool {
-        let mut hooks = self.hooks.write().unwrap();
-        if let Some(pos) = hooks
-            .iter()
-            .position(|h| std::ptr::addr_eq(h.0.as_ref(), hook))
-        {
-            hooks.remove(pos);
-            if hooks.is_empty() {
-                self.fast_path_flag
-                    .store(false, std::sync::atomic::Ordering::Relaxed);
-            }
-            true
+    /// Returns the removed hook, or `None` if the hook is not attached.
+    ///
+    /// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+    /// if hooks cannot be changed, see [`freeze`] and [`require_capability`].
+    #[track_caller]
+    pub fn remove_hook(
+        &self,
+        hook: &dyn HookDyn,
+    ) -> Result<Option<Arc<dyn HookDyn>>, AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        let Some(removed) = hooks.remove(hook) else {
+            return Ok(None);
+        };
+        self.publish(hooks);
+        Ok(Some(removed))
+    }
+
+    /// Remove a hook from the hookable function by its id.
+    /// Returns the removed hook, or `None` if no hook with the given id is attached.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    #[track_caller]
+    pub fn remove_hook_by_id(&self, id: HookId) -> Result<Option<Arc<dyn HookDyn>>, AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        let Some(removed) = hooks.remove_by_id(id) else {
+            return Ok(None);
+        };
+        self.publish(hooks);
+        Ok(Some(removed))
+    }
+
+    /// Clear all hooks from the hookable function.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    #[track_caller]
+    pub fn clear_hooks(&self) -> Result<(), AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        hooks.entries.clear();
+        self.publish(hooks);
+        Ok(())
+    }
+
+    /// Remove all hooks from the hookable function and return them with their priorities,
+    /// in calling order.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    #[track_caller]
+    pub fn drain_hooks(&self) -> Result<DrainedHooks, AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        let drained = hooks
+            .entries
+            .drain(..)
+            .map(|h| (h.hook, h.priority))
+            .collect();
+        self.publish(hooks);
+        Ok(drained)
+    }
+
+    /// Save the hooks currently attached to the hookable function.
+    pub fn snapshot(&self) -> HookSnapshot {
+        let hooks = lock::read(&self.hooks);
+        HookSnapshot {
+            owner: self,
+            entries: hooks.entries.clone(),
+        }
+    }
+
+    /// Replace the attached hooks with the ones saved in `snapshot`.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed,
+    /// and with [`AddHookError::SnapshotMismatch`] if the snapshot was taken from another hookable function.
+    #[track_caller]
+    pub fn restore(&self, snapshot: &HookSnapshot) -> Result<(), AddHookError> {
+        if !std::ptr::eq(snapshot.owner, self) {
+            return Err(AddHookError::SnapshotMismatch);
+        }
+        let mut hooks = self.write_hooks()?;
+        hooks.entries = snapshot.entries.clone();
+        self.publish(hooks);
+        Ok(())
+    }
+
+    /// Enable or disable a hook by its id.
+    /// Disabled hooks stay attached but are skipped when the function is called.
+    /// Returns `false` if no hook with the given id is attached.
+    ///
+    /// Fails like [`Self::remove_hook`] if hooks cannot be changed.
+    #[track_caller]
+    pub fn set_hook_enabled(&self, id: HookId, enabled: bool) -> Result<bool, AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        if let Some(entry) = hooks.entries.iter_mut().find(|h| h.id == id) {
+            entry.enabled = enabled;
+            self.publish(hooks);
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
-    /// Clear all hooks from the hookable function.
-    pub fn clear_hooks(&self) {
-        let mut hooks = self.hooks.write().unwrap();
-        hooks.clear();
+    /// List the hooks attached to the hookable function, in calling order.
+    pub fn list_hooks(&self) -> Vec<HookInfo> {
+        let hooks = lock::read(&self.hooks);
+        hooks.entries.iter().map(HookEntry::info).collect()
+    }
+
+    /// Put the attached hooks in the given calling order, keeping their ids and priorities.
+    /// `order` must contain the id of every attached hook exactly once,
+    /// otherwise [`AddHookError::InvalidOrder`] is returned and nothing changes.
+    /// The constraints of [`Hook::runs_before`] and [`Hook::runs_after`] still apply.
+    ///
+    /// Hooks added afterwards are still placed by priority, see [`Self::add_hook_with_priority`].
+    #[track_caller]
+    pub fn reorder_hooks(&self, order: &[HookId]) -> Result<(), AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        hooks.reorder(order)?;
+        self.publish(hooks);
+        Ok(())
+    }
+
+    /// Sort the attached hooks with a comparator, see [`Self::reorder_hooks`].
+    /// The sort is stable, hooks comparing equal keep their current order.
+    #[track_caller]
+    pub fn sort_hooks_by(
+        &self,
+        mut compare: impl FnMut(&HookInfo, &HookInfo) -> std::cmp::Ordering,
+    ) -> Result<(), AddHookError> {
+        let mut hooks = self.write_hooks()?;
+        let mut infos = hooks
+            .entries
+            .iter()
+            .map(HookEntry::info)
+            .collect::<Vec<_>>();
+        infos.sort_by(&mut compare);
+        let order = infos.iter().map(|h| h.id).collect::<Vec<_>>();
+        hooks.reorder(&order)?;
+        self.publish(hooks);
+        Ok(())
+    }
+
+    /// Lock the hooks to change them, if allowed, see [`freeze`] and [`require_capability`].
+    fn write_hooks(&self) -> Result<RwLockWriteGuard<'_, HookChain>, AddHookError> {
+        access::check()?;
+        Ok(lock::write(&self.hooks))
+    }
+
+    /// Publish the enabled hooks for calls, unlock them,
+    /// then call the lifecycle methods of attached and detached hooks and notify registry observers.
+    /// Must be called after every modification of `hooks`.
+    #[track_caller]
+    fn publish(&self, mut hooks: RwLockWriteGuard<'_, HookChain>) {
+        let published = self.publish_locked(&mut hooks, std::panic::Location::caller());
+        drop(hooks);
+        published.finish(self);
+    }
+
+    /// Publish the enabled hooks for calls, and update the fast path flag.
+    /// Returns the changes to handle once `hooks` is unlocked,
+    /// attributed to the code at `location`.
+    fn publish_locked(
+        &self,
+        hooks: &mut HookChain,
+        location: &'static std::panic::Location<'static>,
+    ) -> Published {
+        let (enabled, ids): (Vec<_>, Vec<_>) = hooks
+            .entries
+            .iter()
+            .filter(|h| h.enabled)
+            .map(|h| (h.hook.clone(), h.id))
+            .unzip();
+        let options = ComposeOptions {
+            hookable: hooks.guarded,
+            ids: &ids,
+            panic_policy: hooks.panic_policy,
+            clone_args: self.clone_args.as_deref(),
+        };
+        let chain = DispatchChain {
+            composed: (self.compose)(&enabled, &options),
+        };
+        let guard = epoch::pin();
+        let old = self.dispatch.swap(
+            Owned::new(Arc::new(chain)),
+            std::sync::atomic::Ordering::AcqRel,
+            &guard,
+        );
+        // SAFETY: the old chain is no longer reachable, and calls loading it are pinned,
+        // so it is released after they cloned it, and destroyed after the last call using it.
+        unsafe { guard.defer_destroy(old) };
+        self.update_fast_path_flag(hooks);
+        let detached = hooks
+            .published
+            .iter()
+            .filter(|old| !hooks.entries.iter().any(|new| new.id == old.id))
+            .map(|old| (old.hook.clone(), old.id))
+            .collect();
+        let attached = hooks
+            .entries
+            .iter()
+            .filter(|new| !hooks.published.iter().any(|old| old.id == new.id))
+            .map(|new| (new.hook.clone(), new.id))
+            .collect();
+        let events = events::diff(&self.name, location, &hooks.published, &hooks.entries);
+        hooks.published = hooks.entries.clone();
+        Published {
+            detached,
+            attached,
+            events,
+        }
+    }
+
+    /// The fast path is taken only if there is no enabled hook.
+    /// It is also taken if all hooks are disabled by [`disable_all`],
+    /// or the function is disabled by the `SAFE_HOOK_DISABLE` environment variable.
+    fn update_fast_path_flag(&self, hooks: &HookChain) {
+        let has_enabled = hooks.entries.iter().any(|h| h.enabled) && self.hooks_active();
+        #[cfg(feature = "metrics")]
+        let has_enabled = has_enabled
+            || self
+                .metrics_enabled
+                .load(std::sync::atomic::Ordering::Acquire);
         self.fast_path_flag
-            .store(false, std::sync::atomic::Ordering::Relaxed);
+            .store(has_enabled, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Whether hooks are called, unless disabled by [`disable_all`]
+    /// or by the `SAFE_HOOK_DISABLE` environment variable.
+    fn hooks_active(&self) -> bool {
+        !self.disabled_by_env && !ALL_DISABLED.load(std::sync::atomic::Ordering::Acquire)
+    }
+}
+
+impl Drop for HookableFuncMetadata {
+    fn drop(&mut self) {
+        // SAFETY: we have exclusive access, so no call is using the chain.
+        unsafe {
+            let chain = self
+                .dispatch
+                .load(std::sync::atomic::Ordering::Relaxed, epoch::unprotected());
+            drop(chain.into_owned());
+        }
     }
 }
 
 /// Call a hookable function with hooks.
 #[doc(hidden)]
-pub fn call_with_hook<R, A>(func: fn(A) -> R, meta: &'static HookableFuncMetadata, args: A) -> R {
-    let hooks = meta.hooks.read().unwrap();
-    let pos = Cell::new(0);
-    #[allow(clippy::type_complexity)]
-    let next_fn_ref: Cell<Option<&dyn Fn(A) -> R>> = Cell::new(None);
-    type HookFn<A, R> = fn(*const (), args: A, next: &dyn Fn(A) -> R) -> R;
-    let next_fn = |args: A| {
-        if pos.get() < hooks.len() {
-            let hook = hooks[pos.get()].0.as_ref();
-            // SAFETY: get_call_fn should return a function pointer to hook_call_wrapper<A>
-            let f: HookFn<A, R> = unsafe { std::mem::transmute(hook.get_call_fn()) };
-            pos.set(pos.get() + 1);
-            let res = f(
-                hook as *const dyn HookDyn as *const (),
-                args,
-                // SAFETY: next_fn_ref must be set before calling next_fn
-                unsafe { next_fn_ref.get().unwrap_unchecked() },
-            );
-            pos.set(pos.get() - 1);
-            res
-        } else {
-            func(args)
+pub fn call_with_hook<R: 'static, A>(
+    meta: &'static HookableFuncMetadata,
+    args: A,
+    location: Option<&'static std::panic::Location<'static>>,
+) -> R {
+    call_with_hook_on(meta, args, location, None)
+}
+
+/// Like [`call_with_hook`], for a method called on `instance`.
+#[inline(always)]
+pub(crate) fn call_with_hook_on<R: 'static, A>(
+    meta: &'static HookableFuncMetadata,
+    args: A,
+    location: Option<&'static std::panic::Location<'static>>,
+    instance: Option<InstanceKey>,
+) -> R {
+    #[cfg(feature = "coverage")]
+    coverage::mark(meta);
+    let _active = panic_report::enter(meta);
+    let _frame = context::enter(meta, location, instance);
+    #[cfg(feature = "stats")]
+    let start = std::time::Instant::now();
+    #[cfg(feature = "metrics")]
+    let result = if meta
+        .metrics_enabled
+        .load(std::sync::atomic::Ordering::Relaxed)
+    {
+        metrics::call_recorded(meta, args)
+    } else {
+        call_chain(meta, args)
+    };
+    #[cfg(not(feature = "metrics"))]
+    let result = call_chain(meta, args);
+    #[cfg(feature = "stats")]
+    meta.stats.record_hooked(start.elapsed());
+    result
+}
+
+/// Call a hookable function on the fast path, when no hook is attached.
+/// Only records statistics and coverage with the `stats` and `coverage` features,
+/// otherwise just calls `f`.
+#[doc(hidden)]
+#[inline(always)]
+pub fn call_fast_path<R>(
+    meta: &'static LazyLock<HookableFuncMetadata>,
+    f: impl FnOnce() -> R,
+) -> R {
+    call_fast_path_of(meta, f)
+}
+
+/// Like [`call_fast_path`], for initialized metadata.
+#[inline(always)]
+fn call_fast_path_of<M, R>(meta: &M, f: impl FnOnce() -> R) -> R
+where
+    M: std::ops::Deref<Target = HookableFuncMetadata>,
+{
+    #[cfg(feature = "coverage")]
+    coverage::mark(meta);
+    #[cfg(feature = "stats")]
+    {
+        let start = std::time::Instant::now();
+        let result = f();
+        meta.stats.record_fast(start.elapsed());
+        result
+    }
+    #[cfg(not(feature = "stats"))]
+    {
+        let _ = meta;
+        f()
+    }
+}
+
+/// Call the published chain of a hookable function,
+/// or the function without hooks beyond the maximum depth, see [`HookableFuncMetadata::set_max_depth`].
+fn call_chain<R, A>(meta: &'static HookableFuncMetadata, args: A) -> R {
+    let max_depth = meta.max_depth.load(std::sync::atomic::Ordering::Relaxed);
+    let _depth = if max_depth != usize::MAX {
+        match reentry::enter(meta, max_depth) {
+            Some(depth) => Some(depth),
+            None => return call_too_deep(meta, args),
         }
+    } else {
+        None
+    };
+    // Pinned only while cloning the chain, so long calls do not delay the release of replaced chains.
+    let chain = {
+        let guard = epoch::pin();
+        // SAFETY: the chain is never null, and it is not released while we are pinned.
+        let chain = unsafe {
+            meta.dispatch
+                .load(std::sync::atomic::Ordering::Acquire, &guard)
+                .deref()
+        };
+        Arc::clone(chain)
     };
-    next_fn_ref.set(Some(&next_fn));
-    next_fn(args)
+    // SAFETY: the chain is composed by compose_chain<R, A'>, where A' differs from A only in lifetimes.
+    let composed = unsafe { &*(chain.composed.as_ref() as *const dyn Any as *const ChainFn<A, R>) };
+    composed(args)
+}
+
+/// Call the depth fallback of a hookable function if any, otherwise the function without hooks.
+#[cold]
+fn call_too_deep<R, A>(meta: &'static HookableFuncMetadata, args: A) -> R {
+    let fallback = lock::read(&meta.depth_fallback).clone();
+    match fallback {
+        Some(fallback) => {
+            // SAFETY: the fallback is a ChainFn<A', R>, where A' differs from A only in lifetimes,
+            // see `set_depth_fallback`.
+            let fallback =
+                unsafe { &*(fallback.as_ref() as *const dyn Any as *const ChainFn<A, R>) };
+            fallback(args)
+        }
+        None => call_bare(meta, args),
+    }
+}
+
+/// Call a hookable function without hooks.
+fn call_bare<R, A>(meta: &'static HookableFuncMetadata, args: A) -> R {
+    // SAFETY: see `call_chain`.
+    let composed =
+        unsafe { &*(meta.bare.composed.as_ref() as *const dyn Any as *const ChainFn<A, R>) };
+    composed(args)
 }
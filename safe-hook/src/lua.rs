@@ -0,0 +1,119 @@
+//! Hooks implemented as Lua functions, available with the `mlua` feature.
+//!
+//! A [`LuaHook`] calls a global function of a script as `function(args, next)`,
+//! with the arguments of the call as an array, and returns its result.
+//! `next(args)` calls the rest of the chain with an array of arguments.
+//! Values are converted to and from Lua values with `serde`,
+//! like with [`JsonHook`](crate::script::JsonHook).
+//!
+//! The script runs once when the hook is created, in its own [`Lua`] state,
+//! which can be prepared with [`LuaHook::with_lua`], e.g. with a memory limit.
+//! Calls are run one at a time.
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::lua::LuaHook;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("lua-discount")]
+//! fn discount(user: String, price: u32) -> u32 {
+//!     price
+//! }
+//!
+//! let script = r#"
+//!     function vip_discount(args, next)
+//!         local price = next(args)
+//!         if args[1] == "vip" then return price // 2 end
+//!         return price
+//!     end
+//! "#;
+//! let hook = LuaHook::<(String, u32), u32>::new(script, "vip_discount").unwrap();
+//! lookup_hookable("lua-discount").unwrap().add_hook(Arc::new(hook)).unwrap();
+//! assert_eq!(discount("vip".to_string(), 100), 50);
+//! assert_eq!(discount("guest".to_string(), 100), 100);
+//! ```
+
+use crate::script::{JsonNext, call_json};
+use crate::{Hook, lock};
+use mlua::{Function, LuaSerdeExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+pub use mlua::Lua;
+
+/// A [`Hook`] calling a Lua function, see the [module docs](self).
+///
+/// If the function raises an error, or if the values cannot be converted,
+/// the call proceeds like with [`JsonHook`](crate::script::JsonHook),
+/// and the error is kept, see [`LuaHook::last_error`].
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct LuaHook<A, R> {
+    lua: Lua,
+    function: Function,
+    name: String,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> LuaHook<A, R> {
+    /// Create a hook calling the global `function` defined by `script` in a new state.
+    pub fn new(script: &str, function: &str) -> mlua::Result<Self> {
+        Self::with_lua(Lua::new(), script, function)
+    }
+
+    /// Create a hook calling the global `function` defined by `script` in `lua`,
+    /// e.g. with limits or additional globals.
+    pub fn with_lua(lua: Lua, script: &str, function: &str) -> mlua::Result<Self> {
+        lua.load(script).exec()?;
+        let global = lua.globals().get(function).map_err(|_| {
+            mlua::Error::runtime(format!("the script does not define {:?}", function))
+        })?;
+        Ok(Self {
+            lua,
+            function: global,
+            name: function.to_string(),
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        })
+    }
+
+    /// The last error of the function, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+
+    /// Call the function with JSON arguments.
+    fn run(&self, args: Value, next: JsonNext) -> mlua::Result<Value> {
+        self.lua.scope(|scope| {
+            let next = scope.create_function(|lua, args: mlua::Value| {
+                let result = next(lua.from_value(args)?).map_err(mlua::Error::runtime)?;
+                lua.to_value(&result)
+            })?;
+            let result: mlua::Value = self.function.call((self.lua.to_value(&args)?, next))?;
+            self.lua.from_value(result)
+        })
+    }
+}
+
+impl<A, R> Hook for LuaHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        call_json(&self.last_error, args, next, |args, next| {
+            self.run(args, next).map_err(|e| e.to_string())
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("LuaHook({})", self.name)
+    }
+}
@@ -0,0 +1,267 @@
+//! Metrics of hookable functions, available with the `metrics` feature.
+//!
+//! Once enabled for a hookable function with [`HookableFuncMetadata::set_metrics_enabled`],
+//! every call reports to the global [`MetricsRecorder`], with the label `hookable` set to its name:
+//! - `safe_hook_calls_total`: a counter of calls.
+//! - `safe_hook_errors_total`: a counter of calls returning `Err`.
+//! - `safe_hook_call_duration_seconds`: a histogram of call durations, including hooks.
+//!
+//! The recorder is meant to forward to a metrics library, e.g. the `metrics` facade:
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::metrics::{MetricsRecorder, set_recorder};
+//!
+//! struct Facade;
+//!
+//! impl MetricsRecorder for Facade {
+//!     fn increment_counter(&self, name: &'static str, hookable: &str, value: u64) {
+//!         // metrics::counter!(name, "hookable" => hookable.to_string()).increment(value);
+//!     }
+//!     fn record_histogram(&self, name: &'static str, hookable: &str, value: f64) {
+//!         // metrics::histogram!(name, "hookable" => hookable.to_string()).record(value);
+//!     }
+//! }
+//!
+//! set_recorder(Arc::new(Facade));
+//! ```
+//!
+//! With the `metrics-rs` feature, [`FacadeRecorder`] does this already.
+//!
+//! Enabling metrics makes calls of the function take the hooked path, even without hooks.
+//!
+//! [`PrometheusRecorder`] keeps the metrics in memory to be scraped by Prometheus.
+
+use crate::{HookableFuncMetadata, call_bare, call_chain, iter_hookables, lock};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+/// A destination of metrics, see the [module docs](self).
+pub trait MetricsRecorder: Send + Sync {
+    /// Increment the counter `name` of the hookable function `hookable` by `value`.
+    fn increment_counter(&self, name: &'static str, hookable: &str, value: u64);
+
+    /// Record `value` into the histogram `name` of the hookable function `hookable`.
+    fn record_histogram(&self, name: &'static str, hookable: &str, value: f64);
+}
+
+static RECORDER: RwLock<Option<Arc<dyn MetricsRecorder>>> = RwLock::new(None);
+
+/// Set the global recorder, replacing the previous one.
+pub fn set_recorder(recorder: Arc<dyn MetricsRecorder>) {
+    *lock::write(&RECORDER) = Some(recorder);
+}
+
+/// Remove the global recorder, so metrics are discarded.
+pub fn clear_recorder() {
+    *lock::write(&RECORDER) = None;
+}
+
+impl HookableFuncMetadata {
+    /// Enable or disable metrics of the hookable function, see [`crate::metrics`].
+    pub fn set_metrics_enabled(&self, enabled: bool) {
+        let hooks = lock::read(&self.hooks);
+        self.metrics_enabled.store(enabled, Ordering::Release);
+        self.update_fast_path_flag(&hooks);
+    }
+
+    /// Check whether metrics of the hookable function are enabled.
+    pub fn metrics_enabled(&self) -> bool {
+        self.metrics_enabled.load(Ordering::Acquire)
+    }
+}
+
+pub(crate) fn call_recorded<R: 'static, A>(meta: &'static HookableFuncMetadata, args: A) -> R {
+    let start = Instant::now();
+    let result = if meta.hooks_active() {
+        call_chain(meta, args)
+    } else {
+        call_bare(meta, args)
+    };
+    let elapsed = start.elapsed();
+    if let Some(recorder) = lock::read(&RECORDER).as_ref() {
+        let name = meta.name();
+        recorder.increment_counter("safe_hook_calls_total", name, 1);
+        let is_err = meta
+            .is_err
+            .downcast_ref::<fn(&R) -> bool>()
+            .is_some_and(|is_err| is_err(&result));
+        if is_err {
+            recorder.increment_counter("safe_hook_errors_total", name, 1);
+        }
+        recorder.record_histogram(
+            "safe_hook_call_duration_seconds",
+            name,
+            elapsed.as_secs_f64(),
+        );
+    }
+    result
+}
+
+/// Upper bounds of the buckets of [`PrometheusRecorder`] histograms, in seconds.
+const BUCKETS: [f64; 8] = [1e-6, 1e-5, 1e-4, 1e-3, 1e-2, 0.1, 1.0, 10.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// Counts of values in each bucket, not cumulative.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+/// A [`MetricsRecorder`] forwarding to the recorder installed in the `metrics` crate facade,
+/// available with the `metrics-rs` feature.
+/// Counters and histograms keep their names, and are labeled with `hookable`.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::metrics::{FacadeRecorder, set_recorder};
+///
+/// // Install an exporter of the `metrics` crate first, e.g. `metrics-exporter-prometheus`.
+/// set_recorder(Arc::new(FacadeRecorder));
+/// ```
+#[cfg(feature = "metrics-rs")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FacadeRecorder;
+
+#[cfg(feature = "metrics-rs")]
+impl MetricsRecorder for FacadeRecorder {
+    fn increment_counter(&self, name: &'static str, hookable: &str, value: u64) {
+        metrics_rs::counter!(name, "hookable" => hookable.to_string()).increment(value);
+    }
+
+    fn record_histogram(&self, name: &'static str, hookable: &str, value: f64) {
+        metrics_rs::histogram!(name, "hookable" => hookable.to_string()).record(value);
+    }
+}
+
+/// A [`MetricsRecorder`] keeping the metrics in memory,
+/// rendered in the Prometheus text exposition format by [`PrometheusRecorder::render`].
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::metrics::{PrometheusRecorder, set_recorder};
+///
+/// let recorder = Arc::new(PrometheusRecorder::new());
+/// set_recorder(recorder.clone());
+/// // Serve this on the `/metrics` endpoint.
+/// let text = recorder.render();
+/// ```
+#[derive(Default)]
+pub struct PrometheusRecorder {
+    counters: Mutex<BTreeMap<(&'static str, String), u64>>,
+    histograms: Mutex<BTreeMap<(&'static str, String), Histogram>>,
+}
+
+impl PrometheusRecorder {
+    /// Create an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the recorded metrics, and the state of the hooks attached to every hookable function:
+    /// - `safe_hook_hooks_attached`: a gauge of the number of hooks attached to each hookable function.
+    /// - `safe_hook_hook_enabled`: a gauge for each attached hook, 1 if enabled, otherwise 0,
+    ///   labeled with the `hookable`, the `hook` description and its `id`.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut last_name = "";
+        for ((name, hookable), value) in lock::lock(&self.counters).iter() {
+            if *name != last_name {
+                let _ = writeln!(out, "# TYPE {} counter", name);
+                last_name = name;
+            }
+            let _ = writeln!(
+                out,
+                "{}{{hookable=\"{}\"}} {}",
+                name,
+                escape(hookable),
+                value
+            );
+        }
+        for ((name, hookable), histogram) in lock::lock(&self.histograms).iter() {
+            if *name != last_name {
+                let _ = writeln!(out, "# TYPE {} histogram", name);
+                last_name = name;
+            }
+            let hookable = escape(hookable);
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{hookable=\"{}\",le=\"{}\"}} {}",
+                    name, hookable, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{hookable=\"{}\",le=\"+Inf\"}} {}",
+                name, hookable, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{}_sum{{hookable=\"{}\"}} {}",
+                name, hookable, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "{}_count{{hookable=\"{}\"}} {}",
+                name, hookable, histogram.count
+            );
+        }
+        let mut hookables = iter_hookables().collect::<Vec<_>>();
+        hookables.sort_by_key(|h| h.name());
+        out.push_str("# TYPE safe_hook_hooks_attached gauge\n");
+        for hookable in &hookables {
+            let _ = writeln!(
+                out,
+                "safe_hook_hooks_attached{{hookable=\"{}\"}} {}",
+                escape(hookable.name()),
+                hookable.list_hooks().len()
+            );
+        }
+        out.push_str("# TYPE safe_hook_hook_enabled gauge\n");
+        for hookable in &hookables {
+            for hook in hookable.list_hooks() {
+                let _ = writeln!(
+                    out,
+                    "safe_hook_hook_enabled{{hookable=\"{}\",hook=\"{}\",id=\"{}\"}} {}",
+                    escape(hookable.name()),
+                    escape(&hook.description),
+                    hook.id.as_u64(),
+                    hook.enabled as u8
+                );
+            }
+        }
+        out
+    }
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn increment_counter(&self, name: &'static str, hookable: &str, value: u64) {
+        *lock::lock(&self.counters)
+            .entry((name, hookable.to_string()))
+            .or_default() += value;
+    }
+
+    fn record_histogram(&self, name: &'static str, hookable: &str, value: f64) {
+        let mut histograms = lock::lock(&self.histograms);
+        let histogram = histograms.entry((name, hookable.to_string())).or_default();
+        if let Some(bucket) = BUCKETS.iter().position(|&bound| value <= bound) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+}
+
+/// Escape a label value of the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,94 @@
+//! Catching panics of hooks for [`PanicPolicy::Skip`] and [`PanicPolicy::Detach`].
+
+use crate::{ChainFn, HookDyn, HookId, HookableFuncMetadata, PanicPolicy, compose_hook};
+use std::any::Any;
+use std::marker::PhantomData;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::Arc;
+
+/// A panic of the rest of the chain, passed through the guard of the hook calling it,
+/// so it is not taken for a panic of the hook.
+struct Downstream(Box<dyn Any + Send>);
+
+/// Compose `hook` on top of the chain `next`, so if the hook panics,
+/// `next` is called with a copy of the arguments instead.
+/// With [`PanicPolicy::Detach`], the hook is also removed from `hookable` by its id.
+pub(crate) fn guard<A: 'static, R: 'static>(
+    hook: &Arc<dyn HookDyn>,
+    id: HookId,
+    next: Box<dyn Any + Send + Sync>,
+    clone_args: fn(&A) -> A,
+    policy: PanicPolicy,
+    hookable: Option<&'static HookableFuncMetadata>,
+) -> Box<dyn Any + Send + Sync> {
+    let next: Arc<ChainFn<A, R>> = Arc::new(*next.downcast().unwrap());
+    let marked: ChainFn<A, R> = Box::new({
+        let next = next.clone();
+        move |args| match catch_unwind(AssertUnwindSafe(|| next(args))) {
+            Ok(result) => result,
+            Err(payload) => resume_unwind(Box::new(Downstream(payload))),
+        }
+    });
+    let layer: ChainFn<A, R> = *compose_hook::<A, R>(hook, Box::new(marked))
+        .downcast()
+        .unwrap();
+    let guarded: ChainFn<A, R> = Box::new(move |args| {
+        let args_copy = clone_args(&args);
+        match catch_unwind(AssertUnwindSafe(|| layer(args))) {
+            Ok(result) => result,
+            Err(payload) => match payload.downcast::<Downstream>() {
+                Ok(downstream) => resume_unwind(downstream.0),
+                Err(_) => {
+                    if policy == PanicPolicy::Detach
+                        && let Some(meta) = hookable
+                    {
+                        // If hooks cannot be changed, the hook stays attached and keeps being skipped.
+                        let _ = meta.remove_hook_by_id(id);
+                    }
+                    next(args_copy)
+                }
+            },
+        }
+    });
+    Box::new(guarded)
+}
+
+/// Finds a `fn(&T) -> T` cloning `T` if it implements [`Clone`], with autoref specialization:
+/// `(&&CloneProbe::<T>::new()).clone_fn()` uses [`ProbeClone`] if possible,
+/// otherwise [`ProbeNotClone`]. Only works with concrete types, it is used inside the macro [`hookable`](crate::hookable).
+#[doc(hidden)]
+pub struct CloneProbe<T>(PhantomData<T>);
+
+impl<T> CloneProbe<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for CloneProbe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeClone<T> {
+    fn clone_fn(&self) -> Option<fn(&T) -> T>;
+}
+
+impl<T: Clone> ProbeClone<T> for &CloneProbe<T> {
+    fn clone_fn(&self) -> Option<fn(&T) -> T> {
+        Some(T::clone)
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeNotClone<T> {
+    fn clone_fn(&self) -> Option<fn(&T) -> T>;
+}
+
+impl<T> ProbeNotClone<T> for CloneProbe<T> {
+    fn clone_fn(&self) -> Option<fn(&T) -> T> {
+        None
+    }
+}
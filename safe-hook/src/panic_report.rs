@@ -0,0 +1,108 @@
+//! Report which hookable functions and hooks were running when a thread panics.
+//!
+//! Hooks change the behavior of functions at runtime, so a backtrace alone
+//! does not tell which hooks were involved in a crash.
+//! Once [`install`] is called, every panic also prints the hookable functions
+//! the thread was calling with hooks, innermost first, and the hooks attached to them.
+//! # Example
+//! ```rust
+//! use safe_hook::panic_report;
+//!
+//! panic_report::install();
+//! // A panic now prints something like:
+//! // safe-hook: the thread was calling hookable functions, innermost first:
+//! //   fn parse(&'static str) -> u32
+//! //     HookId(3) priority 0: my_crate::Validate
+//! ```
+
+use crate::HookableFuncMetadata;
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, TryLockError};
+
+/// Whether [`install`] was called, so calls are tracked.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The hookable functions this thread is calling with hooks, outermost first.
+    static ACTIVE: RefCell<Vec<&'static HookableFuncMetadata>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Print the report on every panic, after the message printed by the current panic hook.
+///
+/// Calls with hooks are only tracked from then on, at the cost of a thread-local push and pop.
+/// Calling it again does nothing.
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if let Some(report) = report() {
+                eprint!("{}", report);
+            }
+        }));
+        INSTALLED.store(true, Ordering::Release);
+    });
+}
+
+/// Describe the hookable functions the current thread is calling with hooks,
+/// innermost first, with their attached hooks.
+/// Returns `None` if there is none, or if [`install`] was not called.
+pub fn report() -> Option<String> {
+    let active = ACTIVE.try_with(|active| active.try_borrow().ok().map(|a| a.clone()));
+    let active = active.ok().flatten().filter(|active| !active.is_empty())?;
+    let mut report =
+        String::from("safe-hook: the thread was calling hookable functions, innermost first:\n");
+    for meta in active.iter().rev() {
+        let _ = writeln!(report, "  {}", meta.signature());
+        // The panic may come from code holding the lock, e.g. a lifecycle method.
+        let hooks = match meta.hooks.try_read() {
+            Ok(hooks) => hooks,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                let _ = writeln!(report, "    (hooks are being modified)");
+                continue;
+            }
+        };
+        for entry in &hooks.entries {
+            let info = entry.info();
+            let _ = write!(
+                report,
+                "    {:?} priority {}: {}",
+                info.id, info.priority, info.description
+            );
+            let _ = writeln!(report, "{}", if info.enabled { "" } else { " (disabled)" });
+        }
+    }
+    Some(report)
+}
+
+/// Removes a hookable function from the active ones when its call returns or unwinds.
+pub(crate) struct ActiveGuard(());
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let _ = ACTIVE.try_with(|active| active.borrow_mut().pop());
+    }
+}
+
+/// Track a call with hooks until the returned guard is dropped, if [`install`] was called.
+/// Otherwise only an atomic load is added to the call.
+#[inline(always)]
+pub(crate) fn enter(meta: &'static HookableFuncMetadata) -> Option<ActiveGuard> {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    push(meta)
+}
+
+#[cold]
+#[inline(never)]
+fn push(meta: &'static HookableFuncMetadata) -> Option<ActiveGuard> {
+    ACTIVE
+        .try_with(|active| active.borrow_mut().push(meta))
+        .ok()
+        .map(|()| ActiveGuard(()))
+}
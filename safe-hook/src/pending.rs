@@ -0,0 +1,274 @@
+use crate::{
+    AddHookError, HookDyn, HookId, HookableFuncMetadata, iter_hookables, lock, lookup_registered,
+};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A hook waiting for the hookable function named `target`.
+struct Held {
+    target: String,
+    hook: Arc<dyn HookDyn>,
+    /// Why the last attempt to attach the hook failed, if it was attempted.
+    error: Option<AddHookError>,
+}
+
+static PENDING: Mutex<Vec<Held>> = Mutex::new(Vec::new());
+static HAS_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Add a hook to the hookable function named `target`, even if it is not registered yet.
+///
+/// If the hookable function is registered, the hook is attached right away and its id is returned.
+/// Otherwise the hook is held, and `None` is returned. Held hooks are attached
+/// once a hookable function with that name is registered, e.g. by a plugin loaded later,
+/// the next time [`lookup_hookable`](crate::lookup_hookable) or [`attach_pending`] is called.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// without holding the hook if hooks cannot be changed, see [`freeze`](crate::freeze).
+/// # Example
+/// ```rust
+/// use safe_hook::{add_hook_pending, lookup_hookable, pending_hooks, IntoHook};
+/// use std::sync::Arc;
+///
+/// let hook = (|args: (u32,), next: &dyn Fn((u32,)) -> u32| -> u32 { next(args) * 2 }).into_hook();
+/// let id = add_hook_pending("plugin-scale", Arc::new(hook)).unwrap();
+/// assert_eq!(id, None);
+/// assert_eq!(pending_hooks(), vec!["plugin-scale".to_string()]);
+/// // Once the plugin defining `plugin-scale` is loaded, looking it up attaches the hook.
+/// assert!(lookup_hookable("plugin-scale").is_none());
+/// ```
+#[track_caller]
+pub fn add_hook_pending(
+    target: &str,
+    hook: Arc<dyn HookDyn>,
+) -> Result<Option<HookId>, AddHookError> {
+    crate::access::check()?;
+    if let Some(meta) = lookup_registered(target) {
+        return meta.add_hook(hook).map(Some);
+    }
+    let mut pending = lock::lock(&PENDING);
+    pending.push(Held {
+        target: target.to_string(),
+        hook,
+        error: None,
+    });
+    HAS_PENDING.store(true, Ordering::Release);
+    Ok(None)
+}
+
+/// Attach the held hooks whose hookable function is now registered, see [`add_hook_pending`],
+/// and the global hooks to newly registered hookable functions, see [`add_global_hook`].
+///
+/// Returns the held hooks that failed to attach, e.g. because of a type mismatch, with their errors.
+/// They stay held and are attached again by the next call, see [`pending_errors`].
+/// Global hooks that failed to attach are also returned, with the name of the hookable function,
+/// and are attached again by the next call too.
+#[track_caller]
+pub fn attach_pending() -> Vec<(String, AddHookError)> {
+    let mut failed = Vec::new();
+    if HAS_GLOBAL.load(Ordering::Acquire) {
+        failed = attach_global();
+    }
+    if !HAS_PENDING.load(Ordering::Acquire) {
+        return failed;
+    }
+    let ready = {
+        let mut pending = lock::lock(&PENDING);
+        let (ready, waiting) = std::mem::take(&mut *pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|held| lookup_registered(&held.target).is_some());
+        *pending = waiting;
+        HAS_PENDING.store(!pending.is_empty(), Ordering::Release);
+        ready
+    };
+    // Attached without holding the lock, as attaching calls back into user code.
+    let mut kept = Vec::new();
+    for mut held in ready {
+        if let Some(meta) = lookup_registered(&held.target)
+            && let Err(err) = meta.add_hook(held.hook.clone())
+        {
+            failed.push((held.target.clone(), err.clone()));
+            held.error = Some(err);
+            kept.push(held);
+        }
+    }
+    if !kept.is_empty() {
+        let mut pending = lock::lock(&PENDING);
+        pending.extend(kept);
+        HAS_PENDING.store(true, Ordering::Release);
+    }
+    failed
+}
+
+/// Get the names of the hookable functions that held hooks are waiting for, see [`add_hook_pending`].
+pub fn pending_hooks() -> Vec<String> {
+    lock::lock(&PENDING)
+        .iter()
+        .map(|held| held.target.clone())
+        .collect()
+}
+
+/// Get the held hooks that failed to attach to their registered hookable function,
+/// with the name of the function and the error of the last attempt, see [`attach_pending`].
+///
+/// They are attached again each time pending hooks are attached,
+/// e.g. when the capability is held, see [`require_capability`](crate::require_capability),
+/// or can be dropped with [`remove_pending`].
+pub fn pending_errors() -> Vec<(String, AddHookError)> {
+    lock::lock(&PENDING)
+        .iter()
+        .filter_map(|held| Some((held.target.clone(), held.error.clone()?)))
+        .collect()
+}
+
+/// Stop holding a hook added by [`add_hook_pending`]. Returns `false` if it is not held.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// and keeps the hook if hooks cannot be changed, see [`freeze`](crate::freeze).
+#[track_caller]
+pub fn remove_pending(hook: &dyn HookDyn) -> Result<bool, AddHookError> {
+    crate::access::check()?;
+    let mut pending = lock::lock(&PENDING);
+    let Some(pos) = pending
+        .iter()
+        .position(|held| std::ptr::addr_eq(held.hook.as_ref(), hook))
+    else {
+        return Ok(false);
+    };
+    pending.remove(pos);
+    HAS_PENDING.store(!pending.is_empty(), Ordering::Release);
+    Ok(true)
+}
+
+/// Identifies a global hook, returned by [`add_global_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlobalHookId(u64);
+
+struct GlobalHook {
+    id: GlobalHookId,
+    hook: Arc<dyn HookDyn>,
+    /// The hookable functions the hook was attached to, with `None` while it is being attached.
+    attached: Vec<(&'static HookableFuncMetadata, Option<HookId>)>,
+}
+
+static GLOBAL: Mutex<Vec<GlobalHook>> = Mutex::new(Vec::new());
+static HAS_GLOBAL: AtomicBool = AtomicBool::new(false);
+
+/// Attach a hook to every hookable function with the same arguments and result types,
+/// including the ones registered later, e.g. by plugins,
+/// which get it the next time [`lookup_hookable`](crate::lookup_hookable) or [`attach_pending`] is called.
+///
+/// Useful for blanket policies, like timing every `(u64,) -> Result<Vec<u8>, String>` function.
+/// Removing the hook from one hookable function does not attach it again.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// if hooks cannot be changed, see [`freeze`](crate::freeze).
+/// # Example
+/// ```rust
+/// use safe_hook::{add_global_hook, remove_global_hook, IntoHook};
+/// use safe_hook_macros::hookable;
+/// use std::sync::Arc;
+///
+/// #[hookable("global-double")]
+/// fn double(x: u16) -> u16 {
+///     x * 2
+/// }
+///
+/// #[hookable("global-triple")]
+/// fn triple(x: u16) -> u16 {
+///     x * 3
+/// }
+///
+/// let plus_one = (|args: (u16,), next: &dyn Fn((u16,)) -> u16| -> u16 { next(args) + 1 }).into_hook();
+/// let id = add_global_hook(Arc::new(plus_one)).unwrap();
+/// assert_eq!(double(1), 3);
+/// assert_eq!(triple(1), 4);
+/// remove_global_hook(id).unwrap();
+/// assert_eq!(double(1), 2);
+/// ```
+#[track_caller]
+pub fn add_global_hook(hook: Arc<dyn HookDyn>) -> Result<GlobalHookId, AddHookError> {
+    crate::access::check()?;
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = GlobalHookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    lock::lock(&GLOBAL).push(GlobalHook {
+        id,
+        hook,
+        attached: Vec::new(),
+    });
+    HAS_GLOBAL.store(true, Ordering::Release);
+    attach_global();
+    Ok(id)
+}
+
+/// Detach a global hook from every hookable function it was attached to,
+/// and stop attaching it to new ones. Returns `false` if no global hook has the given id.
+///
+/// Returns [`AddHookError::RegistryFrozen`] or [`AddHookError::Unauthorized`]
+/// and keeps the hook if hooks cannot be changed, see [`freeze`](crate::freeze).
+#[track_caller]
+pub fn remove_global_hook(id: GlobalHookId) -> Result<bool, AddHookError> {
+    crate::access::check()?;
+    let removed = {
+        let mut global = lock::lock(&GLOBAL);
+        let Some(pos) = global.iter().position(|g| g.id == id) else {
+            return Ok(false);
+        };
+        let removed = global.remove(pos);
+        HAS_GLOBAL.store(!global.is_empty(), Ordering::Release);
+        removed
+    };
+    for (meta, hook_id) in removed.attached {
+        if let Some(hook_id) = hook_id {
+            let _ = meta.remove_hook_by_id(hook_id);
+        }
+    }
+    Ok(true)
+}
+
+/// Attach the global hooks to the matching hookable functions they are not attached to yet,
+/// returning the hookable functions they failed to attach to, with the errors.
+#[track_caller]
+fn attach_global() -> Vec<(String, AddHookError)> {
+    // Claim the work while locked, so concurrent callers do not attach a hook twice,
+    // then attach without holding the lock, as attaching calls back into user code.
+    let work = {
+        let mut global = lock::lock(&GLOBAL);
+        let mut work = Vec::new();
+        for g in global.iter_mut() {
+            let type_info = g.hook.type_info();
+            for meta in iter_hookables() {
+                if meta.type_info == type_info
+                    && !g.attached.iter().any(|(m, _)| std::ptr::eq(*m, meta))
+                {
+                    g.attached.push((meta, None));
+                    work.push((g.id, g.hook.clone(), meta));
+                }
+            }
+        }
+        work
+    };
+    let mut failed = Vec::new();
+    for (id, hook, meta) in work {
+        let result = meta.add_hook(hook);
+        let mut global = lock::lock(&GLOBAL);
+        let Some(g) = global.iter_mut().find(|g| g.id == id) else {
+            // Removed while attaching.
+            drop(global);
+            if let Ok(hook_id) = result {
+                let _ = meta.remove_hook_by_id(hook_id);
+            }
+            continue;
+        };
+        let pos = g.attached.iter().position(|(m, _)| std::ptr::eq(*m, meta));
+        match (result, pos) {
+            (Ok(hook_id), Some(pos)) => g.attached[pos].1 = Some(hook_id),
+            // Unclaimed, so the next call attaches it again.
+            (Err(err), Some(pos)) => {
+                g.attached.remove(pos);
+                failed.push((meta.name().to_string(), err));
+            }
+            (_, None) => {}
+        }
+    }
+    failed
+}
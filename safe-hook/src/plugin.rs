@@ -0,0 +1,431 @@
+//! Loading hooks from shared libraries at runtime, available with the `plugin` feature.
+//!
+//! A plugin is a `cdylib` depending on `safe-hook`, exporting an entry point with [`export_plugin!`].
+//! [`load`] opens the library with `libloading` and calls the entry point with a [`PluginContext`],
+//! through which the plugin enumerates the hookable functions of the host and attaches its hooks,
+//! e.g. for game modding or live extension.
+//!
+//! The plugin statically links its own copy of `safe-hook`, with its own (empty) registry,
+//! so it must use the [`PluginContext`] instead of [`lookup_hookable`](crate::lookup_hookable)
+//! and friends.
+//!
+//! The symbols exported by [`export_plugin!`] are `extern "C"` functions,
+//! so the version can be checked and the entry point called with a stable calling convention.
+//! Beyond them, Rust has no stable ABI: the layout of types, vtables and [`TypeId`](std::any::TypeId)s
+//! are only the same in libraries built with the same compiler, for the same target,
+//! and with the same version and features of `safe-hook`.
+//! All of them are part of [`PLUGIN_VERSION`], checked when loading,
+//! so hooks of independently built plugins are type checked like other hooks,
+//! and plugins built differently are rejected instead of corrupting memory.
+//! Types shared by the host and its plugins should be defined in a crate both depend on,
+//! or be standard types.
+//! Plugins built independently of the host can attach hooks through `#[repr(C)]` types instead,
+//! with the layouts of their types checked, see the `stable` module of the `abi_stable` feature.
+//!
+//! A plugin can be unloaded with the unsafe [`Plugin::unload`], which detaches its hooks,
+//! see [`detach_all_from`], and waits until no call uses them before closing the library.
+//! Otherwise, loaded libraries stay loaded for the life of the process.
+//! # Example
+//! The plugin, built as a `cdylib`:
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::plugin::PluginContext;
+//!
+//! fn init(context: &mut PluginContext) {
+//!     let target = context.lookup_hookable("game-damage").unwrap();
+//!     let hook = safe_hook::fn_hook!(|(damage,): (u32,), next| -> u32 { next((damage * 2,)) });
+//!     context.add_hook(target, Arc::new(hook), 0).unwrap();
+//! }
+//!
+//! safe_hook::export_plugin!(init);
+//! ```
+//! The host:
+//! ```rust,no_run
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("game-damage")]
+//! fn damage(base: u32) -> u32 {
+//!     base
+//! }
+//!
+//! // SAFETY: the plugin is built with the same compiler and version of safe-hook.
+//! let plugin = unsafe { safe_hook::plugin::load("plugins/libdouble_damage.so") }.unwrap();
+//! println!("{} attached {} hooks", plugin.name(), plugin.hooks().len());
+//! assert_eq!(damage(10), 20);
+//! ```
+
+use crate::{AddHookError, HookDyn, HookId, HookableFuncMetadata, iter_hookables, lookup_hookable};
+use libloading::Library;
+use std::ffi::{CStr, c_char};
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// The version of the plugin interface, a plugin must be built with the same one as the host.
+/// It contains the versions of `safe-hook` and of the compiler, the target and the enabled features.
+pub const PLUGIN_VERSION: &str =
+    concat!(env!("CARGO_PKG_VERSION"), " ", env!("SAFE_HOOK_PLUGIN_ABI"));
+
+/// [`PLUGIN_VERSION`] as a C string, returned by the [`VERSION_SYMBOL`] of plugins.
+#[doc(hidden)]
+pub const PLUGIN_VERSION_C: &CStr = match CStr::from_bytes_with_nul(
+    concat!(
+        env!("CARGO_PKG_VERSION"),
+        " ",
+        env!("SAFE_HOOK_PLUGIN_ABI"),
+        "\0"
+    )
+    .as_bytes(),
+) {
+    Ok(version) => version,
+    Err(_) => panic!("The plugin version contains a null byte"),
+};
+
+/// The symbol of the entry point, a [`RawPluginEntry`], exported by [`export_plugin!`].
+pub const ENTRY_SYMBOL: &str = "safe_hook_plugin_entry";
+
+/// The symbol of the version of the plugin, a [`RawPluginVersion`], exported by [`export_plugin!`].
+pub const VERSION_SYMBOL: &str = "safe_hook_plugin_version";
+
+/// The entry point of a plugin, called once when it is loaded.
+pub type PluginEntry = fn(&mut PluginContext);
+
+/// The exported entry point of a plugin, calling its [`PluginEntry`] with the context.
+pub type RawPluginEntry = unsafe extern "C" fn(*mut PluginContext);
+
+/// The exported version of a plugin, returning [`PLUGIN_VERSION`] as a static C string.
+pub type RawPluginVersion = extern "C" fn() -> *const c_char;
+
+/// Export `init`, a `fn(&mut PluginContext)`, as the entry point of a plugin,
+/// see the [`plugin`](crate::plugin) module.
+#[macro_export]
+macro_rules! export_plugin {
+    ($init:expr) => {
+        /// # Safety
+        /// `context` must be a valid context passed by the host.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn safe_hook_plugin_entry(
+            context: *mut $crate::plugin::PluginContext,
+        ) {
+            let init: $crate::plugin::PluginEntry = $init;
+            // SAFETY: the host passes a valid context, see `plugin::load`.
+            init(unsafe { &mut *context })
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn safe_hook_plugin_version() -> *const ::std::ffi::c_char {
+            $crate::plugin::PLUGIN_VERSION_C.as_ptr()
+        }
+    };
+}
+
+/// An error loading a plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// The library could not be opened, with the message of the system.
+    Open(String),
+    /// The library does not export the symbol, it is not built with [`export_plugin!`].
+    MissingSymbol(&'static str),
+    /// Hooks of the plugin are still used after detaching them, by calls in progress
+    /// or by other references to them, so it cannot be unloaded yet.
+    Busy,
+    /// The library is built with another version or features of `safe-hook`,
+    /// or another compiler, see [`PLUGIN_VERSION`].
+    VersionMismatch {
+        /// The version of the host.
+        expected: &'static str,
+        /// The version of the plugin.
+        found: String,
+    },
+    /// The interface of a stable plugin does not match the one of the host,
+    /// with the differences found, see the `stable` module.
+    LayoutMismatch(String),
+}
+
+impl std::fmt::Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::Open(message) => write!(f, "Cannot open plugin: {}", message),
+            PluginError::MissingSymbol(symbol) => {
+                write!(f, "Plugin does not export '{}'", symbol)
+            }
+            PluginError::Busy => write!(f, "Plugin hooks are still in use"),
+            PluginError::VersionMismatch { expected, found } => write!(
+                f,
+                "Plugin is built for safe-hook {}, expected {}",
+                found, expected
+            ),
+            PluginError::LayoutMismatch(message) => {
+                write!(f, "Plugin interface does not match: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+type AddHookFn =
+    fn(&'static HookableFuncMetadata, Arc<dyn HookDyn>, i32) -> Result<HookId, AddHookError>;
+
+/// The functions of the host called by plugins, so they use the registry of the host
+/// instead of their own copy of `safe-hook`.
+/// Passed behind a pointer to the entry point, so its layout is fixed.
+#[repr(C)]
+struct HostFunctions {
+    lookup: fn(&str) -> Option<&'static HookableFuncMetadata>,
+    hookables: fn() -> Vec<&'static HookableFuncMetadata>,
+    add_hook: AddHookFn,
+}
+
+static HOST: HostFunctions = HostFunctions {
+    lookup: lookup_hookable,
+    hookables: || iter_hookables().collect(),
+    add_hook: HookableFuncMetadata::add_hook_with_priority,
+};
+
+/// The access of a plugin to the host, passed to its entry point.
+/// It is passed behind a pointer to the entry point, so its layout is fixed.
+#[repr(C)]
+pub struct PluginContext<'a> {
+    name: &'a str,
+    host: &'static HostFunctions,
+    hooks: Vec<(&'static HookableFuncMetadata, HookId)>,
+    attached: Vec<Weak<dyn HookDyn>>,
+}
+
+impl PluginContext<'_> {
+    /// The name of the plugin, the file name of the library without its extension.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Find a hookable function of the host by name.
+    pub fn lookup_hookable(&self, name: &str) -> Option<&'static HookableFuncMetadata> {
+        (self.host.lookup)(name)
+    }
+
+    /// Get all hookable functions of the host.
+    pub fn hookables(&self) -> Vec<&'static HookableFuncMetadata> {
+        (self.host.hookables)()
+    }
+
+    /// Attach a hook to a hookable function of the host, see
+    /// [`HookableFuncMetadata::add_hook_with_priority`].
+    pub fn add_hook(
+        &mut self,
+        target: &'static HookableFuncMetadata,
+        hook: Arc<dyn HookDyn>,
+        priority: i32,
+    ) -> Result<HookId, AddHookError> {
+        let weak = Arc::downgrade(&hook);
+        let id = (self.host.add_hook)(target, hook, priority)?;
+        self.hooks.push((target, id));
+        self.attached.push(weak);
+        Ok(id)
+    }
+}
+
+/// A loaded plugin, see [`load`].
+pub struct Plugin {
+    name: String,
+    path: Option<PathBuf>,
+    /// Only closed by [`Plugin::unload`], dropping the plugin keeps it loaded.
+    library: Option<ManuallyDrop<Library>>,
+    hooks: Vec<(&'static HookableFuncMetadata, HookId)>,
+    /// The hooks attached by the plugin, to know when they are no longer used.
+    attached: Vec<Weak<dyn HookDyn>>,
+}
+
+impl Plugin {
+    /// Run the entry point of a plugin linked into the host, e.g. for built-in extensions or tests.
+    pub fn from_entry(name: &str, entry: PluginEntry) -> Self {
+        Self::run(name, entry)
+    }
+
+    /// Run `entry` with a new context for the plugin `name`.
+    fn run(name: &str, entry: impl FnOnce(&mut PluginContext)) -> Self {
+        let mut context = PluginContext {
+            name,
+            host: &HOST,
+            hooks: Vec::new(),
+            attached: Vec::new(),
+        };
+        entry(&mut context);
+        Self::with_hooks(name, context.hooks, context.attached)
+    }
+
+    /// A plugin with the hooks attached by its entry point.
+    pub(crate) fn with_hooks(
+        name: &str,
+        hooks: Vec<(&'static HookableFuncMetadata, HookId)>,
+        attached: Vec<Weak<dyn HookDyn>>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            path: None,
+            library: None,
+            hooks,
+            attached,
+        }
+    }
+
+    /// Keep `library`, opened from `path`, loaded until the plugin is unloaded.
+    pub(crate) fn set_library(&mut self, path: &Path, library: Library) {
+        self.path = Some(path.to_path_buf());
+        self.library = Some(ManuallyDrop::new(library));
+    }
+
+    /// The name of the plugin.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The path of the library, `None` for plugins created by [`Plugin::from_entry`].
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The hooks attached by the plugin while loading, with the hookable functions they are attached to.
+    pub fn hooks(&self) -> &[(&'static HookableFuncMetadata, HookId)] {
+        &self.hooks
+    }
+
+    /// Check whether no hook of the plugin is used anymore, by hookable functions,
+    /// calls in progress or other references, so its code can be unloaded.
+    pub fn is_quiescent(&self) -> bool {
+        self.attached.iter().all(|hook| hook.strong_count() == 0)
+    }
+
+    /// Detach the hooks of the plugin, wait up to `timeout` until they are not used anymore,
+    /// see [`Plugin::is_quiescent`], and close the library.
+    ///
+    /// Returns [`PluginError::Busy`] if the hooks are still used after `timeout`,
+    /// the library is kept loaded and `unload` can be called again later.
+    ///
+    /// # Safety
+    /// Only the hooks attached through the [`PluginContext`] are tracked.
+    /// Nothing else of the library may be used after it is closed:
+    /// the plugin must not have left other code running, like threads,
+    /// registered its code elsewhere, like callbacks given to the host,
+    /// or handed out values pointing into the library, like `&'static str`s or boxed trait objects.
+    pub unsafe fn unload(&mut self, timeout: Duration) -> Result<(), PluginError> {
+        detach_all_from(self);
+        let start = Instant::now();
+        while !self.is_quiescent() {
+            if start.elapsed() >= timeout {
+                return Err(PluginError::Busy);
+            }
+            // Let the chains released by finished calls be destroyed.
+            crossbeam_epoch::pin().flush();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        // The vtables of the hooks are in the library, drop them before closing it.
+        self.attached.clear();
+        if let Some(library) = self.library.take() {
+            // No hook of the library is used anymore, and the caller ensures nothing else is.
+            let _ = ManuallyDrop::into_inner(library).close();
+        }
+        Ok(())
+    }
+}
+
+/// Detach all hooks attached by `plugin`, returning the number of hooks detached.
+///
+/// Calls in progress may still use the hooks, see [`Plugin::unload`] to unload the plugin safely.
+/// Hooks that cannot be removed, e.g. after [`freeze`](crate::freeze), are kept in [`Plugin::hooks`].
+pub fn detach_all_from(plugin: &mut Plugin) -> usize {
+    let mut detached = 0;
+    plugin
+        .hooks
+        .retain(|(target, id)| match target.remove_hook_by_id(*id) {
+            Ok(removed) => {
+                detached += usize::from(removed.is_some());
+                false
+            }
+            Err(_) => true,
+        });
+    detached
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+/// Open the library at `path`, resolving all its symbols now,
+/// so a library missing some is rejected instead of failing in the middle of a call.
+///
+/// # Safety
+/// Opening a library runs its initializers.
+pub(crate) unsafe fn open(path: &Path) -> Result<Library, libloading::Error> {
+    #[cfg(unix)]
+    {
+        use libloading::os::unix::{Library, RTLD_LOCAL, RTLD_NOW};
+        unsafe { Library::open(Some(path), RTLD_NOW | RTLD_LOCAL) }.map(Into::into)
+    }
+    #[cfg(not(unix))]
+    unsafe {
+        Library::new(path)
+    }
+}
+
+/// Look up a symbol of the library.
+///
+/// # Safety
+/// `T` must be the type of the symbol.
+pub(crate) unsafe fn symbol<T: Copy>(
+    library: &Library,
+    name: &'static str,
+) -> Result<T, PluginError> {
+    match unsafe { library.get::<T>(name.as_bytes()) } {
+        Ok(symbol) => Ok(*symbol),
+        Err(_) => Err(PluginError::MissingSymbol(name)),
+    }
+}
+
+/// Load the plugin at `path` and run its entry point, which attaches its hooks.
+///
+/// # Safety
+/// Loading a library runs its initializers and its entry point,
+/// which uses Rust types without a stable ABI through the context:
+/// the library must come from a trusted source, built as described in the module documentation.
+pub unsafe fn load(path: impl AsRef<Path>) -> Result<Plugin, PluginError> {
+    let path = path.as_ref();
+    // SAFETY: the caller trusts the library.
+    let library = unsafe { open(path) }.map_err(|error| PluginError::Open(error.to_string()))?;
+    // The library is closed when dropped on error, no code of it has been called.
+    let entry = entry_of(&library)?;
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = name.strip_prefix("lib").unwrap_or(&name);
+    // SAFETY: the context is valid for the call, and the plugin is built with the same ABI.
+    let mut plugin = Plugin::run(name, |context| unsafe { entry(context) });
+    plugin.set_library(path, library);
+    Ok(plugin)
+}
+
+/// Check the version of the library and get its entry point.
+fn entry_of(library: &Library) -> Result<RawPluginEntry, PluginError> {
+    // SAFETY: the symbol is exported by `export_plugin!` as a `RawPluginVersion`,
+    // which only uses C types, so it can be called whatever version of `safe-hook` built it.
+    let version = unsafe { symbol::<RawPluginVersion>(library, VERSION_SYMBOL) }?();
+    // SAFETY: the version is a static C string.
+    let version = unsafe { CStr::from_ptr(version) };
+    if version != PLUGIN_VERSION_C {
+        return Err(PluginError::VersionMismatch {
+            expected: PLUGIN_VERSION,
+            found: version.to_string_lossy().into_owned(),
+        });
+    }
+    // SAFETY: the symbol is exported by `export_plugin!` as a `RawPluginEntry`.
+    unsafe { symbol::<RawPluginEntry>(library, ENTRY_SYMBOL) }
+}
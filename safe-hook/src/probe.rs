@@ -0,0 +1,46 @@
+use std::marker::PhantomData;
+
+/// Finds a `fn(&T) -> bool` checking whether `T` is an error, with autoref specialization:
+/// `(&&ErrProbe::<T>::new()).is_err_fn()` uses [`ProbeResult`] if `T` is a [`Result`],
+/// otherwise [`ProbeNotResult`]. Aliases like [`std::io::Result`] are found too.
+///
+/// Results are only checked with the `metrics` feature, to count errors in metrics,
+/// see [`crate::metrics`]. Without it, no `T` is an error.
+/// Only works with concrete types, it is used inside the macro [`hookable`](crate::hookable).
+#[doc(hidden)]
+pub struct ErrProbe<T>(PhantomData<T>);
+
+impl<T> ErrProbe<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for ErrProbe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeResult<T> {
+    fn is_err_fn(&self) -> fn(&T) -> bool;
+}
+
+#[cfg(feature = "metrics")]
+impl<T, E> ProbeResult<Result<T, E>> for &ErrProbe<Result<T, E>> {
+    fn is_err_fn(&self) -> fn(&Result<T, E>) -> bool {
+        Result::is_err
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeNotResult<T> {
+    fn is_err_fn(&self) -> fn(&T) -> bool;
+}
+
+impl<T> ProbeNotResult<T> for ErrProbe<T> {
+    fn is_err_fn(&self) -> fn(&T) -> bool {
+        |_| false
+    }
+}
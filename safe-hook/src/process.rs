@@ -0,0 +1,347 @@
+//! Hooks running in other processes, available with the `process` feature.
+//!
+//! [`ProcessHook`] runs a hook in a separate process, so untrusted extensions can participate
+//! in hook chains without access to the memory of the host, with a time limit for each call.
+//! The process is started on the first call and reused for the next ones.
+//! To sandbox hooks inside the process instead, see the `wasm` module with the `wasmtime` feature.
+//!
+//! The process reads requests on its stdin and writes answers on its stdout, one JSON object per line:
+//! - The host sends `{"call": "<hookable>", "args": [...]}` for each call.
+//! - The process may send `{"next": [...]}` to call the rest of the chain with the given arguments,
+//!   the host answers `{"result": ...}`.
+//! - The process sends `{"return": ...}` with the result of the call,
+//!   or `{"error": "..."}` to proceed as if the hook was not there.
+//!
+//! Arguments are sent as a JSON array, and results as JSON, both with `serde`.
+//! Stderr of the process is inherited, e.g. for logs.
+//!
+//! Python functions can be used as hooks with [`ProcessHook::python`],
+//! or inside the process, see the `python` module with the `pyo3` feature.
+//!
+//! Any language reading lines of JSON can implement the process, e.g. Lua,
+//! where the function hooking the call gets the arguments and a `next` callable:
+//! ```lua
+//! -- Run with `lua hook.lua`, requires the lua-cjson module.
+//! local json = require("cjson")
+//!
+//! local function hook(args, next)
+//!     args[1] = args[1] * 2
+//!     return next(args) + 1
+//! end
+//!
+//! for line in io.lines() do
+//!     local request = json.decode(line)
+//!     local result = hook(request.args, function(args)
+//!         io.write(json.encode({ next = args }), "\n")
+//!         io.flush()
+//!         return json.decode(io.read("l")).result
+//!     end)
+//!     io.write(json.encode({ ["return"] = result }), "\n")
+//!     io.flush()
+//! end
+//! ```
+//! Lua can also run inside the process, see the `lua` module with the `mlua` feature.
+//! # Example
+//! ```rust
+//! use std::process::Command;
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::process::ProcessHook;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("process-greet")]
+//! fn greet(name: String) -> String {
+//!     format!("Hello, {}!", name)
+//! }
+//!
+//! // A hook written in Python, uppercasing the name.
+//! let mut command = Command::new("python3");
+//! command.arg("-c").arg(r#"
+//! import json, sys
+//! for line in sys.stdin:
+//!     name, = json.loads(line)["args"]
+//!     print(json.dumps({"next": [name.upper()]}), flush=True)
+//!     result = json.loads(sys.stdin.readline())["result"]
+//!     print(json.dumps({"return": result}), flush=True)
+//! "#);
+//! let hook = ProcessHook::<(String,), String>::new(command);
+//! lookup_hookable("process-greet").unwrap().add_hook(Arc::new(hook)).unwrap();
+//! # if std::process::Command::new("python3").arg("--version").output().is_ok() {
+//! assert_eq!(greet("world".to_string()), "Hello, WORLD!");
+//! # }
+//! ```
+
+use crate::{Hook, call_info, lock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// A [`Hook`] running in a separate process, see the [module docs](self).
+///
+/// If the process cannot be started, fails to answer within the time limit,
+/// or answers with invalid JSON, it is killed, restarted on the next call,
+/// and the call proceeds as if the hook was not there:
+/// the rest of the chain is called with the original arguments,
+/// unless the process already called it, then its result is returned.
+/// The error is kept, see [`ProcessHook::last_error`].
+///
+/// Calls are sent to the process one at a time.
+/// Calls made on the same thread by the rest of the chain, e.g. recursive calls, skip the hook.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct ProcessHook<A, R> {
+    command: Mutex<Command>,
+    timeout: Duration,
+    process: Mutex<Option<Process>>,
+    /// The thread talking to the process, if any.
+    owner: Mutex<Option<ThreadId>>,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+/// A running process of a [`ProcessHook`].
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl<A, R> ProcessHook<A, R> {
+    /// Create a hook running `command`, with a time limit of 5 seconds per call.
+    pub fn new(command: Command) -> Self {
+        Self {
+            command: Mutex::new(command),
+            timeout: Duration::from_secs(5),
+            process: Mutex::new(None),
+            owner: Mutex::new(None),
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set the time the process has to answer each call,
+    /// not counting the time spent in the rest of the chain.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The last error of the process, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+
+    /// Create a hook calling the Python function `function` of the file `script` with `python3`,
+    /// as `function(next, *args)`, where `next(*args)` calls the rest of the chain.
+    ///
+    /// Arguments and results are converted to and from Python values through JSON,
+    /// so they are lists, dicts, strings, numbers, booleans or `None`.
+    /// Exceptions are printed to stderr, and the call proceeds as if the hook was not there.
+    /// The standard output of the script is redirected to stderr.
+    /// # Example
+    /// ```python
+    /// # discount.py
+    /// def vip_discount(next, user, price):
+    ///     price = next(user, price)
+    ///     return price // 2 if user == "vip" else price
+    /// ```
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    /// use safe_hook::lookup_hookable;
+    /// use safe_hook::process::ProcessHook;
+    ///
+    /// let hook = ProcessHook::<(String, u32), u32>::python("discount.py", "vip_discount");
+    /// lookup_hookable("price").unwrap().add_hook(Arc::new(hook)).unwrap();
+    /// ```
+    pub fn python(script: impl AsRef<Path>, function: &str) -> Self {
+        let mut command = Command::new("python3");
+        command
+            .arg("-c")
+            .arg(PYTHON_DRIVER)
+            .arg(script.as_ref())
+            .arg(function);
+        Self::new(command)
+    }
+
+    /// Start the process.
+    fn spawn(&self) -> Result<Process, String> {
+        let mut child = lock::lock(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start the process: {}", e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        // Lines are read on another thread, so reads can time out.
+        let (sender, lines) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Process {
+            child,
+            stdin,
+            lines,
+        })
+    }
+}
+
+/// The program running Python hooks, see [`ProcessHook::python`].
+const PYTHON_DRIVER: &str = r#"
+import importlib.util, json, sys, traceback
+
+spec = importlib.util.spec_from_file_location("safe_hook_script", sys.argv[1])
+module = importlib.util.module_from_spec(spec)
+output, sys.stdout = sys.stdout, sys.stderr
+spec.loader.exec_module(module)
+hook = getattr(module, sys.argv[2])
+
+def send(message):
+    output.write(json.dumps(message) + "\n")
+    output.flush()
+
+def next(*args):
+    send({"next": list(args)})
+    return json.loads(sys.stdin.readline())["result"]
+
+for line in sys.stdin:
+    try:
+        send({"return": hook(next, *json.loads(line)["args"])})
+    except Exception as e:
+        traceback.print_exc()
+        send({"error": repr(e)})
+"#;
+
+/// Clears the owner of a [`ProcessHook`] when the call returns or unwinds.
+struct OwnerGuard<'a>(&'a Mutex<Option<ThreadId>>);
+
+impl Drop for OwnerGuard<'_> {
+    fn drop(&mut self) {
+        *lock::lock(self.0) = None;
+    }
+}
+
+impl<A, R> ProcessHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    /// Send a call to the process and answer its messages until it returns.
+    /// The result of the rest of the chain, if called, is kept in `next_result`.
+    /// Errors reported by the process are returned inside `Ok`, the process can still be used.
+    fn exchange(
+        &self,
+        process: &mut Process,
+        args: &A,
+        next: &dyn Fn(A) -> R,
+        next_result: &mut Option<R>,
+    ) -> Result<Result<R, String>, String> {
+        let hookable = call_info().map_or("", |info| info.name);
+        send(process, &json!({ "call": hookable, "args": args }))?;
+        let mut deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let line = process.lines.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => "the process timed out".to_string(),
+                RecvTimeoutError::Disconnected => "the process exited".to_string(),
+            })?;
+            let mut message = serde_json::from_str::<Value>(&line)
+                .map_err(|e| format!("invalid message {:?}: {}", line, e))?;
+            if let Some(result) = message.get_mut("return") {
+                return Ok(serde_json::from_value(result.take())
+                    .map_err(|e| format!("invalid result: {}", e)));
+            }
+            if let Some(error) = message.get("error") {
+                return Ok(Err(match error.as_str() {
+                    Some(error) => error.to_string(),
+                    None => error.to_string(),
+                }));
+            }
+            let Some(args) = message.get_mut("next") else {
+                return Err(format!("unexpected message {:?}", line));
+            };
+            let args = match serde_json::from_value(args.take()) {
+                Ok(args) => args,
+                Err(e) => return Err(format!("invalid arguments: {}", e)),
+            };
+            let start = Instant::now();
+            let result = next(args);
+            deadline += start.elapsed();
+            let answer = json!({ "result": &result });
+            *next_result = Some(result);
+            send(process, &answer)?;
+        }
+    }
+}
+
+/// Send a message to the process.
+fn send(process: &mut Process, message: &Value) -> Result<(), String> {
+    writeln!(process.stdin, "{}", message)
+        .and_then(|_| process.stdin.flush())
+        .map_err(|e| format!("cannot write to the process: {}", e))
+}
+
+impl<A, R> Hook for ProcessHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let current = std::thread::current().id();
+        if *lock::lock(&self.owner) == Some(current) {
+            return next(args);
+        }
+        let mut process = lock::lock(&self.process);
+        *lock::lock(&self.owner) = Some(current);
+        let _owner = OwnerGuard(&self.owner);
+        // Taken out while calling, so a panic in the chain drops the process in an unknown state.
+        let running = match process.take() {
+            Some(running) => Ok(running),
+            None => self.spawn(),
+        };
+        let mut next_result = None;
+        let result = running.and_then(|mut running| {
+            let result = self.exchange(&mut running, &args, next, &mut next_result)?;
+            *process = Some(running);
+            result
+        });
+        match result {
+            Ok(result) => result,
+            Err(error) => {
+                *lock::lock(&self.last_error) = Some(error);
+                match next_result {
+                    Some(result) => result,
+                    None => next(args),
+                }
+            }
+        }
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "ProcessHook({})",
+            lock::lock(&self.command).get_program().to_string_lossy()
+        )
+    }
+}
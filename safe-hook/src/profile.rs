@@ -0,0 +1,118 @@
+use crate::{AddHookError, HookSet, lock};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// The registered profiles. Their hooks are installed and uninstalled without holding the lock,
+/// as attaching and detaching hooks calls back into user code, which may use profiles too.
+static PROFILES: Mutex<BTreeMap<String, Arc<HookSet>>> = Mutex::new(BTreeMap::new());
+
+/// The error returned when enabling, disabling or replacing a profile fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProfileError {
+    /// No profile with the given name is registered.
+    NotFound(String),
+    /// The hooks of the profile cannot be added or removed.
+    AddHook(AddHookError),
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::NotFound(name) => write!(f, "Profile '{}' not found", name),
+            ProfileError::AddHook(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProfileError::NotFound(_) => None,
+            ProfileError::AddHook(err) => Some(err),
+        }
+    }
+}
+
+impl From<AddHookError> for ProfileError {
+    fn from(err: AddHookError) -> Self {
+        ProfileError::AddHook(err)
+    }
+}
+
+/// Register a set of hooks under a profile name.
+/// The profile is inactive until [`enable_profile`] is called.
+/// If a profile with the same name exists, it is disabled and replaced,
+/// unless its hooks cannot be removed, then it is kept and the error is returned.
+pub fn register_profile(name: &str, hooks: HookSet) -> Result<(), ProfileError> {
+    let hooks = Arc::new(hooks);
+    let old = lock::lock(&PROFILES).insert(name.to_string(), hooks.clone());
+    if let Some(old) = old
+        && let Err(err) = old.uninstall()
+    {
+        let mut profiles = lock::lock(&PROFILES);
+        if profiles
+            .get(name)
+            .is_some_and(|current| Arc::ptr_eq(current, &hooks))
+        {
+            profiles.insert(name.to_string(), old);
+        }
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Disable and remove a profile. Returns its hooks, or `None` if it is not registered.
+/// If its hooks cannot be removed, the profile is kept and the error is returned.
+pub fn unregister_profile(name: &str) -> Result<Option<Arc<HookSet>>, ProfileError> {
+    let Some(hooks) = lock::lock(&PROFILES).remove(name) else {
+        return Ok(None);
+    };
+    if let Err(err) = hooks.uninstall() {
+        lock::lock(&PROFILES)
+            .entry(name.to_string())
+            .or_insert(hooks);
+        return Err(err.into());
+    }
+    Ok(Some(hooks))
+}
+
+/// Get a registered profile, without keeping [`PROFILES`] locked.
+fn get(name: &str) -> Result<Arc<HookSet>, ProfileError> {
+    lock::lock(&PROFILES)
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ProfileError::NotFound(name.to_string()))
+}
+
+/// Attach all hooks of a profile. Does nothing if the profile is already active.
+pub fn enable_profile(name: &str) -> Result<(), ProfileError> {
+    get(name)?.install()?;
+    Ok(())
+}
+
+/// Detach all hooks of a profile. Does nothing if the profile is not active.
+pub fn disable_profile(name: &str) -> Result<(), ProfileError> {
+    get(name)?.uninstall()?;
+    Ok(())
+}
+
+/// Check whether a profile is registered and active.
+pub fn is_profile_active(name: &str) -> bool {
+    get(name).is_ok_and(|hooks| hooks.is_installed())
+}
+
+/// Get the names of all active profiles, in alphabetical order.
+pub fn active_profiles() -> Vec<String> {
+    let profiles = lock::lock(&PROFILES).clone();
+    profiles
+        .into_iter()
+        .filter(|(_, hooks)| hooks.is_installed())
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Get the names of all registered profiles, in alphabetical order.
+pub fn registered_profiles() -> Vec<String> {
+    lock::lock(&PROFILES).keys().cloned().collect()
+}
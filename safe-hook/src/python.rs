@@ -0,0 +1,200 @@
+//! Hooks implemented as Python callables, available with the `pyo3` feature.
+//!
+//! A [`PyHook`] calls a Python callable as `callable(next, *args)`,
+//! where `next(*args)` calls the rest of the chain, and returns its result.
+//! Arguments and results are converted with [`IntoPyObject`] and [`FromPyObject`],
+//! so they can be any type `pyo3` converts, including `#[pyclass]` types.
+//!
+//! The interpreter must be initialized before the first call, e.g. with `Python::initialize`,
+//! unless the program is itself loaded by Python, e.g. as an extension module used from a notebook.
+//! `next` can only be called on the thread of the call, and until the callable returns.
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::python::PyHook;
+//! use safe_hook::python::pyo3::prelude::*;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("python-discount")]
+//! fn discount(user: String, price: u32) -> u32 {
+//!     price
+//! }
+//!
+//! Python::initialize();
+//! let hook = Python::attach(|py| -> PyResult<_> {
+//!     let module = PyModule::from_code(
+//!         py,
+//!         cr#"
+//! def vip_discount(next, user, price):
+//!     price = next(user, price)
+//!     return price // 2 if user == "vip" else price
+//! "#,
+//!         c"discount.py",
+//!         c"discount",
+//!     )?;
+//!     Ok(PyHook::<(String, u32), u32>::new(&module.getattr("vip_discount")?))
+//! })
+//! .unwrap();
+//! lookup_hookable("python-discount").unwrap().add_hook(Arc::new(hook)).unwrap();
+//! assert_eq!(discount("vip".to_string(), 100), 50);
+//! assert_eq!(discount("guest".to_string(), 100), 100);
+//! ```
+
+use crate::{Hook, lock};
+use pyo3::BoundObject;
+use pyo3::conversion::FromPyObjectOwned;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyTuple};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+
+pub use pyo3;
+
+/// The rest of the chain of a call, with arguments and result as Python objects.
+type PyNext<'a> = &'a dyn Fn(&Bound<'_, PyTuple>) -> PyResult<Py<PyAny>>;
+
+/// The `next` given to the callable of a call.
+struct NextSlot {
+    thread: ThreadId,
+    /// Cleared when the call returns.
+    next: Mutex<Option<PyNext<'static>>>,
+}
+
+// SAFETY: `next` is only used on `thread`, see `NextSlot::call`.
+unsafe impl Send for NextSlot {}
+unsafe impl Sync for NextSlot {}
+
+impl NextSlot {
+    fn call(&self, args: &Bound<'_, PyTuple>) -> PyResult<Py<PyAny>> {
+        if std::thread::current().id() != self.thread {
+            return Err(PyRuntimeError::new_err("next called on another thread"));
+        }
+        let Some(next) = *lock::lock(&self.next) else {
+            return Err(PyRuntimeError::new_err(
+                "next called after the hook returned",
+            ));
+        };
+        next(args)
+    }
+}
+
+/// Clears a [`NextSlot`] when the call returns or unwinds.
+struct SlotGuard(Arc<NextSlot>);
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        *lock::lock(&self.0.next) = None;
+    }
+}
+
+/// A [`Hook`] calling a Python callable, see the [module docs](self).
+///
+/// If the callable raises an exception, or if the values cannot be converted,
+/// the call proceeds as if the hook was not there:
+/// the rest of the chain is called with the original arguments,
+/// unless the callable already called it, then its result is returned.
+/// The error is kept, see [`PyHook::last_error`].
+/// The arguments and the result of the rest of the chain are cloned for this.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct PyHook<A, R> {
+    callable: Py<PyAny>,
+    name: String,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> PyHook<A, R> {
+    /// Create a hook calling `callable`.
+    pub fn new(callable: &Bound<'_, PyAny>) -> Self {
+        let name = callable
+            .getattr("__qualname__")
+            .and_then(|name| name.extract())
+            .unwrap_or_else(|_| callable.to_string());
+        Self {
+            callable: callable.clone().unbind(),
+            name,
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The last error of the callable, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+}
+
+impl<A, R> PyHook<A, R>
+where
+    A: for<'py> IntoPyObject<'py> + for<'py> FromPyObjectOwned<'py> + Clone + 'static,
+    R: for<'py> IntoPyObject<'py> + for<'py> FromPyObjectOwned<'py> + Clone + 'static,
+{
+    /// Call the callable with the arguments and `next`.
+    /// The result of the rest of the chain, if called, is kept in `next_result`.
+    fn run(
+        &self,
+        py: Python,
+        args: A,
+        next: &dyn Fn(A) -> R,
+        next_result: &RefCell<Option<R>>,
+    ) -> PyResult<R> {
+        let call_next = |args: &Bound<'_, PyTuple>| -> PyResult<Py<PyAny>> {
+            let result = next(args.extract().map_err(Into::into)?);
+            *next_result.borrow_mut() = Some(result.clone());
+            Ok(result
+                .into_pyobject(py)
+                .map_err(Into::into)?
+                .into_bound()
+                .into_any()
+                .unbind())
+        };
+        // SAFETY: the guard clears the slot before returning, so `call_next` is not used after this call.
+        let erased = unsafe { std::mem::transmute::<PyNext, PyNext<'static>>(&call_next) };
+        let slot = Arc::new(NextSlot {
+            thread: std::thread::current().id(),
+            next: Mutex::new(Some(erased)),
+        });
+        let _slot = SlotGuard(slot.clone());
+        let next =
+            PyCFunction::new_closure(py, Some(c"next"), None, move |args, _| slot.call(args))?;
+        let args = args.into_pyobject(py).map_err(Into::into)?.into_bound();
+        let args = args.into_any().cast_into::<PyTuple>()?;
+        let mut items = vec![next.into_any()];
+        items.extend(args.iter());
+        let args = PyTuple::new(py, items)?;
+        let result = self.callable.call1(py, args)?;
+        result.extract(py).map_err(Into::into)
+    }
+}
+
+impl<A, R> Hook for PyHook<A, R>
+where
+    A: for<'py> IntoPyObject<'py> + for<'py> FromPyObjectOwned<'py> + Clone + 'static,
+    R: for<'py> IntoPyObject<'py> + for<'py> FromPyObjectOwned<'py> + Clone + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let next_result = RefCell::new(None);
+        let result = Python::attach(|py| self.run(py, args.clone(), next, &next_result));
+        match result {
+            Ok(result) => result,
+            Err(error) => {
+                *lock::lock(&self.last_error) = Some(error.to_string());
+                match next_result.into_inner() {
+                    Some(result) => result,
+                    None => next(args),
+                }
+            }
+        }
+    }
+
+    fn description(&self) -> String {
+        format!("PyHook({})", self.name)
+    }
+}
@@ -0,0 +1,46 @@
+//! Depth of the calls with hooks each thread is making,
+//! for [`HookableFuncMetadata::set_max_depth`].
+
+use crate::HookableFuncMetadata;
+use std::cell::RefCell;
+
+thread_local! {
+    /// The limited hookable functions this thread is calling with hooks, and the depth of the calls.
+    static ACTIVE: RefCell<Vec<(*const HookableFuncMetadata, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Decrements the depth of a hookable function when its call returns or unwinds.
+pub(crate) struct DepthGuard(*const HookableFuncMetadata);
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        let _ = ACTIVE.try_with(|active| {
+            let mut active = active.borrow_mut();
+            if let Some(idx) = active.iter().position(|(meta, _)| *meta == self.0) {
+                active[idx].1 -= 1;
+                if active[idx].1 == 0 {
+                    active.swap_remove(idx);
+                }
+            }
+        });
+    }
+}
+
+/// Increment the depth of a hookable function on this thread until the returned guard is dropped.
+/// Returns `None` if it is already `max_depth`.
+pub(crate) fn enter(meta: &HookableFuncMetadata, max_depth: usize) -> Option<DepthGuard> {
+    let meta = meta as *const HookableFuncMetadata;
+    ACTIVE
+        .try_with(|active| {
+            let mut active = active.borrow_mut();
+            match active.iter_mut().find(|(m, _)| *m == meta) {
+                Some((_, depth)) if *depth >= max_depth => return None,
+                Some((_, depth)) => *depth += 1,
+                None if max_depth == 0 => return None,
+                None => active.push((meta, 1)),
+            }
+            Some(DepthGuard(meta))
+        })
+        .ok()
+        .flatten()
+}
@@ -0,0 +1,187 @@
+//! Record and replay calls of hookable functions, available with the `serde` feature.
+//!
+//! [`RecordHook`] writes every call of a hookable function as a line of JSON,
+//! `{"args": [...], "result": ...}`, and [`ReplayHook`] serves the recorded results
+//! without calling the rest of the chain, for deterministic offline tests.
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::replay::{RecordHook, ReplayHook};
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("replay-price")]
+//! fn price(item: String) -> u32 {
+//!     item.len() as u32 * 100
+//! }
+//!
+//! let price_hookable = lookup_hookable("replay-price").unwrap();
+//! let record = Arc::new(RecordHook::<(String,), u32, Vec<u8>>::new(Vec::new()));
+//! price_hookable.add_hook(record.clone()).unwrap();
+//! assert_eq!(price("apple".to_string()), 500);
+//! price_hookable.clear_hooks().unwrap();
+//!
+//! let recording = record.take_writer();
+//! let replay = ReplayHook::<(String,), u32>::from_reader(&recording[..]).unwrap();
+//! price_hookable.add_hook(Arc::new(replay)).unwrap();
+//! assert_eq!(price("apple".to_string()), 500);
+//! ```
+
+use crate::{Hook, lock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A [`Hook`] writing every call to a writer as a line of JSON, see the [module docs](self).
+///
+/// Calls are written after they return, errors while writing are ignored.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct RecordHook<A, R, W = Box<dyn Write + Send>> {
+    writer: Mutex<W>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R, W: Write> RecordHook<A, R, W> {
+    /// Create a hook writing the calls to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Flush the writer.
+    pub fn flush(&self) -> std::io::Result<()> {
+        lock::lock(&self.writer).flush()
+    }
+}
+
+impl<A, R, W: Write + Default> RecordHook<A, R, W> {
+    /// Take the writer, replacing it with an empty one, e.g. to get the recording from a `Vec<u8>`.
+    pub fn take_writer(&self) -> W {
+        std::mem::take(&mut *lock::lock(&self.writer))
+    }
+}
+
+impl<A, R> RecordHook<A, R> {
+    /// Create a hook appending the calls to the file at `path`.
+    pub fn to_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::new(Box::new(std::io::LineWriter::new(file))))
+    }
+}
+
+impl<A, R, W> Hook for RecordHook<A, R, W>
+where
+    A: Serialize + 'static,
+    R: Serialize + 'static,
+    W: Write + Send + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let args_value = serde_json::to_value(&args);
+        let result = next(args);
+        if let (Ok(args), Ok(result)) = (args_value, serde_json::to_value(&result)) {
+            let line = serde_json::json!({ "args": args, "result": result });
+            let mut writer = lock::lock(&self.writer);
+            let _ = writeln!(writer, "{}", line);
+        }
+        result
+    }
+}
+
+/// What [`ReplayHook`] does when a call was not recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMiss {
+    /// Panic, so tests notice the unexpected call.
+    #[default]
+    Panic,
+    /// Call the rest of the chain.
+    PassThrough,
+}
+
+/// A [`Hook`] serving results recorded by [`RecordHook`] without calling `next`,
+/// see the [module docs](self).
+///
+/// Calls are matched by their arguments. If the same arguments were recorded more than once,
+/// the results are served in the recorded order, and the last one is repeated.
+pub struct ReplayHook<A, R> {
+    /// Recorded results by the JSON of the arguments, and the number of calls served.
+    responses: Mutex<HashMap<String, (Vec<Value>, usize)>>,
+    miss: ReplayMiss,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> ReplayHook<A, R> {
+    /// Load a recording from `reader`.
+    pub fn from_reader(reader: impl Read) -> std::io::Result<Self> {
+        let mut responses = HashMap::<String, (Vec<Value>, usize)>::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut call: Value = serde_json::from_str(&line)?;
+            let key = call["args"].to_string();
+            let result = call["result"].take();
+            responses.entry(key).or_default().0.push(result);
+        }
+        Ok(Self {
+            responses: Mutex::new(responses),
+            miss: ReplayMiss::default(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Load a recording from the file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    /// Set what to do when a call was not recorded.
+    pub fn on_miss(mut self, miss: ReplayMiss) -> Self {
+        self.miss = miss;
+        self
+    }
+
+    fn lookup(&self, key: &str) -> Option<Value> {
+        let mut responses = lock::lock(&self.responses);
+        let (results, served) = responses.get_mut(key)?;
+        let result = results.get(*served).or(results.last())?.clone();
+        *served += 1;
+        Some(result)
+    }
+}
+
+impl<A, R> Hook for ReplayHook<A, R>
+where
+    A: Serialize + 'static,
+    R: DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let key = serde_json::to_value(&args)
+            .map(|args| args.to_string())
+            .unwrap_or_default();
+        match self.lookup(&key) {
+            Some(result) => serde_json::from_value(result)
+                .unwrap_or_else(|err| panic!("Invalid recorded result for {}: {}", key, err)),
+            None => match self.miss {
+                ReplayMiss::Panic => panic!("No recorded result for {}", key),
+                ReplayMiss::PassThrough => next(args),
+            },
+        }
+    }
+}
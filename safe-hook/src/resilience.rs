@@ -0,0 +1,287 @@
+use crate::{Hook, lock};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type RetryPredicate<E> = Box<dyn Fn(&E) -> bool + Send + Sync>;
+
+/// A [`Hook`] for hookable functions returning [`Result`],
+/// calling `next` again when it returns `Err`, with an exponential backoff between attempts.
+///
+/// By default it makes at most 3 attempts without waiting, and retries on every error,
+/// see [`RetryHook::max_attempts`], [`RetryHook::backoff`] and [`RetryHook::retry_if`].
+/// The arguments are cloned for each attempt,
+/// and can only contain `'static` references, like [`FnHook`](crate::FnHook).
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::time::Duration;
+/// use safe_hook::{lookup_hookable, RetryHook};
+/// use safe_hook_macros::hookable;
+///
+/// static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+///
+/// #[hookable("retry-fetch")]
+/// fn fetch(key: u32) -> Result<u32, String> {
+///     if ATTEMPTS.fetch_add(1, Ordering::Relaxed) < 2 {
+///         Err("unavailable".to_string())
+///     } else {
+///         Ok(key)
+///     }
+/// }
+///
+/// let retry = RetryHook::<(u32,), u32, String>::new()
+///     .max_attempts(5)
+///     .backoff(Duration::from_millis(1))
+///     .retry_if(|err| err == "unavailable");
+/// lookup_hookable("retry-fetch").unwrap().add_hook(Arc::new(retry)).unwrap();
+/// assert_eq!(fetch(7), Ok(7));
+/// assert_eq!(ATTEMPTS.load(Ordering::Relaxed), 3);
+/// ```
+pub struct RetryHook<A, T, E> {
+    max_attempts: u32,
+    backoff: Duration,
+    retry_if: RetryPredicate<E>,
+    _marker: PhantomData<fn(A) -> T>,
+}
+
+impl<A, T, E> RetryHook<A, T, E> {
+    /// Create a hook retrying up to 3 attempts on every error, without waiting.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::ZERO,
+            retry_if: Box::new(|_| true),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The maximum number of calls to `next`, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// The time to wait before the first retry, doubled for each following retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only retry on errors for which `predicate` returns `true`.
+    pub fn retry_if(mut self, predicate: impl Fn(&E) -> bool + Send + Sync + 'static) -> Self {
+        self.retry_if = Box::new(predicate);
+        self
+    }
+}
+
+impl<A, T, E> Default for RetryHook<A, T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, T, E> Hook for RetryHook<A, T, E>
+where
+    A: Clone + 'static,
+    T: 'static,
+    E: 'static,
+{
+    type Args<'b> = A;
+    type Result = Result<T, E>;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> Result<T, E>) -> Result<T, E> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match next(args.clone()) {
+                Err(err) if attempt < self.max_attempts && (self.retry_if)(&err) => {
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through, and their outcomes are tracked.
+    Closed,
+    /// Calls fail immediately, without calling `next`.
+    Open,
+    /// A single probing call goes through to decide whether to close the circuit again,
+    /// other calls fail immediately.
+    HalfOpen,
+}
+
+struct BreakerState {
+    state: CircuitState,
+    /// When the circuit was opened.
+    opened_at: Instant,
+    /// Whether a probing call is in flight, in [`CircuitState::HalfOpen`].
+    probing: bool,
+    /// Outcomes of the latest calls, `true` for failures.
+    outcomes: VecDeque<bool>,
+    window: usize,
+    failure_rate: f64,
+    open_duration: Duration,
+}
+
+/// A [`Hook`] implementing the circuit breaker pattern for hookable functions returning [`Result`].
+///
+/// While [`CircuitState::Closed`], the outcomes of the latest `window` calls are tracked.
+/// Once the window is full and the rate of failures reaches `failure_rate`, the circuit opens:
+/// calls return the error made by `open_error` without calling `next`.
+/// After `open_duration`, a single probing call is let through ([`CircuitState::HalfOpen`]),
+/// which closes the circuit if it succeeds, or opens it again if it fails.
+///
+/// Defaults to a window of 10 calls, a failure rate of 0.5 and 30 seconds open.
+/// All settings can be changed at runtime.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{lookup_hookable, CircuitBreaker, CircuitState};
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("breaker-query")]
+/// fn query(sql: &'static str) -> Result<u32, String> {
+///     Err("timeout".to_string())
+/// }
+///
+/// let breaker = Arc::new(CircuitBreaker::<(&'static str,), u32, String>::new(|| {
+///     "circuit open".to_string()
+/// }));
+/// breaker.set_window(2);
+/// lookup_hookable("breaker-query").unwrap().add_hook(breaker.clone()).unwrap();
+/// assert_eq!(query("select 1"), Err("timeout".to_string()));
+/// assert_eq!(query("select 1"), Err("timeout".to_string()));
+/// assert_eq!(breaker.state(), CircuitState::Open);
+/// assert_eq!(query("select 1"), Err("circuit open".to_string()));
+/// ```
+pub struct CircuitBreaker<A, T, E> {
+    state: Mutex<BreakerState>,
+    open_error: Box<dyn Fn() -> E + Send + Sync>,
+    _marker: PhantomData<fn(A) -> T>,
+}
+
+impl<A, T, E> CircuitBreaker<A, T, E> {
+    /// Create a closed circuit breaker, returning the error made by `open_error` while open.
+    pub fn new(open_error: impl Fn() -> E + Send + Sync + 'static) -> Self {
+        Self {
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                opened_at: Instant::now(),
+                probing: false,
+                outcomes: VecDeque::new(),
+                window: 10,
+                failure_rate: 0.5,
+                open_duration: Duration::from_secs(30),
+            }),
+            open_error: Box::new(open_error),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        lock::lock(&self.state).state
+    }
+
+    /// Close the circuit and forget the tracked outcomes.
+    pub fn reset(&self) {
+        let mut state = lock::lock(&self.state);
+        state.state = CircuitState::Closed;
+        state.probing = false;
+        state.outcomes.clear();
+    }
+
+    /// Set the number of latest calls to compute the failure rate from.
+    pub fn set_window(&self, window: usize) {
+        let mut state = lock::lock(&self.state);
+        state.window = window.max(1);
+        while state.outcomes.len() > state.window {
+            state.outcomes.pop_front();
+        }
+    }
+
+    /// Set the rate of failures, between 0 and 1, that opens the circuit.
+    pub fn set_failure_rate(&self, failure_rate: f64) {
+        lock::lock(&self.state).failure_rate = failure_rate;
+    }
+
+    /// Set how long the circuit stays open before probing.
+    pub fn set_open_duration(&self, open_duration: Duration) {
+        lock::lock(&self.state).open_duration = open_duration;
+    }
+
+    /// Decide whether a call goes through, and whether it is a probing call.
+    fn admit(&self) -> Option<bool> {
+        let mut state = lock::lock(&self.state);
+        match state.state {
+            CircuitState::Closed => Some(false),
+            CircuitState::Open if state.opened_at.elapsed() >= state.open_duration => {
+                state.state = CircuitState::HalfOpen;
+                state.probing = true;
+                Some(true)
+            }
+            CircuitState::HalfOpen if !state.probing => {
+                state.probing = true;
+                Some(true)
+            }
+            CircuitState::Open | CircuitState::HalfOpen => None,
+        }
+    }
+
+    fn record(&self, probe: bool, failed: bool) {
+        let mut state = lock::lock(&self.state);
+        let state = &mut *state;
+        if probe {
+            state.probing = false;
+            if failed {
+                state.state = CircuitState::Open;
+                state.opened_at = Instant::now();
+            } else {
+                state.state = CircuitState::Closed;
+                state.outcomes.clear();
+            }
+        } else if state.state == CircuitState::Closed {
+            state.outcomes.push_back(failed);
+            while state.outcomes.len() > state.window {
+                state.outcomes.pop_front();
+            }
+            let failures = state.outcomes.iter().filter(|&&failed| failed).count();
+            if state.outcomes.len() == state.window
+                && failures as f64 >= state.failure_rate * state.window as f64
+            {
+                state.state = CircuitState::Open;
+                state.opened_at = Instant::now();
+                state.outcomes.clear();
+            }
+        }
+    }
+}
+
+impl<A, T, E> Hook for CircuitBreaker<A, T, E>
+where
+    A: 'static,
+    T: 'static,
+    E: 'static,
+{
+    type Args<'b> = A;
+    type Result = Result<T, E>;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> Result<T, E>) -> Result<T, E> {
+        let Some(probe) = self.admit() else {
+            return Err((self.open_error)());
+        };
+        let result = next(args);
+        self.record(probe, result.is_err());
+        result
+    }
+}
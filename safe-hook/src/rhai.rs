@@ -0,0 +1,156 @@
+//! Hooks implemented as [Rhai](https://rhai.rs) scripts, available with the `rhai` feature.
+//!
+//! A [`RhaiHook`] calls a function of a script with the arguments of the call as an array,
+//! and returns its result. The function can call `next(args)`
+//! to call the rest of the chain with an array of arguments.
+//! Values are converted to and from the dynamic values of Rhai with `serde`,
+//! like with [`JsonHook`](crate::script::JsonHook).
+//!
+//! The script is compiled once. Only its functions are used,
+//! its top-level statements are not run.
+//! Limits, e.g. on the number of operations of a call, are set on the [`Engine`]
+//! given to [`RhaiHook::with_engine`].
+//!
+//! Rhai depends on `smartstring`, which implements `Add<SmartString>` for `String`,
+//! so `string + &other` may need to be written `string + other.as_str()`.
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::rhai::RhaiHook;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("rhai-discount")]
+//! fn discount(user: String, price: u32) -> u32 {
+//!     price
+//! }
+//!
+//! let script = r#"
+//!     fn vip_discount(args) {
+//!         let price = next(args);
+//!         if args[0] == "vip" { price / 2 } else { price }
+//!     }
+//! "#;
+//! let hook = RhaiHook::<(String, u32), u32>::new(script, "vip_discount").unwrap();
+//! lookup_hookable("rhai-discount").unwrap().add_hook(Arc::new(hook)).unwrap();
+//! assert_eq!(discount("vip".to_string(), 100), 50);
+//! assert_eq!(discount("guest".to_string(), 100), 100);
+//! ```
+
+use crate::script::{JsonNext, call_json};
+use crate::{Hook, lock};
+use rhai::{AST, Array, CallFnOptions, Dynamic, EvalAltResult, Scope};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+pub use rhai::Engine;
+
+thread_local! {
+    /// The `next` of the calls of scripts running on this thread, the innermost last.
+    static NEXT: RefCell<Vec<JsonNext<'static>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes the `next` of a call from [`NEXT`] when the call returns or unwinds.
+struct NextGuard;
+
+impl Drop for NextGuard {
+    fn drop(&mut self) {
+        NEXT.with_borrow_mut(|next| next.pop());
+    }
+}
+
+/// A [`Hook`] calling a function of a Rhai script, see the [module docs](self).
+///
+/// If the script fails, or if the values cannot be converted,
+/// the call proceeds like with [`JsonHook`](crate::script::JsonHook),
+/// and the error is kept, see [`RhaiHook::last_error`].
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct RhaiHook<A, R> {
+    engine: Engine,
+    ast: AST,
+    function: String,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> RhaiHook<A, R> {
+    /// Create a hook calling `function` of `script` with a new engine.
+    pub fn new(script: &str, function: &str) -> Result<Self, Box<EvalAltResult>> {
+        Self::with_engine(Engine::new(), script, function)
+    }
+
+    /// Create a hook calling `function` of `script` with `engine`,
+    /// e.g. with limits or additional functions.
+    /// The `next` function is registered on it.
+    pub fn with_engine(
+        mut engine: Engine,
+        script: &str,
+        function: &str,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        engine.register_fn("next", call_next);
+        let ast = engine.compile(script)?;
+        if !ast.iter_functions().any(|f| f.name == function) {
+            return Err(format!("the script does not define {:?}", function).into());
+        }
+        Ok(Self {
+            engine,
+            ast,
+            function: function.to_string(),
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        })
+    }
+
+    /// The last error of the script, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+
+    /// Call the function with JSON arguments.
+    fn run(&self, args: Value, next: JsonNext) -> Result<Value, Box<EvalAltResult>> {
+        // SAFETY: the guard removes `next` before returning, so it is not used after this call.
+        let next = unsafe { std::mem::transmute::<JsonNext, JsonNext<'static>>(next) };
+        NEXT.with_borrow_mut(|stack| stack.push(next));
+        let _next = NextGuard;
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &self.ast,
+            &self.function,
+            (rhai::serde::to_dynamic(args)?,),
+        )?;
+        rhai::serde::from_dynamic(&result)
+    }
+}
+
+impl<A, R> Hook for RhaiHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        call_json(&self.last_error, args, next, |args, next| {
+            self.run(args, next).map_err(|e| e.to_string())
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("RhaiHook({})", self.function)
+    }
+}
+
+/// The `next` function of scripts, calling the rest of the chain of the innermost call.
+fn call_next(args: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+    let Some(next) = NEXT.with_borrow(|next| next.last().copied()) else {
+        return Err("next called outside of a hook".into());
+    };
+    let args = rhai::serde::from_dynamic(&Dynamic::from_array(args))?;
+    rhai::serde::to_dynamic(next(args)?)
+}
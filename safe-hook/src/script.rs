@@ -0,0 +1,132 @@
+//! Hooks implemented outside of Rust, exchanging JSON, available with the `serde` feature.
+//!
+//! [`JsonHook`] sees the arguments and the result of calls as JSON values,
+//! to bridge hookable functions to an embedded scripting engine,
+//! e.g. by converting values to and from the dynamic values of the engine.
+//! Engines supported by this crate are in the `rhai`, `lua`, `python` and `wasm` modules,
+//! and hooks can run in other processes, see the `process` module with the `process` feature.
+
+use crate::{Hook, lock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+/// The `next` of a [`JsonHook`], calling the rest of the chain with JSON arguments.
+pub type JsonNext<'a> = &'a dyn Fn(Value) -> Result<Value, String>;
+
+type JsonHookFn = Box<dyn Fn(Value, JsonNext) -> Result<Value, String> + Send + Sync>;
+
+/// A [`Hook`] seeing the arguments as a JSON array and the result as JSON,
+/// e.g. to let operators patch behavior with scripts at runtime without recompiling.
+///
+/// The function receives the arguments and a `next` to call the rest of the chain,
+/// and returns the result.
+/// If it returns an error, or if the values cannot be converted,
+/// the call proceeds as if the hook was not there:
+/// the rest of the chain is called with the original arguments,
+/// unless the function already called it, then its result is returned.
+/// The error is kept, see [`JsonHook::last_error`].
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::lookup_hookable;
+/// use safe_hook::script::JsonHook;
+/// use safe_hook_macros::hookable;
+/// use serde_json::json;
+///
+/// #[hookable("json-hook-discount")]
+/// fn discount(user: String, price: u32) -> u32 {
+///     price
+/// }
+///
+/// // With a scripting engine, this would evaluate a script with the values.
+/// let hook = JsonHook::<(String, u32), u32>::new(|args, next| {
+///     if args[0] == "vip" {
+///         return Ok(json!(args[1].as_u64().unwrap() / 2));
+///     }
+///     next(args)
+/// });
+/// lookup_hookable("json-hook-discount").unwrap().add_hook(Arc::new(hook)).unwrap();
+/// assert_eq!(discount("vip".to_string(), 100), 50);
+/// assert_eq!(discount("guest".to_string(), 100), 100);
+/// ```
+pub struct JsonHook<A, R> {
+    f: JsonHookFn,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> JsonHook<A, R> {
+    /// Create a hook calling `f` with the arguments and the rest of the chain.
+    pub fn new(
+        f: impl Fn(Value, JsonNext) -> Result<Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            f: Box::new(f),
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The last error of the function, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+}
+
+impl<A, R> Hook for JsonHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        call_json(&self.last_error, args, next, |args, next| {
+            (self.f)(args, next)
+        })
+    }
+}
+
+/// Call `f` with the arguments as JSON and a `next` calling the rest of the chain,
+/// see [`JsonHook`] for how errors are handled, they are kept in `last_error`.
+pub(crate) fn call_json<A, R>(
+    last_error: &Mutex<Option<String>>,
+    args: A,
+    next: &dyn Fn(A) -> R,
+    f: impl FnOnce(Value, JsonNext) -> Result<Value, String>,
+) -> R
+where
+    A: Serialize + DeserializeOwned,
+    R: Serialize + DeserializeOwned,
+{
+    // The result of the rest of the chain, kept in case the function fails after calling it.
+    let next_result = std::cell::RefCell::new(None);
+    let json_next = |args: Value| -> Result<Value, String> {
+        let args = serde_json::from_value(args).map_err(|e| format!("invalid arguments: {}", e))?;
+        let result = next(args);
+        let value = serde_json::to_value(&result).map_err(|e| e.to_string());
+        *next_result.borrow_mut() = Some(result);
+        value
+    };
+    let result = serde_json::to_value(&args)
+        .map_err(|e| e.to_string())
+        .and_then(|value| f(value, &json_next))
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|e| format!("invalid result: {}", e))
+        });
+    match result {
+        Ok(result) => result,
+        Err(error) => {
+            *lock::lock(last_error) = Some(error);
+            match next_result.into_inner() {
+                Some(result) => result,
+                None => next(args),
+            }
+        }
+    }
+}
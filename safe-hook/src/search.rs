@@ -0,0 +1,200 @@
+use crate::{
+    AddHookError, HookDyn, HookId, HookableFuncMetadata, attach_pending, iter_hookables,
+    names_retained,
+};
+use std::any::TypeId;
+use std::sync::Arc;
+
+/// Get the names of the registered hookable functions closest to `name`, sorted,
+/// e.g. to report a typo when [`lookup_hookable`](crate::lookup_hookable) returns `None`.
+///
+/// Only the names at the smallest edit distance are returned,
+/// if it is within a third of the length of `name` (at least 1).
+/// Nothing is returned if the names are not kept, with the `hashed-names` feature
+/// or in release builds with the `strip-names` feature.
+/// # Example
+/// ```rust
+/// use safe_hook::{hookable, lookup_hookable, similar_hookables};
+///
+/// #[hookable("similar-checkout")]
+/// fn checkout(total: u64) -> u64 {
+///     total
+/// }
+///
+/// assert!(lookup_hookable("similar-chekout").is_none());
+/// # #[cfg(not(any(feature = "hashed-names", feature = "strip-names")))]
+/// assert_eq!(similar_hookables("similar-chekout"), vec!["similar-checkout".to_string()]);
+/// ```
+pub fn similar_hookables(name: &str) -> Vec<String> {
+    if !names_retained() {
+        return Vec::new();
+    }
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut similar = iter_hookables()
+        .filter(|meta| meta.name() != name)
+        .map(|meta| (edit_distance(name, meta.name()), meta.name().to_string()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+    similar.sort();
+    similar.dedup();
+    let closest = similar.first().map(|(distance, _)| *distance);
+    similar
+        .into_iter()
+        .take_while(|(distance, _)| Some(*distance) == closest)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Get the registered hookable functions whose names match the glob `pattern`, sorted by name,
+/// e.g. to instrument every function of a module.
+/// `*` matches any sequence of characters, including `::`, and `?` matches any single character.
+/// Nothing is returned if the names are not kept, with the `hashed-names` feature
+/// or in release builds with the `strip-names` feature.
+///
+/// Like [`lookup_hookable`](crate::lookup_hookable), it also attaches pending hooks.
+/// # Example
+/// ```rust
+/// use safe_hook::{hookable, lookup_hookables_matching};
+///
+/// #[hookable("matching-db::get")]
+/// fn get(key: u64) -> u64 {
+///     key
+/// }
+///
+/// #[hookable("matching-db::put")]
+/// fn put(key: u64) -> u64 {
+///     key
+/// }
+///
+/// let names = lookup_hookables_matching("matching-db::*")
+///     .iter()
+///     .map(|meta| meta.name())
+///     .collect::<Vec<_>>();
+/// # #[cfg(not(any(feature = "hashed-names", feature = "strip-names")))]
+/// assert_eq!(names, ["matching-db::get", "matching-db::put"]);
+/// ```
+pub fn lookup_hookables_matching(pattern: &str) -> Vec<&'static HookableFuncMetadata> {
+    attach_pending();
+    if !names_retained() {
+        return Vec::new();
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let mut matching = iter_hookables()
+        .filter(|meta| glob_matches(&pattern, meta.name()))
+        .collect::<Vec<_>>();
+    matching.sort_by_key(|meta| meta.name());
+    matching
+}
+
+/// Get the registered hookable functions with the arguments `A` and the result `R`, sorted by name,
+/// e.g. to check that a hook of these types can be attached somewhere.
+/// Like for [`Hook`](crate::Hook), `A` is the arguments tuple, with `'static` references.
+///
+/// Like [`lookup_hookable`](crate::lookup_hookable), it also attaches pending hooks.
+/// # Example
+/// ```rust
+/// use safe_hook::{hookable, lookup_by_signature};
+///
+/// #[hookable("signature-trim")]
+/// fn trim(text: &str) -> String {
+///     text.trim().to_string()
+/// }
+///
+/// let found = lookup_by_signature::<(&'static str,), String>();
+/// # #[cfg(not(feature = "hashed-names"))]
+/// assert!(found.iter().any(|meta| meta.name() == "signature-trim"));
+/// assert!(lookup_by_signature::<(&'static str, u8, u8, u8), String>().is_empty());
+/// ```
+pub fn lookup_by_signature<A: 'static, R: 'static>() -> Vec<&'static HookableFuncMetadata> {
+    attach_pending();
+    let type_info = (TypeId::of::<R>(), TypeId::of::<A>());
+    let mut matching = iter_hookables()
+        .filter(|meta| meta.type_info == type_info)
+        .collect::<Vec<_>>();
+    matching.sort_by_key(|meta| meta.name());
+    matching
+}
+
+/// Attach `hook` with `priority` to every registered hookable function
+/// with the arguments `A` and the result `R`, see [`lookup_by_signature`].
+/// Returns the result of attaching to each of them, sorted by name.
+///
+/// Only the hookable functions registered now are hooked, see [`add_global_hook`](crate::add_global_hook)
+/// to also hook the ones registered later.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::{attach_to_all_matching, hookable, IntoHook};
+///
+/// #[hookable("attach-all-celsius")]
+/// fn celsius(value: f32) -> f32 {
+///     value
+/// }
+///
+/// #[hookable("attach-all-fahrenheit")]
+/// fn fahrenheit(value: f32) -> f32 {
+///     value
+/// }
+///
+/// let round = (|args: (f32,), next: &dyn Fn((f32,)) -> f32| -> f32 { next(args).round() }).into_hook();
+/// let results = attach_to_all_matching::<(f32,), f32>(Arc::new(round), 0);
+/// assert!(results.iter().all(|(_, result)| result.is_ok()));
+/// assert_eq!(celsius(21.4), 21.0);
+/// assert_eq!(fahrenheit(70.6), 71.0);
+/// ```
+#[track_caller]
+pub fn attach_to_all_matching<A: 'static, R: 'static>(
+    hook: Arc<dyn HookDyn>,
+    priority: i32,
+) -> Vec<(&'static HookableFuncMetadata, Result<HookId, AddHookError>)> {
+    lookup_by_signature::<A, R>()
+        .into_iter()
+        .map(|meta| (meta, meta.add_hook_with_priority(hook.clone(), priority)))
+        .collect()
+}
+
+/// Check whether `name` matches the glob `pattern`, with `*` and `?` wildcards.
+fn glob_matches(pattern: &[char], name: &str) -> bool {
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // The position after the last `*` and the name position it is trying to match.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character.
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
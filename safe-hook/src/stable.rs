@@ -0,0 +1,523 @@
+//! Hooks of plugins built independently of the host, available with the `abi_stable` feature.
+//!
+//! Plugins loaded with [`plugin::load`](crate::plugin::load) share Rust types, vtables and
+//! [`TypeId`]s with the host, so they must be built with the same compiler and features.
+//! Stable plugins only exchange `#[repr(C)]` types with the host, described by the [`StableAbi`]
+//! layouts of `abi_stable`: the layout of the interface is checked when loading,
+//! and the layouts of the arguments and the result of each hook when attaching it.
+//! A plugin built with another compiler or a compatible version of `safe-hook` can attach its hooks,
+//! and hooks built for other types are rejected with the differences found.
+//!
+//! The host chooses the hookable functions available to stable plugins with [`expose`].
+//! Their arguments must be tuples of up to four [`StableAbi`] types, see [`StableArgs`],
+//! and their result a [`StableAbi`] type, e.g. integers, `RString` or `RVec<T>` of `abi_stable`.
+//! A stable plugin exports its entry point with [`export_stable_plugin!`](crate::export_stable_plugin),
+//! and attaches [`StableHook`]s through the [`StableContext`].
+//! Loaded plugins are [`Plugin`]s, unloaded like other plugins.
+//!
+//! Panics cannot unwind through the interface:
+//! a panic in a hook of a stable plugin, or in the rest of the chain it calls, aborts the process.
+//! # Example
+//! ```rust
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::stable::{self, StableContext, StableHook};
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("stable-damage")]
+//! fn damage(base: u32) -> u32 {
+//!     base
+//! }
+//!
+//! // The plugin, built as a `cdylib` with `safe_hook::export_stable_plugin!(init)`.
+//! fn init(context: &mut StableContext) {
+//!     let hook = StableHook::new(|(damage,): (u32,), next: &dyn Fn((u32,)) -> u32| {
+//!         next((damage * 2,))
+//!     });
+//!     context.add_hook("stable-damage", hook, 0).unwrap();
+//! }
+//!
+//! // The host, which would load the library with `stable::load`.
+//! stable::expose::<(u32,), u32>(lookup_hookable("stable-damage").unwrap()).unwrap();
+//! let plugin = stable::from_entry("double-damage", init);
+//! assert_eq!(plugin.hooks().len(), 1);
+//! assert_eq!(damage(10), 20);
+//! ```
+
+use crate::plugin::{Plugin, PluginError, open, symbol};
+use crate::{AddHookError, Hook, HookDyn, HookId, HookableFuncMetadata, lock, lookup_hookable};
+use abi_stable::StableAbi;
+use abi_stable::abi_stability::abi_checking::check_layout_compatibility;
+use abi_stable::std_types::{RResult, RStr, RString, RVec, Tuple1, Tuple2, Tuple3, Tuple4};
+use abi_stable::type_layout::TypeLayout;
+use std::any::TypeId;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::path::Path;
+use std::sync::{Arc, Mutex, Weak};
+
+pub use abi_stable;
+
+/// The symbol of the entry point of a stable plugin, a [`RawStableEntry`],
+/// exported by [`export_stable_plugin!`](crate::export_stable_plugin).
+pub const STABLE_ENTRY_SYMBOL: &str = "safe_hook_stable_plugin_entry";
+
+/// The symbol of the layout of the interface of a stable plugin, a [`RawStableLayout`],
+/// exported by [`export_stable_plugin!`](crate::export_stable_plugin).
+pub const STABLE_LAYOUT_SYMBOL: &str = "safe_hook_stable_plugin_layout";
+
+/// The entry point of a stable plugin, called once when it is loaded.
+pub type StableEntry = fn(&mut StableContext);
+
+/// The exported entry point of a stable plugin, calling its [`StableEntry`] with the host.
+pub type RawStableEntry = unsafe extern "C" fn(&StableHost);
+
+/// The exported layout of the [`StableHost`] a stable plugin is built for.
+pub type RawStableLayout = extern "C" fn() -> &'static TypeLayout;
+
+/// Export `init`, a `fn(&mut StableContext)`, as the entry point of a stable plugin,
+/// see the [`stable`](crate::stable) module.
+#[macro_export]
+macro_rules! export_stable_plugin {
+    ($init:expr) => {
+        /// # Safety
+        /// `host` must be passed by a host with the layout of this plugin.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn safe_hook_stable_plugin_entry(host: &$crate::stable::StableHost) {
+            let init: $crate::stable::StableEntry = $init;
+            $crate::stable::__run(host, init)
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn safe_hook_stable_plugin_layout()
+        -> &'static $crate::stable::abi_stable::type_layout::TypeLayout {
+            $crate::stable::__layout()
+        }
+    };
+}
+
+/// Arguments of hookable functions that can be passed to stable hooks:
+/// tuples of up to four [`StableAbi`] types, passed as the tuples of `abi_stable`.
+pub trait StableArgs: Sized + 'static {
+    /// The `#[repr(C)]` form of the arguments.
+    type Repr: StableAbi;
+
+    /// Convert the arguments to their `#[repr(C)]` form.
+    fn into_repr(self) -> Self::Repr;
+
+    /// Convert the arguments from their `#[repr(C)]` form.
+    fn from_repr(repr: Self::Repr) -> Self;
+}
+
+impl StableArgs for () {
+    type Repr = ();
+
+    fn into_repr(self) -> Self::Repr {}
+
+    fn from_repr(_: Self::Repr) -> Self {}
+}
+
+macro_rules! impl_stable_args {
+    ($repr:ident, $($name:ident),+) => {
+        impl<$($name: StableAbi + 'static),+> StableArgs for ($($name,)+) {
+            type Repr = $repr<$($name),+>;
+
+            fn into_repr(self) -> Self::Repr {
+                $repr::from_tuple(self)
+            }
+
+            fn from_repr(repr: Self::Repr) -> Self {
+                repr.into_tuple()
+            }
+        }
+    };
+}
+
+impl_stable_args!(Tuple1, A);
+impl_stable_args!(Tuple2, A, B);
+impl_stable_args!(Tuple3, A, B, C);
+impl_stable_args!(Tuple4, A, B, C, D);
+
+/// The rest of the chain of a call, passed by the host to a hook of a plugin.
+#[repr(C)]
+#[derive(StableAbi)]
+struct RawNext {
+    data: *const c_void,
+    /// Call the rest of the chain with the arguments at `args`, moved out,
+    /// and write its result to `result`.
+    call: unsafe extern "C" fn(data: *const c_void, args: *mut c_void, result: *mut c_void),
+}
+
+impl RawNext {
+    /// Call the rest of the chain.
+    ///
+    /// # Safety
+    /// `A` and `R` must be the types of the chain.
+    unsafe fn call<A: StableArgs, R>(&self, args: A) -> R {
+        let mut args = ManuallyDrop::new(args.into_repr());
+        let mut result = MaybeUninit::<R>::uninit();
+        unsafe {
+            (self.call)(
+                self.data,
+                (&mut *args as *mut A::Repr).cast(),
+                result.as_mut_ptr().cast(),
+            );
+            result.assume_init()
+        }
+    }
+}
+
+/// A hook of a stable plugin, with its types erased, and their layouts to check them.
+#[repr(C)]
+#[derive(StableAbi)]
+struct RawStableHook {
+    data: *mut c_void,
+    /// Call the hook with the arguments at `args`, moved out, and write its result to `result`.
+    call: unsafe extern "C" fn(
+        data: *const c_void,
+        args: *mut c_void,
+        next: &RawNext,
+        result: *mut c_void,
+    ),
+    drop: unsafe extern "C" fn(data: *mut c_void),
+    args: &'static TypeLayout,
+    result: &'static TypeLayout,
+    description: RString,
+}
+
+// SAFETY: the closure behind `data` is `Send + Sync`, see `StableHook::new`.
+unsafe impl Send for RawStableHook {}
+unsafe impl Sync for RawStableHook {}
+
+impl Drop for RawStableHook {
+    fn drop(&mut self) {
+        // SAFETY: `data` is owned by the hook and dropped once.
+        unsafe { (self.drop)(self.data) }
+    }
+}
+
+/// A hook of a stable plugin calling a closure, attached with [`StableContext::add_hook`].
+///
+/// Like [`FnHook`](crate::FnHook), the closure is called with the arguments and `next`,
+/// and the arguments can only contain `'static` references.
+pub struct StableHook<A, R> {
+    raw: RawStableHook,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A: StableArgs, R: StableAbi> StableHook<A, R> {
+    /// Create a hook calling `f` with the arguments and `next`.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(A, &dyn Fn(A) -> R) -> R + Send + Sync + 'static,
+    {
+        unsafe extern "C" fn call<A: StableArgs, R, F: Fn(A, &dyn Fn(A) -> R) -> R>(
+            data: *const c_void,
+            args: *mut c_void,
+            next: &RawNext,
+            result: *mut c_void,
+        ) {
+            // SAFETY: the host checked the layouts of `A` and `R`, see `attach`.
+            unsafe {
+                let f = &*data.cast::<F>();
+                let args = A::from_repr(args.cast::<A::Repr>().read());
+                let next = |args: A| next.call::<A, R>(args);
+                result.cast::<R>().write(f(args, &next));
+            }
+        }
+
+        unsafe extern "C" fn drop<F>(data: *mut c_void) {
+            // SAFETY: `data` is the box created below.
+            std::mem::drop(unsafe { Box::from_raw(data.cast::<F>()) });
+        }
+
+        Self {
+            raw: RawStableHook {
+                data: Box::into_raw(Box::new(f)).cast(),
+                call: call::<A, R, F>,
+                drop: drop::<F>,
+                args: <A::Repr as StableAbi>::LAYOUT,
+                result: R::LAYOUT,
+                description: std::any::type_name::<F>().into(),
+            },
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The functions of the host called by stable plugins, passed to their entry point.
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct StableHost {
+    name: RString,
+    /// The hooks attached by the plugin, see `HostState`.
+    state: *mut c_void,
+    hookables: extern "C" fn() -> RVec<RString>,
+    add_hook: unsafe extern "C" fn(
+        state: *mut c_void,
+        name: RStr<'_>,
+        hook: RawStableHook,
+        priority: i32,
+    ) -> RResult<(), RString>,
+}
+
+/// The access of a stable plugin to the host, passed to its entry point.
+pub struct StableContext<'a> {
+    host: &'a StableHost,
+}
+
+impl StableContext<'_> {
+    /// The name of the plugin, the file name of the library without its extension.
+    pub fn name(&self) -> &str {
+        self.host.name.as_str()
+    }
+
+    /// Get the names of the hookable functions exposed by the host, see [`expose`].
+    pub fn hookables(&self) -> Vec<String> {
+        (self.host.hookables)()
+            .into_iter()
+            .map(RString::into_string)
+            .collect()
+    }
+
+    /// Attach a hook to the exposed hookable function `name` of the host.
+    /// Fails with a description of the error if the function is not exposed,
+    /// if the layouts of the types of the hook do not match it,
+    /// or if the host cannot attach the hook, see [`AddHookError`].
+    pub fn add_hook<A, R>(
+        &mut self,
+        name: &str,
+        hook: StableHook<A, R>,
+        priority: i32,
+    ) -> Result<(), String> {
+        // SAFETY: the host is valid for the call of the entry point.
+        unsafe { (self.host.add_hook)(self.host.state, name.into(), hook.raw, priority) }
+            .into_result()
+            .map_err(RString::into_string)
+    }
+}
+
+/// Run the entry point of a plugin with the host, used by
+/// [`export_stable_plugin!`](crate::export_stable_plugin).
+#[doc(hidden)]
+pub fn __run(host: &StableHost, init: StableEntry) {
+    init(&mut StableContext { host })
+}
+
+/// The layout of the interface, used by [`export_stable_plugin!`](crate::export_stable_plugin).
+#[doc(hidden)]
+pub fn __layout() -> &'static TypeLayout {
+    StableHost::LAYOUT
+}
+
+/// A hookable function exposed to stable plugins, see [`expose`].
+struct Exposed {
+    meta: &'static HookableFuncMetadata,
+    args: &'static TypeLayout,
+    result: &'static TypeLayout,
+    /// Wrap a hook of a plugin, whose layouts are checked, into a hook of the hookable function.
+    adapt: fn(RawStableHook) -> Arc<dyn HookDyn>,
+}
+
+static EXPOSED: Mutex<Vec<Exposed>> = Mutex::new(Vec::new());
+
+/// A hook of a stable plugin, as a hook of a hookable function with the arguments `A` and the result `R`.
+struct Adapter<A, R> {
+    raw: RawStableHook,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A: StableArgs, R: StableAbi + 'static> Hook for Adapter<A, R> {
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        unsafe extern "C" fn call_next<A: StableArgs, R>(
+            data: *const c_void,
+            args: *mut c_void,
+            result: *mut c_void,
+        ) {
+            // SAFETY: `data` is the `next` of the call below,
+            // and the plugin passes the types checked in `attach`.
+            unsafe {
+                let next = &*data.cast::<&dyn Fn(A) -> R>();
+                let args = A::from_repr(args.cast::<A::Repr>().read());
+                result.cast::<R>().write(next(args));
+            }
+        }
+
+        let next: &dyn Fn(A) -> R = next;
+        let next = RawNext {
+            data: (&next as *const &dyn Fn(A) -> R).cast(),
+            call: call_next::<A, R>,
+        };
+        let mut args = ManuallyDrop::new(args.into_repr());
+        let mut result = MaybeUninit::<R>::uninit();
+        // SAFETY: the layouts of the types of the hook are checked in `attach`.
+        unsafe {
+            (self.raw.call)(
+                self.raw.data,
+                (&mut *args as *mut A::Repr).cast(),
+                &next,
+                result.as_mut_ptr().cast(),
+            );
+            result.assume_init()
+        }
+    }
+
+    fn description(&self) -> String {
+        self.raw.description.to_string()
+    }
+}
+
+/// Make `meta` available to stable plugins, with its arguments `A` and result `R`.
+/// Exposing a hookable function again replaces its types.
+///
+/// Returns [`AddHookError::TypeMismatch`] if `A` and `R` are not the types of `meta`.
+pub fn expose<A, R>(meta: &'static HookableFuncMetadata) -> Result<(), AddHookError>
+where
+    A: StableArgs,
+    R: StableAbi + 'static,
+{
+    let found = (TypeId::of::<R>(), TypeId::of::<A>());
+    if found != meta.type_info {
+        return Err(meta.type_mismatch(found, crate::stored_type_names::<R, A>()));
+    }
+    let mut exposed = lock::lock(&EXPOSED);
+    exposed.retain(|exposed| !std::ptr::eq(exposed.meta, meta));
+    exposed.push(Exposed {
+        meta,
+        args: <A::Repr as StableAbi>::LAYOUT,
+        result: R::LAYOUT,
+        adapt: |raw| {
+            Arc::new(Adapter::<A, R> {
+                raw,
+                _marker: PhantomData,
+            })
+        },
+    });
+    Ok(())
+}
+
+/// The hooks attached by a stable plugin while loading, see [`Plugin::hooks`].
+#[derive(Default)]
+struct HostState {
+    hooks: Vec<(&'static HookableFuncMetadata, HookId)>,
+    attached: Vec<Weak<dyn HookDyn>>,
+}
+
+extern "C" fn exposed_names() -> RVec<RString> {
+    lock::lock(&EXPOSED)
+        .iter()
+        .map(|exposed| exposed.meta.name().into())
+        .collect()
+}
+
+unsafe extern "C" fn add_hook(
+    state: *mut c_void,
+    name: RStr<'_>,
+    hook: RawStableHook,
+    priority: i32,
+) -> RResult<(), RString> {
+    // SAFETY: `state` is the state of the plugin being loaded, see `run`.
+    let state = unsafe { &mut *state.cast::<HostState>() };
+    match attach(name.as_str(), hook, priority) {
+        Ok((meta, id, hook)) => {
+            state.hooks.push((meta, id));
+            state.attached.push(hook);
+            RResult::ROk(())
+        }
+        Err(message) => RResult::RErr(message.into()),
+    }
+}
+
+/// Check the layouts of the types of `hook` and attach it to the exposed hookable function `name`.
+fn attach(
+    name: &str,
+    hook: RawStableHook,
+    priority: i32,
+) -> Result<(&'static HookableFuncMetadata, HookId, Weak<dyn HookDyn>), String> {
+    let not_exposed = || format!("'{}' is not exposed to stable plugins", name);
+    let meta = lookup_hookable(name).ok_or_else(not_exposed)?;
+    let exposed = lock::lock(&EXPOSED);
+    let exposed = exposed
+        .iter()
+        .find(|exposed| std::ptr::eq(exposed.meta, meta))
+        .ok_or_else(not_exposed)?;
+    check_layout_compatibility(exposed.args, hook.args)
+        .map_err(|error| format!("The arguments of the hook do not match: {}", error))?;
+    check_layout_compatibility(exposed.result, hook.result)
+        .map_err(|error| format!("The result of the hook does not match: {}", error))?;
+    let hook = (exposed.adapt)(hook);
+    let weak = Arc::downgrade(&hook);
+    let id = meta
+        .add_hook_with_priority(hook, priority)
+        .map_err(|error| error.to_string())?;
+    Ok((meta, id, weak))
+}
+
+/// Run `entry` with a new host for the plugin `name`.
+fn run(name: &str, entry: impl FnOnce(&StableHost)) -> Plugin {
+    let mut state = HostState::default();
+    let host = StableHost {
+        name: name.into(),
+        state: (&mut state as *mut HostState).cast(),
+        hookables: exposed_names,
+        add_hook,
+    };
+    entry(&host);
+    Plugin::with_hooks(name, state.hooks, state.attached)
+}
+
+/// Run the entry point of a stable plugin linked into the host, e.g. for built-in extensions or tests.
+pub fn from_entry(name: &str, entry: StableEntry) -> Plugin {
+    run(name, |host| __run(host, entry))
+}
+
+/// Check the layout of the interface of a stable plugin and run its entry point,
+/// as exported by [`export_stable_plugin!`](crate::export_stable_plugin).
+///
+/// Returns [`PluginError::LayoutMismatch`] if the plugin is built for another interface.
+///
+/// # Safety
+/// `layout` and `entry` must be exported by the same plugin.
+pub unsafe fn from_raw_entry(
+    name: &str,
+    layout: RawStableLayout,
+    entry: RawStableEntry,
+) -> Result<Plugin, PluginError> {
+    check_layout_compatibility(StableHost::LAYOUT, layout())
+        .map_err(|error| PluginError::LayoutMismatch(error.to_string()))?;
+    // SAFETY: the plugin is built for the layout of the host.
+    Ok(run(name, |host| unsafe { entry(host) }))
+}
+
+/// Load the stable plugin at `path` and run its entry point, which attaches its hooks.
+///
+/// # Safety
+/// Loading a library runs its initializers and its entry point:
+/// the library must come from a trusted source.
+/// Only the layouts of the interface and of the types of the hooks are checked,
+/// see the [module docs](self).
+pub unsafe fn load(path: impl AsRef<Path>) -> Result<Plugin, PluginError> {
+    let path = path.as_ref();
+    // SAFETY: the caller trusts the library.
+    let library = unsafe { open(path) }.map_err(|error| PluginError::Open(error.to_string()))?;
+    // SAFETY: the symbols are exported by `export_stable_plugin!` with these types,
+    // the layout function only uses `abi_stable` types, stable across versions of the compiler.
+    let (layout, entry) = unsafe {
+        (
+            symbol::<RawStableLayout>(&library, STABLE_LAYOUT_SYMBOL)?,
+            symbol::<RawStableEntry>(&library, STABLE_ENTRY_SYMBOL)?,
+        )
+    };
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = name.strip_prefix("lib").unwrap_or(&name);
+    // SAFETY: the symbols are exported by the library.
+    let mut plugin = unsafe { from_raw_entry(name, layout, entry) }?;
+    plugin.set_library(path, library);
+    Ok(plugin)
+}
@@ -0,0 +1,63 @@
+use crate::HookableFuncMetadata;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Call statistics of a hookable function, see [`HookableFuncMetadata::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CallStats {
+    /// Calls taking the fast path, without hooks.
+    pub fast_path_calls: u64,
+    /// Calls taking the hooked path.
+    pub hooked_calls: u64,
+    /// The cumulative time of all calls, including hooks.
+    pub total_time: Duration,
+}
+
+impl CallStats {
+    /// The number of all calls.
+    pub fn calls(&self) -> u64 {
+        self.fast_path_calls + self.hooked_calls
+    }
+}
+
+/// Counters of a hookable function. Updated with relaxed atomics,
+/// so the counters read together may be slightly out of sync.
+#[derive(Default)]
+pub(crate) struct Stats {
+    fast_path_calls: AtomicU64,
+    hooked_calls: AtomicU64,
+    total_nanos: AtomicU64,
+}
+
+impl Stats {
+    pub(crate) fn record_fast(&self, elapsed: Duration) {
+        self.fast_path_calls.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_hooked(&self, elapsed: Duration) {
+        self.hooked_calls.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl HookableFuncMetadata {
+    /// Get the call statistics of the hookable function, available with the `stats` feature.
+    pub fn stats(&self) -> CallStats {
+        CallStats {
+            fast_path_calls: self.stats.fast_path_calls.load(Ordering::Relaxed),
+            hooked_calls: self.stats.hooked_calls.load(Ordering::Relaxed),
+            total_time: Duration::from_nanos(self.stats.total_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Reset the call statistics of the hookable function.
+    pub fn reset_stats(&self) {
+        self.stats.fast_path_calls.store(0, Ordering::Relaxed);
+        self.stats.hooked_calls.store(0, Ordering::Relaxed);
+        self.stats.total_nanos.store(0, Ordering::Relaxed);
+    }
+}
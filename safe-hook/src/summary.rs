@@ -0,0 +1,57 @@
+use crate::{HookInfo, iter_hookables};
+
+/// The state of a hookable function, see [`registry_summary`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HookableSummary {
+    /// The name of the hookable function.
+    pub name: String,
+    /// The signature of the hookable function, see [`HookableFuncMetadata::signature`](crate::HookableFuncMetadata::signature).
+    pub signature: String,
+    /// Whether the hookable function is disabled by the `SAFE_HOOK_DISABLE` environment variable.
+    pub disabled_by_env: bool,
+    /// The attached hooks, in calling order.
+    pub hooks: Vec<HookInfo>,
+    /// The call statistics, with the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub stats: crate::CallStats,
+}
+
+/// The state of all hookable functions and their hooks, see [`registry_summary`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegistrySummary {
+    /// Whether all hooks are disabled by [`disable_all`](crate::disable_all).
+    pub all_disabled: bool,
+    /// The hookable functions, sorted by name.
+    pub hookables: Vec<HookableSummary>,
+}
+
+/// Summarize the hookable functions and their hooks,
+/// e.g. to include the instrumentation state in diagnostics.
+/// Serializable with the `serde` feature.
+/// # Example
+/// ```rust
+/// use safe_hook::registry_summary;
+///
+/// for hookable in registry_summary().hookables {
+///     println!("{}: {} hooks", hookable.signature, hookable.hooks.len());
+/// }
+/// ```
+pub fn registry_summary() -> RegistrySummary {
+    let mut hookables = iter_hookables()
+        .map(|meta| HookableSummary {
+            name: meta.name().to_string(),
+            signature: meta.signature(),
+            disabled_by_env: meta.is_disabled_by_env(),
+            hooks: meta.list_hooks(),
+            #[cfg(feature = "stats")]
+            stats: meta.stats(),
+        })
+        .collect::<Vec<_>>();
+    hookables.sort_by(|a, b| a.name.cmp(&b.name));
+    RegistrySummary {
+        all_disabled: crate::is_all_disabled(),
+        hookables,
+    }
+}
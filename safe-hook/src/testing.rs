@@ -0,0 +1,538 @@
+//! Hooks for tests of code calling hookable functions.
+//!
+//! [`Spy`] records the calls of a hookable function, to check how it was called,
+//! and [`Stub`] replaces the function with canned results.
+//! [`Expectation`] combines both in a mock-style API, verified when dropped.
+//! [`HookGuard`] (or the [`hook_test`](crate::hook_test) attribute) restores the hooks after a test.
+
+use crate::{AddHookError, Hook, HookId, HookSnapshot, HookableFuncMetadata, iter_hookables, lock};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The sequence number of the next call recorded by any [`Spy`],
+/// so calls of different functions can be ordered.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// A call recorded by a [`Spy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpyCall<A, R> {
+    /// The arguments of the call.
+    pub args: A,
+    /// The result of the call.
+    pub result: R,
+    /// The order of the call among the calls recorded by all spies.
+    pub sequence: u64,
+}
+
+/// A [`Hook`] recording the arguments and results of every call,
+/// with accessors and assertions for tests.
+///
+/// The arguments are cloned before the call, and the result after it.
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::lookup_hookable;
+/// use safe_hook::testing::Spy;
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("spy-send")]
+/// fn send(to: &'static str, amount: u32) -> bool {
+///     true
+/// }
+///
+/// let spy = Arc::new(Spy::<(&'static str, u32), bool>::new());
+/// lookup_hookable("spy-send").unwrap().add_hook(spy.clone()).unwrap();
+/// send("amy", 10);
+/// spy.assert_called_times(1);
+/// spy.assert_called_with(&("amy", 10));
+/// ```
+pub struct Spy<A, R> {
+    calls: Mutex<Vec<SpyCall<A, R>>>,
+}
+
+impl<A: Clone, R: Clone> Spy<A, R> {
+    /// Create a spy without calls.
+    pub fn new() -> Self {
+        Self {
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get the recorded calls, in calling order.
+    pub fn calls(&self) -> Vec<SpyCall<A, R>> {
+        lock::lock(&self.calls).clone()
+    }
+
+    /// Get the arguments of the recorded calls, in calling order.
+    pub fn args(&self) -> Vec<A> {
+        lock::lock(&self.calls)
+            .iter()
+            .map(|call| call.args.clone())
+            .collect()
+    }
+
+    /// Get the results of the recorded calls, in calling order.
+    pub fn results(&self) -> Vec<R> {
+        lock::lock(&self.calls)
+            .iter()
+            .map(|call| call.result.clone())
+            .collect()
+    }
+
+    /// Get the last recorded call.
+    pub fn last_call(&self) -> Option<SpyCall<A, R>> {
+        lock::lock(&self.calls).last().cloned()
+    }
+
+    /// The number of recorded calls.
+    pub fn call_count(&self) -> usize {
+        lock::lock(&self.calls).len()
+    }
+
+    /// Check whether any call was recorded.
+    pub fn was_called(&self) -> bool {
+        self.call_count() > 0
+    }
+
+    /// Forget the recorded calls.
+    pub fn clear(&self) {
+        lock::lock(&self.calls).clear();
+    }
+}
+
+impl<A: Clone + PartialEq, R: Clone> Spy<A, R> {
+    /// Check whether a call with `args` was recorded.
+    pub fn was_called_with(&self, args: &A) -> bool {
+        lock::lock(&self.calls)
+            .iter()
+            .any(|call| call.args == *args)
+    }
+}
+
+impl<A: Clone + Debug, R: Clone> Spy<A, R> {
+    /// Assert that exactly `times` calls were recorded.
+    /// # Panics
+    /// Panics with the recorded arguments otherwise.
+    #[track_caller]
+    pub fn assert_called_times(&self, times: usize) {
+        let args = self.args();
+        assert!(
+            args.len() == times,
+            "Expected {} calls, got {}: {:?}",
+            times,
+            args.len(),
+            args
+        );
+    }
+
+    /// Assert that the first call of this spy was recorded before the first call of `other`.
+    /// # Panics
+    /// Panics if either spy has no call, or the calls are in the other order.
+    #[track_caller]
+    pub fn assert_called_before<B: Clone, S: Clone>(&self, other: &Spy<B, S>) {
+        let first = lock::lock(&self.calls).first().map(|call| call.sequence);
+        let other_first = lock::lock(&other.calls).first().map(|call| call.sequence);
+        match (first, other_first) {
+            (Some(first), Some(other_first)) => assert!(
+                first < other_first,
+                "Expected to be called before the other spy"
+            ),
+            (None, _) => panic!("Expected to be called, but was not"),
+            (_, None) => panic!("Expected the other spy to be called, but it was not"),
+        }
+    }
+}
+
+impl<A: Clone + PartialEq + Debug, R: Clone> Spy<A, R> {
+    /// Assert that a call with `args` was recorded.
+    /// # Panics
+    /// Panics with the recorded arguments otherwise.
+    #[track_caller]
+    pub fn assert_called_with(&self, args: &A) {
+        assert!(
+            self.was_called_with(args),
+            "Expected a call with {:?}, got {:?}",
+            args,
+            self.args()
+        );
+    }
+}
+
+impl<A: Clone, R: Clone> Default for Spy<A, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, R> Hook for Spy<A, R>
+where
+    A: Clone + Send + 'static,
+    R: Clone + Send + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let result = next(args.clone());
+        lock::lock(&self.calls).push(SpyCall {
+            args,
+            result: result.clone(),
+            sequence,
+        });
+        result
+    }
+}
+
+type StubFn<A, R> = Box<dyn Fn(A) -> R + Send + Sync>;
+
+/// A [`Hook`] returning pre-programmed results without calling `next`,
+/// to replace a hookable function in tests.
+///
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use safe_hook::lookup_hookable;
+/// use safe_hook::testing::Stub;
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("stub-now")]
+/// fn now() -> u64 {
+///     std::time::UNIX_EPOCH.elapsed().unwrap().as_secs()
+/// }
+///
+/// let stub = Stub::<(), u64>::sequence([100, 200]);
+/// lookup_hookable("stub-now").unwrap().add_hook(Arc::new(stub)).unwrap();
+/// assert_eq!(now(), 100);
+/// assert_eq!(now(), 200);
+/// assert_eq!(now(), 200);
+/// ```
+pub struct Stub<A, R> {
+    respond: StubFn<A, R>,
+}
+
+impl<A, R> Stub<A, R> {
+    /// Return `f(args)` for every call.
+    pub fn with(f: impl Fn(A) -> R + Send + Sync + 'static) -> Self {
+        Self {
+            respond: Box::new(f),
+        }
+    }
+}
+
+impl<A, R: Clone + Send + Sync + 'static> Stub<A, R> {
+    /// Return `value` for every call.
+    pub fn returning(value: R) -> Self {
+        Self::with(move |_| value.clone())
+    }
+
+    /// Return the values in order, one per call, then repeat the last one.
+    /// # Panics
+    /// Panics if `values` is empty.
+    pub fn sequence(values: impl IntoIterator<Item = R>) -> Self {
+        let values = values.into_iter().collect::<Vec<_>>();
+        assert!(!values.is_empty(), "Stub sequence must not be empty");
+        let next = AtomicUsize::new(0);
+        Self::with(move |_| {
+            let index = next.fetch_add(1, Ordering::Relaxed).min(values.len() - 1);
+            values[index].clone()
+        })
+    }
+}
+
+impl<A: 'static, R: 'static> Hook for Stub<A, R> {
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, _next: &dyn for<'c> Fn(A) -> R) -> R {
+        (self.respond)(args)
+    }
+}
+
+type Matcher<A> = Box<dyn Fn(&A) -> bool + Send + Sync>;
+
+/// An expectation on the calls of a hookable function, for mock-style tests.
+///
+/// Calls with arguments matching [`Expectation::with`] are counted,
+/// the count must be in the range set by [`Expectation::times`] and friends,
+/// and calls with other arguments are reported as unexpected.
+/// By default any arguments match, at least one call is expected,
+/// and the calls go on to the original function unless [`Expectation::returning`] is set.
+///
+/// Attach it with [`Expectation::attach`], the returned guard verifies the expectation
+/// when dropped, see [`ExpectationGuard`].
+/// # Example
+/// ```rust
+/// use safe_hook::lookup_hookable;
+/// use safe_hook::testing::Expectation;
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("expect-charge")]
+/// fn charge(user: u64, amount: u64) -> bool {
+///     unimplemented!("talks to the payment provider")
+/// }
+///
+/// let charge_hookable = lookup_hookable("expect-charge").unwrap();
+/// let expectation = Expectation::<(u64, u64), bool>::new()
+///     .with(|&(user, amount)| user == 7 && amount > 0)
+///     .times(2)
+///     .returning(|_| true)
+///     .attach(charge_hookable)
+///     .unwrap();
+/// assert!(charge(7, 10));
+/// assert!(charge(7, 20));
+/// expectation.verify();
+/// ```
+pub struct Expectation<A, R> {
+    matcher: Matcher<A>,
+    min: usize,
+    max: usize,
+    respond: Option<StubFn<A, R>>,
+    matched: AtomicUsize,
+    unexpected: Mutex<Vec<String>>,
+}
+
+impl<A: Debug, R> Expectation<A, R> {
+    /// Create an expectation of at least one call with any arguments.
+    pub fn new() -> Self {
+        Self {
+            matcher: Box::new(|_| true),
+            min: 1,
+            max: usize::MAX,
+            respond: None,
+            matched: AtomicUsize::new(0),
+            unexpected: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Only count calls for which `matcher` returns `true`, other calls are unexpected.
+    pub fn with(mut self, matcher: impl Fn(&A) -> bool + Send + Sync + 'static) -> Self {
+        self.matcher = Box::new(matcher);
+        self
+    }
+
+    /// Expect exactly `times` matching calls.
+    pub fn times(mut self, times: usize) -> Self {
+        self.min = times;
+        self.max = times;
+        self
+    }
+
+    /// Expect at least `times` matching calls.
+    pub fn at_least(mut self, times: usize) -> Self {
+        self.min = times;
+        self.max = usize::MAX;
+        self
+    }
+
+    /// Expect at most `times` matching calls.
+    pub fn at_most(mut self, times: usize) -> Self {
+        self.min = 0;
+        self.max = times;
+        self
+    }
+
+    /// Expect no call at all.
+    pub fn never(self) -> Self {
+        self.times(0)
+    }
+
+    /// Return `f(args)` for matching calls instead of calling `next`.
+    pub fn returning(mut self, f: impl Fn(A) -> R + Send + Sync + 'static) -> Self {
+        self.respond = Some(Box::new(f));
+        self
+    }
+
+    /// The number of matching calls so far.
+    pub fn call_count(&self) -> usize {
+        self.matched.load(Ordering::Acquire)
+    }
+
+    /// Check whether the expectation is met, returning a description of the failures otherwise.
+    pub fn check(&self) -> Result<(), String> {
+        let mut failures = Vec::new();
+        let matched = self.call_count();
+        if matched < self.min || matched > self.max {
+            let expected = match (self.min, self.max) {
+                (min, max) if min == max => format!("{}", min),
+                (min, usize::MAX) => format!("at least {}", min),
+                (_, max) => format!("at most {}", max),
+            };
+            failures.push(format!(
+                "expected {} matching calls, got {}",
+                expected, matched
+            ));
+        }
+        for args in lock::lock(&self.unexpected).iter() {
+            failures.push(format!("unexpected call with {}", args));
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+
+    /// Verify the expectation.
+    /// # Panics
+    /// Panics with a description of the failures if the expectation is not met.
+    #[track_caller]
+    pub fn verify(&self) {
+        if let Err(failures) = self.check() {
+            panic!("Expectation not met: {}", failures);
+        }
+    }
+}
+
+impl<A, R> Expectation<A, R>
+where
+    A: Debug + 'static,
+    R: 'static,
+{
+    /// Attach the expectation to `target`, returning a guard that detaches and verifies it when dropped.
+    pub fn attach(
+        self,
+        target: &'static HookableFuncMetadata,
+    ) -> Result<ExpectationGuard<A, R>, AddHookError> {
+        let expectation = Arc::new(self);
+        let id = target.add_hook(expectation.clone())?;
+        Ok(ExpectationGuard {
+            expectation,
+            target,
+            id,
+        })
+    }
+}
+
+impl<A: Debug, R> Default for Expectation<A, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, R> Hook for Expectation<A, R>
+where
+    A: Debug + 'static,
+    R: 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        if !(self.matcher)(&args) {
+            lock::lock(&self.unexpected).push(format!("{:?}", args));
+            return next(args);
+        }
+        self.matched.fetch_add(1, Ordering::AcqRel);
+        match &self.respond {
+            Some(respond) => respond(args),
+            None => next(args),
+        }
+    }
+}
+
+/// An [`Expectation`] attached to a hookable function, created by [`Expectation::attach`].
+///
+/// When dropped, the expectation is detached and verified,
+/// failing the test if it is not met (unless the thread is already panicking).
+pub struct ExpectationGuard<A: Debug, R> {
+    expectation: Arc<Expectation<A, R>>,
+    target: &'static HookableFuncMetadata,
+    id: HookId,
+}
+
+impl<A: Debug, R> ExpectationGuard<A, R> {
+    /// The number of matching calls so far.
+    pub fn call_count(&self) -> usize {
+        self.expectation.call_count()
+    }
+
+    /// Verify the expectation now, see [`Expectation::verify`].
+    #[track_caller]
+    pub fn verify(&self) {
+        self.expectation.verify();
+    }
+}
+
+impl<A: Debug, R> Drop for ExpectationGuard<A, R> {
+    fn drop(&mut self) {
+        let _ = self.target.remove_hook_by_id(self.id);
+        if !std::thread::panicking() {
+            self.expectation.verify();
+        }
+    }
+}
+
+/// Held by [`HookGuard`]s, so tests changing hooks do not run at the same time.
+static GUARD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Restores the hooks of every hookable function when dropped, even if the test panics.
+///
+/// Creating the guard snapshots the hooks of all hookable functions and whether
+/// [`disable_all`](crate::disable_all) is in effect, dropping it restores them.
+/// It also holds a global lock, so tests using guards run one at a time,
+/// tests not using guards can still change hooks concurrently.
+/// Guards cannot be nested on the same thread, a second guard would wait forever.
+///
+/// The [`hook_test`](crate::hook_test) attribute creates a guard around a test body.
+/// # Example
+/// ```rust
+/// use safe_hook::lookup_hookable;
+/// use safe_hook::testing::{HookGuard, Stub};
+/// use std::sync::Arc;
+/// use safe_hook_macros::hookable;
+///
+/// #[hookable("guard-now")]
+/// fn now() -> u64 {
+///     1_700_000_000
+/// }
+///
+/// {
+///     let _guard = HookGuard::new();
+///     let stub = Stub::<(), u64>::returning(0);
+///     lookup_hookable("guard-now").unwrap().add_hook(Arc::new(stub)).unwrap();
+///     assert_eq!(now(), 0);
+/// }
+/// assert_eq!(now(), 1_700_000_000);
+/// ```
+pub struct HookGuard {
+    snapshots: Vec<(&'static HookableFuncMetadata, HookSnapshot)>,
+    all_disabled: bool,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+impl HookGuard {
+    /// Snapshot the hooks of all hookable functions, waiting for other guards to be dropped.
+    pub fn new() -> Self {
+        let lock = lock::lock(&GUARD_LOCK);
+        Self {
+            snapshots: iter_hookables()
+                .map(|meta| (meta, meta.snapshot()))
+                .collect(),
+            all_disabled: crate::is_all_disabled(),
+            _lock: lock,
+        }
+    }
+}
+
+impl Default for HookGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        for (meta, snapshot) in &self.snapshots {
+            let _ = meta.restore(snapshot);
+        }
+        let _ = if self.all_disabled {
+            crate::disable_all()
+        } else {
+            crate::enable_all()
+        };
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{AddHookError, HookDyn, HookId, HookableFuncMetadata, access, lock};
+use std::sync::Arc;
+
+enum Operation {
+    Add(Arc<dyn HookDyn>, i32),
+    Remove(Arc<dyn HookDyn>),
+    RemoveById(HookId),
+}
+
+/// A batch of hook mutations across one or more hookable functions,
+/// applied all or nothing by [`HookTransaction::commit`].
+///
+/// Either all operations are applied, or none of them is.
+/// While committing, all involved hookable functions are locked,
+/// so no other change of their hooks can interleave with the transaction.
+///
+/// Calls do not take these locks, and the new hooks are published one hookable function at a time,
+/// so a concurrent call of one function may already run the new hooks
+/// while a call of another function still runs the old ones.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use safe_hook::{hookable, lookup_hookable, Hook, HookTransaction};
+///
+/// #[hookable("transaction-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// #[hookable("transaction-sub")]
+/// fn sub(left: i64, right: i64) -> i64 {
+///     left - right
+/// }
+///
+/// struct PlusOne;
+///
+/// impl Hook for PlusOne {
+///     type Args<'a> = (i64, i64);
+///     type Result = i64;
+///     fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+///         next(args) + 1
+///     }
+/// }
+///
+/// let hook = Arc::new(PlusOne);
+/// let mut transaction = HookTransaction::new();
+/// transaction.add_hook(lookup_hookable("transaction-add").unwrap(), hook.clone(), 0);
+/// transaction.add_hook(lookup_hookable("transaction-sub").unwrap(), hook.clone(), 0);
+/// transaction.commit().unwrap();
+/// assert_eq!(add(1, 2), 4);
+/// assert_eq!(sub(1, 2), 0);
+/// ```
+#[derive(Default)]
+pub struct HookTransaction<'a> {
+    operations: Vec<(&'a HookableFuncMetadata, Operation)>,
+}
+
+impl<'a> HookTransaction<'a> {
+    /// Create an empty transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hook to `target` with the given priority when the transaction is committed.
+    pub fn add_hook(
+        &mut self,
+        target: &'a HookableFuncMetadata,
+        hook: Arc<dyn HookDyn>,
+        priority: i32,
+    ) -> &mut Self {
+        self.operations
+            .push((target, Operation::Add(hook, priority)));
+        self
+    }
+
+    /// Remove a hook from `target` when the transaction is committed.
+    /// Removing a hook that is not attached does nothing.
+    pub fn remove_hook(
+        &mut self,
+        target: &'a HookableFuncMetadata,
+        hook: Arc<dyn HookDyn>,
+    ) -> &mut Self {
+        self.operations.push((target, Operation::Remove(hook)));
+        self
+    }
+
+    /// Remove a hook from `target` by its id when the transaction is committed.
+    /// Removing a hook that is not attached does nothing.
+    pub fn remove_hook_by_id(&mut self, target: &'a HookableFuncMetadata, id: HookId) -> &mut Self {
+        self.operations.push((target, Operation::RemoveById(id)));
+        self
+    }
+
+    /// Apply all operations, or none of them, see [`HookTransaction`].
+    /// Returns the ids of the added hooks, in the order they were added to the transaction.
+    /// If any operation fails, no hookable function is modified and the error is returned.
+    #[track_caller]
+    pub fn commit(self) -> Result<Vec<HookId>, AddHookError> {
+        access::check()?;
+        // Lock in address order, so concurrent transactions cannot deadlock.
+        let mut targets = self
+            .operations
+            .iter()
+            .map(|(target, _)| *target)
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|target| *target as *const HookableFuncMetadata);
+        targets.dedup_by_key(|target| *target as *const HookableFuncMetadata);
+        let mut guards = targets
+            .iter()
+            .map(|target| lock::write(&target.hooks))
+            .collect::<Vec<_>>();
+
+        // Apply to copies first, so nothing is modified if an operation fails.
+        let mut chains = guards
+            .iter()
+            .map(|guard| (**guard).clone())
+            .collect::<Vec<_>>();
+        let mut ids = Vec::new();
+        for (target, operation) in self.operations {
+            let idx = targets
+                .iter()
+                .position(|t| std::ptr::eq(*t, target))
+                .unwrap();
+            match operation {
+                Operation::Add(hook, priority) => {
+                    target.check_hook(hook.as_ref())?;
+                    ids.push(chains[idx].add(target, hook, priority)?);
+                }
+                Operation::Remove(hook) => {
+                    chains[idx].remove(hook.as_ref());
+                }
+                Operation::RemoveById(id) => {
+                    chains[idx].remove_by_id(id);
+                }
+            }
+        }
+
+        let location = std::panic::Location::caller();
+        let mut published = Vec::new();
+        for ((target, guard), chain) in targets.iter().zip(guards.iter_mut()).zip(chains) {
+            **guard = chain;
+            published.push(target.publish_locked(guard, location));
+        }
+        drop(guards);
+        for (target, published) in targets.iter().zip(published) {
+            published.finish(target);
+        }
+        Ok(ids)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{AddHookError, ChainFn, HookDyn, HookId, HookableFuncMetadata};
+use std::any::{Any, TypeId, type_name};
+use std::sync::Arc;
+
+/// A hook that can be attached to hookable functions of any signature,
+/// seeing the arguments and the result as [`Any`], see [`HookableFuncMetadata::add_untyped_hook`].
+///
+/// Useful for logging or metrics hooks that only need the name and timing of calls,
+/// or that downcast the arguments of a few known signatures.
+/// # Example
+/// ```rust
+/// use safe_hook::{iter_hookables, lookup_hookable, UntypedCall, UntypedHook};
+/// use safe_hook_macros::hookable;
+/// use std::sync::Arc;
+/// use std::time::Instant;
+///
+/// #[hookable("untyped-add")]
+/// fn add(left: i64, right: i64) -> i64 {
+///     left + right
+/// }
+///
+/// struct Timing;
+///
+/// impl UntypedHook for Timing {
+///     fn call(&self, call: &mut UntypedCall<'_>) {
+///         if let Some((left, _)) = call.args().and_then(|args| args.downcast_mut::<(i64, i64)>()) {
+///             *left += 1;
+///         }
+///         let start = Instant::now();
+///         call.proceed();
+///         println!("{} took {:?}", call.hookable(), start.elapsed());
+///     }
+/// }
+///
+/// let timing = Arc::new(Timing);
+/// lookup_hookable("untyped-add").unwrap().add_untyped_hook(timing).unwrap();
+/// assert_eq!(add(1, 2), 4);
+/// ```
+pub trait UntypedHook: Send + Sync + 'static {
+    /// The hook function, called when the target function is called.
+    /// If it neither calls [`UntypedCall::proceed`] nor sets a result,
+    /// the call proceeds after it returns.
+    fn call(&self, call: &mut UntypedCall<'_>);
+
+    /// A human-readable description of the hook, see [`Hook::description`](crate::Hook::description).
+    /// Defaults to the type name of the hook.
+    fn description(&self) -> String {
+        type_name::<Self>().to_string()
+    }
+}
+
+/// A call of a hookable function seen by an [`UntypedHook`].
+pub struct UntypedCall<'a> {
+    hookable: &'a str,
+    state: &'a mut dyn ErasedCall,
+}
+
+impl UntypedCall<'_> {
+    /// The name of the hookable function.
+    pub fn hookable(&self) -> &str {
+        self.hookable
+    }
+
+    /// The type name of the arguments tuple, e.g. `(i64, i64)`.
+    pub fn args_type_name(&self) -> &'static str {
+        self.state.type_names().1
+    }
+
+    /// The type name of the result.
+    pub fn result_type_name(&self) -> &'static str {
+        self.state.type_names().0
+    }
+
+    /// The arguments tuple, or `None` once the call proceeded or a result is set.
+    pub fn args(&mut self) -> Option<&mut dyn Any> {
+        self.state.args()
+    }
+
+    /// Call the next hook or the original function with the arguments.
+    /// # Panics
+    /// Panics if the call already proceeded or a result is set.
+    pub fn proceed(&mut self) {
+        self.state.proceed();
+    }
+
+    /// The result, or `None` until the call proceeded or a result is set.
+    pub fn result(&mut self) -> Option<&mut dyn Any> {
+        self.state.result()
+    }
+
+    /// Set the result, so the call does not proceed (or its result is replaced).
+    /// Returns the value back if it is not of the result type.
+    pub fn set_result(&mut self, result: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
+        self.state.set_result(result)
+    }
+}
+
+trait ErasedCall {
+    fn type_names(&self) -> (&'static str, &'static str);
+    fn args(&mut self) -> Option<&mut dyn Any>;
+    fn proceed(&mut self);
+    fn result(&mut self) -> Option<&mut dyn Any>;
+    fn set_result(&mut self, result: Box<dyn Any>) -> Result<(), Box<dyn Any>>;
+}
+
+struct CallState<'n, A, R> {
+    args: Option<A>,
+    result: Option<R>,
+    next: &'n (dyn Fn(A) -> R + Send + Sync),
+}
+
+impl<A: 'static, R: 'static> ErasedCall for CallState<'_, A, R> {
+    fn type_names(&self) -> (&'static str, &'static str) {
+        (type_name::<R>(), type_name::<A>())
+    }
+
+    fn args(&mut self) -> Option<&mut dyn Any> {
+        self.args.as_mut().map(|args| args as &mut dyn Any)
+    }
+
+    fn proceed(&mut self) {
+        let args = self
+            .args
+            .take()
+            .expect("The call already proceeded or has a result");
+        self.result = Some((self.next)(args));
+    }
+
+    fn result(&mut self) -> Option<&mut dyn Any> {
+        self.result.as_mut().map(|result| result as &mut dyn Any)
+    }
+
+    fn set_result(&mut self, result: Box<dyn Any>) -> Result<(), Box<dyn Any>> {
+        self.result = Some(*result.downcast::<R>()?);
+        self.args = None;
+        Ok(())
+    }
+}
+
+/// An [`UntypedHook`] attached to a hookable function, with the types of the function.
+#[doc(hidden)]
+pub struct UntypedEntry {
+    hook: Arc<dyn UntypedHook>,
+    hookable: Arc<str>,
+    type_info: (TypeId, TypeId),
+}
+
+// SAFETY: the entry is only composed by `compose_chain` of the hookable function it was created for,
+// or of another one with the same types, see `type_info`.
+unsafe impl HookDyn for UntypedEntry {
+    fn compose(self: Arc<Self>, _next: Box<dyn Any + Send + Sync>) -> Box<dyn Any + Send + Sync> {
+        unreachable!("Untyped hooks are composed by compose_chain")
+    }
+    fn type_info(&self) -> (TypeId, TypeId) {
+        self.type_info
+    }
+    fn description(&self) -> String {
+        self.hook.description()
+    }
+    fn runs_before(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn runs_after(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn on_attach(&self, _hookable: &HookableFuncMetadata) {}
+    fn on_detach(&self, _hookable: &HookableFuncMetadata) {}
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>> {
+        None
+    }
+    fn untyped(&self) -> Option<&UntypedEntry> {
+        Some(self)
+    }
+}
+
+impl UntypedEntry {
+    /// Wrap `next`, a [`ChainFn<A, R>`], into a callable of the same type that calls the hook.
+    pub(crate) fn compose<A: 'static, R: 'static>(
+        &self,
+        next: Box<dyn Any + Send + Sync>,
+    ) -> Box<dyn Any + Send + Sync> {
+        let next = *next
+            .downcast::<ChainFn<A, R>>()
+            .expect("Hook type mismatch");
+        let hook = self.hook.clone();
+        let hookable = self.hookable.clone();
+        let composed: ChainFn<A, R> = Box::new(move |args| {
+            let mut state = CallState {
+                args: Some(args),
+                result: None,
+                next: next.as_ref(),
+            };
+            hook.call(&mut UntypedCall {
+                hookable: &hookable,
+                state: &mut state,
+            });
+            if state.result.is_none() {
+                state.proceed();
+            }
+            state.result.unwrap()
+        });
+        Box::new(composed)
+    }
+}
+
+impl HookableFuncMetadata {
+    /// Add an [`UntypedHook`] to the hookable function with default (0) priority.
+    ///
+    /// Fails with [`AddHookError::UntypedNotSupported`] if the arguments or the result
+    /// of the function contain references, which cannot be seen as [`Any`].
+    #[track_caller]
+    pub fn add_untyped_hook(&self, hook: Arc<dyn UntypedHook>) -> Result<HookId, AddHookError> {
+        if self.invoke.is_none() {
+            return Err(AddHookError::UntypedNotSupported);
+        }
+        self.add_hook(Arc::new(UntypedEntry {
+            hook,
+            hookable: self.name.as_str().into(),
+            type_info: self.type_info,
+        }))
+    }
+}
@@ -0,0 +1,252 @@
+//! Hooks implemented as WebAssembly modules, available with the `wasmtime` feature.
+//!
+//! A [`WasmHook`] runs a function exported by a module with [`wasmtime`],
+//! so untrusted extensions can participate in hook chains without access to the memory of the host,
+//! with limits on the fuel and time of each call.
+//! Each call runs in a new instance of the module, so no state is kept between calls.
+//!
+//! Arguments and results are exchanged as JSON, like with [`JsonHook`](crate::script::JsonHook),
+//! through the memory of the module, which must export:
+//! - `memory`: its memory.
+//! - `alloc(len: i32) -> i32`: allocate `len` bytes and return their address.
+//! - The hook function `(ptr: i32, len: i32) -> i64`, receiving the arguments as a JSON array
+//!   and returning the address of the JSON result in the high 32 bits and its length in the low ones.
+//!
+//! The module may import `safe_hook.next(ptr: i32, len: i32) -> i64` to call the rest of the chain
+//! with a JSON array of arguments, it returns the JSON result like the hook function.
+//!
+//! If the module traps, runs out of fuel or time, or returns invalid JSON,
+//! the call proceeds as if the hook was not there, and the error is kept, see [`WasmHook::last_error`].
+//!
+//! A guest written in Rust and compiled to `wasm32-unknown-unknown` could look like this:
+//! ```rust,ignore
+//! #[unsafe(no_mangle)]
+//! pub extern "C" fn alloc(len: i32) -> i32 {
+//!     Vec::<u8>::with_capacity(len as usize).leak().as_mut_ptr() as i32
+//! }
+//!
+//! #[unsafe(no_mangle)]
+//! pub extern "C" fn hook(ptr: i32, len: i32) -> i64 {
+//!     let args = unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+//!     let (user, price): (String, u32) = serde_json::from_slice(args).unwrap();
+//!     let result = serde_json::to_vec(&if user == "vip" { price / 2 } else { price }).unwrap();
+//!     let result = result.leak();
+//!     ((result.as_ptr() as i64) << 32) | result.len() as i64
+//! }
+//! ```
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use safe_hook::lookup_hookable;
+//! use safe_hook::wasm::WasmHook;
+//! use safe_hook_macros::hookable;
+//!
+//! #[hookable("wasm-answer")]
+//! fn answer() -> u32 {
+//!     0
+//! }
+//!
+//! // A module returning 42, in the text format.
+//! let module = r#"(module
+//!     (memory (export "memory") 1)
+//!     (data (i32.const 0) "42")
+//!     (func (export "alloc") (param i32) (result i32) (i32.const 16))
+//!     (func (export "hook") (param i32 i32) (result i64) (i64.const 2)))"#;
+//! let hook = WasmHook::<(), u32>::new(module, "hook")
+//!     .unwrap()
+//!     .fuel(1_000_000)
+//!     .timeout(Duration::from_millis(100));
+//! lookup_hookable("wasm-answer").unwrap().add_hook(Arc::new(hook)).unwrap();
+//! assert_eq!(answer(), 42);
+//! ```
+
+use crate::script::{JsonNext, call_json};
+use crate::{Hook, lock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use wasmtime::{
+    AsContext, AsContextMut, Caller, Config, Engine, Extern, InstancePre, Linker, Memory, Module,
+    Store, TypedFunc,
+};
+
+/// The interval at which the epoch of the engine is incremented, the granularity of time limits.
+const TICK: Duration = Duration::from_millis(10);
+
+/// The engine shared by all modules, with a thread incrementing its epoch every [`TICK`].
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config).expect("invalid wasmtime configuration");
+        let ticking = engine.clone();
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(TICK);
+                ticking.increment_epoch();
+            }
+        });
+        engine
+    })
+}
+
+/// A [`Hook`] running a function of a WebAssembly module, see the [module docs](self).
+///
+/// Like [`FnHook`](crate::FnHook), the arguments can only contain `'static` references.
+pub struct WasmHook<A, R> {
+    instance: InstancePre<State>,
+    function: String,
+    fuel: u64,
+    timeout: Duration,
+    last_error: Mutex<Option<String>>,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> WasmHook<A, R> {
+    /// Create a hook calling the exported `function` of a module, in the binary or text format,
+    /// with a time limit of 5 seconds per call and no fuel limit.
+    pub fn new(wasm: impl AsRef<[u8]>, function: &str) -> wasmtime::Result<Self> {
+        let module = Module::new(engine(), wasm)?;
+        if module.get_export(function).is_none() {
+            return Err(wasmtime::Error::msg(format!(
+                "the module does not export {:?}",
+                function
+            )));
+        }
+        let mut linker = Linker::new(engine());
+        linker.func_wrap("safe_hook", "next", call_next)?;
+        Ok(Self {
+            instance: linker.instantiate_pre(&module)?,
+            function: function.to_string(),
+            fuel: u64::MAX,
+            timeout: Duration::from_secs(5),
+            last_error: Mutex::new(None),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Set the fuel each call can consume, roughly the number of instructions it can run.
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Set the time each call can run,
+    /// not counting the time spent in the rest of the chain.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The last error of the module, if any.
+    pub fn last_error(&self) -> Option<String> {
+        lock::lock(&self.last_error).clone()
+    }
+
+    /// The number of epoch ticks of the time limit.
+    fn deadline(&self) -> u64 {
+        self.timeout.as_nanos().div_ceil(TICK.as_nanos()).max(1) as u64
+    }
+
+    /// Run the hook function with JSON arguments.
+    fn run(&self, args: Value, next: JsonNext) -> wasmtime::Result<Value> {
+        // SAFETY: the store is dropped before returning, so `next` is not used after this call.
+        let next = unsafe { std::mem::transmute::<JsonNext, JsonNext<'static>>(next) };
+        let deadline = self.deadline();
+        let mut store = Store::new(engine(), State { next, deadline });
+        store.set_fuel(self.fuel)?;
+        store.set_epoch_deadline(deadline);
+        let instance = self.instance.instantiate(&mut store)?;
+        let hook = instance.get_typed_func::<(i32, i32), i64>(&mut store, &self.function)?;
+        let guest = Guest::new(&mut store, |store, name| instance.get_export(store, name))?;
+        let args = serde_json::to_vec(&args)?;
+        let ptr = guest.write(&mut store, &args)?;
+        let result = hook.call(&mut store, (ptr, args.len() as i32))?;
+        let result = guest.read(&store, result)?;
+        Ok(serde_json::from_slice(&result)?)
+    }
+}
+
+impl<A, R> Hook for WasmHook<A, R>
+where
+    A: Serialize + DeserializeOwned + 'static,
+    R: Serialize + DeserializeOwned + 'static,
+{
+    type Args<'b> = A;
+    type Result = R;
+
+    fn call(&self, args: A, next: &dyn for<'c> Fn(A) -> R) -> R {
+        call_json(&self.last_error, args, next, |args, next| {
+            self.run(args, next).map_err(|e| format!("{:#}", e))
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("WasmHook({})", self.function)
+    }
+}
+
+/// The data of the store of a call.
+struct State {
+    next: JsonNext<'static>,
+    /// The number of epoch ticks of the time limit.
+    deadline: u64,
+}
+
+/// The exports of an instance used to exchange JSON with it.
+struct Guest {
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+impl Guest {
+    fn new<S: AsContextMut>(
+        mut store: S,
+        get_export: impl Fn(&mut S, &str) -> Option<Extern>,
+    ) -> wasmtime::Result<Self> {
+        let Some(Extern::Memory(memory)) = get_export(&mut store, "memory") else {
+            return Err(wasmtime::Error::msg(
+                "the module does not export its memory",
+            ));
+        };
+        let Some(Extern::Func(alloc)) = get_export(&mut store, "alloc") else {
+            return Err(wasmtime::Error::msg("the module does not export alloc"));
+        };
+        let alloc = alloc.typed(&store)?;
+        Ok(Self { memory, alloc })
+    }
+
+    /// Copy `bytes` to memory allocated by the instance, returning their address.
+    fn write(&self, mut store: impl AsContextMut, bytes: &[u8]) -> wasmtime::Result<i32> {
+        let ptr = self.alloc.call(&mut store, bytes.len() as i32)?;
+        self.memory.write(&mut store, ptr as u32 as usize, bytes)?;
+        Ok(ptr)
+    }
+
+    /// Read the bytes at the address in the high 32 bits of `packed`, with the length in the low ones.
+    fn read(&self, store: impl AsContext, packed: i64) -> wasmtime::Result<Vec<u8>> {
+        let mut bytes = vec![0; packed as u32 as usize];
+        self.memory
+            .read(&store, (packed >> 32) as u32 as usize, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// The `safe_hook.next` import, calling the rest of the chain.
+fn call_next(mut caller: Caller<State>, ptr: i32, len: i32) -> wasmtime::Result<i64> {
+    let guest = Guest::new(&mut caller, |caller, name| caller.get_export(name))?;
+    let args = guest.read(&caller, ((ptr as i64) << 32) | len as u32 as i64)?;
+    let result =
+        (caller.data().next)(serde_json::from_slice(&args)?).map_err(wasmtime::Error::msg)?;
+    // The time spent in the rest of the chain is not counted.
+    let deadline = caller.data().deadline;
+    caller.as_context_mut().set_epoch_deadline(deadline);
+    let result = serde_json::to_vec(&result)?;
+    let ptr = guest.write(&mut caller, &result)?;
+    Ok(((ptr as i64) << 32) | result.len() as i64)
+}
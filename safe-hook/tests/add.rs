@@ -1,3 +1,5 @@
+#![cfg(lookup_by_name)]
+
 use safe_hook::{Hook, lookup_hookable};
 use safe_hook_macros::hookable;
 use std::fmt::Debug;
@@ -51,10 +53,53 @@ fn test() {
     assert_eq!(add(1, 2), 5);
     add_hookable.add_hook(hook3.clone()).unwrap();
     assert_eq!(add(1, 2), 6);
-    add_hookable.remove_hook(hook1.as_ref());
+    add_hookable.remove_hook(hook1.as_ref()).unwrap();
     assert_eq!(add(1, 2), 5);
-    add_hookable.remove_hook(hook2.as_ref());
+    add_hookable.remove_hook(hook2.as_ref()).unwrap();
     assert_eq!(add(1, 2), 4);
-    add_hookable.remove_hook(hook3.as_ref());
+    add_hookable.remove_hook(hook3.as_ref()).unwrap();
     assert_eq!(add(1, 2), 3);
 }
+
+#[hookable("neg")]
+fn neg(x: i64) -> i64 {
+    -x
+}
+
+struct HookNeg;
+
+impl Hook for HookNeg {
+    type Args<'a> = (i64,);
+    type Result = i64;
+    fn call(&self, args: (i64,), next: &dyn Fn((i64,)) -> i64) -> i64 {
+        next((args.0 + 1,))
+    }
+}
+
+#[test]
+fn test_single_arg() {
+    let neg_hookable = lookup_hookable("neg").unwrap();
+    #[cfg(not(feature = "hashed-names"))]
+    assert_eq!(neg_hookable.signature(), "fn neg(i64) -> i64");
+    assert_eq!(neg(1), -1);
+    neg_hookable.add_hook(Arc::new(HookNeg)).unwrap();
+    assert_eq!(neg(1), -2);
+}
+
+mod inner {
+    use safe_hook_macros::hookable;
+
+    /// Visibility and attributes are kept.
+    #[inline]
+    #[hookable("inner-sub")]
+    pub fn sub(mut left: i64, right: i64) -> i64 {
+        left -= right;
+        left
+    }
+}
+
+#[test]
+fn test_visibility() {
+    assert_eq!(inner::sub(3, 2), 1);
+    assert!(lookup_hookable("inner-sub").is_some());
+}
@@ -0,0 +1,209 @@
+#![cfg(all(feature = "admin", original_names))]
+
+use safe_hook::admin::{self, AdminServer};
+use safe_hook::{is_all_disabled, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+#[hookable("admin-inc")]
+fn inc(x: u32) -> u32 {
+    x + 1
+}
+
+fn ask(reader: &mut impl BufRead, writer: &mut impl Write, command: &str) -> Vec<String> {
+    writeln!(writer, "{}", command).unwrap();
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\n" {
+            return lines;
+        }
+        lines.push(line.trim_end().to_string());
+    }
+}
+
+#[test]
+fn test_execute() {
+    let inc_hookable = lookup_hookable("admin-inc").unwrap();
+    let id = inc_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 { next(args) * 10 })
+        .unwrap();
+    assert!(
+        admin::execute("list")
+            .unwrap()
+            .contains("fn admin-inc(u32) -> u32 hooks=1")
+    );
+    let hooks = admin::execute("hooks admin-inc").unwrap();
+    assert!(hooks.starts_with(&format!("{} priority=0 enabled=true ", id.as_u64())));
+    assert_eq!(inc(1), 20);
+
+    let command = format!("disable admin-inc {}", id.as_u64());
+    assert_eq!(admin::execute(&command), Ok("ok".to_string()));
+    assert_eq!(inc(1), 2);
+    let command = format!("enable admin-inc {}", id.as_u64());
+    assert_eq!(admin::execute(&command), Ok("ok".to_string()));
+    assert_eq!(inc(1), 20);
+    let command = format!("remove admin-inc {}", id.as_u64());
+    assert_eq!(admin::execute(&command), Ok("ok".to_string()));
+    assert_eq!(inc(1), 2);
+
+    assert_eq!(
+        admin::execute(&command),
+        Err(format!("Hook {} not found on 'admin-inc'", id.as_u64()))
+    );
+    assert_eq!(
+        admin::execute("hooks missing"),
+        Err("Hookable 'missing' not found".to_string())
+    );
+    assert_eq!(
+        admin::execute("remove admin-inc x"),
+        Err("Invalid hook id 'x'".to_string())
+    );
+    assert_eq!(
+        admin::execute("frobnicate"),
+        Err("Unknown command 'frobnicate', try 'help'".to_string())
+    );
+    assert_eq!(admin::execute(""), Err("Empty command".to_string()));
+}
+
+#[hookable("admin-twice")]
+fn twice(x: u32) -> u32 {
+    x * 2
+}
+
+#[test]
+fn test_tcp() {
+    let server = AdminServer::bind_tcp("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let twice_hookable = lookup_hookable("admin-twice").unwrap();
+    assert_eq!(
+        ask(&mut reader, &mut stream, "hooks admin-twice"),
+        Vec::<String>::new()
+    );
+    let first = twice_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 { next(args) + 1 })
+        .unwrap();
+    let second = twice_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 { next(args) + 2 })
+        .unwrap();
+    let hooks = ask(&mut reader, &mut stream, "hooks admin-twice");
+    assert_eq!(hooks.len(), 2);
+    assert!(hooks[0].starts_with(&second.as_u64().to_string()));
+    assert!(hooks[1].starts_with(&first.as_u64().to_string()));
+    let command = format!("remove admin-twice {}", first.as_u64());
+    assert_eq!(ask(&mut reader, &mut stream, &command), vec!["ok"]);
+    assert_eq!(
+        ask(&mut reader, &mut stream, "hooks"),
+        vec!["error: Unknown command 'hooks', try 'help'"]
+    );
+    assert_eq!(twice(1), 4);
+    twice_hookable.clear_hooks().unwrap();
+    writeln!(stream, "quit").unwrap();
+    drop(server);
+}
+
+#[test]
+fn test_tcp_closes_on_unknown_command() {
+    let server = AdminServer::bind_tcp("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    // What a web page can make a browser send to the server.
+    let request = "POST / HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: text/plain\r\n\
+                   Content-Length: 12\r\n\r\ndisable-all\n";
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    assert_eq!(
+        response,
+        "error: Unknown command 'POST / HTTP/1.1', try 'help'\n\n"
+    );
+    assert!(!is_all_disabled());
+    drop(server);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix() {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::temp_dir().join(format!("safe-hook-admin-{}.sock", std::process::id()));
+    let server = AdminServer::bind_unix(&path).unwrap();
+    assert_eq!(server.local_addr(), None);
+    let mut stream = UnixStream::connect(&path).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let help = ask(&mut reader, &mut stream, "help");
+    assert!(help[0].starts_with("commands: list"));
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[hookable("admin-http")]
+fn http(text: String) -> String {
+    text
+}
+
+#[test]
+fn test_http() {
+    let http_hookable = lookup_hookable("admin-http").unwrap();
+    let id = http_hookable
+        .add_hook_fn(|args: (String,), next| -> String { next(args) + "!" })
+        .unwrap()
+        .as_u64();
+    let response = admin::handle_http("GET", "/hookables", &[]);
+    assert_eq!(response.status, 200);
+    assert!(response.body.contains(
+        r#"{"name":"admin-http","signature":"fn admin-http(alloc::string::String) -> alloc::string::String","hooks":1}"#
+    ));
+    let response = admin::handle_http("GET", "/hookables/admin-http/hooks?verbose", &[]);
+    assert!(response.body.starts_with(&format!(
+        r#"[{{"id":{},"priority":0,"enabled":true,"description":"#,
+        id
+    )));
+
+    let path = format!("/hookables/admin-http/hooks/{}/disable", id);
+    assert_eq!(admin::handle_http("POST", &path, &[]).status, 403);
+    let headers = [("x-safe-hook-admin", "1")];
+    assert_eq!(admin::handle_http("POST", &path, &headers).status, 200);
+    assert_eq!(http("a".to_string()), "a");
+    assert_eq!(admin::handle_http("GET", &path, &[]).status, 405);
+    assert_eq!(
+        admin::handle_http("POST", "/hookables/admin-http/hooks/x/enable", &headers),
+        admin::HttpResponse {
+            status: 400,
+            body: r#"{"error":"Invalid hook id 'x'"}"#.to_string()
+        }
+    );
+    assert_eq!(
+        admin::handle_http("GET", "/hookables/\"quoted\"/hooks", &[]).body,
+        r#"{"error":"Hookable '\"quoted\"' not found"}"#
+    );
+
+    let server = AdminServer::bind_http("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    write!(
+        stream,
+        "DELETE /hookables/admin-http/hooks/{} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        id
+    )
+    .unwrap();
+    let mut response = String::new();
+    std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert_eq!(http_hookable.list_hooks().len(), 1);
+
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    write!(
+        stream,
+        "DELETE /hookables/admin-http/hooks/{} HTTP/1.1\r\nHost: localhost\r\nX-Safe-Hook-Admin: 1\r\n\r\n",
+        id
+    )
+    .unwrap();
+    let mut response = String::new();
+    std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n{}"));
+    assert!(http_hookable.list_hooks().is_empty());
+    assert_eq!(http("a".to_string()), "a");
+}
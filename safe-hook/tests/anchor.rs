@@ -0,0 +1,61 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("concat")]
+fn concat(left: i64, right: i64) -> i64 {
+    left * 10 + right
+}
+
+struct HookDigit {
+    name: &'static str,
+    digit: i64,
+}
+
+impl Hook for HookDigit {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) * 10 + self.digit
+    }
+    fn description(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+fn digit(name: &'static str, digit: i64) -> Arc<HookDigit> {
+    Arc::new(HookDigit { name, digit })
+}
+
+#[test]
+fn test() {
+    let concat_hookable = lookup_hookable("concat").unwrap();
+    let cache = concat_hookable
+        .add_hook_with_priority(digit("cache", 3), 5)
+        .unwrap();
+    concat_hookable.add_hook(digit("other", 4)).unwrap();
+    // Outer hooks append their digit last.
+    assert_eq!(concat(1, 2), 1243);
+
+    concat_hookable
+        .add_hook_before("cache", digit("auth", 5))
+        .unwrap();
+    concat_hookable
+        .add_hook_after(cache, digit("metrics", 6))
+        .unwrap();
+    assert_eq!(concat(1, 2), 124635);
+    let hooks = concat_hookable.list_hooks();
+    let names = hooks
+        .iter()
+        .map(|h| h.description.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["auth", "cache", "metrics", "other"]);
+    assert!(hooks[..3].iter().all(|h| h.priority == 5));
+
+    assert_eq!(
+        concat_hookable.add_hook_before("missing", digit("x", 7)),
+        Err(AddHookError::AnchorNotFound)
+    );
+}
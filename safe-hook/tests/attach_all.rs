@@ -0,0 +1,62 @@
+#![cfg(original_names)]
+
+use safe_hook::{
+    AddHookError, DuplicatePolicy, IntoHook, attach_to_all_matching, hookable, lookup_hookable,
+};
+use std::sync::Arc;
+
+#[hookable("attach-all-square")]
+fn square(value: u16) -> u16 {
+    value * value
+}
+
+#[hookable("attach-all-double")]
+fn double(value: u16) -> u16 {
+    value * 2
+}
+
+#[hookable("attach-all-other")]
+fn other(value: u32) -> u32 {
+    value
+}
+
+#[test]
+fn attach_to_compatible_hookables() {
+    lookup_hookable("attach-all-double")
+        .unwrap()
+        .set_duplicate_policy(DuplicatePolicy::Reject)
+        .unwrap();
+    let hook = Arc::new(
+        (|args: (u16,), next: &dyn Fn((u16,)) -> u16| -> u16 { next(args) + 1 }).into_hook(),
+    );
+    lookup_hookable("attach-all-double")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+
+    let results = attach_to_all_matching::<(u16,), u16>(hook, 0);
+    let results = results
+        .iter()
+        .map(|(meta, result)| (meta.name(), result.is_ok()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        [("attach-all-double", false), ("attach-all-square", true)]
+    );
+    assert_eq!(square(3), 10);
+    assert_eq!(double(3), 7);
+    assert_eq!(other(3), 3);
+}
+
+#[test]
+fn per_target_errors() {
+    let mismatched =
+        Arc::new((|args: (u64,), next: &dyn Fn((u64,)) -> u64| -> u64 { next(args) }).into_hook());
+    let results = attach_to_all_matching::<(u32,), u32>(mismatched, 0);
+    let (meta, result) = results
+        .into_iter()
+        .find(|(meta, _)| meta.name() == "attach-all-other")
+        .unwrap();
+    assert_eq!(meta.name(), "attach-all-other");
+    assert!(matches!(result, Err(AddHookError::TypeMismatch { .. })));
+}
@@ -0,0 +1,65 @@
+#![cfg(original_names)]
+
+use safe_hook::{HookEventKind, HookSet, IntoHook, audit, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("audited")]
+fn audited(x: u8) -> u8 {
+    x
+}
+
+#[test]
+fn test() {
+    let sunk = Arc::new(Mutex::new(Vec::new()));
+    audit::set_sink({
+        let sunk = sunk.clone();
+        move |record| sunk.lock().unwrap().push(record.to_string())
+    });
+    audit::enable(2);
+    assert!(audit::is_enabled());
+    let audited_hookable = lookup_hookable("audited").unwrap();
+    let hook =
+        Arc::new((|args: (u8,), next: &dyn Fn((u8,)) -> u8| -> u8 { next(args) }).into_hook());
+    let add_line = line!() + 1;
+    let id = audited_hookable.add_hook_with_priority(hook.clone(), 3);
+    let id = id.unwrap();
+    audited_hookable.set_hook_enabled(id, false).unwrap();
+    let remove_line = line!() + 1;
+    audited_hookable.remove_hook(hook.as_ref()).unwrap();
+
+    let records = audit::records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].event.kind, HookEventKind::Disabled);
+    assert_eq!(records[1].event.kind, HookEventKind::Removed);
+    assert_eq!(records[1].event.location.file(), file!());
+    assert_eq!(records[1].event.location.line(), remove_line);
+    assert_eq!(records[1].thread, std::thread::current().id());
+
+    let sunk = sunk.lock().unwrap().clone();
+    assert_eq!(sunk.len(), 3);
+    assert!(sunk[0].contains(" added safe_hook::adapter::FnHook<"));
+    assert!(sunk[0].contains(&format!(
+        " ({:?}, priority 3) on 'audited' at {}:{}:",
+        id,
+        file!(),
+        add_line
+    )));
+
+    let mut set = HookSet::new();
+    set.add_hook("audited", hook, 0);
+    let install_line = line!() + 1;
+    set.install().unwrap();
+    let record = audit::records().pop().unwrap();
+    assert_eq!(record.event.kind, HookEventKind::Added);
+    assert_eq!(record.event.location.line(), install_line);
+    set.uninstall().unwrap();
+
+    audit::disable();
+    assert!(!audit::is_enabled());
+    audit::clear();
+    audited_hookable.clear_hooks().unwrap();
+    assert!(audit::records().is_empty());
+    audit::clear_sink();
+    assert_eq!(audited(1), 1);
+}
@@ -0,0 +1,52 @@
+#![cfg(all(feature = "axum", lookup_by_name))]
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use safe_hook::admin::{self, ADMIN_HEADER};
+use safe_hook::lookup_hookable;
+use safe_hook_macros::hookable;
+use tower::ServiceExt;
+
+#[hookable("axum-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test() {
+    let id = lookup_hookable("axum-add")
+        .unwrap()
+        .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+        .unwrap()
+        .as_u64();
+    let app = axum::Router::new().nest("/admin", admin::router());
+
+    let get = Request::get("/admin/hookables/axum-add/hooks")
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(&app, get).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.starts_with(&format!(r#"[{{"id":{},"#, id)));
+
+    let path = format!("/admin/hookables/axum-add/hooks/{}/disable", id);
+    let forged = Request::post(&path).body(Body::empty()).unwrap();
+    assert_eq!(send(&app, forged).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(add(1, 2), 4);
+
+    let post = Request::post(&path)
+        .header(ADMIN_HEADER, "1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(&app, post).await, (StatusCode::OK, "{}".to_string()));
+    assert_eq!(add(1, 2), 3);
+
+    let missing = Request::get("/admin/nothing").body(Body::empty()).unwrap();
+    assert_eq!(send(&app, missing).await.0, StatusCode::NOT_FOUND);
+}
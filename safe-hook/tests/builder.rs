@@ -0,0 +1,76 @@
+#![cfg(original_names)]
+
+use safe_hook::{
+    AddHookError, HookableFuncMetadata, PanicPolicy, call_info, iter_hookables, lookup_hookable,
+};
+use safe_hook_macros::hookable;
+
+#[hookable("builder-taken")]
+fn taken() {}
+
+fn concat((left, right): (String, String)) -> String {
+    left + right.as_str()
+}
+
+#[test]
+fn test() {
+    let concat_point = HookableFuncMetadata::builder("builder-concat", concat)
+        .clone_args()
+        .register()
+        .unwrap();
+    assert_eq!(concat_point.call(("a".into(), "b".into())), "ab");
+    let concat_hookable = lookup_hookable("builder-concat").unwrap();
+    assert!(std::ptr::eq(concat_hookable, concat_point.metadata()));
+    assert!(iter_hookables().any(|meta| meta.name() == "builder-concat"));
+    assert_eq!(
+        concat_hookable.signature(),
+        "fn builder-concat(alloc::string::String, alloc::string::String) -> alloc::string::String"
+    );
+
+    concat_hookable
+        .add_hook_fn(|args: (String, String), next| -> String {
+            // Dynamic calls have no location.
+            if let Some(location) = call_info().unwrap().location {
+                assert_eq!(location.file(), file!());
+            }
+            next(args).to_uppercase()
+        })
+        .unwrap();
+    assert_eq!(concat_point.call(("a".into(), "b".into())), "AB");
+    let result = concat_hookable
+        .invoke(Box::new(("c".to_string(), "d".to_string())))
+        .unwrap();
+    assert_eq!(*result.downcast::<String>().unwrap(), "CD");
+    assert_eq!(
+        concat_hookable.call_original::<(String, String), String>(("a".into(), "b".into())),
+        Ok("ab".to_string())
+    );
+    concat_hookable.set_panic_policy(PanicPolicy::Skip).unwrap();
+    concat_hookable.clear_hooks().unwrap();
+
+    assert_eq!(
+        HookableFuncMetadata::builder("builder-concat", concat)
+            .register()
+            .err(),
+        Some(AddHookError::AlreadyRegistered(
+            "builder-concat".to_string()
+        ))
+    );
+    assert_eq!(
+        HookableFuncMetadata::builder("builder-taken", |(): ()| ())
+            .register()
+            .err(),
+        Some(AddHookError::AlreadyRegistered("builder-taken".to_string()))
+    );
+    taken();
+
+    let len_point =
+        HookableFuncMetadata::builder("builder-len", |(text,): (&'static str,)| text.len())
+            .register()
+            .unwrap();
+    assert_eq!(
+        len_point.metadata().set_panic_policy(PanicPolicy::Skip),
+        Err(AddHookError::ArgsNotClone)
+    );
+    assert_eq!(len_point.call(("abc",)), 3);
+}
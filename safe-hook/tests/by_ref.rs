@@ -0,0 +1,39 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{ByRef, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+struct Big {
+    data: [i64; 64],
+}
+
+#[hookable("sum", by_ref)]
+fn sum(big: Big, extra: i64) -> i64 {
+    big.data.iter().sum::<i64>() + extra
+}
+
+struct HookSum;
+
+impl Hook for HookSum {
+    type Args<'a> = &'a mut ByRef<(Big, i64)>;
+    type Result = i64;
+    fn call<'a>(
+        &'a self,
+        args: &'a mut ByRef<(Big, i64)>,
+        next: &dyn for<'c> Fn(&'c mut ByRef<(Big, i64)>) -> i64,
+    ) -> i64 {
+        args.0.data[0] += 1;
+        args.1 += 10;
+        next(args) * 2
+    }
+}
+
+#[test]
+fn test() {
+    let sum_hookable = lookup_hookable("sum").unwrap();
+    assert_eq!(sum(Big { data: [1; 64] }, 0), 64);
+    sum_hookable.add_hook(Arc::new(HookSum)).unwrap();
+    sum_hookable.add_hook(Arc::new(HookSum)).unwrap();
+    assert_eq!(sum(Big { data: [1; 64] }, 0), (((64 + 2 + 20) * 2) * 2));
+}
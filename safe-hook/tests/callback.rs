@@ -0,0 +1,87 @@
+#![cfg(original_names)]
+
+use safe_hook::{AddHookError, Hookable, lookup_hookable};
+use safe_hook_macros::hookable;
+
+#[hookable("callback-taken")]
+fn taken() {}
+
+type PickFn = Box<dyn Fn((Vec<u32>,)) -> u32>;
+
+struct Strategy {
+    name: String,
+    pick: Hookable<PickFn>,
+}
+
+impl Strategy {
+    fn new(name: &str, pick: impl Fn((Vec<u32>,)) -> u32 + 'static) -> Self {
+        Self {
+            name: name.to_string(),
+            pick: Hookable::new("callback-pick", Box::new(pick) as PickFn).unwrap(),
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let min = Strategy::new("min", |(values,)| values.into_iter().min().unwrap());
+    let max = Strategy::new("max", |(values,)| values.into_iter().max().unwrap());
+    assert_eq!(min.name, "min");
+    assert_eq!(min.pick.call((vec![3, 1, 2],)), 1);
+    assert_eq!(max.pick.call((vec![3, 1, 2],)), 3);
+
+    let pick_hookable = lookup_hookable("callback-pick").unwrap();
+    assert!(std::ptr::eq(pick_hookable, min.pick.metadata()));
+    assert!(std::ptr::eq(pick_hookable, max.pick.metadata()));
+    // The hook calls another callback, which must not be confused with the one being hooked.
+    pick_hookable
+        .add_hook_fn(move |(mut values,): (Vec<u32>,), next| -> u32 {
+            values.push(10);
+            next((values,))
+        })
+        .unwrap();
+    assert_eq!(min.pick.call((vec![3, 1, 2],)), 1);
+    assert_eq!(max.pick.call((vec![3, 1, 2],)), 10);
+
+    let nested = Hookable::new("callback-nested", |(x,): (u32,)| {
+        x + max.pick.call((vec![x],))
+    })
+    .unwrap();
+    assert_eq!(nested.call((1,)), 11);
+    assert_eq!((nested.get())((1,)), 11);
+    pick_hookable.clear_hooks().unwrap();
+
+    assert!(matches!(
+        Hookable::new("callback-pick", |(x,): (u64,)| x as u32).err(),
+        Some(AddHookError::TypeMismatch { .. })
+    ));
+    assert_eq!(
+        Hookable::new("callback-taken", |(): ()| ()).err(),
+        Some(AddHookError::AlreadyRegistered(
+            "callback-taken".to_string()
+        ))
+    );
+    taken();
+}
+
+#[test]
+fn test_call_original() {
+    let y = Hookable::new("callback-original-y", |(s,): (String,)| s).unwrap();
+    let x = Hookable::new("callback-original-x", |(v,): (u64,)| {
+        // The innermost callback of the thread is the one of `x`, not of `y`.
+        let result = y
+            .metadata()
+            .call_original::<(String,), String>(("text".to_string(),));
+        assert_eq!(result, Err(AddHookError::NoOriginal));
+        v
+    })
+    .unwrap();
+    x.metadata()
+        .add_hook_fn(|args: (u64,), next| -> u64 { next(args) + 1 })
+        .unwrap();
+    assert_eq!(x.call((1,)), 2);
+    assert_eq!(
+        x.metadata().call_original::<(u64,), u64>((1,)),
+        Err(AddHookError::NoOriginal)
+    );
+}
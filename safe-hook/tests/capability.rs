@@ -0,0 +1,90 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, HookTransaction, IntoHook, add_global_hook, add_hook_pending, disable_all,
+    hookable, lookup_hookable, pending_hooks, remove_pending, require_capability,
+};
+use std::sync::Arc;
+
+#[hookable("capability-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+fn plus_one() -> Arc<dyn safe_hook::HookDyn> {
+    Arc::new(
+        (|args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 { next(args) + 1 })
+            .into_hook(),
+    )
+}
+
+// Requiring the capability cannot be undone, so everything is checked in one test.
+#[test]
+fn mutations_require_capability() {
+    let meta = lookup_hookable("capability-add").unwrap();
+    let before = meta.add_hook(plus_one()).unwrap();
+
+    let capability = require_capability().unwrap();
+    assert!(require_capability().is_none());
+
+    assert!(matches!(
+        meta.add_hook(plus_one()),
+        Err(AddHookError::Unauthorized)
+    ));
+    let mut transaction = HookTransaction::new();
+    transaction.add_hook(meta, plus_one(), 0);
+    assert!(matches!(
+        transaction.commit(),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert!(matches!(
+        meta.remove_hook_by_id(before),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert!(matches!(
+        meta.set_hook_enabled(before, false),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert!(matches!(
+        meta.clear_hooks(),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert!(matches!(disable_all(), Err(AddHookError::Unauthorized)));
+    // Hooks cannot be held to be attached later, e.g. under the capability of startup code.
+    assert!(matches!(
+        add_hook_pending("capability-later", plus_one()),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert!(pending_hooks().is_empty());
+    assert!(matches!(
+        add_global_hook(plus_one()),
+        Err(AddHookError::Unauthorized)
+    ));
+    let held = plus_one();
+    capability
+        .scope(|| add_hook_pending("capability-later", held.clone()))
+        .unwrap();
+    assert!(matches!(
+        remove_pending(held.as_ref()),
+        Err(AddHookError::Unauthorized)
+    ));
+    assert_eq!(pending_hooks(), vec!["capability-later".to_string()]);
+    assert_eq!(add(1, 2), 4);
+
+    let (capability, other) = std::thread::spawn(move || {
+        let id = capability.scope(|| meta.add_hook(plus_one())).unwrap();
+        (capability, id)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(add(1, 2), 5);
+    assert!(meta.remove_hook_by_id(other).is_err());
+    assert!(
+        capability
+            .scope(|| meta.remove_hook_by_id(other))
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(add(1, 2), 4);
+    assert_eq!(capability.scope(|| remove_pending(held.as_ref())), Ok(true));
+}
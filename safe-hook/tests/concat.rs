@@ -1,3 +1,5 @@
+#![cfg(lookup_by_name)]
+
 use safe_hook::Hook;
 use safe_hook_macros::hookable;
 use std::sync::Arc;
@@ -22,8 +24,7 @@ impl Hook for ConcatHook {
     ) -> Self::Result {
         let (left, right) = args;
         let lest_new = format!("{}-{}", left, self.mid);
-        let res = next((&lest_new, right));
-        res
+        next((&lest_new, right))
     }
 }
 
@@ -41,12 +42,12 @@ fn test() {
     });
     hookable_metadata.add_hook(hook2.clone()).unwrap();
     assert_eq!(concat("abc", "def"), "abc-hook2-hook1-def");
-    let x = hookable_metadata.remove_hook(hook1.as_ref());
-    assert!(x);
+    let x = hookable_metadata.remove_hook(hook1.as_ref()).unwrap();
+    assert!(std::ptr::addr_eq(x.unwrap().as_ref(), hook1.as_ref()));
     assert_eq!(concat("abc", "def"), "abc-hook2-def");
-    let x = hookable_metadata.remove_hook(hook2.as_ref());
-    assert!(x);
+    let x = hookable_metadata.remove_hook(hook2.as_ref()).unwrap();
+    assert!(x.is_some());
     assert_eq!(concat("abc", "def"), "abc-def");
-    let x = hookable_metadata.remove_hook(hook2.as_ref());
-    assert!(!x);
+    let x = hookable_metadata.remove_hook(hook2.as_ref()).unwrap();
+    assert!(x.is_none());
 }
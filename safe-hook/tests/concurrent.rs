@@ -0,0 +1,57 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let callers = (0..4)
+        .map(|_| {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let res = add(1, 2);
+                    assert!(
+                        res == 3 || res == 4 || res == 14,
+                        "unexpected result {}",
+                        res
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..1000 {
+        let hook1 = Arc::new(HookAdd { x: 1 });
+        let hook2 = Arc::new(HookAdd { x: 10 });
+        add_hookable.add_hook(hook1.clone()).unwrap();
+        add_hookable.add_hook(hook2.clone()).unwrap();
+        add_hookable.remove_hook(hook2.as_ref()).unwrap();
+        add_hookable.remove_hook(hook1.as_ref()).unwrap();
+    }
+    stop.store(true, Ordering::Relaxed);
+    for caller in callers {
+        caller.join().unwrap();
+    }
+    assert_eq!(add(1, 2), 3);
+}
@@ -0,0 +1,33 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{ConditionalHook, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("describe")]
+fn describe(name: &str, age: u32) -> String {
+    format!("{} ({})", name, age)
+}
+
+struct HookRedact;
+
+impl Hook for HookRedact {
+    type Args<'a> = (&'a str, u32);
+    type Result = String;
+    fn call(&self, args: (&str, u32), next: &dyn Fn((&str, u32)) -> String) -> String {
+        next(("***", args.1))
+    }
+    fn description(&self) -> String {
+        "redact".to_string()
+    }
+}
+
+#[test]
+fn test() {
+    let describe_hookable = lookup_hookable("describe").unwrap();
+    let hook = ConditionalHook::new(HookRedact, |args: &(&str, u32)| args.1 < 18);
+    describe_hookable.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(describe("Amy", 30), "Amy (30)");
+    assert_eq!(describe("Bob", 12), "*** (12)");
+    assert_eq!(describe_hookable.list_hooks()[0].description, "redact");
+}
@@ -0,0 +1,105 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{HookContext, call_info, lookup_hookable, with_context};
+use safe_hook_macros::hookable;
+use std::time::SystemTime;
+
+#[hookable("context-outer")]
+fn outer(x: u32) -> u32 {
+    // The original function runs inside the context of its call.
+    with_context(|context| context.get::<u32>().copied())
+        .flatten()
+        .unwrap_or(0)
+        + inner(x)
+}
+
+#[hookable("context-inner")]
+fn inner(x: u32) -> u32 {
+    x
+}
+
+#[hookable("context-info")]
+fn info(x: u8) -> u8 {
+    x
+}
+
+struct Tag(&'static str);
+
+#[test]
+fn test() {
+    let mut context = HookContext::default();
+    assert!(!context.contains::<u32>());
+    assert_eq!(context.insert(1u32), None);
+    assert_eq!(context.insert(2u32), Some(1));
+    *context.get_mut::<u32>().unwrap() += 1;
+    assert_eq!(context.get::<u32>(), Some(&3));
+    assert_eq!(*context.get_or_insert_with(|| 5u64), 5);
+    assert_eq!(context.remove::<u32>(), Some(3));
+    assert!(context.get::<u32>().is_none());
+
+    assert!(with_context(|_| ()).is_none());
+    let outer_hookable = lookup_hookable("context-outer").unwrap();
+    let inner_hookable = lookup_hookable("context-inner").unwrap();
+    outer_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 {
+            let tag = with_context(|context| context.get::<Tag>().unwrap().0).unwrap();
+            assert_eq!(tag, "outer");
+            next(args)
+        })
+        .unwrap();
+    outer_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 {
+            with_context(|context| {
+                context.insert(Tag("outer"));
+                context.insert(100u32);
+                // Calls made while accessing the context get their own.
+                assert_eq!(inner(1), 1);
+            })
+            .unwrap();
+            next(args)
+        })
+        .unwrap();
+    inner_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 {
+            assert!(with_context(|context| context.contains::<Tag>()) == Some(false));
+            next(args)
+        })
+        .unwrap();
+    assert_eq!(outer(1), 101);
+    assert!(with_context(|_| ()).is_none());
+    outer_hookable.clear_hooks().unwrap();
+    inner_hookable.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_call_info() {
+    assert!(call_info().is_none());
+    let inner_hookable = lookup_hookable("context-info").unwrap();
+    let infos = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    for _ in 0..2 {
+        inner_hookable
+            .add_hook_fn({
+                let infos = infos.clone();
+                move |args: (u8,), next| -> u8 {
+                    infos.lock().unwrap().push(call_info().unwrap());
+                    next(args)
+                }
+            })
+            .unwrap();
+    }
+    let before = SystemTime::now();
+    info(1);
+    info(2);
+    let infos = infos.lock().unwrap().clone();
+    assert_eq!(infos.len(), 4);
+    #[cfg(not(feature = "hashed-names"))]
+    assert_eq!(infos[0].name, "context-info");
+    // The hooks of a call get the same information.
+    assert_eq!(infos[0], infos[1]);
+    assert_eq!(infos[2], infos[3]);
+    assert!(infos[1].call_id < infos[2].call_id);
+    assert_eq!(infos[0].thread, std::thread::current().id());
+    assert!(infos[0].timestamp >= before);
+    assert!(call_info().is_none());
+    inner_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,46 @@
+#![cfg(all(feature = "coverage", original_names))]
+
+use safe_hook::{coverage, lookup_hookable};
+use safe_hook_macros::hookable;
+
+#[hookable("visited")]
+fn visited(x: u32) -> u32 {
+    x
+}
+
+#[hookable("hooked")]
+fn hooked(x: u32) -> u32 {
+    x
+}
+
+#[hookable("unvisited")]
+fn unvisited(x: u32) -> u32 {
+    x
+}
+
+#[test]
+fn test() {
+    let report = coverage::report();
+    assert_eq!(report.executed, Vec::<String>::new());
+    assert_eq!(report.missed, vec!["hooked", "unvisited", "visited"]);
+    assert!(!report.is_complete());
+
+    visited(1);
+    let hooked_hookable = lookup_hookable("hooked").unwrap();
+    let id = hooked_hookable
+        .add_hook_fn(|args: (u32,), next| -> u32 { next(args) })
+        .unwrap();
+    hooked(2);
+    hooked_hookable.remove_hook_by_id(id).unwrap();
+    assert!(hooked_hookable.was_executed());
+    let report = coverage::report();
+    assert_eq!(report.executed, vec!["hooked", "visited"]);
+    assert_eq!(report.missed, vec!["unvisited"]);
+
+    unvisited(3);
+    assert!(coverage::report().is_complete());
+
+    coverage::reset();
+    assert!(!lookup_hookable("visited").unwrap().was_executed());
+    assert_eq!(coverage::report().executed, Vec::<String>::new());
+}
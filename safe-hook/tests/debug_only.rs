@@ -0,0 +1,37 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add", debug_only)]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add");
+    if cfg!(debug_assertions) {
+        add_hookable
+            .unwrap()
+            .add_hook(Arc::new(HookAdd { x: 1 }))
+            .unwrap();
+        assert_eq!(add(1, 2), 4);
+    } else {
+        assert!(add_hookable.is_none());
+        assert_eq!(add(1, 2), 3);
+    }
+}
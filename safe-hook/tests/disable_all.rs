@@ -0,0 +1,48 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, disable_all, enable_all, is_all_disabled, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let sub_hookable = lookup_hookable("sub").unwrap();
+    add_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    assert_eq!(add(1, 2), 4);
+
+    disable_all().unwrap();
+    assert!(is_all_disabled());
+    assert_eq!(add(1, 2), 3);
+    // Hooks added while disabled are not called either.
+    sub_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    assert_eq!(sub(1, 2), -1);
+    assert_eq!(add_hookable.list_hooks().len(), 1);
+
+    enable_all().unwrap();
+    assert!(!is_all_disabled());
+    assert_eq!(add(1, 2), 4);
+    assert_eq!(sub(1, 2), 0);
+}
@@ -0,0 +1,89 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, DuplicatePolicy, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let hook = Arc::new(HookAdd { x: 1 });
+    assert_eq!(add_hookable.duplicate_policy(), DuplicatePolicy::Allow);
+    assert!(!add_hookable.contains_hook(hook.as_ref()));
+
+    // Allow
+    add_hookable.add_hook(hook.clone()).unwrap();
+    add_hookable.add_hook(hook.clone()).unwrap();
+    assert!(add_hookable.contains_hook(hook.as_ref()));
+    assert_eq!(add(1, 2), 5);
+    add_hookable.clear_hooks().unwrap();
+
+    // Reject
+    add_hookable
+        .set_duplicate_policy(DuplicatePolicy::Reject)
+        .unwrap();
+    let id = add_hookable.add_hook(hook.clone()).unwrap();
+    assert_eq!(
+        add_hookable.add_hook(hook.clone()),
+        Err(AddHookError::Duplicate(id))
+    );
+    assert_eq!(add(1, 2), 4);
+    add_hookable.clear_hooks().unwrap();
+
+    // Replace
+    add_hookable
+        .set_duplicate_policy(DuplicatePolicy::Replace)
+        .unwrap();
+    let other = Arc::new(HookAdd { x: 10 });
+    add_hookable.add_hook(hook.clone()).unwrap();
+    add_hookable.add_hook(other.clone()).unwrap();
+    let id = add_hookable
+        .add_hook_with_priority(hook.clone(), -1)
+        .unwrap();
+    let hooks = add_hookable.list_hooks();
+    assert_eq!(hooks.len(), 2);
+    assert_eq!((hooks[1].id, hooks[1].priority), (id, -1));
+    assert_eq!(add(1, 2), 14);
+    add_hookable.clear_hooks().unwrap();
+}
+
+#[hookable("staging-add")]
+fn staging_add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn test_drain() {
+    let staging = lookup_hookable("staging-add").unwrap();
+    let hook1 = Arc::new(HookAdd { x: 1 });
+    let hook2 = Arc::new(HookAdd { x: 10 });
+    staging.add_hook_with_priority(hook1.clone(), 1).unwrap();
+    staging.add_hook_with_priority(hook2.clone(), 2).unwrap();
+    assert_eq!(staging_add(1, 2), 14);
+
+    let drained = staging.drain_hooks().unwrap();
+    assert_eq!(staging_add(1, 2), 3);
+    assert!(staging.list_hooks().is_empty());
+    assert_eq!(drained.len(), 2);
+    assert!(std::ptr::addr_eq(drained[0].0.as_ref(), hook2.as_ref()));
+    assert_eq!(drained[0].1, 2);
+    assert!(std::ptr::addr_eq(drained[1].0.as_ref(), hook1.as_ref()));
+    assert_eq!(drained[1].1, 1);
+}
@@ -0,0 +1,42 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+// The only test in this binary, so nothing reads the environment concurrently.
+#[test]
+fn test() {
+    unsafe { std::env::set_var("SAFE_HOOK_DISABLE", "mul, add") };
+    let add_hookable = lookup_hookable("add").unwrap();
+    let sub_hookable = lookup_hookable("sub").unwrap();
+    assert!(add_hookable.is_disabled_by_env());
+    assert!(!sub_hookable.is_disabled_by_env());
+
+    add_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    sub_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(sub(1, 2), 0);
+}
@@ -0,0 +1,63 @@
+#![cfg(original_names)]
+
+use safe_hook::{
+    HookEventKind, HookTransaction, IntoHook, add_registry_observer, lookup_hookable,
+    remove_registry_observer,
+};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("watched")]
+fn watched(x: i32) -> i32 {
+    x
+}
+
+#[test]
+fn test() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let observer = add_registry_observer({
+        let events = events.clone();
+        move |event| {
+            if event.hookable == "watched" {
+                // Observers run after the hookable is unlocked, so they can inspect it.
+                let count = lookup_hookable("watched").unwrap().list_hooks().len();
+                events
+                    .lock()
+                    .unwrap()
+                    .push((event.kind, event.hook.priority, count));
+            }
+        }
+    });
+    let watched_hookable = lookup_hookable("watched").unwrap();
+    let hook =
+        Arc::new((|args: (i32,), next: &dyn Fn((i32,)) -> i32| -> i32 { next(args) }).into_hook());
+    let id = watched_hookable
+        .add_hook_with_priority(hook.clone(), 5)
+        .unwrap();
+    watched_hookable.set_hook_enabled(id, false).unwrap();
+    watched_hookable.set_hook_enabled(id, false).unwrap();
+    watched_hookable.set_hook_enabled(id, true).unwrap();
+    watched_hookable.remove_hook_by_id(id).unwrap();
+    let mut transaction = HookTransaction::new();
+    transaction.add_hook(watched_hookable, hook.clone(), 1);
+    transaction.commit().unwrap();
+    watched_hookable.clear_hooks().unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            (HookEventKind::Added, 5, 1),
+            (HookEventKind::Disabled, 5, 1),
+            (HookEventKind::Enabled, 5, 1),
+            (HookEventKind::Removed, 5, 0),
+            (HookEventKind::Added, 1, 1),
+            (HookEventKind::Removed, 1, 0),
+        ]
+    );
+
+    assert!(remove_registry_observer(observer));
+    assert!(!remove_registry_observer(observer));
+    watched_hookable.add_hook(hook).unwrap();
+    watched_hookable.clear_hooks().unwrap();
+    assert_eq!(events.lock().unwrap().len(), 6);
+    assert_eq!(watched(3), 3);
+}
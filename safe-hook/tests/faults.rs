@@ -0,0 +1,93 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::faults::{FaultHook, Latency, LatencyHook};
+use safe_hook::lookup_hookable;
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[hookable("balance")]
+fn balance(account: u32) -> Result<i64, String> {
+    Ok(account as i64 * 100)
+}
+
+#[hookable("checksum")]
+fn checksum(data: Vec<u8>) -> u32 {
+    data.iter().map(|&b| b as u32).sum()
+}
+
+#[test]
+fn test_error() {
+    let balance_hookable = lookup_hookable("balance").unwrap();
+    let fault = Arc::new(
+        FaultHook::<(u32,), Result<i64, String>>::error(0.3, || "unavailable".to_string())
+            .with_seed(42),
+    );
+    balance_hookable.add_hook(fault.clone()).unwrap();
+    let errors = (0..1000).filter(|_| balance(1).is_err()).count();
+    assert!((200..400).contains(&errors), "{} errors", errors);
+    assert_eq!(fault.probability(), 0.3);
+
+    fault.set_probability(0.0);
+    assert!((0..100).all(|_| balance(1) == Ok(100)));
+    fault.set_probability(1.0);
+    assert!((0..100).all(|_| balance(1) == Err("unavailable".to_string())));
+}
+
+#[test]
+fn test_corrupt_and_panic() {
+    let checksum_hookable = lookup_hookable("checksum").unwrap();
+    let corrupt = Arc::new(FaultHook::<(Vec<u8>,), u32>::corrupt(1.0, |sum| sum ^ 1));
+    checksum_hookable.add_hook(corrupt.clone()).unwrap();
+    assert_eq!(checksum(vec![1, 2, 3]), 7);
+    checksum_hookable.remove_hook(corrupt.as_ref()).unwrap();
+
+    let panic = Arc::new(FaultHook::<(Vec<u8>,), u32>::panic(1.0, "injected panic"));
+    checksum_hookable.add_hook(panic.clone()).unwrap();
+    let err = std::panic::catch_unwind(|| checksum(vec![1])).unwrap_err();
+    assert_eq!(err.downcast_ref::<String>().unwrap(), "injected panic");
+    checksum_hookable.remove_hook(panic.as_ref()).unwrap();
+    assert_eq!(checksum(vec![1, 2, 3]), 6);
+}
+
+#[hookable("ping")]
+fn ping() -> u32 {
+    1
+}
+
+#[test]
+fn test_latency() {
+    let ping_hookable = lookup_hookable("ping").unwrap();
+    let latency = Arc::new(LatencyHook::<(), u32>::before(Latency::Fixed(
+        Duration::from_millis(20),
+    )));
+    ping_hookable.add_hook(latency.clone()).unwrap();
+    let start = Instant::now();
+    assert_eq!(ping(), 1);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    latency.set_latency(Latency::Uniform(
+        Duration::from_millis(5),
+        Duration::from_millis(10),
+    ));
+    for _ in 0..5 {
+        let start = Instant::now();
+        ping();
+        assert!(start.elapsed() >= Duration::from_millis(5));
+    }
+
+    latency.set_latency(Latency::Custom(Arc::new(|| Duration::from_millis(15))));
+    let start = Instant::now();
+    ping();
+    assert!(start.elapsed() >= Duration::from_millis(15));
+    ping_hookable.clear_hooks().unwrap();
+
+    let latency =
+        LatencyHook::<(), u32>::after(Latency::Exponential(Duration::from_millis(1))).with_seed(7);
+    ping_hookable.add_hook(Arc::new(latency)).unwrap();
+    let start = Instant::now();
+    for _ in 0..100 {
+        assert_eq!(ping(), 1);
+    }
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
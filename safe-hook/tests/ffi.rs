@@ -0,0 +1,85 @@
+#![cfg(all(feature = "ffi", original_names))]
+
+use safe_hook::ffi::{
+    SAFE_HOOK_INVALID_ARGUMENT, SAFE_HOOK_NOT_FOUND, safe_hook_list, safe_hook_list_hooks,
+    safe_hook_lookup, safe_hook_remove, safe_hook_remove_by_name, safe_hook_set_enabled,
+};
+use safe_hook::lookup_hookable;
+use safe_hook_macros::hookable;
+use std::ffi::{CStr, c_char, c_void};
+
+#[hookable("ffi-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+extern "C" fn collect_name(name: *const c_char, _hooks: usize, user_data: *mut c_void) {
+    let names = unsafe { &mut *(user_data as *mut Vec<String>) };
+    names.push(
+        unsafe { CStr::from_ptr(name) }
+            .to_str()
+            .unwrap()
+            .to_string(),
+    );
+}
+
+extern "C" fn collect_hook(
+    id: u64,
+    priority: i32,
+    enabled: bool,
+    _description: *const c_char,
+    user_data: *mut c_void,
+) {
+    let hooks = unsafe { &mut *(user_data as *mut Vec<(u64, i32, bool)>) };
+    hooks.push((id, priority, enabled));
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("ffi-add").unwrap();
+    let id = add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+        .unwrap();
+    add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) * 2 })
+        .unwrap();
+    unsafe {
+        assert_eq!(safe_hook_lookup(c"ffi-add".as_ptr()), 2);
+        assert_eq!(
+            safe_hook_lookup(c"ffi-missing".as_ptr()),
+            SAFE_HOOK_NOT_FOUND
+        );
+        assert_eq!(
+            safe_hook_lookup(std::ptr::null()),
+            SAFE_HOOK_INVALID_ARGUMENT
+        );
+
+        let mut names = Vec::<String>::new();
+        let count = safe_hook_list(collect_name, &mut names as *mut _ as *mut c_void);
+        assert_eq!(count as usize, names.len());
+        assert!(names.contains(&"ffi-add".to_string()));
+
+        assert_eq!(
+            safe_hook_set_enabled(c"ffi-add".as_ptr(), id.as_u64(), false),
+            0
+        );
+        let mut hooks = Vec::<(u64, i32, bool)>::new();
+        let count = safe_hook_list_hooks(
+            c"ffi-add".as_ptr(),
+            collect_hook,
+            &mut hooks as *mut _ as *mut c_void,
+        );
+        assert_eq!(count, 2);
+        assert!(hooks.contains(&(id.as_u64(), 0, false)));
+        assert_eq!(add(1, 2), 6);
+
+        assert_eq!(safe_hook_remove(c"ffi-add".as_ptr(), id.as_u64()), 0);
+        assert_eq!(
+            safe_hook_remove(c"ffi-add".as_ptr(), id.as_u64()),
+            SAFE_HOOK_NOT_FOUND
+        );
+        assert_eq!(safe_hook_remove_by_name(c"ffi-add".as_ptr()), 1);
+        assert_eq!(safe_hook_lookup(c"ffi-add".as_ptr()), 0);
+    }
+    assert_eq!(add(1, 2), 3);
+}
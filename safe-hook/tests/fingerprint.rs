@@ -0,0 +1,76 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, HookDyn, HookableFuncMetadata, IntoHook, hookable, lookup_hookable,
+    type_fingerprint,
+};
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+#[hookable("fingerprint-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+/// A hook as built by another build of a plugin: same `TypeId`s, another fingerprint.
+struct Foreign {
+    hook: Arc<dyn HookDyn>,
+    fingerprint: (u64, u64),
+}
+
+unsafe impl HookDyn for Foreign {
+    fn compose(self: Arc<Self>, next: Box<dyn Any + Send + Sync>) -> Box<dyn Any + Send + Sync> {
+        self.hook.clone().compose(next)
+    }
+    fn type_info(&self) -> (TypeId, TypeId) {
+        self.hook.type_info()
+    }
+    fn description(&self) -> String {
+        self.hook.description()
+    }
+    fn runs_before(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn runs_after(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn on_attach(&self, _hookable: &HookableFuncMetadata) {}
+    fn on_detach(&self, _hookable: &HookableFuncMetadata) {}
+    fn observer(self: Arc<Self>) -> Option<Box<dyn Any + Send + Sync>> {
+        None
+    }
+    fn fingerprint(&self) -> Option<(u64, u64)> {
+        Some(self.fingerprint)
+    }
+}
+
+fn plus_one() -> Arc<dyn HookDyn> {
+    Arc::new(
+        (|args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 { next(args) + 1 })
+            .into_hook(),
+    )
+}
+
+#[test]
+fn fingerprints_are_checked() {
+    let meta = lookup_hookable("fingerprint-add").unwrap();
+    let expected = (type_fingerprint::<i64>(), type_fingerprint::<(i64, i64)>());
+    assert_eq!(meta.fingerprint(), expected);
+    assert_eq!(plus_one().fingerprint(), Some(expected));
+
+    let foreign = Foreign {
+        hook: plus_one(),
+        fingerprint: (expected.0, type_fingerprint::<(i32, i32)>()),
+    };
+    assert!(matches!(
+        meta.add_hook(Arc::new(foreign)),
+        Err(AddHookError::FingerprintMismatch { found, .. }) if found.0 == expected.0
+    ));
+
+    let matching = Foreign {
+        hook: plus_one(),
+        fingerprint: expected,
+    };
+    meta.add_hook(Arc::new(matching)).unwrap();
+    assert_eq!(add(1, 2), 4);
+}
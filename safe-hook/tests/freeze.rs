@@ -0,0 +1,114 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, DuplicatePolicy, HookSet, HookTransaction, IntoHook, TieBreak, disable_all,
+    freeze, hookable, is_all_disabled, is_frozen, lookup_hookable,
+};
+use std::sync::Arc;
+
+#[hookable("freeze-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+fn plus_one() -> Arc<dyn safe_hook::HookDyn> {
+    Arc::new(
+        (|args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 { next(args) + 1 })
+            .into_hook(),
+    )
+}
+
+// Freezing cannot be undone, so everything is checked in one test.
+#[test]
+fn frozen_hooks_cannot_change() {
+    let meta = lookup_hookable("freeze-add").unwrap();
+    let id = meta.add_hook(plus_one()).unwrap();
+    let mut set = HookSet::new();
+    set.add_hook("freeze-add", plus_one(), 0);
+    set.install().unwrap();
+    assert!(!is_frozen());
+
+    freeze();
+    assert!(is_frozen());
+    assert!(matches!(
+        meta.add_hook(plus_one()),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    let mut transaction = HookTransaction::new();
+    transaction.remove_hook_by_id(meta, id);
+    assert!(matches!(
+        transaction.commit(),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.remove_hook_by_id(id),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.set_hook_enabled(id, false),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.drain_hooks(),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.clear_hooks(),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(set.uninstall(), Err(AddHookError::RegistryFrozen)));
+    assert!(set.is_installed());
+    assert!(matches!(
+        meta.set_max_depth(Some(1)),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.set_depth_fallback(|(left, right): (i64, i64)| left - right),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.set_duplicate_policy(DuplicatePolicy::Reject),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(
+        meta.set_tie_break(TieBreak::Fifo),
+        Err(AddHookError::RegistryFrozen)
+    ));
+    assert!(matches!(disable_all(), Err(AddHookError::RegistryFrozen)));
+    assert!(!is_all_disabled());
+    assert_eq!(meta.max_depth(), None);
+    assert_eq!(meta.tie_break(), TieBreak::Lifo);
+    assert_eq!(meta.list_hooks().len(), 2);
+    assert_eq!(add(1, 2), 5);
+
+    #[cfg(feature = "admin")]
+    {
+        let command = format!("remove freeze-add {}", id.as_u64());
+        assert!(safe_hook::admin::execute(&command).is_err());
+        assert!(safe_hook::admin::execute("disable-all").is_err());
+        let path = format!("/hookables/freeze-add/hooks/{}", id.as_u64());
+        let headers = [(safe_hook::admin::ADMIN_HEADER, "1")];
+        assert_eq!(
+            safe_hook::admin::handle_http("DELETE", &path, &headers).status,
+            403
+        );
+    }
+    #[cfg(feature = "ffi")]
+    {
+        use safe_hook::ffi::{
+            SAFE_HOOK_REFUSED, safe_hook_disable_all, safe_hook_remove, safe_hook_set_enabled,
+        };
+        let name = c"freeze-add".as_ptr();
+        assert_eq!(
+            unsafe { safe_hook_remove(name, id.as_u64()) },
+            SAFE_HOOK_REFUSED
+        );
+        assert_eq!(
+            unsafe { safe_hook_set_enabled(name, id.as_u64(), false) },
+            SAFE_HOOK_REFUSED
+        );
+        assert_eq!(safe_hook_disable_all(), SAFE_HOOK_REFUSED);
+    }
+    assert!(!is_all_disabled());
+    assert_eq!(meta.list_hooks().len(), 2);
+}
@@ -0,0 +1,82 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, DuplicatePolicy, HookDyn, IntoHook, add_global_hook, attach_pending,
+    lookup_hookable, remove_global_hook,
+};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[hookable("fetch-a")]
+fn fetch_a(key: u64) -> Result<Vec<u8>, String> {
+    Ok(vec![key as u8])
+}
+
+#[hookable("fetch-b")]
+fn fetch_b(key: u64) -> Result<Vec<u8>, String> {
+    Err(format!("missing {}", key))
+}
+
+#[hookable("fetch-other")]
+fn fetch_other(key: u32) -> Result<Vec<u8>, String> {
+    Ok(vec![key as u8])
+}
+
+#[hookable("negate-rejecting")]
+fn negate(x: i8) -> i8 {
+    -x
+}
+
+#[test]
+fn test() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let counter = (|args: (u64,),
+                    next: &dyn Fn((u64,)) -> Result<Vec<u8>, String>|
+     -> Result<Vec<u8>, String> {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        next(args)
+    })
+    .into_hook();
+    let id = add_global_hook(Arc::new(counter)).unwrap();
+    assert_eq!(fetch_a(1), Ok(vec![1]));
+    assert_eq!(fetch_b(2), Err("missing 2".to_string()));
+    assert_eq!(fetch_other(3), Ok(vec![3]));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    assert!(
+        lookup_hookable("fetch-other")
+            .unwrap()
+            .list_hooks()
+            .is_empty()
+    );
+
+    // Removing it from one hookable does not attach it again.
+    lookup_hookable("fetch-a").unwrap().clear_hooks().unwrap();
+    let _ = fetch_a(1);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+    assert!(remove_global_hook(id).unwrap());
+    assert!(!remove_global_hook(id).unwrap());
+    assert!(lookup_hookable("fetch-b").unwrap().list_hooks().is_empty());
+    let _ = fetch_b(2);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+    // Failing to attach is reported, and attaching is tried again later.
+    let negate_hookable = lookup_hookable("negate-rejecting").unwrap();
+    let plus_one: Arc<dyn HookDyn> =
+        Arc::new((|args: (i8,), next: &dyn Fn((i8,)) -> i8| -> i8 { next(args) + 1 }).into_hook());
+    negate_hookable
+        .set_duplicate_policy(DuplicatePolicy::Reject)
+        .unwrap();
+    let direct = negate_hookable.add_hook(plus_one.clone()).unwrap();
+    let id = add_global_hook(plus_one).unwrap();
+    let failed = attach_pending();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].1, AddHookError::Duplicate(direct));
+    assert_eq!(negate_hookable.list_hooks().len(), 1);
+    negate_hookable.remove_hook_by_id(direct).unwrap();
+    assert!(attach_pending().is_empty());
+    assert_eq!(negate(1), 0);
+    assert!(remove_global_hook(id).unwrap());
+    assert_eq!(negate(1), -1);
+}
@@ -0,0 +1,32 @@
+#![cfg(all(feature = "hashed-names", lookup_by_name))]
+
+use safe_hook::{
+    HookableFuncMetadata, hashed_name, hookable, iter_hookables, lookup_hookable,
+    lookup_hookables_matching, similar_hookables,
+};
+
+#[hookable("hashed-names-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn macro_names_are_hashed() {
+    assert_eq!(add(1, 2), 3);
+    let meta = lookup_hookable("hashed-names-add").unwrap();
+    assert_eq!(meta.name(), hashed_name("hashed-names-add"));
+    assert!(iter_hookables().all(|meta| meta.name().starts_with('#')));
+    assert!(similar_hookables("hashed-names-ad").is_empty());
+    assert!(lookup_hookables_matching("hashed-names-*").is_empty());
+}
+
+#[test]
+fn builder_names_are_hashed() {
+    let point =
+        HookableFuncMetadata::builder("hashed-names-mul", |(left, right): (i64, i64)| left * right)
+            .register()
+            .unwrap();
+    assert_eq!(point.call((2, 3)), 6);
+    let meta = lookup_hookable("hashed-names-mul").unwrap();
+    assert_eq!(meta.name(), hashed_name("hashed-names-mul"));
+}
@@ -0,0 +1,77 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, IntoHook, fn_hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("join")]
+fn join(left: String, right: String) -> String {
+    format!("{}{}", left, right)
+}
+
+#[test]
+fn test() {
+    let join_hookable = lookup_hookable("join").unwrap();
+    let id = join_hookable
+        .add_hook_fn(|(left, right): (String, String), next| -> String { next((right, left)) })
+        .unwrap();
+    assert_eq!(join("a".to_string(), "b".to_string()), "ba");
+
+    let hook = (|args: (String, String), next: &dyn Fn((String, String)) -> String| {
+        format!("[{}]", next(args))
+    })
+    .into_hook();
+    join_hookable
+        .add_hook_with_priority(Arc::new(hook), 1)
+        .unwrap();
+    assert_eq!(join("a".to_string(), "b".to_string()), "[ba]");
+
+    assert!(join_hookable.remove_hook_by_id(id).unwrap().is_some());
+    assert_eq!(join("a".to_string(), "b".to_string()), "[ab]");
+
+    assert!(matches!(
+        join_hookable.add_hook_fn(|args: (i64,), next| -> i64 { next(args) }),
+        Err(AddHookError::TypeMismatch { .. })
+    ));
+}
+
+#[hookable("repeat")]
+fn repeat(text: String, times: usize) -> String {
+    text.repeat(times)
+}
+
+#[test]
+fn test_fn_hook() {
+    let repeat_hookable = lookup_hookable("repeat").unwrap();
+    let hook = fn_hook!(|args: (String, usize), next| -> String { next((args.0, args.1 + 1)) });
+    repeat_hookable.add_hook(Arc::new(hook)).unwrap();
+    let suffix = "!".to_string();
+    let hook = fn_hook!(move |(text, times): (String, usize), next| -> String {
+        next((text + suffix.as_str(), times))
+    });
+    repeat_hookable.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(repeat("a".to_string(), 1), "a!a!");
+}
+
+#[hookable("mismatch-add")]
+fn mismatch_add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn test_type_mismatch_message() {
+    let add_hookable = lookup_hookable("mismatch-add").unwrap();
+    let err = add_hookable
+        .add_hook_fn(|args: (i32, i32), next| -> i32 { next(args) })
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Hook type mismatch: expected (i64, i64) -> i64, got (i32, i32) -> i32"
+    );
+    let err = add_hookable.call_original::<(u8,), u8>((1,)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Hook type mismatch: expected (i64, i64) -> i64, got (u8,) -> u8"
+    );
+    assert_eq!(mismatch_add(1, 2), 3);
+}
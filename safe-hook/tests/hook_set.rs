@@ -0,0 +1,62 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, HookSet, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let hook = Arc::new(HookAdd { x: 1 });
+    let mut set = HookSet::new();
+    set.add_hook("add", hook.clone(), 0)
+        .add_hook("sub", hook.clone(), 0)
+        .add_hook("add", Arc::new(HookAdd { x: 10 }), 1);
+    assert!(!set.is_installed());
+
+    set.install().unwrap();
+    assert!(set.is_installed());
+    assert_eq!(add(1, 2), 14);
+    assert_eq!(sub(1, 2), 0);
+    // Installing twice does nothing.
+    set.install().unwrap();
+    assert_eq!(add(1, 2), 14);
+
+    set.uninstall().unwrap();
+    assert!(!set.is_installed());
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(sub(1, 2), -1);
+    assert!(lookup_hookable("add").unwrap().list_hooks().is_empty());
+
+    let mut bad_set = HookSet::new();
+    bad_set
+        .add_hook("add", hook.clone(), 0)
+        .add_hook("missing", hook.clone(), 0);
+    assert_eq!(
+        bad_set.install(),
+        Err(AddHookError::NotFound("missing".to_string()))
+    );
+    assert!(!bad_set.is_installed());
+    assert_eq!(add(1, 2), 3);
+}
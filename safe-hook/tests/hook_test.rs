@@ -0,0 +1,51 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::testing::{HookGuard, Stub};
+use safe_hook::{hook_test, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("clock")]
+fn clock() -> u64 {
+    100
+}
+
+#[test]
+fn test_hook_guard_restores_on_panic() {
+    let clock_hookable = lookup_hookable("clock").unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guard = HookGuard::new();
+        clock_hookable
+            .add_hook(Arc::new(Stub::<(), u64>::returning(2)))
+            .unwrap();
+        safe_hook::disable_all().unwrap();
+        panic!("test failed");
+    }));
+    assert!(result.is_err());
+    let _guard = HookGuard::new();
+    assert!(clock_hookable.list_hooks().is_empty());
+    assert!(!safe_hook::is_all_disabled());
+    assert_eq!(clock(), 100);
+}
+
+#[hook_test]
+#[should_panic(expected = "leaves a hook")]
+fn test_hook_test_panics() {
+    lookup_hookable("clock")
+        .unwrap()
+        .add_hook(Arc::new(Stub::<(), u64>::returning(3)))
+        .unwrap();
+    assert_eq!(clock(), 3);
+    panic!("leaves a hook");
+}
+
+#[hook_test]
+fn test_hook_test_leaves_hook() -> Result<(), String> {
+    let clock_hookable = lookup_hookable("clock").unwrap();
+    assert!(clock_hookable.list_hooks().is_empty());
+    clock_hookable
+        .add_hook(Arc::new(Stub::<(), u64>::returning(4)))
+        .map_err(|e| e.to_string())?;
+    assert_eq!(clock(), 4);
+    Ok(())
+}
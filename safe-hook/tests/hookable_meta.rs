@@ -0,0 +1,36 @@
+#![cfg(original_names)]
+
+use safe_hook::{IntoHook, hookable, hookable_meta, lookup_hookable};
+use std::sync::Arc;
+
+mod math {
+    use safe_hook::hookable;
+
+    #[hookable("hookable-meta-mul")]
+    pub fn mul(left: i64, right: i64) -> i64 {
+        left * right
+    }
+}
+
+#[hookable("hookable-meta-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn hookable_meta_by_path() {
+    let meta = hookable_meta!(add).unwrap();
+    assert!(std::ptr::eq(
+        meta,
+        lookup_hookable("hookable-meta-add").unwrap()
+    ));
+    let meta = hookable_meta!(math::mul).unwrap();
+    assert_eq!(meta.name(), "hookable-meta-mul");
+
+    let hook = (|args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 { next(args) + 1 })
+        .into_hook();
+    let id = meta.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(math::mul(2, 3), 7);
+    meta.remove_hook_by_id(id).unwrap();
+    assert_eq!(math::mul(2, 3), 6);
+}
@@ -0,0 +1,87 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{InvokeError, lookup_hookable};
+use safe_hook_macros::hookable_trait;
+use std::collections::HashMap;
+
+#[hookable_trait("users")]
+pub trait UserRepository {
+    fn find(&self, id: u64) -> Option<String>;
+    fn insert(&mut self, id: u64, name: &str);
+    fn count(&self) -> usize {
+        0
+    }
+}
+
+#[hookable_trait]
+trait Clock {
+    fn now(&self) -> u64;
+}
+
+#[derive(Default)]
+struct InMemory {
+    users: HashMap<u64, String>,
+}
+
+impl UserRepository for InMemory {
+    fn find(&self, id: u64) -> Option<String> {
+        self.users.get(&id).cloned()
+    }
+
+    fn insert(&mut self, id: u64, name: &str) {
+        self.users.insert(id, name.to_string());
+    }
+
+    fn count(&self) -> usize {
+        self.users.len()
+    }
+}
+
+struct Fixed;
+
+impl Clock for Fixed {
+    fn now(&self) -> u64 {
+        42
+    }
+}
+
+#[test]
+fn test() {
+    let mut repository: Box<dyn UserRepository> =
+        Box::new(UserRepositoryProxy::new(InMemory::default()));
+    repository.insert(1, "alice");
+    assert_eq!(repository.find(1), Some("alice".to_string()));
+    assert_eq!(repository.count(), 1);
+
+    let find_hookable = lookup_hookable("users::find").unwrap();
+    find_hookable
+        .add_hook_fn(|(id,): (u64,), next| -> Option<String> {
+            next((id,)).or_else(|| Some("guest".to_string()))
+        })
+        .unwrap();
+    let insert_hookable = lookup_hookable("users::insert").unwrap();
+    insert_hookable
+        .add_hook_fn(|(id, name): (u64, &'static str), next| -> () { next((id + 1, name)) })
+        .unwrap();
+    repository.insert(1, "bob");
+    assert_eq!(repository.find(1), Some("alice".to_string()));
+    assert_eq!(repository.find(2), Some("bob".to_string()));
+    assert_eq!(repository.find(3), Some("guest".to_string()));
+    assert_eq!(repository.count(), 2);
+    assert_eq!(
+        find_hookable.invoke(Box::new((1u64,))).err(),
+        Some(InvokeError::NotSupported)
+    );
+    find_hookable.clear_hooks().unwrap();
+    insert_hookable.clear_hooks().unwrap();
+    assert_eq!(repository.find(3), None);
+
+    let clock = ClockProxy::new(Fixed);
+    lookup_hookable("Clock::now")
+        .unwrap()
+        .add_hook_fn(|(): (), next| -> u64 { next(()) + 1 })
+        .unwrap();
+    assert_eq!(clock.now(), 43);
+    assert_eq!(clock.get_ref().now(), 42);
+    assert_eq!(clock.into_inner().now(), 42);
+}
@@ -0,0 +1,56 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{HookExt, InstanceKey, call_info, fn_hook, lookup_hookable};
+use safe_hook_macros::hookable_trait;
+use std::sync::Arc;
+
+#[hookable_trait("instance-counter")]
+trait Counter {
+    fn add(&mut self, n: u32) -> u32;
+}
+
+#[derive(Default)]
+struct Total(u32);
+
+impl Counter for Total {
+    fn add(&mut self, n: u32) -> u32 {
+        self.0 += n;
+        self.0
+    }
+}
+
+#[test]
+fn test() {
+    let mut first = Box::new(CounterProxy::new(Total::default()));
+    let mut second = Box::new(CounterProxy::new(Total::default()));
+    let mut keyed = CounterProxy::with_key(Total::default(), 7);
+    assert_eq!(first.instance_key(), InstanceKey::of(first.as_ref()));
+    assert_ne!(first.instance_key(), second.instance_key());
+    assert_eq!(keyed.instance_key(), InstanceKey::Key(7));
+
+    let add_hookable = lookup_hookable("instance-counter::add").unwrap();
+    let double = fn_hook!(|(n,): (u32,), next| -> u32 { next((n * 2,)) });
+    add_hookable
+        .add_hook(Arc::new(double.for_instance(second.instance_key())))
+        .unwrap();
+    let keyed_hook = fn_hook!(|args: (u32,), next| -> u32 {
+        assert_eq!(call_info().unwrap().instance, Some(InstanceKey::Key(7)));
+        next(args) + 100
+    })
+    .for_instance(7);
+    assert_eq!(keyed_hook.instance(), InstanceKey::Key(7));
+    let keyed_id = add_hookable.add_hook(Arc::new(keyed_hook)).unwrap();
+
+    assert_eq!(first.add(1), 1);
+    assert_eq!(second.add(1), 2);
+    assert_eq!(keyed.add(1), 101);
+    // The key is kept when the proxy is moved.
+    let mut moved = keyed;
+    assert_eq!(moved.add(1), 102);
+
+    add_hookable.remove_hook_by_id(keyed_id).unwrap();
+    assert_eq!(moved.add(1), 3);
+    assert_eq!(second.add(1), 4);
+    add_hookable.clear_hooks().unwrap();
+    assert_eq!(second.add(1), 5);
+}
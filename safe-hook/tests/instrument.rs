@@ -0,0 +1,102 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{FlightRecorder, LoggingHook, SpanHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("parse")]
+fn parse(text: String) -> Result<i32, String> {
+    text.parse()
+        .map_err(|_| format!("invalid number: {}", text))
+}
+
+#[test]
+fn test_logging() {
+    let parse_hookable = lookup_hookable("parse").unwrap();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = lines.clone();
+    let hook = LoggingHook::<(String,), Result<i32, String>>::new("parse")
+        .with_sink(move |line| sink.lock().unwrap().push(line.to_string()));
+    parse_hookable.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(parse("12".to_string()), Ok(12));
+    assert!(parse("x".to_string()).is_err());
+
+    let lines = lines.lock().unwrap();
+    assert!(lines[0].starts_with(r#"parse("12") -> Ok(12) ("#));
+    assert!(lines[1].starts_with(r#"parse("x") -> Err("invalid number: x") ("#));
+    assert_eq!(
+        parse_hookable.list_hooks()[0].description,
+        "LoggingHook(parse)"
+    );
+}
+
+#[hookable("twice")]
+fn twice(x: u64) -> u64 {
+    EVENTS.lock().unwrap().push("call".to_string());
+    x * 2
+}
+
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Span(&'static str);
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        EVENTS.lock().unwrap().push(format!("exit {}", self.0));
+    }
+}
+
+#[test]
+fn test_span() {
+    let twice_hookable = lookup_hookable("twice").unwrap();
+    let hook = SpanHook::<_, u64, _>::new("twice", |name, args: &(u64,)| {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("enter {} {:?}", name, args));
+        Span(name)
+    });
+    twice_hookable.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(twice(4), 8);
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        ["enter twice (4,)", "call", "exit twice"]
+    );
+}
+
+#[hookable("checked_div")]
+fn checked_div(left: i32, right: i32) -> i32 {
+    left / right
+}
+
+#[test]
+fn test_flight_recorder() {
+    let checked_div_hookable = lookup_hookable("checked_div").unwrap();
+    let recorder = Arc::new(FlightRecorder::<(i32, i32), i32>::new(2));
+    checked_div_hookable.add_hook(recorder.clone()).unwrap();
+    assert_eq!(checked_div(6, 3), 2);
+    assert_eq!(checked_div(8, 2), 4);
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| checked_div(1, 0))
+        .unwrap()
+        .join()
+        .unwrap_err();
+
+    let records = recorder.dump();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].args, "(8, 2)");
+    assert_eq!(records[0].result.as_deref(), Some("4"));
+    assert_eq!(records[0].thread, std::thread::current().id());
+    assert_eq!(records[1].args, "(1, 0)");
+    assert_eq!(records[1].result, None);
+    assert_eq!(records[1].thread_name.as_deref(), Some("worker"));
+    assert!(
+        records[1]
+            .to_string()
+            .contains("(worker) (1, 0) -> panicked")
+    );
+
+    recorder.clear();
+    assert!(recorder.dump().is_empty());
+}
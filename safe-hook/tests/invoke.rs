@@ -0,0 +1,78 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, InvokeError, lookup_hookable};
+use safe_hook_macros::hookable;
+
+#[hookable("invoke-concat")]
+fn concat(left: String, right: String) -> String {
+    left + right.as_str()
+}
+
+#[hookable("invoke-unit")]
+fn unit() {}
+
+#[hookable("invoke-borrowed")]
+fn borrowed(text: &str) -> usize {
+    text.len()
+}
+
+#[hookable("invoke-original")]
+fn original(text: &str) -> usize {
+    text.len()
+}
+
+#[test]
+fn test() {
+    let concat_hookable = lookup_hookable("invoke-concat").unwrap();
+    concat_hookable
+        .add_hook_fn(|args: (String, String), next| -> String { next(args).to_uppercase() })
+        .unwrap();
+    let result = concat_hookable
+        .invoke(Box::new(("a".to_string(), "b".to_string())))
+        .unwrap();
+    assert_eq!(*result.downcast::<String>().unwrap(), "AB");
+    assert_eq!(
+        concat_hookable.invoke(Box::new(("a", "b"))).unwrap_err(),
+        InvokeError::ArgsMismatch {
+            expected: "(alloc::string::String, alloc::string::String)"
+        }
+    );
+    concat_hookable.clear_hooks().unwrap();
+    assert_eq!(concat("a".to_string(), "b".to_string()), "ab");
+
+    let result = lookup_hookable("invoke-unit")
+        .unwrap()
+        .invoke(Box::new(()))
+        .unwrap();
+    assert!(result.downcast::<()>().is_ok());
+    unit();
+
+    let err = lookup_hookable("invoke-borrowed")
+        .unwrap()
+        .invoke(Box::new(("abc",)))
+        .unwrap_err();
+    assert_eq!(err, InvokeError::NotSupported);
+    assert_eq!(
+        err.to_string(),
+        "Hookable with references cannot be invoked dynamically"
+    );
+    assert_eq!(borrowed("abc"), 3);
+}
+
+#[test]
+fn test_call_original() {
+    let original_hookable = lookup_hookable("invoke-original").unwrap();
+    original_hookable
+        .add_hook_fn(|args: (&'static str,), next| -> usize { next(args) * 10 })
+        .unwrap();
+    assert_eq!(original("abc"), 30);
+    assert_eq!(
+        original_hookable.call_original::<(&'static str,), usize>(("abc",)),
+        Ok(3)
+    );
+    assert!(matches!(
+        original_hookable.call_original::<(&'static str,), u32>(("abc",)),
+        Err(AddHookError::TypeMismatch { .. })
+    ));
+    original_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,62 @@
+#![cfg(all(feature = "serde", lookup_by_name))]
+
+use safe_hook::{InvokeError, lookup_hookable};
+use safe_hook_macros::hookable;
+use serde_json::json;
+
+#[hookable("json-div", json)]
+fn div(left: i64, right: i64) -> Result<i64, String> {
+    if right == 0 {
+        Err("division by zero".to_string())
+    } else {
+        Ok(left / right)
+    }
+}
+
+#[hookable("json-negate", json)]
+fn negate(x: f64) -> f64 {
+    -x
+}
+
+#[hookable("json-plain")]
+fn plain(x: u8) -> u8 {
+    x
+}
+
+#[test]
+fn test() {
+    let div_hookable = lookup_hookable("json-div").unwrap();
+    assert_eq!(
+        div_hookable.invoke_json(json!([7, 2])),
+        Ok(json!({"Ok": 3}))
+    );
+    assert_eq!(
+        div_hookable.invoke_json(json!([7, 0])),
+        Ok(json!({"Err": "division by zero"}))
+    );
+    assert!(matches!(
+        div_hookable.invoke_json(json!(["7", 2])),
+        Err(InvokeError::Json(_))
+    ));
+    assert!(matches!(
+        div_hookable.invoke_json(json!([7])),
+        Err(InvokeError::Json(_))
+    ));
+    assert_eq!(div(7, 2), Ok(3));
+
+    let negate_hookable = lookup_hookable("json-negate").unwrap();
+    negate_hookable
+        .add_hook_fn(|args: (f64,), next| -> f64 { next(args) * 2.0 })
+        .unwrap();
+    assert_eq!(negate_hookable.invoke_json(json!([1.5])), Ok(json!(-3.0)));
+    negate_hookable.clear_hooks().unwrap();
+    assert_eq!(negate(1.5), -1.5);
+
+    assert_eq!(
+        lookup_hookable("json-plain")
+            .unwrap()
+            .invoke_json(json!([1])),
+        Err(InvokeError::NotSupported)
+    );
+    assert_eq!(plain(1), 1);
+}
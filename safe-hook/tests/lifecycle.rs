@@ -0,0 +1,80 @@
+#![cfg(original_names)]
+
+use safe_hook::{Hook, HookExt, HookTransaction, HookableFuncMetadata, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("attached")]
+fn attached(x: i32) -> i32 {
+    x
+}
+
+#[derive(Default)]
+struct Tracked {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Hook for Tracked {
+    type Args<'a> = (i32,);
+    type Result = i32;
+
+    fn call(&self, args: (i32,), next: &dyn Fn((i32,)) -> i32) -> i32 {
+        next(args) + 1
+    }
+
+    fn on_attach(&self, hookable: &HookableFuncMetadata) {
+        // The hookable is unlocked, so it can be inspected.
+        let count = hookable.list_hooks().len();
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("attach {} {}", hookable.name(), count));
+    }
+
+    fn on_detach(&self, hookable: &HookableFuncMetadata) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("detach {}", hookable.name()));
+    }
+}
+
+#[test]
+fn test() {
+    let attached_hookable = lookup_hookable("attached").unwrap();
+    let hook = Arc::new(Tracked::default());
+    let id = attached_hookable.add_hook(hook.clone()).unwrap();
+    attached_hookable.set_hook_enabled(id, false).unwrap();
+    attached_hookable.set_hook_enabled(id, true).unwrap();
+    assert_eq!(attached(1), 2);
+    attached_hookable.remove_hook_by_id(id).unwrap();
+
+    let mut transaction = HookTransaction::new();
+    transaction.add_hook(attached_hookable, hook.clone(), 0);
+    transaction.add_hook(attached_hookable, hook.clone(), 0);
+    transaction.commit().unwrap();
+    attached_hookable.clear_hooks().unwrap();
+
+    assert_eq!(
+        *hook.log.lock().unwrap(),
+        vec![
+            "attach attached 1",
+            "detach attached",
+            "attach attached 2",
+            "attach attached 2",
+            "detach attached",
+            "detach attached",
+        ]
+    );
+
+    // Wrappers forward the lifecycle methods to the inner hook.
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let once = Tracked { log: log.clone() }.once();
+    attached_hookable.add_hook(Arc::new(once)).unwrap();
+    assert_eq!(attached(1), 2);
+    assert_eq!(attached(1), 1);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["attach attached 1", "detach attached"]
+    );
+}
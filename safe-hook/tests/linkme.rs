@@ -0,0 +1,37 @@
+#![cfg(all(feature = "linkme", lookup_by_name))]
+
+use safe_hook::{HOOKABLES, hookable_trait, iter_hookables, lookup_hookable};
+use safe_hook_macros::hookable;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable_trait("greeter")]
+trait Greeter {
+    fn greet(&self, name: String) -> String;
+}
+
+struct English;
+
+impl Greeter for English {
+    fn greet(&self, name: String) -> String {
+        format!("Hello, {}!", name)
+    }
+}
+
+#[test]
+fn test() {
+    // Both hookable functions and methods are collected in the distributed slice.
+    assert_eq!(HOOKABLES.len(), 2);
+    assert_eq!(iter_hookables().count(), 2);
+    let add_hookable = lookup_hookable("add").unwrap();
+    add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) * 2 })
+        .unwrap();
+    assert_eq!(add(1, 2), 6);
+    assert!(lookup_hookable("greeter::greet").is_some());
+    let greeter = GreeterProxy::new(English);
+    assert_eq!(greeter.greet("linkme".to_string()), "Hello, linkme!");
+}
@@ -0,0 +1,149 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, Priority, TieBreak, iter_hookables, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("mul")]
+fn mul(left: i64, right: i64) -> i64 {
+    left * right
+}
+
+struct HookMul {
+    x: i64,
+}
+
+impl Hook for HookMul {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) * self.x
+    }
+    fn description(&self) -> String {
+        format!("mul by {}", self.x)
+    }
+}
+
+#[hookable("div")]
+fn div(left: i64, right: i64) -> i64 {
+    left / right
+}
+
+#[test]
+fn test_iter_hookables() {
+    let mut names = iter_hookables().map(|h| h.name()).collect::<Vec<_>>();
+    names.sort();
+    #[cfg(not(feature = "hashed-names"))]
+    assert_eq!(names, ["div", "mul", "pow", "rem"]);
+    assert_eq!(names.len(), 4);
+    assert_eq!(div(6, 3), 2);
+}
+
+#[test]
+fn test() {
+    let mul_hookable = lookup_hookable("mul").unwrap();
+    assert!(mul_hookable.list_hooks().is_empty());
+    let id1 = mul_hookable
+        .add_hook_with_priority(Arc::new(HookMul { x: 2 }), 1)
+        .unwrap();
+    let id2 = mul_hookable.add_hook(Arc::new(HookMul { x: 3 })).unwrap();
+    assert_ne!(id1, id2);
+    assert_eq!(mul(1, 2), 12);
+
+    let hooks = mul_hookable.list_hooks();
+    assert_eq!(hooks.len(), 2);
+    assert_eq!((hooks[0].id, hooks[0].priority), (id1, 1));
+    assert_eq!((hooks[1].id, hooks[1].priority), (id2, 0));
+    assert_eq!(hooks[0].description, "mul by 2");
+    assert!(hooks.iter().all(|h| h.enabled));
+
+    assert!(mul_hookable.set_hook_enabled(id1, false).unwrap());
+    assert!(!mul_hookable.list_hooks()[0].enabled);
+    assert_eq!(mul(1, 2), 6);
+    assert!(mul_hookable.set_hook_enabled(id2, false).unwrap());
+    assert_eq!(mul(1, 2), 2);
+    assert!(mul_hookable.set_hook_enabled(id1, true).unwrap());
+    assert_eq!(mul(1, 2), 4);
+
+    mul_hookable.clear_hooks().unwrap();
+    assert!(mul_hookable.list_hooks().is_empty());
+    assert!(!mul_hookable.set_hook_enabled(id1, true).unwrap());
+    assert_eq!(mul(1, 2), 2);
+}
+
+#[test]
+fn test_signature() {
+    let div_hookable = lookup_hookable("div").unwrap();
+    assert_eq!(div_hookable.args_type_name(), "(i64, i64)");
+    assert_eq!(div_hookable.result_type_name(), "i64");
+    #[cfg(not(feature = "hashed-names"))]
+    assert_eq!(div_hookable.signature(), "fn div(i64, i64) -> i64");
+}
+
+struct HookI32;
+
+impl Hook for HookI32 {
+    type Args<'a> = (i32, i32);
+    type Result = i32;
+    fn call(&self, args: (i32, i32), next: &dyn Fn((i32, i32)) -> i32) -> i32 {
+        next(args)
+    }
+}
+
+#[test]
+fn test_type_mismatch() {
+    let div_hookable = lookup_hookable("div").unwrap();
+    let err = div_hookable.add_hook(Arc::new(HookI32)).unwrap_err();
+    assert!(matches!(err, AddHookError::TypeMismatch { .. }));
+    assert!(div_hookable.list_hooks().is_empty());
+}
+
+#[hookable("pow")]
+fn pow(left: i64, right: i64) -> i64 {
+    left.pow(right as u32)
+}
+
+#[test]
+fn test_tier() {
+    let pow_hookable = lookup_hookable("pow").unwrap();
+    let last = pow_hookable
+        .add_hook_with_tier(Arc::new(HookMul { x: 2 }), Priority::Last)
+        .unwrap();
+    let first = pow_hookable
+        .add_hook_with_tier(Arc::new(HookMul { x: 3 }), Priority::First)
+        .unwrap();
+    let normal = pow_hookable.add_hook(Arc::new(HookMul { x: 5 })).unwrap();
+    let early = pow_hookable
+        .add_hook_with_priority(Arc::new(HookMul { x: 7 }), Priority::Early.into())
+        .unwrap();
+    let ids = pow_hookable
+        .list_hooks()
+        .iter()
+        .map(|h| h.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [first, early, normal, last]);
+    assert_eq!(pow(2, 1), 2 * 2 * 3 * 5 * 7);
+}
+
+#[hookable("rem")]
+fn rem(left: i64, right: i64) -> i64 {
+    left % right
+}
+
+#[test]
+fn test_tie_break() {
+    let rem_hookable = lookup_hookable("rem").unwrap();
+    assert_eq!(rem_hookable.tie_break(), TieBreak::Lifo);
+    let a = rem_hookable.add_hook(Arc::new(HookMul { x: 2 })).unwrap();
+    let b = rem_hookable.add_hook(Arc::new(HookMul { x: 3 })).unwrap();
+    rem_hookable.set_tie_break(TieBreak::Fifo).unwrap();
+    assert_eq!(rem_hookable.tie_break(), TieBreak::Fifo);
+    let c = rem_hookable.add_hook(Arc::new(HookMul { x: 5 })).unwrap();
+    let ids = rem_hookable
+        .list_hooks()
+        .iter()
+        .map(|h| h.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [b, a, c]);
+    assert_eq!(rem(5, 3), 2 * 2 * 3 * 5);
+}
@@ -0,0 +1,84 @@
+#![cfg(all(feature = "mlua", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::lua::{Lua, LuaHook};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("lua-factorial")]
+fn factorial(n: u64) -> u64 {
+    if n <= 1 { 1 } else { n * factorial(n - 1) }
+}
+
+#[hookable("lua-square")]
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+#[hookable("lua-identity")]
+fn identity(x: i64) -> i64 {
+    x
+}
+
+const SCRIPT: &str = r#"
+    function add_one(args, next)
+        return next(args) + 1
+    end
+
+    function scale_arg(args, next)
+        args[1] = args[1] * factor
+        return next(args)
+    end
+
+    function fail(args, next)
+        error("failed")
+    end
+
+    function fail_after_next(args, next)
+        next(args)
+        error("failed")
+    end
+
+    function invalid(args, next)
+        return "not a number"
+    end
+"#;
+
+#[test]
+fn test_lua_next() {
+    let meta = lookup_hookable("lua-factorial").unwrap();
+    // Recursive calls nest the functions.
+    let hook = Arc::new(LuaHook::<(u64,), u64>::new(SCRIPT, "add_one").unwrap());
+    meta.add_hook(hook.clone()).unwrap();
+    assert_eq!(factorial(3), ((1 + 1) * 2 + 1) * 3 + 1);
+    assert_eq!(hook.last_error(), None);
+    meta.clear_hooks().unwrap();
+
+    let lua = Lua::new();
+    lua.globals().set("factor", 3).unwrap();
+    let meta = lookup_hookable("lua-identity").unwrap();
+    meta.add_hook(Arc::new(
+        LuaHook::<(i64,), i64>::with_lua(lua, SCRIPT, "scale_arg").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(identity(14), 42);
+    meta.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_lua_errors() {
+    let meta = lookup_hookable("lua-square").unwrap();
+    let fail = Arc::new(LuaHook::<(u64,), u64>::new(SCRIPT, "fail").unwrap());
+    let after_next = Arc::new(LuaHook::<(u64,), u64>::new(SCRIPT, "fail_after_next").unwrap());
+    let invalid = Arc::new(LuaHook::<(u64,), u64>::new(SCRIPT, "invalid").unwrap());
+    for hook in [&fail, &after_next, &invalid] {
+        let id = meta.add_hook(hook.clone()).unwrap();
+        assert_eq!(square(4), 16);
+        assert!(hook.last_error().is_some());
+        meta.remove_hook_by_id(id).unwrap();
+    }
+    assert!(fail.last_error().unwrap().contains("failed"));
+
+    assert!(LuaHook::<(u64,), u64>::new(SCRIPT, "missing").is_err());
+    assert!(LuaHook::<(u64,), u64>::new("function (", "add_one").is_err());
+}
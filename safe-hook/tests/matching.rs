@@ -0,0 +1,59 @@
+#![cfg(original_names)]
+
+use safe_hook::{IntoHook, hookable, lookup_hookables_matching};
+use std::sync::Arc;
+
+#[hookable("storage::read")]
+fn read(key: u64) -> u64 {
+    key
+}
+
+#[hookable("storage::write")]
+fn write(key: u64) -> u64 {
+    key
+}
+
+#[hookable("storage::cache::read")]
+fn cache_read(key: u64) -> u64 {
+    key
+}
+
+#[hookable("network::read")]
+fn network_read(key: u64) -> u64 {
+    key
+}
+
+fn names(pattern: &str) -> Vec<&'static str> {
+    lookup_hookables_matching(pattern)
+        .iter()
+        .map(|meta| meta.name())
+        .collect()
+}
+
+#[test]
+fn glob_patterns() {
+    assert_eq!(
+        names("storage::*"),
+        ["storage::cache::read", "storage::read", "storage::write"]
+    );
+    assert_eq!(
+        names("*::read"),
+        ["network::read", "storage::cache::read", "storage::read"]
+    );
+    assert_eq!(names("storage::?????"), ["storage::write"]);
+    assert_eq!(names("storage::read"), ["storage::read"]);
+    assert!(names("storage::").is_empty());
+}
+
+#[test]
+fn hook_all_matching() {
+    for meta in lookup_hookables_matching("storage::*") {
+        let hook =
+            (|args: (u64,), next: &dyn Fn((u64,)) -> u64| -> u64 { next(args) + 1 }).into_hook();
+        meta.add_hook(Arc::new(hook)).unwrap();
+    }
+    assert_eq!(read(1), 2);
+    assert_eq!(write(1), 2);
+    assert_eq!(cache_read(1), 2);
+    assert_eq!(network_read(1), 1);
+}
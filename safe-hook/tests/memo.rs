@@ -0,0 +1,70 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{MemoHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[hookable("slow_add")]
+fn slow_add(left: i64, right: i64) -> i64 {
+    CALLS.fetch_add(1, Ordering::Relaxed);
+    left + right
+}
+
+static FIB_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[hookable("fib")]
+fn fib(n: u64) -> u64 {
+    FIB_CALLS.fetch_add(1, Ordering::Relaxed);
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+fn calls() -> usize {
+    CALLS.load(Ordering::Relaxed)
+}
+
+#[test]
+fn test_capacity() {
+    let slow_add_hookable = lookup_hookable("slow_add").unwrap();
+    let memo = Arc::new(MemoHook::<(i64, i64), i64>::new().capacity(2));
+    slow_add_hookable.add_hook(memo.clone()).unwrap();
+    assert_eq!(slow_add(1, 2), 3);
+    assert_eq!(slow_add(1, 2), 3);
+    assert_eq!(calls(), 1);
+    assert_eq!(slow_add(2, 3), 5);
+    assert_eq!(slow_add(3, 4), 7);
+    assert_eq!(memo.len(), 2);
+    // (1, 2) is evicted as the oldest.
+    assert_eq!(slow_add(1, 2), 3);
+    assert_eq!(calls(), 4);
+    assert_eq!(slow_add(3, 4), 7);
+    assert_eq!(calls(), 4);
+
+    memo.clear();
+    assert!(memo.is_empty());
+    assert_eq!(slow_add(3, 4), 7);
+    assert_eq!(calls(), 5);
+
+    slow_add_hookable.remove_hook(memo.as_ref()).unwrap();
+    let memo = Arc::new(MemoHook::<(i64, i64), i64>::new().ttl(Duration::from_millis(50)));
+    slow_add_hookable.add_hook(memo.clone()).unwrap();
+    assert_eq!(slow_add(5, 5), 10);
+    assert_eq!(slow_add(5, 5), 10);
+    assert_eq!(calls(), 6);
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(slow_add(5, 5), 10);
+    assert_eq!(calls(), 7);
+}
+
+#[test]
+fn test_recursive() {
+    let fib_hookable = lookup_hookable("fib").unwrap();
+    fib_hookable
+        .add_hook(Arc::new(MemoHook::<(u64,), u64>::new()))
+        .unwrap();
+    assert_eq!(fib(50), 12586269025);
+    assert_eq!(FIB_CALLS.load(Ordering::Relaxed), 51);
+}
@@ -0,0 +1,131 @@
+#![cfg(all(feature = "metrics", original_names))]
+
+use safe_hook::metrics::{MetricsRecorder, PrometheusRecorder, set_recorder};
+use safe_hook::{disable_all, enable_all, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("parse")]
+fn parse(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.parse()
+}
+
+#[derive(Default)]
+struct Recorder {
+    counters: Mutex<Vec<(&'static str, String, u64)>>,
+    histograms: Mutex<Vec<(&'static str, String)>>,
+}
+
+impl MetricsRecorder for Recorder {
+    fn increment_counter(&self, name: &'static str, hookable: &str, value: u64) {
+        self.counters
+            .lock()
+            .unwrap()
+            .push((name, hookable.to_string(), value));
+    }
+    fn record_histogram(&self, name: &'static str, hookable: &str, value: f64) {
+        assert!(value >= 0.0);
+        self.histograms
+            .lock()
+            .unwrap()
+            .push((name, hookable.to_string()));
+    }
+}
+
+#[test]
+fn test() {
+    let recorder = Arc::new(Recorder::default());
+    set_recorder(recorder.clone());
+    let parse_hookable = lookup_hookable("parse").unwrap();
+    assert_eq!(parse("1"), Ok(1));
+    assert!(recorder.counters.lock().unwrap().is_empty());
+
+    parse_hookable.set_metrics_enabled(true);
+    assert!(parse_hookable.metrics_enabled());
+    assert_eq!(parse("2"), Ok(2));
+    assert!(parse("x").is_err());
+    let calls = ("safe_hook_calls_total", "parse".to_string(), 1);
+    let errors = ("safe_hook_errors_total", "parse".to_string(), 1);
+    assert_eq!(
+        *recorder.counters.lock().unwrap(),
+        [calls.clone(), calls.clone(), errors]
+    );
+    assert_eq!(recorder.histograms.lock().unwrap().len(), 2);
+
+    // Hooks stay disabled while metrics are recorded.
+    parse_hookable
+        .add_hook_fn(|_: (&'static str,), _| -> Result<u32, std::num::ParseIntError> { Ok(0) })
+        .unwrap();
+    assert_eq!(parse("3"), Ok(0));
+    disable_all().unwrap();
+    assert_eq!(parse("3"), Ok(3));
+    enable_all().unwrap();
+    assert_eq!(recorder.counters.lock().unwrap().len(), 5);
+
+    parse_hookable.set_metrics_enabled(false);
+    assert_eq!(parse("3"), Ok(0));
+    assert_eq!(recorder.counters.lock().unwrap().len(), 5);
+
+    // Aliases of `Result` count errors too.
+    lookup_hookable("read").unwrap().set_metrics_enabled(true);
+    assert!(read("/nonexistent/safe-hook").is_err());
+    let errors = ("safe_hook_errors_total", "read".to_string(), 1);
+    assert!(recorder.counters.lock().unwrap().contains(&errors));
+}
+
+#[hookable("read")]
+fn read(path: &str) -> std::io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+#[hookable("greet")]
+fn greet(name: &str) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[test]
+fn test_prometheus() {
+    let recorder = PrometheusRecorder::new();
+    recorder.increment_counter("safe_hook_calls_total", "greet", 2);
+    recorder.record_histogram("safe_hook_call_duration_seconds", "greet", 0.0005);
+    recorder.record_histogram("safe_hook_call_duration_seconds", "greet", 20.0);
+    let greet_hookable = lookup_hookable("greet").unwrap();
+    let id = greet_hookable
+        .add_hook_fn(|args: (&'static str,), next| -> String { next(args) })
+        .unwrap();
+    greet_hookable.set_hook_enabled(id, false).unwrap();
+    assert_eq!(greet("Amy"), "Hello, Amy!");
+
+    let text = recorder.render();
+    let expected = [
+        "# TYPE safe_hook_calls_total counter",
+        "safe_hook_calls_total{hookable=\"greet\"} 2",
+        "# TYPE safe_hook_call_duration_seconds histogram",
+        "safe_hook_call_duration_seconds_bucket{hookable=\"greet\",le=\"0.0001\"} 0",
+        "safe_hook_call_duration_seconds_bucket{hookable=\"greet\",le=\"0.001\"} 1",
+        "safe_hook_call_duration_seconds_bucket{hookable=\"greet\",le=\"10\"} 1",
+        "safe_hook_call_duration_seconds_bucket{hookable=\"greet\",le=\"+Inf\"} 2",
+        "safe_hook_call_duration_seconds_sum{hookable=\"greet\"} 20.0005",
+        "safe_hook_call_duration_seconds_count{hookable=\"greet\"} 2",
+        "# TYPE safe_hook_hooks_attached gauge",
+        "safe_hook_hooks_attached{hookable=\"greet\"} 1",
+        "# TYPE safe_hook_hook_enabled gauge",
+    ];
+    for line in expected {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing {}\n{}",
+            line,
+            text
+        );
+    }
+    let enabled = format!(",id=\"{}\"}} 0", id.as_u64());
+    assert!(
+        text.lines().any(
+            |l| l.starts_with("safe_hook_hook_enabled{hookable=\"greet\",hook=\"")
+                && l.ends_with(&enabled)
+        ),
+        "{}",
+        text
+    );
+}
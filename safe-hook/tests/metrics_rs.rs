@@ -0,0 +1,53 @@
+#![cfg(all(feature = "metrics-rs", original_names))]
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use safe_hook::lookup_hookable;
+use safe_hook::metrics::{FacadeRecorder, set_recorder};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("facade-parse")]
+fn parse(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.parse()
+}
+
+#[test]
+fn test() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+    set_recorder(Arc::new(FacadeRecorder));
+    lookup_hookable("facade-parse")
+        .unwrap()
+        .set_metrics_enabled(true);
+    assert_eq!(parse("1"), Ok(1));
+    assert!(parse("x").is_err());
+
+    let mut counters = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter_map(|(key, _, _, value)| {
+            let (_, key) = key.into_parts();
+            let label = key.labels().next().unwrap();
+            assert_eq!((label.key(), label.value()), ("hookable", "facade-parse"));
+            match value {
+                DebugValue::Counter(count) => Some((key.name().to_string(), count)),
+                DebugValue::Histogram(values) => {
+                    assert_eq!(key.name(), "safe_hook_call_duration_seconds");
+                    assert_eq!(values.len(), 2);
+                    None
+                }
+                DebugValue::Gauge(_) => panic!("Unexpected gauge"),
+            }
+        })
+        .collect::<Vec<_>>();
+    counters.sort();
+    assert_eq!(
+        counters,
+        [
+            ("safe_hook_calls_total".to_string(), 2),
+            ("safe_hook_errors_total".to_string(), 1)
+        ]
+    );
+}
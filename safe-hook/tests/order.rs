@@ -0,0 +1,81 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("concat")]
+fn concat(left: i64, right: i64) -> i64 {
+    left * 10 + right
+}
+
+struct HookDigit {
+    name: &'static str,
+    digit: i64,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+impl Hook for HookDigit {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) * 10 + self.digit
+    }
+    fn description(&self) -> String {
+        self.name.to_string()
+    }
+    fn runs_before(&self) -> Vec<String> {
+        self.before.clone()
+    }
+    fn runs_after(&self) -> Vec<String> {
+        self.after.clone()
+    }
+}
+
+fn digit(name: &'static str, digit: i64, before: &[&str], after: &[&str]) -> Arc<HookDigit> {
+    Arc::new(HookDigit {
+        name,
+        digit,
+        before: before.iter().map(|s| s.to_string()).collect(),
+        after: after.iter().map(|s| s.to_string()).collect(),
+    })
+}
+
+fn names() -> Vec<String> {
+    lookup_hookable("concat")
+        .unwrap()
+        .list_hooks()
+        .into_iter()
+        .map(|h| h.description)
+        .collect()
+}
+
+#[test]
+fn test() {
+    let concat_hookable = lookup_hookable("concat").unwrap();
+    concat_hookable
+        .add_hook_with_priority(digit("metrics", 3, &[], &[]), 10)
+        .unwrap();
+    // Lower priority, but must run before metrics.
+    concat_hookable
+        .add_hook_with_priority(digit("tracing", 4, &["metrics"], &[]), 0)
+        .unwrap();
+    assert_eq!(names(), ["tracing", "metrics"]);
+    // Higher priority, but must run after auth, which is not attached yet.
+    concat_hookable
+        .add_hook_with_priority(digit("cache", 5, &[], &["auth"]), 20)
+        .unwrap();
+    assert_eq!(names(), ["cache", "tracing", "metrics"]);
+    concat_hookable
+        .add_hook(digit("auth", 6, &[], &[]))
+        .unwrap();
+    assert_eq!(names(), ["auth", "cache", "tracing", "metrics"]);
+    assert_eq!(concat(1, 2), 123456);
+
+    assert_eq!(
+        concat_hookable.add_hook(digit("loop", 7, &["auth"], &["cache"])),
+        Err(AddHookError::OrderCycle)
+    );
+    assert_eq!(names(), ["auth", "cache", "tracing", "metrics"]);
+}
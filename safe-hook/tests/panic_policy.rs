@@ -0,0 +1,86 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, PanicPolicy, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::panic::catch_unwind;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[hookable("panic-policy-add")]
+fn add(left: i64, right: i64) -> i64 {
+    if left < 0 {
+        panic!("negative");
+    }
+    left + right
+}
+
+struct NotClone(u8);
+
+#[hookable("panic-policy-not-clone")]
+fn not_clone(x: NotClone) -> u8 {
+    x.0
+}
+
+#[test]
+fn test_skip() {
+    let add_hookable = lookup_hookable("panic-policy-add").unwrap();
+    assert_eq!(add_hookable.panic_policy(), PanicPolicy::Propagate);
+    add_hookable.set_panic_policy(PanicPolicy::Skip).unwrap();
+    assert_eq!(add_hookable.panic_policy(), PanicPolicy::Skip);
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    // Called last, it sees the arguments before the faulty hook changed them.
+    add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            next(args) * 10
+        })
+        .unwrap();
+    add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 {
+            let result = next((args.0 + 100, args.1));
+            if args.1 == 0 {
+                panic!("faulty hook");
+            }
+            result
+        })
+        .unwrap();
+    add_hookable
+        .add_hook_fn(|args: (i64, i64), next| -> i64 { next(args) + 1 })
+        .unwrap();
+
+    assert_eq!(add(1, 2), 1031);
+    assert_eq!(add(1, 0), 11);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+    assert_eq!(add_hookable.list_hooks().len(), 3);
+
+    // Panics of the original function are not taken for panics of the hooks.
+    assert!(catch_unwind(|| add(-200, 2)).is_err());
+    assert_eq!(CALLS.load(Ordering::Relaxed), 4);
+    assert_eq!(add_hookable.list_hooks().len(), 3);
+
+    add_hookable.clear_hooks().unwrap();
+    add_hookable
+        .set_panic_policy(PanicPolicy::Propagate)
+        .unwrap();
+    add_hookable
+        .add_hook_fn(|_: (i64, i64), _| -> i64 { panic!("faulty hook") })
+        .unwrap();
+    assert!(catch_unwind(|| add(1, 2)).is_err());
+    add_hookable.set_panic_policy(PanicPolicy::Detach).unwrap();
+    assert_eq!(add(1, 2), 3);
+    assert!(add_hookable.list_hooks().is_empty());
+    add_hookable
+        .set_panic_policy(PanicPolicy::Propagate)
+        .unwrap();
+}
+
+#[test]
+fn test_not_clone() {
+    let not_clone_hookable = lookup_hookable("panic-policy-not-clone").unwrap();
+    assert_eq!(
+        not_clone_hookable.set_panic_policy(PanicPolicy::Skip),
+        Err(AddHookError::ArgsNotClone)
+    );
+    assert_eq!(not_clone_hookable.panic_policy(), PanicPolicy::Propagate);
+    assert_eq!(not_clone(NotClone(1)), 1);
+}
@@ -0,0 +1,76 @@
+#![cfg(original_names)]
+
+use safe_hook::{IntoHook, lookup_hookable, panic_report};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("panic-report-outer")]
+fn outer(x: u8) -> u8 {
+    inner(x)
+}
+
+#[hookable("panic-report-inner")]
+fn inner(x: u8) -> u8 {
+    x
+}
+
+static CAPTURED: Mutex<Option<String>> = Mutex::new(None);
+
+#[test]
+fn test() {
+    let outer_hookable = lookup_hookable("panic-report-outer").unwrap();
+    let inner_hookable = lookup_hookable("panic-report-inner").unwrap();
+    outer_hookable
+        .add_hook_fn(|args: (u8,), next| -> u8 { next(args) })
+        .unwrap();
+    // Calls are not tracked until the report is installed.
+    let before = inner_hookable
+        .add_hook_fn(|args: (u8,), next| -> u8 {
+            *CAPTURED.lock().unwrap() = panic_report::report();
+            next(args)
+        })
+        .unwrap();
+    assert_eq!(outer(1), 1);
+    assert_eq!(*CAPTURED.lock().unwrap(), None);
+    inner_hookable.remove_hook_by_id(before).unwrap();
+
+    panic_report::install();
+    panic_report::install();
+    let id = inner_hookable
+        .add_hook_fn(|args: (u8,), next| -> u8 {
+            *CAPTURED.lock().unwrap() = panic_report::report();
+            if args.0 == 0 {
+                panic!("zero");
+            }
+            next(args)
+        })
+        .unwrap();
+    let last = (|args: (u8,), next: &dyn Fn((u8,)) -> u8| -> u8 { next(args) }).into_hook();
+    let last_id = inner_hookable
+        .add_hook_with_priority(Arc::new(last), -1)
+        .unwrap();
+    inner_hookable.set_hook_enabled(last_id, false).unwrap();
+
+    assert_eq!(outer(1), 1);
+    let report = CAPTURED.lock().unwrap().take().unwrap();
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "safe-hook: the thread was calling hookable functions, innermost first:"
+    );
+    assert_eq!(lines[1], "  fn panic-report-inner(u8) -> u8");
+    assert!(lines[2].starts_with(&format!("    {:?} priority 0: ", id)));
+    assert!(lines[3].starts_with(&format!("    {:?} priority -1: ", last_id)));
+    assert!(lines[3].ends_with(" (disabled)"));
+    assert_eq!(lines[4], "  fn panic-report-outer(u8) -> u8");
+    assert_eq!(lines.len(), 6);
+    assert!(panic_report::report().is_none());
+
+    // The active functions are removed while unwinding.
+    assert!(std::panic::catch_unwind(|| outer(0)).is_err());
+    assert!(CAPTURED.lock().unwrap().is_some());
+    assert!(panic_report::report().is_none());
+
+    outer_hookable.clear_hooks().unwrap();
+    inner_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,65 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, HookableFuncMetadata, IntoHook, add_hook_pending, attach_pending,
+    lookup_hookable, pending_errors, pending_hooks, remove_pending,
+};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("present")]
+fn present(x: u32) -> u32 {
+    x
+}
+
+#[test]
+fn test() {
+    let double = Arc::new(
+        (|args: (u32,), next: &dyn Fn((u32,)) -> u32| -> u32 { next(args) * 2 }).into_hook(),
+    );
+    let id = add_hook_pending("present", double.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(present(2), 4);
+    lookup_hookable("present")
+        .unwrap()
+        .remove_hook_by_id(id)
+        .unwrap();
+
+    assert_eq!(add_hook_pending("absent", double.clone()).unwrap(), None);
+    assert_eq!(
+        add_hook_pending("absent-too", double.clone()).unwrap(),
+        None
+    );
+    assert_eq!(pending_hooks(), vec!["absent", "absent-too"]);
+    assert!(lookup_hookable("absent").is_none());
+    assert!(attach_pending().is_empty());
+    assert_eq!(pending_hooks(), vec!["absent", "absent-too"]);
+    assert!(remove_pending(double.as_ref()).unwrap());
+    assert_eq!(pending_hooks(), vec!["absent-too"]);
+    assert!(remove_pending(double.as_ref()).unwrap());
+    assert!(!remove_pending(double.as_ref()).unwrap());
+    assert!(pending_hooks().is_empty());
+    assert_eq!(present(2), 2);
+
+    // Hooks failing to attach stay held with their error.
+    assert_eq!(
+        add_hook_pending("pending-text", double.clone()).unwrap(),
+        None
+    );
+    assert!(pending_errors().is_empty());
+    let text = HookableFuncMetadata::builder("pending-text", |(text,): (String,)| text)
+        .register()
+        .unwrap();
+    assert_eq!(text.call(("a".to_string(),)), "a");
+    assert_eq!(pending_hooks(), vec!["pending-text"]);
+    let errors = pending_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "pending-text");
+    assert!(matches!(errors[0].1, AddHookError::TypeMismatch { .. }));
+    let failed = attach_pending();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(pending_errors(), failed);
+    assert!(remove_pending(double.as_ref()).unwrap());
+    assert!(pending_errors().is_empty());
+}
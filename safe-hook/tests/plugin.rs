@@ -0,0 +1,125 @@
+#![cfg(all(feature = "plugin", unix, original_names))]
+
+use safe_hook::plugin::{
+    self, PLUGIN_VERSION, Plugin, PluginContext, PluginError, RawPluginEntry, RawPluginVersion,
+    VERSION_SYMBOL, detach_all_from,
+};
+use safe_hook::{HookDyn, export_plugin, fn_hook};
+use safe_hook_macros::hookable;
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[hookable("plugin-damage")]
+fn damage(base: u32) -> u32 {
+    base
+}
+
+#[hookable("plugin-heal")]
+fn heal(base: u32) -> u32 {
+    base
+}
+
+/// A reference to a hook of the plugin, kept by the host.
+static KEPT: Mutex<Option<Arc<dyn HookDyn>>> = Mutex::new(None);
+
+fn init(context: &mut PluginContext) {
+    assert_eq!(context.name(), "double-damage");
+    assert!(
+        context
+            .hookables()
+            .iter()
+            .any(|hookable| hookable.name() == "plugin-damage")
+    );
+    let target = context.lookup_hookable("plugin-damage").unwrap();
+    let hook = fn_hook!(|(base,): (u32,), next| -> u32 { next((base * 2,)) });
+    context.add_hook(target, Arc::new(hook), 0).unwrap();
+}
+
+fn init_heal(context: &mut PluginContext) {
+    let target = context.lookup_hookable("plugin-heal").unwrap();
+    let hook: Arc<dyn HookDyn> = Arc::new(fn_hook!(|(base,): (u32,), next| -> u32 {
+        next((base + 1,))
+    }));
+    *KEPT.lock().unwrap() = Some(hook.clone());
+    context.add_hook(target, hook, 0).unwrap();
+}
+
+#[test]
+fn test_from_entry() {
+    let mut plugin = Plugin::from_entry("double-damage", init);
+    assert_eq!(plugin.name(), "double-damage");
+    assert_eq!(plugin.path(), None);
+    assert_eq!(plugin.hooks().len(), 1);
+    assert_eq!(plugin.hooks()[0].0.name(), "plugin-damage");
+    assert_eq!(damage(10), 20);
+    assert!(!plugin.is_quiescent());
+
+    assert_eq!(detach_all_from(&mut plugin), 1);
+    assert_eq!(detach_all_from(&mut plugin), 0);
+    assert_eq!(damage(10), 10);
+    unsafe { plugin.unload(Duration::from_secs(10)) }.unwrap();
+    assert!(plugin.is_quiescent());
+}
+
+#[test]
+fn test_unload_busy() {
+    let mut plugin = Plugin::from_entry("heal", init_heal);
+    assert_eq!(heal(1), 2);
+    assert_eq!(
+        unsafe { plugin.unload(Duration::from_millis(10)) },
+        Err(PluginError::Busy)
+    );
+    assert_eq!(heal(1), 1);
+    KEPT.lock().unwrap().take();
+    unsafe { plugin.unload(Duration::from_secs(10)) }.unwrap();
+}
+
+#[test]
+fn test_load_errors() {
+    let error = unsafe { plugin::load("/nonexistent/libplugin.so") }.unwrap_err();
+    assert!(matches!(error, PluginError::Open(_)));
+    // A library which is not a plugin, opening it may fail on systems without glibc.
+    match unsafe { plugin::load("libc.so.6") } {
+        Ok(plugin) => panic!("libc loaded as a plugin: {:?}", plugin),
+        Err(error) => assert!(matches!(
+            error,
+            PluginError::MissingSymbol(VERSION_SYMBOL) | PluginError::Open(_)
+        )),
+    }
+}
+
+#[test]
+fn test_version() {
+    assert!(PLUGIN_VERSION.starts_with(env!("CARGO_PKG_VERSION")));
+    assert!(PLUGIN_VERSION.contains("rustc "));
+    assert!(PLUGIN_VERSION.contains("plugin"));
+}
+
+#[hookable("plugin-armor")]
+fn armor(base: u32) -> u32 {
+    base
+}
+
+fn init_armor(context: &mut PluginContext) {
+    let target = context.lookup_hookable("plugin-armor").unwrap();
+    let hook = fn_hook!(|(base,): (u32,), next| -> u32 { next((base + 5,)) });
+    context.add_hook(target, Arc::new(hook), 0).unwrap();
+}
+
+export_plugin!(init_armor);
+
+/// The exported symbols, with the types the host loads them as.
+const ENTRY: RawPluginEntry = safe_hook_plugin_entry;
+const VERSION: RawPluginVersion = safe_hook_plugin_version;
+
+#[test]
+fn test_exported_symbols() {
+    let version = unsafe { CStr::from_ptr(VERSION()) };
+    assert_eq!(version.to_str().unwrap(), PLUGIN_VERSION);
+
+    let mut plugin = Plugin::from_entry("armor", |context| unsafe { ENTRY(context) });
+    assert_eq!(armor(1), 6);
+    unsafe { plugin.unload(Duration::from_secs(10)) }.unwrap();
+    assert_eq!(armor(1), 1);
+}
@@ -0,0 +1,118 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, Post, PostHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("greet")]
+fn greet(name: &str, times: usize) -> String {
+    format!("Hello, {}!", name).repeat(times)
+}
+
+#[derive(Default)]
+struct HookLog {
+    lines: Mutex<Vec<String>>,
+}
+
+impl PostHook for HookLog {
+    type Args<'a> = (&'a str, usize);
+    type Result = String;
+    fn after(&self, args: &(&str, usize), result: &String) {
+        self.lines
+            .lock()
+            .unwrap()
+            .push(format!("greet({}, {}) = {}", args.0, args.1, result));
+    }
+}
+
+struct HookShout;
+
+impl Hook for HookShout {
+    type Args<'a> = (&'a str, usize);
+    type Result = String;
+    fn call(&self, args: (&str, usize), next: &dyn Fn((&str, usize)) -> String) -> String {
+        next(args).to_uppercase()
+    }
+}
+
+#[test]
+fn test() {
+    let greet_hookable = lookup_hookable("greet").unwrap();
+    let log = Arc::new(Post(HookLog::default()));
+    greet_hookable
+        .add_hook_with_priority(log.clone(), 1)
+        .unwrap();
+    assert_eq!(greet("Bob", 1), "Hello, Bob!");
+
+    // The observer sees the result of the rest of the chain.
+    greet_hookable.add_hook(Arc::new(HookShout)).unwrap();
+    assert_eq!(greet("Amy", 2), "HELLO, AMY!HELLO, AMY!");
+
+    assert_eq!(
+        *log.0.lines.lock().unwrap(),
+        [
+            "greet(Bob, 1) = Hello, Bob!",
+            "greet(Amy, 2) = HELLO, AMY!HELLO, AMY!"
+        ]
+    );
+}
+
+#[hookable("len")]
+fn len(text: String) -> usize {
+    text.len()
+}
+
+struct HookRecord {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl PostHook for HookRecord {
+    type Args<'a> = (String,);
+    type Result = usize;
+    fn after(&self, args: &(String,), result: &usize) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{}: {} {}", self.name, args.0, result));
+    }
+}
+
+struct HookTrim;
+
+impl Hook for HookTrim {
+    type Args<'a> = (String,);
+    type Result = usize;
+    fn call(&self, args: (String,), next: &dyn Fn((String,)) -> usize) -> usize {
+        next((args.0.trim().to_string(),))
+    }
+}
+
+#[test]
+fn test_observers_only() {
+    let len_hookable = lookup_hookable("len").unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let record = |name| {
+        Arc::new(Post(HookRecord {
+            name,
+            calls: calls.clone(),
+        }))
+    };
+    len_hookable
+        .add_hook_with_priority(record("outer"), 1)
+        .unwrap();
+    len_hookable.add_hook(record("inner")).unwrap();
+    assert_eq!(len(" ab ".to_string()), 4);
+    // Called in the same order as a nested chain.
+    assert_eq!(*calls.lock().unwrap(), ["inner:  ab  4", "outer:  ab  4"]);
+
+    calls.lock().unwrap().clear();
+    let trim = len_hookable.add_hook(Arc::new(HookTrim)).unwrap();
+    assert_eq!(len(" ab ".to_string()), 2);
+    assert_eq!(*calls.lock().unwrap(), ["inner: ab 2", "outer:  ab  2"]);
+
+    calls.lock().unwrap().clear();
+    len_hookable.remove_hook_by_id(trim).unwrap();
+    assert_eq!(len(" ab ".to_string()), 4);
+    assert_eq!(*calls.lock().unwrap(), ["inner:  ab  4", "outer:  ab  4"]);
+}
@@ -0,0 +1,70 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Pre, PreHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[hookable("square")]
+fn square(x: i64) -> i64 {
+    CALLS.fetch_add(1, Ordering::Relaxed);
+    x * x
+}
+
+struct HookCache {
+    key: i64,
+    value: i64,
+}
+
+impl PreHook for HookCache {
+    type Args<'a> = (i64,);
+    type Result = i64;
+    fn before(&self, args: (i64,)) -> ControlFlow<i64, (i64,)> {
+        if args.0 == self.key {
+            ControlFlow::Break(self.value)
+        } else {
+            ControlFlow::Continue(args)
+        }
+    }
+}
+
+struct HookAbs;
+
+impl PreHook for HookAbs {
+    type Args<'a> = (i64,);
+    type Result = i64;
+    fn before(&self, args: (i64,)) -> ControlFlow<i64, (i64,)> {
+        ControlFlow::Continue((args.0.abs(),))
+    }
+    fn description(&self) -> String {
+        "abs".to_string()
+    }
+}
+
+#[test]
+fn test() {
+    let square_hookable = lookup_hookable("square").unwrap();
+    square_hookable
+        .add_hook_with_priority(Arc::new(Pre(HookCache { key: 3, value: 9 })), 1)
+        .unwrap();
+    square_hookable.add_hook(Arc::new(Pre(HookAbs))).unwrap();
+
+    assert_eq!(square(3), 9);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+    assert_eq!(square(-4), 16);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    // The cache runs first, so -3 is not a hit.
+    assert_eq!(square(-3), 9);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+    let descriptions = square_hookable
+        .list_hooks()
+        .into_iter()
+        .map(|h| h.description)
+        .collect::<Vec<_>>();
+    assert_eq!(descriptions[1], "abs");
+    assert!(descriptions[0].ends_with("HookCache"));
+}
@@ -0,0 +1,150 @@
+#![cfg(all(feature = "process", original_names))]
+
+use safe_hook::process::ProcessHook;
+use safe_hook::{Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[hookable("process-double")]
+fn double(x: i64) -> i64 {
+    x * 2
+}
+
+#[hookable("process-slow")]
+fn slow(x: i64) -> i64 {
+    x
+}
+
+#[hookable("process-missing")]
+fn missing(x: i64) -> i64 {
+    x
+}
+
+fn python(script: &str) -> Option<Command> {
+    Command::new("python3").arg("--version").output().ok()?;
+    let mut command = Command::new("python3");
+    command.arg("-c").arg(script);
+    Some(command)
+}
+
+#[test]
+fn test_process() {
+    let Some(command) = python(
+        r#"
+import json, sys
+for line in sys.stdin:
+    request = json.loads(line)
+    assert request["call"] == "process-double"
+    x, = request["args"]
+    if x < 0:
+        print("not json", flush=True)
+        continue
+    print(json.dumps({"next": [x + 1]}), flush=True)
+    result = json.loads(sys.stdin.readline())["result"]
+    print(json.dumps({"return": result + 1000}), flush=True)
+"#,
+    ) else {
+        return;
+    };
+    let hook = Arc::new(ProcessHook::<(i64,), i64>::new(command));
+    lookup_hookable("process-double")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+    assert_eq!(double(1), 1004);
+    assert_eq!(double(2), 1006);
+    assert_eq!(hook.last_error(), None);
+    // Invalid answers are ignored, and the process is restarted.
+    assert_eq!(double(-1), -2);
+    assert!(hook.last_error().unwrap().starts_with("invalid message"));
+    assert_eq!(double(1), 1004);
+}
+
+#[test]
+fn test_timeout() {
+    let Some(command) = python(
+        r#"
+import json, sys, time
+for line in sys.stdin:
+    x, = json.loads(line)["args"]
+    print(json.dumps({"next": [x + 1]}), flush=True)
+    sys.stdin.readline()
+    time.sleep(10)
+"#,
+    ) else {
+        return;
+    };
+    let hook = ProcessHook::<(i64,), i64>::new(command).timeout(Duration::from_millis(200));
+    let hook = Arc::new(hook);
+    lookup_hookable("process-slow")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+    // The result of the chain is kept when the process times out after calling it.
+    assert_eq!(slow(1), 2);
+    assert_eq!(hook.last_error().unwrap(), "the process timed out");
+}
+
+#[test]
+fn test_spawn_error() {
+    let hook = Arc::new(ProcessHook::<(i64,), i64>::new(Command::new(
+        "/nonexistent/safe-hook-script",
+    )));
+    assert_eq!(
+        hook.description(),
+        "ProcessHook(/nonexistent/safe-hook-script)"
+    );
+    lookup_hookable("process-missing")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+    assert_eq!(missing(1), 1);
+    assert!(
+        hook.last_error()
+            .unwrap()
+            .starts_with("cannot start the process")
+    );
+}
+
+#[hookable("process-python")]
+fn checkout(_user: String, price: u32) -> u32 {
+    price + 1
+}
+
+#[test]
+fn test_python() {
+    if python("").is_none() {
+        return;
+    }
+    let script = std::env::temp_dir().join(format!("safe_hook_{}.py", std::process::id()));
+    std::fs::write(
+        &script,
+        r#"
+def vip_discount(next, user, price):
+    print("printed to stderr")
+    if user == "error":
+        raise ValueError(user)
+    price = next(user, price)
+    return price // 2 if user == "vip" else price
+"#,
+    )
+    .unwrap();
+    let hook = Arc::new(ProcessHook::<(String, u32), u32>::python(
+        &script,
+        "vip_discount",
+    ));
+    lookup_hookable("process-python")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+    assert_eq!(checkout("vip".to_string(), 99), 50);
+    assert_eq!(checkout("guest".to_string(), 99), 100);
+    assert_eq!(hook.last_error(), None);
+    assert_eq!(checkout("error".to_string(), 99), 100);
+    assert_eq!(hook.last_error().unwrap(), "ValueError('error')");
+    // The process is kept after errors reported by the script.
+    assert_eq!(checkout("vip".to_string(), 99), 50);
+    std::fs::remove_file(script).unwrap();
+}
@@ -0,0 +1,103 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    Hook, HookSet, HookableFuncMetadata, ProfileError, active_profiles, disable_profile,
+    enable_profile, is_profile_active, register_profile, registered_profiles, unregister_profile,
+};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+/// Enables the profile "inner" while attached.
+struct EnablesInner;
+
+impl Hook for EnablesInner {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args)
+    }
+    fn on_attach(&self, _: &HookableFuncMetadata) {
+        enable_profile("inner").unwrap();
+    }
+    fn on_detach(&self, _: &HookableFuncMetadata) {
+        disable_profile("inner").unwrap();
+    }
+}
+
+#[test]
+fn test() {
+    let mut debug = HookSet::new();
+    debug
+        .add_hook("add", Arc::new(HookAdd { x: 1 }), 0)
+        .add_hook("sub", Arc::new(HookAdd { x: 1 }), 0);
+    register_profile("debug", debug).unwrap();
+    let mut chaos = HookSet::new();
+    chaos.add_hook("add", Arc::new(HookAdd { x: 100 }), 0);
+    register_profile("chaos", chaos).unwrap();
+    assert_eq!(registered_profiles(), ["chaos", "debug"]);
+    assert!(active_profiles().is_empty());
+
+    enable_profile("debug").unwrap();
+    assert!(is_profile_active("debug"));
+    assert_eq!(add(1, 2), 4);
+    assert_eq!(sub(1, 2), 0);
+    enable_profile("chaos").unwrap();
+    assert_eq!(active_profiles(), ["chaos", "debug"]);
+    assert_eq!(add(1, 2), 104);
+
+    disable_profile("debug").unwrap();
+    assert!(!is_profile_active("debug"));
+    assert_eq!(active_profiles(), ["chaos"]);
+    assert_eq!(add(1, 2), 103);
+    assert_eq!(sub(1, 2), -1);
+
+    assert!(unregister_profile("chaos").unwrap().is_some());
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(registered_profiles(), ["debug"]);
+
+    assert_eq!(
+        enable_profile("missing"),
+        Err(ProfileError::NotFound("missing".to_string()))
+    );
+    assert_eq!(
+        disable_profile("missing"),
+        Err(ProfileError::NotFound("missing".to_string()))
+    );
+    assert!(unregister_profile("missing").unwrap().is_none());
+
+    // Hooks can use profiles when they are attached or detached by a profile.
+    let mut inner = HookSet::new();
+    inner.add_hook("add", Arc::new(HookAdd { x: 10 }), 0);
+    register_profile("inner", inner).unwrap();
+    let mut outer = HookSet::new();
+    outer.add_hook("sub", Arc::new(EnablesInner), 0);
+    register_profile("outer", outer).unwrap();
+    enable_profile("outer").unwrap();
+    assert_eq!(active_profiles(), ["inner", "outer"]);
+    assert_eq!(add(1, 2), 13);
+    disable_profile("outer").unwrap();
+    assert!(active_profiles().is_empty());
+    assert_eq!(add(1, 2), 3);
+}
@@ -0,0 +1,110 @@
+#![cfg(all(feature = "pyo3", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::python::PyHook;
+use safe_hook::python::pyo3::prelude::*;
+use safe_hook_macros::hookable;
+use std::sync::{Arc, OnceLock};
+
+#[hookable("python-factorial")]
+fn factorial(n: u64) -> u64 {
+    if n <= 1 { 1 } else { n * factorial(n - 1) }
+}
+
+#[hookable("python-square")]
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+#[hookable("python-join")]
+fn join(items: Vec<String>, separator: String) -> String {
+    items.join(&separator)
+}
+
+const SCRIPT: &std::ffi::CStr = cr#"
+kept = []
+
+def add_one(next, n):
+    return next(n) + 1
+
+def upper(next, items, separator):
+    return next([item.upper() for item in items], separator)
+
+def fail(next, x):
+    raise ValueError("failed")
+
+def fail_after_next(next, x):
+    next(x)
+    raise ValueError("failed")
+
+def invalid(next, x):
+    return "not a number"
+
+def keep_next(next, x):
+    kept.append(next)
+    return next(x)
+
+def call_kept(next, x):
+    return kept[0](x)
+"#;
+
+fn hook<A, R>(function: &str) -> Arc<PyHook<A, R>> {
+    static MODULE: OnceLock<Py<PyModule>> = OnceLock::new();
+    Python::initialize();
+    Python::attach(|py| {
+        let module = MODULE.get_or_init(|| {
+            PyModule::from_code(py, SCRIPT, c"hooks.py", c"hooks")
+                .unwrap()
+                .unbind()
+        });
+        Arc::new(PyHook::new(&module.bind(py).getattr(function).unwrap()))
+    })
+}
+
+#[test]
+fn test_python_next() {
+    let meta = lookup_hookable("python-factorial").unwrap();
+    // Recursive calls nest the callables.
+    let add_one = hook::<(u64,), u64>("add_one");
+    meta.add_hook(add_one.clone()).unwrap();
+    assert_eq!(factorial(3), ((1 + 1) * 2 + 1) * 3 + 1);
+    assert_eq!(add_one.last_error(), None);
+    meta.clear_hooks().unwrap();
+
+    let meta = lookup_hookable("python-join").unwrap();
+    meta.add_hook(hook::<(Vec<String>, String), String>("upper"))
+        .unwrap();
+    assert_eq!(join(vec!["a".into(), "b".into()], "-".into()), "A-B");
+    meta.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_python_errors() {
+    let meta = lookup_hookable("python-square").unwrap();
+    let fail = hook::<(u64,), u64>("fail");
+    let after_next = hook::<(u64,), u64>("fail_after_next");
+    let invalid = hook::<(u64,), u64>("invalid");
+    for hook in [&fail, &after_next, &invalid] {
+        let id = meta.add_hook(hook.clone()).unwrap();
+        assert_eq!(square(4), 16);
+        assert!(hook.last_error().is_some());
+        meta.remove_hook_by_id(id).unwrap();
+    }
+    assert!(fail.last_error().unwrap().contains("ValueError: failed"));
+
+    // `next` cannot be used after the call returned.
+    let keep = hook::<(u64,), u64>("keep_next");
+    let id = meta.add_hook(keep).unwrap();
+    assert_eq!(square(2), 4);
+    meta.remove_hook_by_id(id).unwrap();
+    let call_kept = hook::<(u64,), u64>("call_kept");
+    meta.add_hook(call_kept.clone()).unwrap();
+    assert_eq!(square(3), 9);
+    assert!(
+        call_kept
+            .last_error()
+            .unwrap()
+            .contains("after the hook returned")
+    );
+    meta.clear_hooks().unwrap();
+}
@@ -0,0 +1,101 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[hookable("reentrancy-fib")]
+fn fib(n: u64) -> u64 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+#[hookable("reentrancy-ping")]
+fn ping(n: u64) -> u64 {
+    n
+}
+
+#[hookable("reentrancy-pong")]
+fn pong(n: u64) -> u64 {
+    ping(n)
+}
+
+#[hookable("reentrancy-depth")]
+fn depth(n: u64) -> Result<u64, String> {
+    Ok(n)
+}
+
+#[test]
+fn test() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let fib_hookable = lookup_hookable("reentrancy-fib").unwrap();
+    assert!(!fib_hookable.reentrancy_guard());
+    fib_hookable
+        .add_hook_fn(|args: (u64,), next| -> u64 {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            next(args)
+        })
+        .unwrap();
+    assert_eq!(fib(5), 5);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 15);
+
+    // Recursive calls skip the hooks.
+    fib_hookable.set_reentrancy_guard(true).unwrap();
+    assert!(fib_hookable.reentrancy_guard());
+    assert_eq!(fib(5), 5);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 16);
+    // Guards are released after the call.
+    assert_eq!(fib(5), 5);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 17);
+    fib_hookable.clear_hooks().unwrap();
+
+    // Indirect reentry through another hookable function.
+    let ping_hookable = lookup_hookable("reentrancy-ping").unwrap();
+    ping_hookable.set_reentrancy_guard(true).unwrap();
+    ping_hookable
+        .add_hook_fn(|args: (u64,), next| -> u64 {
+            if args.0 > 0 {
+                pong(args.0 - 1) + 1
+            } else {
+                next(args)
+            }
+        })
+        .unwrap();
+    assert_eq!(pong(3), 3);
+    ping_hookable.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_max_depth() {
+    let depth_hookable = lookup_hookable("reentrancy-depth").unwrap();
+    assert_eq!(depth_hookable.max_depth(), None);
+    depth_hookable
+        .add_hook_fn(|args: (u64,), _| -> Result<u64, String> { depth(args.0 + 1) })
+        .unwrap();
+    depth_hookable.set_max_depth(Some(5)).unwrap();
+    assert_eq!(depth_hookable.max_depth(), Some(5));
+    assert!(!depth_hookable.reentrancy_guard());
+    assert_eq!(depth(0), Ok(5));
+    assert_eq!(depth(10), Ok(15));
+
+    assert!(matches!(
+        depth_hookable.set_depth_fallback(|_: (u32,)| -> Result<u64, String> { Ok(0) }),
+        Err(AddHookError::TypeMismatch { .. })
+    ));
+    depth_hookable
+        .set_depth_fallback(|args: (u64,)| -> Result<u64, String> {
+            Err(format!("too deep at {}", args.0))
+        })
+        .unwrap();
+    assert_eq!(depth(0), Err("too deep at 5".to_string()));
+    depth_hookable.set_max_depth(Some(0)).unwrap();
+    assert_eq!(depth(0), Err("too deep at 0".to_string()));
+    depth_hookable.clear_depth_fallback().unwrap();
+    assert_eq!(depth(0), Ok(0));
+
+    depth_hookable.set_reentrancy_guard(true).unwrap();
+    assert_eq!(depth_hookable.max_depth(), Some(1));
+    assert_eq!(depth(0), Ok(1));
+    depth_hookable.set_reentrancy_guard(false).unwrap();
+    assert_eq!(depth_hookable.max_depth(), None);
+    depth_hookable.clear_hooks().unwrap();
+}
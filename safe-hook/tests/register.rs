@@ -0,0 +1,48 @@
+#![cfg(original_names)]
+
+use safe_hook::{
+    HookableFuncMetadata, IntoHook, add_hook_pending, callback_metadata, hookable, iter_hookables,
+    lookup_hookable, register_static,
+};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, LazyLock};
+
+#[hookable("register-static-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn register_static_skips_collected() {
+    assert_eq!(add(1, 2), 3);
+    let meta = lookup_hookable("register-static-add").unwrap();
+    register_static(meta);
+    let count = iter_hookables()
+        .filter(|meta| meta.name() == "register-static-add")
+        .count();
+    assert_eq!(count, 1);
+}
+
+static FLAG: AtomicBool = AtomicBool::new(false);
+static META: LazyLock<HookableFuncMetadata> =
+    LazyLock::new(|| callback_metadata::<i64, (i64,)>("register-static-late".to_string(), &FLAG));
+
+#[test]
+fn register_static_attaches_pending() {
+    let hook = (|args: (i64,), next: &dyn Fn((i64,)) -> i64| -> i64 { next(args) + 1 }).into_hook();
+    assert_eq!(
+        add_hook_pending("register-static-late", Arc::new(hook)).unwrap(),
+        None
+    );
+    assert!(lookup_hookable("register-static-late").is_none());
+
+    register_static(&META);
+    register_static(&META);
+    let meta = lookup_hookable("register-static-late").unwrap();
+    assert!(std::ptr::eq(meta, &*META));
+    assert_eq!(meta.list_hooks().len(), 1);
+    let count = iter_hookables()
+        .filter(|meta| meta.name() == "register-static-late")
+        .count();
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,38 @@
+#![cfg(original_names)]
+
+use safe_hook::{IntoHook, hookable, iter_hookables, lookup_hookable, register_hookables};
+use std::sync::Arc;
+
+#[hookable("register-hookables-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+mod text {
+    use safe_hook::hookable;
+
+    #[hookable("register-hookables-concat")]
+    pub fn concat(left: String, right: String) -> String {
+        left + right.as_str()
+    }
+}
+
+#[test]
+fn register_hookables_by_path() {
+    register_hookables!(add, text::concat);
+    register_hookables!(add,);
+    for name in ["register-hookables-add", "register-hookables-concat"] {
+        let count = iter_hookables().filter(|meta| meta.name() == name).count();
+        assert_eq!(count, 1, "{name}");
+    }
+
+    let hook = (|(left, right): (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 {
+        next((left, right)) * 10
+    })
+    .into_hook();
+    let meta = lookup_hookable("register-hookables-add").unwrap();
+    let id = meta.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(add(1, 2), 30);
+    meta.remove_hook_by_id(id).unwrap();
+    assert_eq!(text::concat("a".into(), "b".into()), "ab");
+}
@@ -0,0 +1,67 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("digits")]
+fn digits(left: i64, right: i64) -> i64 {
+    left * 10 + right
+}
+
+struct HookDigit {
+    digit: i64,
+}
+
+impl Hook for HookDigit {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) * 10 + self.digit
+    }
+    fn description(&self) -> String {
+        format!("digit {}", self.digit)
+    }
+}
+
+#[test]
+fn test() {
+    let digits_hookable = lookup_hookable("digits").unwrap();
+    let a = digits_hookable
+        .add_hook_with_priority(Arc::new(HookDigit { digit: 1 }), 3)
+        .unwrap();
+    let b = digits_hookable
+        .add_hook_with_priority(Arc::new(HookDigit { digit: 2 }), 2)
+        .unwrap();
+    let c = digits_hookable
+        .add_hook_with_priority(Arc::new(HookDigit { digit: 3 }), 1)
+        .unwrap();
+    // Outer hooks append their digit last.
+    assert_eq!(digits(1, 2), 12321);
+
+    digits_hookable.reorder_hooks(&[c, a, b]).unwrap();
+    assert_eq!(digits(1, 2), 12213);
+    let priorities = digits_hookable
+        .list_hooks()
+        .iter()
+        .map(|h| h.priority)
+        .collect::<Vec<_>>();
+    assert_eq!(priorities, [1, 3, 2]);
+
+    assert_eq!(
+        digits_hookable.reorder_hooks(&[c, a]),
+        Err(AddHookError::InvalidOrder)
+    );
+    assert_eq!(
+        digits_hookable.reorder_hooks(&[c, a, a]),
+        Err(AddHookError::InvalidOrder)
+    );
+    assert_eq!(digits(1, 2), 12213);
+
+    digits_hookable
+        .sort_hooks_by(|x, y| x.description.cmp(&y.description))
+        .unwrap();
+    assert_eq!(digits(1, 2), 12321);
+
+    digits_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,57 @@
+#![cfg(all(feature = "serde", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::replay::{RecordHook, ReplayHook, ReplayMiss};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[hookable("fetch")]
+fn fetch(user: String, page: u32) -> Result<Vec<String>, String> {
+    let call = CALLS.fetch_add(1, Ordering::Relaxed);
+    if page == 0 {
+        return Err("no page 0".to_string());
+    }
+    Ok(vec![format!("{}-{}-{}", user, page, call)])
+}
+
+#[test]
+fn test() {
+    let fetch_hookable = lookup_hookable("fetch").unwrap();
+    let path = std::env::temp_dir().join(format!("safe-hook-replay-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let record = RecordHook::<(String, u32), Result<Vec<String>, String>>::to_file(&path).unwrap();
+    let record = Arc::new(record);
+    fetch_hookable.add_hook(record.clone()).unwrap();
+    assert_eq!(fetch("amy".to_string(), 1), Ok(vec!["amy-1-0".to_string()]));
+    assert_eq!(fetch("amy".to_string(), 1), Ok(vec!["amy-1-1".to_string()]));
+    assert!(fetch("amy".to_string(), 0).is_err());
+    record.flush().unwrap();
+    fetch_hookable.clear_hooks().unwrap();
+
+    let replay = ReplayHook::<(String, u32), Result<Vec<String>, String>>::from_file(&path)
+        .unwrap()
+        .on_miss(ReplayMiss::PassThrough);
+    fetch_hookable.add_hook(Arc::new(replay)).unwrap();
+    assert_eq!(fetch("amy".to_string(), 1), Ok(vec!["amy-1-0".to_string()]));
+    assert_eq!(fetch("amy".to_string(), 1), Ok(vec!["amy-1-1".to_string()]));
+    assert_eq!(fetch("amy".to_string(), 1), Ok(vec!["amy-1-1".to_string()]));
+    assert_eq!(fetch("amy".to_string(), 0), Err("no page 0".to_string()));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+    // Not recorded, so the original function is called.
+    assert_eq!(fetch("bob".to_string(), 2), Ok(vec!["bob-2-3".to_string()]));
+    fetch_hookable.clear_hooks().unwrap();
+
+    let replay =
+        ReplayHook::<(String, u32), Result<Vec<String>, String>>::from_file(&path).unwrap();
+    fetch_hookable.add_hook(Arc::new(replay)).unwrap();
+    let err = std::panic::catch_unwind(|| fetch("bob".to_string(), 2)).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<String>().unwrap(),
+        r#"No recorded result for ["bob",2]"#
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,93 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{CircuitBreaker, CircuitState, RetryHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[hookable("send")]
+fn send(message: String) -> Result<usize, String> {
+    ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+    if message.starts_with("fatal") {
+        Err("fatal".to_string())
+    } else {
+        Err("busy".to_string())
+    }
+}
+
+#[test]
+fn test_retry() {
+    let send_hookable = lookup_hookable("send").unwrap();
+    let retry = RetryHook::<(String,), usize, String>::new()
+        .max_attempts(4)
+        .backoff(Duration::from_millis(10))
+        .retry_if(|err| err == "busy");
+    send_hookable.add_hook(Arc::new(retry)).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(send("hello".to_string()), Err("busy".to_string()));
+    assert_eq!(ATTEMPTS.swap(0, Ordering::Relaxed), 4);
+    // Waits 10 + 20 + 40 ms between the attempts.
+    assert!(start.elapsed() >= Duration::from_millis(70));
+
+    assert_eq!(send("fatal error".to_string()), Err("fatal".to_string()));
+    assert_eq!(ATTEMPTS.swap(0, Ordering::Relaxed), 1);
+}
+
+static HEALTHY: AtomicBool = AtomicBool::new(false);
+
+#[hookable("query")]
+fn query(id: u32) -> Result<u32, String> {
+    if HEALTHY.load(Ordering::Relaxed) {
+        Ok(id)
+    } else {
+        Err("timeout".to_string())
+    }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let query_hookable = lookup_hookable("query").unwrap();
+    let breaker = Arc::new(CircuitBreaker::<(u32,), u32, String>::new(|| {
+        "open".to_string()
+    }));
+    breaker.set_window(4);
+    breaker.set_failure_rate(0.5);
+    breaker.set_open_duration(Duration::from_millis(50));
+    query_hookable.add_hook(breaker.clone()).unwrap();
+
+    HEALTHY.store(true, Ordering::Relaxed);
+    assert_eq!(query(1), Ok(1));
+    assert_eq!(query(2), Ok(2));
+    HEALTHY.store(false, Ordering::Relaxed);
+    assert_eq!(query(3), Err("timeout".to_string()));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(query(4), Err("timeout".to_string()));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert_eq!(query(5), Err("open".to_string()));
+
+    // The probe fails, so the circuit opens again.
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(query(6), Err("timeout".to_string()));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert_eq!(query(7), Err("open".to_string()));
+
+    // The probe succeeds, so the circuit closes.
+    std::thread::sleep(Duration::from_millis(60));
+    HEALTHY.store(true, Ordering::Relaxed);
+    assert_eq!(query(8), Ok(8));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+
+    breaker.set_open_duration(Duration::from_secs(60));
+    HEALTHY.store(false, Ordering::Relaxed);
+    for _ in 0..4 {
+        let _ = query(9);
+    }
+    assert_eq!(breaker.state(), CircuitState::Open);
+    breaker.reset();
+    HEALTHY.store(true, Ordering::Relaxed);
+    assert_eq!(query(10), Ok(10));
+}
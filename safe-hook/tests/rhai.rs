@@ -0,0 +1,84 @@
+#![cfg(all(feature = "rhai", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::rhai::{Engine, RhaiHook};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("rhai-factorial")]
+fn factorial(n: u64) -> u64 {
+    if n <= 1 { 1 } else { n * factorial(n - 1) }
+}
+
+#[hookable("rhai-square")]
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+#[hookable("rhai-identity")]
+fn identity(x: i64) -> i64 {
+    x
+}
+
+const SCRIPT: &str = r#"
+    fn add_one(args) {
+        next(args) + 1
+    }
+
+    fn double_arg(args) {
+        args[0] *= 2;
+        next(args)
+    }
+
+    fn fail(args) {
+        throw "failed";
+    }
+
+    fn fail_after_next(args) {
+        next(args);
+        throw "failed";
+    }
+
+    fn spin(args) {
+        loop {}
+    }
+"#;
+
+#[test]
+fn test_rhai_next() {
+    let meta = lookup_hookable("rhai-factorial").unwrap();
+    // Recursive calls nest the scripts.
+    let hook = Arc::new(RhaiHook::<(u64,), u64>::new(SCRIPT, "add_one").unwrap());
+    meta.add_hook(hook.clone()).unwrap();
+    assert_eq!(factorial(3), ((1 + 1) * 2 + 1) * 3 + 1);
+    assert_eq!(hook.last_error(), None);
+    meta.clear_hooks().unwrap();
+
+    let meta = lookup_hookable("rhai-identity").unwrap();
+    meta.add_hook(Arc::new(
+        RhaiHook::<(i64,), i64>::new(SCRIPT, "double_arg").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(identity(21), 42);
+    meta.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_rhai_errors() {
+    let meta = lookup_hookable("rhai-square").unwrap();
+    let fail = Arc::new(RhaiHook::<(u64,), u64>::new(SCRIPT, "fail").unwrap());
+    let after_next = Arc::new(RhaiHook::<(u64,), u64>::new(SCRIPT, "fail_after_next").unwrap());
+    let mut engine = Engine::new();
+    engine.set_max_operations(1000);
+    let spin = Arc::new(RhaiHook::<(u64,), u64>::with_engine(engine, SCRIPT, "spin").unwrap());
+    for hook in [&fail, &after_next, &spin] {
+        let id = meta.add_hook(hook.clone()).unwrap();
+        assert_eq!(square(4), 16);
+        assert!(hook.last_error().is_some());
+        meta.remove_hook_by_id(id).unwrap();
+    }
+    assert!(fail.last_error().unwrap().contains("failed"));
+
+    assert!(RhaiHook::<(u64,), u64>::new(SCRIPT, "missing").is_err());
+    assert!(RhaiHook::<(u64,), u64>::new("fn (", "add_one").is_err());
+}
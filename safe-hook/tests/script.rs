@@ -0,0 +1,36 @@
+#![cfg(all(feature = "serde", original_names))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::script::JsonHook;
+use safe_hook_macros::hookable;
+use serde_json::json;
+use std::sync::Arc;
+
+#[hookable("script-json")]
+fn scaled(x: u32, factor: u32) -> u32 {
+    x * factor
+}
+
+#[test]
+fn test_json_hook() {
+    let hook = Arc::new(JsonHook::<(u32, u32), u32>::new(|args, next| {
+        match args[0].as_u64() {
+            Some(0) => Err("zero".to_string()),
+            Some(1) => Ok(json!("not a number")),
+            _ => {
+                let result = next(json!([args[0], 10]))?;
+                Ok(json!(result.as_u64().unwrap() + 1))
+            }
+        }
+    }));
+    lookup_hookable("script-json")
+        .unwrap()
+        .add_hook(hook.clone())
+        .unwrap();
+    assert_eq!(scaled(2, 3), 21);
+    assert_eq!(hook.last_error(), None);
+    assert_eq!(scaled(0, 3), 0);
+    assert_eq!(hook.last_error().unwrap(), "zero");
+    assert_eq!(scaled(1, 3), 3);
+    assert!(hook.last_error().unwrap().starts_with("invalid result"));
+}
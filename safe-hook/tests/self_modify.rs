@@ -0,0 +1,77 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{Hook, HookableFuncMetadata, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+/// Removes itself on the first call.
+struct OnceHook {
+    target: &'static HookableFuncMetadata,
+}
+
+impl Hook for OnceHook {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        assert!(self.target.remove_hook(self).unwrap().is_some());
+        next(args) + 100
+    }
+}
+
+/// Adds another hook on every call.
+struct SpawnHook {
+    target: &'static HookableFuncMetadata,
+}
+
+impl Hook for SpawnHook {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        self.target
+            .add_hook_with_priority(Arc::new(HookAdd { x: 1 }), -1)
+            .unwrap();
+        next(args)
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    add_hookable
+        .add_hook(Arc::new(OnceHook {
+            target: add_hookable,
+        }))
+        .unwrap();
+    assert_eq!(add(1, 2), 103);
+    assert_eq!(add(1, 2), 3);
+    assert!(add_hookable.list_hooks().is_empty());
+
+    let spawn = Arc::new(SpawnHook {
+        target: add_hookable,
+    });
+    add_hookable.add_hook(spawn.clone()).unwrap();
+    // The hook added during a call is not called until the next call.
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(add(1, 2), 4);
+    assert_eq!(add(1, 2), 5);
+    add_hookable.remove_hook(spawn.as_ref()).unwrap();
+    assert_eq!(add(1, 2), 6);
+    add_hookable.clear_hooks().unwrap();
+}
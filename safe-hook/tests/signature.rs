@@ -0,0 +1,38 @@
+#![cfg(original_names)]
+
+use safe_hook::{HookableFuncMetadata, hookable, lookup_by_signature};
+
+#[hookable("by-signature-min")]
+fn min(left: i128, right: i128) -> i128 {
+    left.min(right)
+}
+
+#[hookable("by-signature-max")]
+fn max(left: i128, right: i128) -> i128 {
+    left.max(right)
+}
+
+#[hookable("by-signature-label")]
+fn label(value: i128, unit: &'static str) -> String {
+    format!("{}{}", value, unit)
+}
+
+fn names(found: Vec<&'static HookableFuncMetadata>) -> Vec<&'static str> {
+    found.iter().map(|meta| meta.name()).collect()
+}
+
+#[test]
+fn exact_signatures() {
+    assert_eq!(
+        names(lookup_by_signature::<(i128, i128), i128>()),
+        ["by-signature-max", "by-signature-min"]
+    );
+    assert_eq!(
+        names(lookup_by_signature::<(i128, &'static str), String>()),
+        ["by-signature-label"]
+    );
+    assert!(lookup_by_signature::<(i128, i128), i64>().is_empty());
+    assert!(lookup_by_signature::<(i128,), i128>().is_empty());
+    assert_eq!(min(1, 2) + max(1, 2), 3);
+    assert_eq!(label(1, "m"), "1m");
+}
@@ -0,0 +1,41 @@
+#![cfg(original_names)]
+
+use safe_hook::{hookable, similar_hookables};
+
+#[hookable("similar-parse-header")]
+fn parse_header(line: String) -> String {
+    line
+}
+
+#[hookable("similar-parse-headers")]
+fn parse_headers(lines: String) -> String {
+    lines
+}
+
+#[hookable("similar-parse-body")]
+fn parse_body(body: String) -> String {
+    body
+}
+
+#[test]
+fn closest_names_first() {
+    assert_eq!(
+        similar_hookables("similar-parse-headerz"),
+        vec![
+            "similar-parse-header".to_string(),
+            "similar-parse-headers".to_string()
+        ]
+    );
+    assert_eq!(
+        similar_hookables("similar-parse-header"),
+        vec!["similar-parse-headers".to_string()]
+    );
+    assert_eq!(
+        similar_hookables("similar-parse-bdy"),
+        vec!["similar-parse-body".to_string()]
+    );
+    assert!(similar_hookables("unrelated").is_empty());
+    assert_eq!(parse_header("a".into()), "a");
+    assert_eq!(parse_headers("a".into()), "a");
+    assert_eq!(parse_body("a".into()), "a");
+}
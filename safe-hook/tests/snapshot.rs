@@ -0,0 +1,69 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let id = add_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    let snapshot = add_hookable.snapshot();
+    assert_eq!(add(1, 2), 4);
+
+    add_hookable.set_hook_enabled(id, false).unwrap();
+    add_hookable.add_hook(Arc::new(HookAdd { x: 10 })).unwrap();
+    assert_eq!(add(1, 2), 13);
+
+    add_hookable.restore(&snapshot).unwrap();
+    assert_eq!(add(1, 2), 4);
+    let hooks = add_hookable.list_hooks();
+    assert_eq!(hooks.len(), 1);
+    assert_eq!(hooks[0].id, id);
+    assert!(hooks[0].enabled);
+
+    add_hookable.clear_hooks().unwrap();
+    assert_eq!(add(1, 2), 3);
+    add_hookable.restore(&snapshot).unwrap();
+    assert_eq!(add(1, 2), 4);
+}
+
+#[test]
+fn test_restore_other() {
+    let sub_hookable = lookup_hookable("sub").unwrap();
+    sub_hookable.add_hook(Arc::new(HookAdd { x: 1 })).unwrap();
+    let snapshot = lookup_hookable("add").unwrap().snapshot();
+    assert_eq!(
+        sub_hookable.restore(&snapshot),
+        Err(AddHookError::SnapshotMismatch)
+    );
+    assert_eq!(
+        AddHookError::SnapshotMismatch.to_string(),
+        "Snapshot was taken from another hookable function"
+    );
+    // The hooks are left unchanged.
+    assert_eq!(sub(1, 2), 0);
+    sub_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,141 @@
+#![cfg(all(feature = "abi_stable", unix, original_names))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::plugin::PluginError;
+use safe_hook::stable::abi_stable::StableAbi;
+use safe_hook::stable::abi_stable::std_types::RString;
+use safe_hook::stable::abi_stable::type_layout::TypeLayout;
+use safe_hook::stable::{self, STABLE_LAYOUT_SYMBOL, StableContext, StableHook};
+use safe_hook::{AddHookError, export_stable_plugin};
+use safe_hook_macros::hookable;
+use std::time::Duration;
+
+#[hookable("stable-damage")]
+fn damage(base: u32) -> u32 {
+    base
+}
+
+#[hookable("stable-greet")]
+fn greet(name: RString, excited: bool) -> RString {
+    let mark = if excited { "!" } else { "." };
+    format!("Hello, {}{}", name, mark).into()
+}
+
+#[hookable("stable-hidden")]
+fn hidden(base: u32) -> u32 {
+    base
+}
+
+fn init(context: &mut StableContext) {
+    assert_eq!(context.name(), "double-damage");
+    assert!(
+        context
+            .hookables()
+            .iter()
+            .any(|name| name == "stable-damage")
+    );
+    let hook = StableHook::new(|(base,): (u32,), next: &dyn Fn((u32,)) -> u32| next((base * 2,)));
+    context.add_hook("stable-damage", hook, 0).unwrap();
+    let hook = StableHook::new(
+        |(name, _): (RString, bool), next: &dyn Fn((RString, bool)) -> RString| {
+            let greeting = next((name, true));
+            format!("{} Welcome.", greeting).into()
+        },
+    );
+    context.add_hook("stable-greet", hook, 0).unwrap();
+}
+
+#[test]
+fn test_from_entry() {
+    stable::expose::<(u32,), u32>(lookup_hookable("stable-damage").unwrap()).unwrap();
+    let greet_meta = lookup_hookable("stable-greet").unwrap();
+    stable::expose::<(RString, bool), RString>(greet_meta).unwrap();
+
+    let mut plugin = stable::from_entry("double-damage", init);
+    assert_eq!(plugin.hooks().len(), 2);
+    assert_eq!(damage(10), 20);
+    assert_eq!(greet("Ada".into(), false), "Hello, Ada! Welcome.");
+    assert!(greet_meta.list_hooks()[0].description.contains("init"));
+    assert!(!plugin.is_quiescent());
+
+    unsafe { plugin.unload(Duration::from_secs(10)) }.unwrap();
+    assert_eq!(damage(10), 10);
+    assert_eq!(greet("Ada".into(), false), "Hello, Ada.");
+}
+
+#[test]
+fn test_mismatch() {
+    let meta = lookup_hookable("stable-hidden").unwrap();
+    assert!(matches!(
+        stable::expose::<(u64,), u32>(meta),
+        Err(AddHookError::TypeMismatch { .. })
+    ));
+
+    let plugin = stable::from_entry("mismatch", |context| {
+        let hook = || StableHook::new(|args: (u32,), next: &dyn Fn((u32,)) -> u32| next(args));
+        let error = context.add_hook("stable-hidden", hook(), 0).unwrap_err();
+        assert_eq!(error, "'stable-hidden' is not exposed to stable plugins");
+
+        stable::expose::<(u32,), u32>(lookup_hookable("stable-hidden").unwrap()).unwrap();
+        let wide = StableHook::new(|args: (u64,), next: &dyn Fn((u64,)) -> u32| next(args));
+        let error = context.add_hook("stable-hidden", wide, 0).unwrap_err();
+        assert!(error.starts_with("The arguments of the hook do not match"));
+        let wide = StableHook::new(|args: (u32,), next: &dyn Fn((u32,)) -> u64| next(args));
+        let error = context.add_hook("stable-hidden", wide, 0).unwrap_err();
+        assert!(error.starts_with("The result of the hook does not match"));
+    });
+    assert!(plugin.hooks().is_empty());
+    assert_eq!(hidden(1), 1);
+}
+
+#[hookable("stable-armor")]
+fn armor(base: u32) -> u32 {
+    base
+}
+
+fn init_armor(context: &mut StableContext) {
+    let hook = StableHook::new(|(base,): (u32,), next: &dyn Fn((u32,)) -> u32| next((base + 5,)));
+    context.add_hook("stable-armor", hook, 0).unwrap();
+}
+
+export_stable_plugin!(init_armor);
+
+extern "C" fn other_layout() -> &'static TypeLayout {
+    <u32 as StableAbi>::LAYOUT
+}
+
+#[test]
+fn test_exported_symbols() {
+    stable::expose::<(u32,), u32>(lookup_hookable("stable-armor").unwrap()).unwrap();
+    let error =
+        unsafe { stable::from_raw_entry("armor", other_layout, safe_hook_stable_plugin_entry) }
+            .unwrap_err();
+    assert!(matches!(error, PluginError::LayoutMismatch(_)));
+    assert_eq!(armor(1), 1);
+
+    let mut plugin = unsafe {
+        stable::from_raw_entry(
+            "armor",
+            safe_hook_stable_plugin_layout,
+            safe_hook_stable_plugin_entry,
+        )
+    }
+    .unwrap();
+    assert_eq!(armor(1), 6);
+    unsafe { plugin.unload(Duration::from_secs(10)) }.unwrap();
+    assert_eq!(armor(1), 1);
+}
+
+#[test]
+fn test_load_errors() {
+    let error = unsafe { stable::load("/nonexistent/libplugin.so") }.unwrap_err();
+    assert!(matches!(error, PluginError::Open(_)));
+    // A library which is not a plugin, opening it may fail on systems without glibc.
+    match unsafe { stable::load("libc.so.6") } {
+        Ok(plugin) => panic!("libc loaded as a plugin: {:?}", plugin),
+        Err(error) => assert!(matches!(
+            error,
+            PluginError::MissingSymbol(STABLE_LAYOUT_SYMBOL) | PluginError::Open(_)
+        )),
+    }
+}
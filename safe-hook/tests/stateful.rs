@@ -0,0 +1,61 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{StatefulHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[hookable("lookup")]
+fn lookup(key: String) -> Option<usize> {
+    key.parse().ok()
+}
+
+#[hookable("stateful-factorial")]
+fn factorial(n: u64) -> u64 {
+    if n <= 1 { 1 } else { n * factorial(n - 1) }
+}
+
+#[test]
+fn test() {
+    let lookup_hookable = lookup_hookable("lookup").unwrap();
+    let misses = Arc::new(StatefulHook::new(
+        BTreeMap::new(),
+        |misses: &mut BTreeMap<String, usize>, args: &(String,), result: &Option<usize>| {
+            if result.is_none() {
+                *misses.entry(args.0.clone()).or_default() += 1;
+            }
+        },
+    ));
+    lookup_hookable.add_hook(misses.clone()).unwrap();
+    assert_eq!(lookup("1".to_string()), Some(1));
+    assert_eq!(lookup("x".to_string()), None);
+    assert_eq!(lookup("y".to_string()), None);
+    assert_eq!(lookup("x".to_string()), None);
+
+    let expected = BTreeMap::from([("x".to_string(), 2), ("y".to_string(), 1)]);
+    assert_eq!(*misses.state(), expected);
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    lookup("z".to_string());
+                }
+            });
+        }
+    });
+    assert_eq!(misses.state()["z"], 400);
+}
+
+#[test]
+fn test_reentrant() {
+    // The state is not locked during `next`, so recursive calls do not deadlock.
+    let calls = Arc::new(StatefulHook::new(
+        Vec::new(),
+        |calls: &mut Vec<u64>, args: &(u64,), result: &u64| calls.push(args.0 * 100 + result),
+    ));
+    let meta = lookup_hookable("stateful-factorial").unwrap();
+    meta.add_hook(calls.clone()).unwrap();
+    assert_eq!(factorial(3), 6);
+    assert_eq!(*calls.state(), [101, 202, 306]);
+    meta.clear_hooks().unwrap();
+}
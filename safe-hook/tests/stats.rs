@@ -0,0 +1,34 @@
+#![cfg(all(feature = "stats", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook_macros::hookable;
+use std::time::Duration;
+
+#[hookable("nap")]
+fn nap(millis: u64) -> u64 {
+    std::thread::sleep(Duration::from_millis(millis));
+    millis
+}
+
+#[test]
+fn test() {
+    let nap_hookable = lookup_hookable("nap").unwrap();
+    nap(5);
+    nap(5);
+    let stats = nap_hookable.stats();
+    assert_eq!(stats.fast_path_calls, 2);
+    assert_eq!(stats.hooked_calls, 0);
+    assert!(stats.total_time >= Duration::from_millis(10));
+
+    let id = nap_hookable
+        .add_hook_fn(|args: (u64,), next| -> u64 { next(args) + 1 })
+        .unwrap();
+    assert_eq!(nap(1), 2);
+    nap_hookable.remove_hook_by_id(id).unwrap();
+    let stats = nap_hookable.stats();
+    assert_eq!(stats.hooked_calls, 1);
+    assert_eq!(stats.calls(), 3);
+
+    nap_hookable.reset_stats();
+    assert_eq!(nap_hookable.stats(), Default::default());
+}
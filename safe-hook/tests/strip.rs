@@ -0,0 +1,16 @@
+#![cfg(safe_hook_strip)]
+
+use safe_hook::{hookable, hookable_meta, iter_hookables, lookup_hookable};
+
+#[hookable("strip-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn hookables_are_stripped() {
+    assert_eq!(add(1, 2), 3);
+    assert!(hookable_meta!(add).is_none());
+    assert!(lookup_hookable("strip-add").is_none());
+    assert_eq!(iter_hookables().count(), 0);
+}
@@ -0,0 +1,30 @@
+#![cfg(all(feature = "strip-names", not(any(safe_hook_strip, feature = "hashed-names"))))]
+
+use safe_hook::{IntoHook, hookable, hookable_meta, lookup_hookable};
+use std::sync::Arc;
+
+#[hookable("strip-names-add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[test]
+fn names_are_stripped_in_release() {
+    let meta = hookable_meta!(add).unwrap();
+    if cfg!(debug_assertions) {
+        assert_eq!(meta.name(), "strip-names-add");
+        assert_eq!(meta.result_type_name(), "i64");
+        assert!(lookup_hookable("strip-names-add").is_some());
+    } else {
+        assert_eq!(meta.name(), "");
+        assert_eq!(meta.args_type_name(), "");
+        assert_eq!(meta.result_type_name(), "");
+        assert!(lookup_hookable("strip-names-add").is_none());
+    }
+
+    let hook = (|args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64| -> i64 { next(args) * 2 })
+        .into_hook();
+    let id = meta.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(add(1, 2), 6);
+    meta.remove_hook_by_id(id).unwrap();
+}
@@ -0,0 +1,54 @@
+#![cfg(original_names)]
+
+use safe_hook::{lookup_hookable, registry_summary};
+use safe_hook_macros::hookable;
+
+#[hookable("summary-a")]
+fn a(x: u8) -> u8 {
+    x
+}
+
+#[hookable("summary-b")]
+fn b() {}
+
+#[test]
+fn test() {
+    let a_hookable = lookup_hookable("summary-a").unwrap();
+    let id = a_hookable
+        .add_hook_fn(|args: (u8,), next| -> u8 { next(args) })
+        .unwrap();
+    a_hookable.set_hook_enabled(id, false).unwrap();
+    a(1);
+    b();
+
+    let summary = registry_summary();
+    assert!(!summary.all_disabled);
+    let names = summary
+        .hookables
+        .iter()
+        .map(|h| h.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["summary-a", "summary-b"]);
+    let a_summary = &summary.hookables[0];
+    assert_eq!(a_summary.signature, "fn summary-a(u8) -> u8");
+    assert_eq!(a_summary.hooks, a_hookable.list_hooks());
+    assert!(!a_summary.hooks[0].enabled);
+    assert!(summary.hookables[1].hooks.is_empty());
+
+    #[cfg(feature = "stats")]
+    assert_eq!(a_summary.stats.calls(), 1);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["all_disabled"], false);
+        assert_eq!(json["hookables"][0]["name"], "summary-a");
+        assert_eq!(json["hookables"][0]["hooks"][0]["id"], id.as_u64());
+        assert_eq!(json["hookables"][0]["hooks"][0]["priority"], 0);
+        assert_eq!(json["hookables"][0]["hooks"][0]["enabled"], false);
+        assert_eq!(json["hookables"][1]["disabled_by_env"], false);
+        #[cfg(feature = "stats")]
+        assert_eq!(json["hookables"][0]["stats"]["fast_path_calls"], 1);
+    }
+    a_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,100 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{ConditionalHook, Hook, HookExt, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let sub_hookable = lookup_hookable("sub").unwrap();
+    add_hookable
+        .add_hook(Arc::new(HookAdd { x: 1 }.once()))
+        .unwrap();
+    let shared = Arc::new(HookAdd { x: 10 }.take(3));
+    add_hookable.add_hook(shared.clone()).unwrap();
+    sub_hookable.add_hook(shared.clone()).unwrap();
+
+    assert_eq!(add(1, 2), 14);
+    assert_eq!(add_hookable.list_hooks().len(), 1);
+    assert_eq!(add(1, 2), 13);
+    assert_eq!(shared.remaining(), 1);
+    // The shared hook is spent, and detached from both functions.
+    assert_eq!(sub(5, 2), 13);
+    assert_eq!(shared.remaining(), 0);
+    assert!(add_hookable.list_hooks().is_empty());
+    assert!(sub_hookable.list_hooks().is_empty());
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(sub(5, 2), 3);
+}
+
+#[hookable("mul")]
+fn mul(left: i64, right: i64) -> i64 {
+    left * right
+}
+
+#[test]
+fn test_sampled() {
+    let mul_hookable = lookup_hookable("mul").unwrap();
+    mul_hookable
+        .add_hook(Arc::new(HookAdd { x: 1 }.sampled(3)))
+        .unwrap();
+    let results = (0..7).map(|_| mul(2, 3)).collect::<Vec<_>>();
+    assert_eq!(results, [7, 6, 6, 7, 6, 6, 7]);
+}
+
+#[hookable("div")]
+fn div(left: i64, right: i64) -> i64 {
+    left / right
+}
+
+#[test]
+fn test_rate_limited() {
+    let div_hookable = lookup_hookable("div").unwrap();
+    div_hookable
+        .add_hook(Arc::new(HookAdd { x: 1 }.rate_limited(5)))
+        .unwrap();
+    let hooked = (0..20).filter(|_| div(6, 3) == 3).count();
+    assert_eq!(hooked, 5);
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let hooked = (0..20).filter(|_| div(6, 3) == 3).count();
+    assert!((2..=5).contains(&hooked));
+}
+
+#[hookable("rem")]
+fn rem(left: i64, right: i64) -> i64 {
+    left % right
+}
+
+#[test]
+fn test_wrapped_take() {
+    let rem_hookable = lookup_hookable("rem").unwrap();
+    let hook = ConditionalHook::new(HookAdd { x: 10 }.once(), |_: &(i64, i64)| true);
+    rem_hookable.add_hook(Arc::new(hook)).unwrap();
+    assert_eq!(rem(7, 4), 13);
+    // The spent inner hook detaches the wrapper it is attached through.
+    assert!(rem_hookable.list_hooks().is_empty());
+    assert_eq!(rem(7, 4), 3);
+}
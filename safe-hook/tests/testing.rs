@@ -0,0 +1,130 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::lookup_hookable;
+use safe_hook::testing::{Expectation, Spy, Stub};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("open")]
+fn open(path: String) -> Result<u32, String> {
+    if path.is_empty() {
+        Err("empty path".to_string())
+    } else {
+        Ok(path.len() as u32)
+    }
+}
+
+#[hookable("close")]
+fn close(fd: u32) {
+    let _ = fd;
+}
+
+#[test]
+fn test_spy() {
+    let open_spy = Arc::new(Spy::<(String,), Result<u32, String>>::new());
+    let close_spy = Arc::new(Spy::<(u32,), ()>::new());
+    lookup_hookable("open")
+        .unwrap()
+        .add_hook(open_spy.clone())
+        .unwrap();
+    lookup_hookable("close")
+        .unwrap()
+        .add_hook(close_spy.clone())
+        .unwrap();
+    assert!(!open_spy.was_called());
+
+    let fd = open("a.txt".to_string()).unwrap();
+    close(fd);
+    assert!(open(String::new()).is_err());
+
+    open_spy.assert_called_times(2);
+    open_spy.assert_called_with(&("a.txt".to_string(),));
+    assert!(!open_spy.was_called_with(&("b.txt".to_string(),)));
+    assert_eq!(open_spy.results(), [Ok(5), Err("empty path".to_string())]);
+    assert_eq!(open_spy.last_call().unwrap().args, (String::new(),));
+    close_spy.assert_called_with(&(5,));
+    open_spy.assert_called_before(&close_spy);
+    let result = std::panic::catch_unwind(|| close_spy.assert_called_before(&open_spy));
+    assert!(result.is_err());
+
+    let calls = open_spy.calls();
+    assert!(calls[0].sequence < close_spy.calls()[0].sequence);
+    assert!(close_spy.calls()[0].sequence < calls[1].sequence);
+
+    open_spy.clear();
+    assert_eq!(open_spy.call_count(), 0);
+}
+
+#[hookable("quote")]
+fn quote(symbol: &'static str) -> Option<f64> {
+    panic!("no network access for {}", symbol)
+}
+
+#[test]
+fn test_stub() {
+    let quote_hookable = lookup_hookable("quote").unwrap();
+    let stub = Arc::new(Stub::<(&'static str,), Option<f64>>::returning(Some(1.5)));
+    quote_hookable.add_hook(stub.clone()).unwrap();
+    assert_eq!(quote("ABC"), Some(1.5));
+    quote_hookable.remove_hook(stub.as_ref()).unwrap();
+
+    quote_hookable
+        .add_hook(Arc::new(Stub::with(|(symbol,): (&'static str,)| {
+            (symbol == "ABC").then_some(2.5)
+        })))
+        .unwrap();
+    assert_eq!(quote("ABC"), Some(2.5));
+    assert_eq!(quote("XYZ"), None);
+    quote_hookable.clear_hooks().unwrap();
+
+    quote_hookable
+        .add_hook(Arc::new(Stub::<(&'static str,), _>::sequence([
+            None,
+            Some(1.0),
+        ])))
+        .unwrap();
+    assert_eq!(quote("ABC"), None);
+    assert_eq!(quote("ABC"), Some(1.0));
+    assert_eq!(quote("ABC"), Some(1.0));
+}
+
+#[hookable("notify")]
+fn notify(user: u64, message: String) -> bool {
+    let _ = (user, message);
+    false
+}
+
+#[test]
+fn test_expectation() {
+    let notify_hookable = lookup_hookable("notify").unwrap();
+    {
+        let expectation = Expectation::<(u64, String), bool>::new()
+            .with(|(user, _)| *user == 1)
+            .times(2)
+            .returning(|_| true)
+            .attach(notify_hookable)
+            .unwrap();
+        assert!(notify(1, "a".to_string()));
+        assert_eq!(expectation.call_count(), 1);
+        assert!(notify(1, "b".to_string()));
+    }
+    assert!(notify_hookable.list_hooks().is_empty());
+    assert!(!notify(1, "c".to_string()));
+
+    let expectation = Expectation::<(u64, String), bool>::new()
+        .with(|(user, _)| *user == 1)
+        .at_least(1)
+        .attach(notify_hookable)
+        .unwrap();
+    assert!(!notify(2, "d".to_string()));
+    let err =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(expectation))).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<String>().unwrap(),
+        r#"Expectation not met: expected at least 1 matching calls, got 0; unexpected call with (2, "d")"#
+    );
+    assert!(notify_hookable.list_hooks().is_empty());
+
+    let expectation = Expectation::<(u64, String), bool>::new().never();
+    assert!(expectation.check().is_ok());
+}
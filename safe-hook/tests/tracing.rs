@@ -0,0 +1,49 @@
+#![cfg(all(feature = "tracing", lookup_by_name))]
+
+use safe_hook::{TracingSpanHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .finish();
+    lookup_hookable("add")
+        .unwrap()
+        .add_hook(Arc::new(TracingSpanHook::<(i64, i64), i64>::new()))
+        .unwrap();
+    tracing::subscriber::with_default(subscriber, || assert_eq!(add(1, 2), 3));
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    #[cfg(not(feature = "hashed-names"))]
+    assert!(
+        output.contains("hookable{name=\"add\" args=(1, 2) elapsed="),
+        "{output}"
+    );
+    assert!(output.contains("close"), "{output}");
+}
@@ -0,0 +1,46 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{call_info, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::panic::Location;
+use std::sync::Mutex;
+
+#[hookable("track-caller-tracked", track_caller)]
+fn tracked(x: u8) -> u8 {
+    x
+}
+
+#[hookable("track-caller-untracked")]
+fn untracked(x: u8) -> u8 {
+    x
+}
+
+static LOCATIONS: Mutex<Vec<Option<&'static Location<'static>>>> = Mutex::new(Vec::new());
+
+fn record(args: (u8,), next: &dyn Fn((u8,)) -> u8) -> u8 {
+    LOCATIONS
+        .lock()
+        .unwrap()
+        .push(call_info().unwrap().location);
+    next(args)
+}
+
+#[test]
+fn test() {
+    let tracked_hookable = lookup_hookable("track-caller-tracked").unwrap();
+    let untracked_hookable = lookup_hookable("track-caller-untracked").unwrap();
+    tracked_hookable.add_hook_fn(record).unwrap();
+    untracked_hookable.add_hook_fn(record).unwrap();
+
+    let line = line!() + 1;
+    assert_eq!(tracked(1), 1);
+    assert_eq!(untracked(2), 2);
+    let locations = std::mem::take(&mut *LOCATIONS.lock().unwrap());
+    let location = locations[0].unwrap();
+    assert_eq!(location.file(), file!());
+    assert_eq!(location.line(), line);
+    assert_eq!(locations[1], None);
+
+    tracked_hookable.clear_hooks().unwrap();
+    untracked_hookable.clear_hooks().unwrap();
+}
@@ -0,0 +1,71 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{AddHookError, Hook, HookTransaction, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+
+#[hookable("add")]
+fn add(left: i64, right: i64) -> i64 {
+    left + right
+}
+
+#[hookable("sub")]
+fn sub(left: i64, right: i64) -> i64 {
+    left - right
+}
+
+#[hookable("add32")]
+fn add32(left: i32, right: i32) -> i32 {
+    left + right
+}
+
+struct HookAdd {
+    x: i64,
+}
+
+impl Hook for HookAdd {
+    type Args<'a> = (i64, i64);
+    type Result = i64;
+    fn call(&self, args: (i64, i64), next: &dyn Fn((i64, i64)) -> i64) -> i64 {
+        next(args) + self.x
+    }
+}
+
+#[test]
+fn test() {
+    let add_hookable = lookup_hookable("add").unwrap();
+    let sub_hookable = lookup_hookable("sub").unwrap();
+    let add32_hookable = lookup_hookable("add32").unwrap();
+    let hook1 = Arc::new(HookAdd { x: 1 });
+    let hook2 = Arc::new(HookAdd { x: 10 });
+
+    let mut transaction = HookTransaction::new();
+    transaction
+        .add_hook(add_hookable, hook1.clone(), 0)
+        .add_hook(sub_hookable, hook1.clone(), 0)
+        .add_hook(add_hookable, hook2.clone(), 0);
+    let ids = transaction.commit().unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(add(1, 2), 14);
+    assert_eq!(sub(1, 2), 0);
+
+    // The type mismatch on `add32` rolls back the whole transaction.
+    let mut transaction = HookTransaction::new();
+    transaction
+        .remove_hook(add_hookable, hook1.clone())
+        .remove_hook(sub_hookable, hook1.clone())
+        .add_hook(add32_hookable, hook1.clone(), 0);
+    let err = transaction.commit().unwrap_err();
+    assert!(matches!(err, AddHookError::TypeMismatch { .. }));
+    assert_eq!(add(1, 2), 14);
+    assert_eq!(sub(1, 2), 0);
+    assert_eq!(add32(1, 2), 3);
+
+    let mut transaction = HookTransaction::new();
+    transaction
+        .remove_hook(add_hookable, hook1.clone())
+        .remove_hook(sub_hookable, hook1.clone());
+    assert_eq!(transaction.commit(), Ok(vec![]));
+    assert_eq!(add(1, 2), 13);
+    assert_eq!(sub(1, 2), -1);
+}
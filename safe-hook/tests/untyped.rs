@@ -0,0 +1,98 @@
+#![cfg(original_names)]
+
+use safe_hook::{AddHookError, UntypedCall, UntypedHook, lookup_hookable};
+use safe_hook_macros::hookable;
+use std::sync::{Arc, Mutex};
+
+#[hookable("untyped-len")]
+fn len(text: String) -> usize {
+    text.len()
+}
+
+#[hookable("untyped-neg")]
+fn neg(x: i32) -> Option<i32> {
+    x.checked_neg()
+}
+
+#[hookable("untyped-borrowed")]
+fn borrowed(text: &str) -> usize {
+    text.len()
+}
+
+#[derive(Default)]
+struct Recorder {
+    calls: Mutex<Vec<String>>,
+}
+
+impl UntypedHook for Recorder {
+    fn call(&self, call: &mut UntypedCall<'_>) {
+        let line = format!(
+            "{}: {} -> {}",
+            call.hookable(),
+            call.args_type_name(),
+            call.result_type_name()
+        );
+        self.calls.lock().unwrap().push(line);
+    }
+}
+
+struct Override;
+
+impl UntypedHook for Override {
+    fn call(&self, call: &mut UntypedCall<'_>) {
+        if let Some((text,)) = call.args().and_then(|a| a.downcast_mut::<(String,)>()) {
+            text.push('!');
+        }
+        if call
+            .args()
+            .is_some_and(|a| a.downcast_ref::<(i32,)>() == Some(&(0,)))
+        {
+            assert!(call.set_result(Box::new("wrong type")).is_err());
+            call.set_result(Box::new(None::<i32>)).unwrap();
+            assert!(call.args().is_none());
+            return;
+        }
+        call.proceed();
+        if let Some(len) = call.result().and_then(|r| r.downcast_mut::<usize>()) {
+            *len *= 10;
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let recorder = Arc::new(Recorder::default());
+    let len_hookable = lookup_hookable("untyped-len").unwrap();
+    let neg_hookable = lookup_hookable("untyped-neg").unwrap();
+    len_hookable.add_untyped_hook(recorder.clone()).unwrap();
+    neg_hookable.add_untyped_hook(recorder.clone()).unwrap();
+    len_hookable.add_untyped_hook(Arc::new(Override)).unwrap();
+    neg_hookable.add_untyped_hook(Arc::new(Override)).unwrap();
+
+    assert_eq!(len("ab".to_string()), 30);
+    assert_eq!(neg(2), Some(-2));
+    assert_eq!(neg(0), None);
+    assert_eq!(
+        *recorder.calls.lock().unwrap(),
+        vec![
+            "untyped-len: (alloc::string::String,) -> usize",
+            // The second call returns early in `Override`, called first.
+            "untyped-neg: (i32,) -> core::option::Option<i32>",
+        ]
+    );
+    assert_eq!(
+        len_hookable.list_hooks()[0].description,
+        std::any::type_name::<Override>()
+    );
+
+    assert_eq!(
+        lookup_hookable("untyped-borrowed")
+            .unwrap()
+            .add_untyped_hook(recorder.clone()),
+        Err(AddHookError::UntypedNotSupported)
+    );
+    assert_eq!(borrowed("abc"), 3);
+    len_hookable.clear_hooks().unwrap();
+    neg_hookable.clear_hooks().unwrap();
+    assert_eq!(len("ab".to_string()), 2);
+}
@@ -0,0 +1,86 @@
+#![cfg(lookup_by_name)]
+
+use safe_hook::{
+    AddHookError, Hook, HookTransaction, HookableFuncMetadata, hookable, lookup_hookable,
+};
+use std::sync::Arc;
+
+#[hookable("version-price", version = "2.1.0")]
+fn price(amount: u64) -> u64 {
+    amount
+}
+
+#[hookable("version-legacy", version = "0.3.1")]
+fn legacy(amount: u64) -> u64 {
+    amount
+}
+
+#[hookable("version-untagged")]
+fn untagged(amount: u64) -> u64 {
+    amount
+}
+
+struct Discount(Option<&'static str>);
+
+impl Hook for Discount {
+    type Args<'a> = (u64,);
+    type Result = u64;
+    fn call(&self, args: (u64,), next: &dyn Fn((u64,)) -> u64) -> u64 {
+        next(args) - 1
+    }
+    fn version(&self) -> Option<String> {
+        self.0.map(str::to_string)
+    }
+}
+
+#[test]
+fn incompatible_majors_are_rejected() {
+    let meta = lookup_hookable("version-price").unwrap();
+    assert_eq!(meta.version(), Some("2.1.0"));
+    let err = meta
+        .add_hook(Arc::new(Discount(Some("1.4.0"))))
+        .unwrap_err();
+    assert!(matches!(
+        &err,
+        AddHookError::IncompatibleVersion { expected, found } if expected == "2.1.0" && found == "1.4.0"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Hook version 1.4.0 is incompatible with hookable version 2.1.0"
+    );
+    let mut transaction = HookTransaction::new();
+    transaction.add_hook(meta, Arc::new(Discount(Some("3.0.0"))), 0);
+    assert!(transaction.commit().is_err());
+
+    meta.add_hook(Arc::new(Discount(Some("2.0.5")))).unwrap();
+    meta.add_hook(Arc::new(Discount(None))).unwrap();
+    assert_eq!(price(10), 8);
+}
+
+#[test]
+fn zero_majors_compare_minors() {
+    let meta = lookup_hookable("version-legacy").unwrap();
+    assert!(meta.add_hook(Arc::new(Discount(Some("0.2.0")))).is_err());
+    meta.add_hook(Arc::new(Discount(Some("0.3.0")))).unwrap();
+    assert_eq!(legacy(10), 9);
+}
+
+#[test]
+fn untagged_hookables_accept_any_version() {
+    let meta = lookup_hookable("version-untagged").unwrap();
+    assert_eq!(meta.version(), None);
+    meta.add_hook(Arc::new(Discount(Some("7.0.0")))).unwrap();
+    assert_eq!(untagged(10), 9);
+}
+
+#[test]
+fn builder_version() {
+    let point = HookableFuncMetadata::builder("version-built", |(amount,): (u64,)| amount)
+        .version("1.0.0")
+        .register()
+        .unwrap();
+    let meta = lookup_hookable("version-built").unwrap();
+    assert_eq!(meta.version(), Some("1.0.0"));
+    assert!(meta.add_hook(Arc::new(Discount(Some("2.0.0")))).is_err());
+    assert_eq!(point.call((10,)), 10);
+}
@@ -0,0 +1,102 @@
+#![cfg(all(feature = "wasmtime", lookup_by_name))]
+
+use safe_hook::lookup_hookable;
+use safe_hook::wasm::WasmHook;
+use safe_hook_macros::hookable;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[hookable("wasm-double")]
+fn double(x: i64) -> i64 {
+    CALLS.fetch_add(1, Ordering::SeqCst);
+    x * 2
+}
+
+#[hookable("wasm-identity")]
+fn identity(x: i64) -> i64 {
+    x
+}
+
+const MODULE: &str = r#"(module
+    (import "safe_hook" "next" (func $next (param i32 i32) (result i64)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "42")
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "alloc") (param $len i32) (result i32)
+        (global.get $heap)
+        (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+    (func (export "forward") (param i32 i32) (result i64)
+        (call $next (local.get 0) (local.get 1)))
+    (func (export "forward_then_trap") (param i32 i32) (result i64)
+        (drop (call $next (local.get 0) (local.get 1)))
+        unreachable)
+    (func (export "answer") (param i32 i32) (result i64)
+        (i64.const 2))
+    (func (export "trap") (param i32 i32) (result i64)
+        unreachable)
+    (func (export "spin") (param i32 i32) (result i64)
+        (loop $spin (br $spin))
+        (i64.const 0)))"#;
+
+#[test]
+fn test_wasm_next() {
+    let hook = Arc::new(WasmHook::<(i64,), i64>::new(MODULE, "forward").unwrap());
+    let meta = lookup_hookable("wasm-double").unwrap();
+    meta.add_hook(hook.clone()).unwrap();
+    let calls = CALLS.load(Ordering::SeqCst);
+    assert_eq!(double(21), 42);
+    assert_eq!(CALLS.load(Ordering::SeqCst), calls + 1);
+    assert_eq!(hook.last_error(), None);
+    meta.clear_hooks().unwrap();
+
+    // The result of the rest of the chain is kept when the module traps after calling it.
+    let hook = Arc::new(WasmHook::<(i64,), i64>::new(MODULE, "forward_then_trap").unwrap());
+    meta.add_hook(hook.clone()).unwrap();
+    assert_eq!(double(4), 8);
+    assert_eq!(CALLS.load(Ordering::SeqCst), calls + 2);
+    assert!(hook.last_error().unwrap().contains("unreachable"));
+    meta.clear_hooks().unwrap();
+}
+
+#[test]
+fn test_wasm_limits() {
+    let meta = lookup_hookable("wasm-identity").unwrap();
+    let answer = Arc::new(WasmHook::<(i64,), i64>::new(MODULE, "answer").unwrap());
+    meta.add_hook(answer.clone()).unwrap();
+    assert_eq!(identity(1), 42);
+    meta.clear_hooks().unwrap();
+
+    let trap = Arc::new(WasmHook::<(i64,), i64>::new(MODULE, "trap").unwrap());
+    meta.add_hook(trap.clone()).unwrap();
+    assert_eq!(identity(1), 1);
+    assert!(trap.last_error().unwrap().contains("unreachable"));
+    meta.clear_hooks().unwrap();
+
+    let fuel = Arc::new(
+        WasmHook::<(i64,), i64>::new(MODULE, "spin")
+            .unwrap()
+            .fuel(10_000),
+    );
+    meta.add_hook(fuel.clone()).unwrap();
+    assert_eq!(identity(2), 2);
+    assert!(fuel.last_error().unwrap().contains("fuel"));
+    meta.clear_hooks().unwrap();
+
+    let timeout = Arc::new(
+        WasmHook::<(i64,), i64>::new(MODULE, "spin")
+            .unwrap()
+            .timeout(Duration::from_millis(50)),
+    );
+    meta.add_hook(timeout.clone()).unwrap();
+    let start = Instant::now();
+    assert_eq!(identity(3), 3);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(timeout.last_error().is_some());
+    meta.clear_hooks().unwrap();
+
+    assert!(WasmHook::<(i64,), i64>::new(MODULE, "missing").is_err());
+    assert!(WasmHook::<(i64,), i64>::new("not wasm", "hook").is_err());
+}